@@ -148,7 +148,7 @@ impl Atomic {
     ///
     /// This is the canonical representation of the atomic value
     /// according to xs:schema.
-    pub(crate) fn string_value(&self) -> String {
+    pub fn string_value(&self) -> String {
         self.clone().into_canonical()
     }
 
@@ -241,6 +241,15 @@ impl Atomic {
         matches!(self, Atomic::Untyped(_))
     }
 
+    /// The name of the type of the atomic value, such as `xs:integer` or
+    /// `xs:dateTime`.
+    ///
+    /// This is useful for diagnostics, such as logging the type of a value
+    /// that caused a type error.
+    pub fn type_name(&self) -> &'static str {
+        self.schema_type().prefixed_name()
+    }
+
     pub(crate) fn schema_type(&self) -> Xs {
         match self {
             Atomic::String(string_type, _) => string_type.schema_type(),
@@ -336,6 +345,33 @@ impl Atomic {
         self.equal(other, collation, default_offset)
     }
 
+    /// Deep-equal comparison with a tolerance.
+    ///
+    /// This is like deep_equal, but if either value is a float or a double,
+    /// numeric values within `epsilon` of each other compare equal as well.
+    pub(crate) fn approx_equal(
+        &self,
+        other: &Atomic,
+        epsilon: f64,
+        collation: &Collation,
+        default_offset: chrono::FixedOffset,
+    ) -> bool {
+        if self.deep_equal(other, collation, default_offset) {
+            return true;
+        }
+        let is_floating = |atomic: &Atomic| matches!(atomic, Atomic::Float(_) | Atomic::Double(_));
+        if !(self.is_numeric() && other.is_numeric() && (is_floating(self) || is_floating(other))) {
+            return false;
+        }
+        match (
+            self.clone().cast_to_double(),
+            other.clone().cast_to_double(),
+        ) {
+            (Ok(Atomic::Double(a)), Ok(Atomic::Double(b))) => (a.0 - b.0).abs() <= epsilon,
+            _ => false,
+        }
+    }
+
     pub(crate) fn fallible_compare(
         &self,
         other: &Atomic,
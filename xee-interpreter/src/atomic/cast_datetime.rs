@@ -579,6 +579,21 @@ impl atomic::Atomic {
     }
 }
 
+/// Parse a date as used in HTTP and email headers, as described for
+/// `fn:parse-ietf-date`.
+///
+/// Names are matched case-insensitively. Any failure, including a date or
+/// time that doesn't exist, results in FORG0010.
+pub(crate) fn parse_ietf_date(s: &str) -> error::Result<NaiveDateTimeWithOffset> {
+    let s = s.to_ascii_lowercase();
+    let parser = ietf_date_parser();
+    let ietf_date = parser
+        .parse(&s)
+        .into_result()
+        .map_err(|_| error::Error::FORG0010)?;
+    ietf_date.date_time().ok_or(error::Error::FORG0010)
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum ParserError {
     ExpectedFound {
@@ -1095,6 +1110,267 @@ fn g_year_month_parser<'a>() -> impl Parser<'a, &'a str, GYearMonth, MyExtra> {
         })
 }
 
+// the components of an IETF date, before validation
+struct IetfDate {
+    year: i32,
+    month: u32,
+    day: u32,
+    time: IetfTime,
+}
+
+struct IetfTime {
+    hours: u32,
+    minutes: u32,
+    seconds: u32,
+    milliseconds: u32,
+    // offset in minutes east of UTC, and whether its minutes are valid
+    offset: Option<(i32, bool)>,
+}
+
+impl IetfDate {
+    fn date_time(&self) -> Option<NaiveDateTimeWithOffset> {
+        let time = &self.time;
+        let date = chrono::NaiveDate::from_ymd_opt(self.year, self.month, self.day)?;
+        let (hours, twentyfour) = if time.hours == 24 {
+            if time.minutes != 0 || time.seconds != 0 || time.milliseconds != 0 {
+                return None;
+            }
+            (0, true)
+        } else {
+            (time.hours, false)
+        };
+        let naive_time = chrono::NaiveTime::from_hms_milli_opt(
+            hours,
+            time.minutes,
+            time.seconds,
+            time.milliseconds,
+        )?;
+        let date_time = date.and_time(naive_time);
+        let date_time = if twentyfour {
+            date_time + chrono::Duration::days(1)
+        } else {
+            date_time
+        };
+        // without a timezone, the time is in UTC
+        let offset = match time.offset {
+            Some((offset, true)) => chrono::FixedOffset::east_opt(offset * 60)?,
+            Some((_, false)) => return None,
+            None => chrono::offset::Utc.fix(),
+        };
+        Some(NaiveDateTimeWithOffset::new(date_time, Some(offset)))
+    }
+}
+
+// https://www.w3.org/TR/xpath-functions-31/#func-parse-ietf-date
+// The parsers below expect lowercased input.
+fn ietf_whitespace_parser<'a>() -> impl Parser<'a, &'a str, (), MyExtra> {
+    one_of(" \t\r\n").repeated().at_least(1)
+}
+
+fn ietf_digits_parser<'a>(min: usize, max: usize) -> impl Parser<'a, &'a str, u32, MyExtra> {
+    // we never parse more than two digits, so this cannot overflow
+    digit_parser()
+        .repeated()
+        .at_least(min)
+        .at_most(max)
+        .collect::<String>()
+        .map(|digits| digits.parse().unwrap())
+}
+
+fn ietf_day_name_parser<'a>() -> impl Parser<'a, &'a str, (), MyExtra> {
+    // full names have to be tried before their abbreviations
+    choice((
+        just("monday"),
+        just("tuesday"),
+        just("wednesday"),
+        just("thursday"),
+        just("friday"),
+        just("saturday"),
+        just("sunday"),
+        just("mon"),
+        just("tue"),
+        just("wed"),
+        just("thu"),
+        just("fri"),
+        just("sat"),
+        just("sun"),
+    ))
+    .ignored()
+}
+
+fn ietf_month_name_parser<'a>() -> impl Parser<'a, &'a str, u32, MyExtra> {
+    choice((
+        just("jan").to(1),
+        just("feb").to(2),
+        just("mar").to(3),
+        just("apr").to(4),
+        just("may").to(5),
+        just("jun").to(6),
+        just("jul").to(7),
+        just("aug").to(8),
+        just("sep").to(9),
+        just("oct").to(10),
+        just("nov").to(11),
+        just("dec").to(12),
+    ))
+}
+
+fn ietf_date_separator_parser<'a>() -> impl Parser<'a, &'a str, (), MyExtra> {
+    let whitespace = ietf_whitespace_parser().boxed();
+    whitespace
+        .clone()
+        .or_not()
+        .then(just('-'))
+        .then(whitespace.clone().or_not())
+        .ignored()
+        .or(whitespace)
+}
+
+fn ietf_year_parser<'a>() -> impl Parser<'a, &'a str, i32, MyExtra> {
+    // a two digit year is in the twentieth century
+    ietf_digits_parser(2, 2)
+        .then(ietf_digits_parser(2, 2).or_not())
+        .map(|(first, rest)| match rest {
+            Some(rest) => (first * 100 + rest) as i32,
+            None => 1900 + first as i32,
+        })
+}
+
+fn ietf_tz_name_parser<'a>() -> impl Parser<'a, &'a str, i32, MyExtra> {
+    // "utc" has to be tried before "ut"
+    choice((
+        just("utc").to(0),
+        just("ut").to(0),
+        just("gmt").to(0),
+        just("est").to(-5),
+        just("edt").to(-4),
+        just("cst").to(-6),
+        just("cdt").to(-5),
+        just("mst").to(-7),
+        just("mdt").to(-6),
+        just("pst").to(-8),
+        just("pdt").to(-7),
+    ))
+    .map(|hours: i32| hours * 60)
+}
+
+fn ietf_tz_offset_parser<'a>() -> impl Parser<'a, &'a str, (i32, bool), MyExtra> {
+    let whitespace = ietf_whitespace_parser().boxed();
+    // an offset may be followed by a timezone name as a comment, which we
+    // ignore
+    let comment = whitespace
+        .clone()
+        .or_not()
+        .then(just('('))
+        .then(whitespace.clone().or_not())
+        .then(ietf_tz_name_parser())
+        .then(whitespace.clone().or_not())
+        .then(just(')'))
+        .ignored();
+    one_of("+-")
+        .then(ietf_digits_parser(1, 2))
+        .then_ignore(just(':').or_not())
+        .then(ietf_digits_parser(2, 2).or_not())
+        .then_ignore(comment.or_not())
+        .map(|((sign, hours), minutes)| {
+            let minutes = minutes.unwrap_or(0);
+            let offset = (hours * 60 + minutes) as i32;
+            let offset = if sign == '-' { -offset } else { offset };
+            // like any timezone, the offset can be at most 14 hours
+            (offset, minutes < 60 && offset.abs() <= 14 * 60)
+        })
+}
+
+fn ietf_time_parser<'a>() -> impl Parser<'a, &'a str, IetfTime, MyExtra> {
+    let seconds = ietf_digits_parser(2, 2)
+        .then(just('.').ignore_then(digits_parser()).or_not())
+        .map(|(seconds, fraction)| {
+            // ignore anything below milliseconds
+            let milliseconds = fraction
+                .map(|fraction| {
+                    let fraction = if fraction.len() > 3 {
+                        &fraction[..3]
+                    } else {
+                        &fraction
+                    };
+                    fraction.parse::<u32>().unwrap() * 10u32.pow(3 - fraction.len() as u32)
+                })
+                .unwrap_or(0);
+            (seconds, milliseconds)
+        });
+    let timezone = ietf_tz_name_parser()
+        .map(|offset| (offset, true))
+        .or(ietf_tz_offset_parser());
+    ietf_digits_parser(1, 2)
+        .then_ignore(just(':'))
+        .then(ietf_digits_parser(2, 2))
+        .then(just(':').ignore_then(seconds).or_not())
+        .then(
+            ietf_whitespace_parser()
+                .or_not()
+                .ignore_then(timezone)
+                .or_not(),
+        )
+        .map(|(((hours, minutes), seconds), offset)| {
+            let (seconds, milliseconds) = seconds.unwrap_or((0, 0));
+            IetfTime {
+                hours,
+                minutes,
+                seconds,
+                milliseconds,
+                offset,
+            }
+        })
+}
+
+fn ietf_date_parser<'a>() -> impl Parser<'a, &'a str, IetfDate, MyExtra> {
+    let whitespace = ietf_whitespace_parser().boxed();
+    let date_separator = ietf_date_separator_parser().boxed();
+    let month_name = ietf_month_name_parser().boxed();
+    let day = ietf_digits_parser(1, 2).boxed();
+    let year = ietf_year_parser().boxed();
+    let time = ietf_time_parser().boxed();
+
+    let date_spec = day
+        .clone()
+        .then_ignore(date_separator.clone())
+        .then(month_name.clone())
+        .then_ignore(date_separator.clone())
+        .then(year.clone())
+        .then_ignore(whitespace.clone())
+        .then(time.clone())
+        .map(|(((day, month), year), time)| IetfDate {
+            year,
+            month,
+            day,
+            time,
+        });
+    let asctime = month_name
+        .then_ignore(date_separator)
+        .then(day)
+        .then_ignore(whitespace.clone())
+        .then(time)
+        .then_ignore(whitespace.clone())
+        .then(year)
+        .map(|(((month, day), time), year)| IetfDate {
+            year,
+            month,
+            day,
+            time,
+        });
+    let day_name = ietf_day_name_parser()
+        .then(just(',').or_not())
+        .then(whitespace.clone());
+
+    whitespace
+        .clone()
+        .or_not()
+        .ignore_then(day_name.or_not())
+        .ignore_then(date_spec.or(asctime))
+        .then_ignore(whitespace.or_not())
+        .then_ignore(end())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::atomic::datetime::ToDateTimeStamp;
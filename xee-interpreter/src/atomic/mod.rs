@@ -29,6 +29,7 @@ mod types;
 mod xpath_repr;
 
 pub use atomic_core::Atomic;
+pub(crate) use cast_datetime::parse_ietf_date;
 pub(crate) use compare::{AtomicCompare, AtomicCompareValue};
 pub(crate) use datetime::ToDateTimeStamp;
 pub use datetime::{
@@ -48,5 +49,5 @@ pub(crate) use op_mod::op_mod;
 pub(crate) use op_multiply::op_multiply;
 pub(crate) use op_ne::OpNe;
 pub(crate) use op_subtract::op_subtract;
-pub(crate) use round::{round_atomic, round_half_to_even_atomic};
+pub(crate) use round::{round_atomic, round_float, round_half_to_even_atomic};
 pub use types::{BinaryType, IntegerType, StringType};
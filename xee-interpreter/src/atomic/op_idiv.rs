@@ -1,3 +1,4 @@
+use std::fmt::Display;
 use std::rc::Rc;
 
 use ibig::IBig;
@@ -42,7 +43,7 @@ fn op_idiv_integer(a: Rc<IBig>, b: Rc<IBig>) -> error::Result<atomic::Atomic> {
 
 fn op_idiv_float<F>(a: F, b: F) -> error::Result<atomic::Atomic>
 where
-    F: Float + Into<atomic::Atomic>,
+    F: Float + Display + Into<atomic::Atomic>,
 {
     if b.is_zero() {
         return Err(error::Error::FOAR0001);
@@ -52,8 +53,12 @@ where
     }
 
     let v = op_div_float(a, b);
-    let v: i128 = v.trunc().to_i128().ok_or(error::Error::FOAR0002)?;
-    let i: IBig = v.into();
+    if v.is_infinite() {
+        return Err(error::Error::FOAR0002);
+    }
+    // the result may be too large for any machine integer, but as the
+    // truncated float is an integer, its decimal notation is exact
+    let i: IBig = format!("{:.0}", v.trunc()).parse().unwrap();
     Ok(i.into())
 }
 
@@ -143,6 +148,20 @@ mod tests {
         assert_eq!(result, Err(error::Error::FOAR0001));
     }
 
+    #[test]
+    fn test_numeric_integer_divide_large_double() {
+        let a = 1e40f64.into();
+        let b = 1i64.into();
+        let result = op_idiv(a, b).unwrap();
+        assert_eq!(
+            result,
+            "10000000000000000303786028427003666890752"
+                .parse::<IBig>()
+                .unwrap()
+                .into()
+        );
+    }
+
     #[test]
     fn test_numeric_integer_divide_3_point_0_by_inf() {
         let a = 3.0f64.into();
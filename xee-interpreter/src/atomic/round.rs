@@ -1,7 +1,9 @@
 use std::{cmp::Ordering, rc::Rc};
 
-use ibig::{ops::Abs, IBig};
-use num::Zero;
+use ibig::{
+    ops::{Abs, DivRemEuclid},
+    IBig,
+};
 use ordered_float::OrderedFloat;
 use rust_decimal::{Decimal, RoundingStrategy};
 
@@ -16,8 +18,8 @@ pub(crate) fn round_atomic(arg: atomic::Atomic, precision: i32) -> error::Result
         // even though the spec claims we should cast to an infinite
         // precision decimal, we don't have such a thing, so we
         // make do with doing the operation directly on f32 and f64
-        atomic::Atomic::Float(OrderedFloat(f)) => Ok(round_float(f, precision)?.into()),
-        atomic::Atomic::Double(OrderedFloat(d)) => Ok(round_float(d, precision)?.into()),
+        atomic::Atomic::Float(OrderedFloat(f)) => Ok(round_float(f, precision).into()),
+        atomic::Atomic::Double(OrderedFloat(d)) => Ok(round_float(d, precision).into()),
         _ => Err(error::Error::XPTY0004),
     }
 }
@@ -34,22 +36,34 @@ fn round_integer(i: Rc<IBig>, precision: i32) -> Result<atomic::Atomic, error::E
 }
 
 fn round_integer_negative(arg: IBig, precision: u32) -> atomic::Atomic {
-    // TODO: this is definitely not the most optimized way to
-    // implement this.
-
-    // The qt3 test suite doesn't seem to cover
-    // the integer case very well either, so I wrote a few more tests.
-    let d = 10u32.pow(precision);
-    let mut divided = arg.clone() / d;
-    let remainder = arg.clone() % d;
-    if remainder.abs() > (d / 2).into() {
-        if arg < 0.into() {
-            divided -= 1;
+    if let Some((quotient, remainder, d)) = divide_by_power_of_ten(&arg, precision) {
+        // ties go towards positive infinity
+        let quotient = if remainder * 2 >= d {
+            quotient + 1
         } else {
-            divided += 1;
-        }
+            quotient
+        };
+        (quotient * d).into()
+    } else {
+        IBig::from(0).into()
     }
-    (divided * d).into()
+}
+
+// Divide by 10^precision, rounding towards negative infinity, so that the
+// remainder is never negative. Returns the quotient, the remainder and
+// 10^precision.
+//
+// If 10^precision is more than twice as large as arg, rounding always
+// results in zero, so we return None rather than calculate a potentially
+// enormous power.
+fn divide_by_power_of_ten(arg: &IBig, precision: u32) -> Option<(IBig, IBig, IBig)> {
+    let digits = arg.abs().to_string().len();
+    if precision as usize > digits {
+        return None;
+    }
+    let d = IBig::from(10).pow(precision as usize);
+    let (quotient, remainder) = arg.clone().div_rem_euclid(d.clone());
+    Some((quotient, remainder, d))
 }
 
 fn round_decimal(arg: Decimal, precision: i32) -> error::Result<atomic::Atomic> {
@@ -58,45 +72,79 @@ fn round_decimal(arg: Decimal, precision: i32) -> error::Result<atomic::Atomic>
     } else {
         RoundingStrategy::MidpointTowardZero
     };
+    Ok(round_decimal_with_strategy(arg, precision, rounding_strategy)?.into())
+}
+
+fn round_decimal_with_strategy(
+    arg: Decimal,
+    precision: i32,
+    rounding_strategy: RoundingStrategy,
+) -> error::Result<Decimal> {
     match precision.cmp(&0) {
-        Ordering::Equal | Ordering::Greater => Ok(arg
-            .round_dp_with_strategy(precision as u32, rounding_strategy)
-            .into()),
+        Ordering::Equal | Ordering::Greater => {
+            Ok(arg.round_dp_with_strategy(precision as u32, rounding_strategy))
+        }
         Ordering::Less => {
-            let d: Decimal = 10u32.pow(precision.unsigned_abs()).into();
+            // a decimal can't represent 10^29, so rounding to that or
+            // beyond results in zero. Strictly speaking values above 5 *
+            // 10^28 should round up, but the result wouldn't be
+            // representable anyway.
+            let Some(d) = decimal_power_of_ten(precision.unsigned_abs()) else {
+                return Ok(Decimal::ZERO);
+            };
             let arg = arg / d;
             let arg = arg.round_dp_with_strategy(0, rounding_strategy);
-            let arg = arg * d;
-            Ok(arg.into())
+            arg.checked_mul(d).ok_or(error::Error::FOAR0002)
         }
     }
 }
 
-fn round_float<F: num_traits::Float>(arg: F, precision: i32) -> error::Result<F> {
-    if arg.is_nan() || arg.is_infinite() || arg.is_zero() {
-        return Ok(arg);
+fn decimal_power_of_ten(exponent: u32) -> Option<Decimal> {
+    // a decimal has at most 28 digits
+    if exponent > 28 {
+        return None;
     }
+    Some(Decimal::from_i128_with_scale(10i128.pow(exponent), 0))
+}
 
-    match precision.cmp(&0) {
-        Ordering::Equal => Ok(round_float_ties_to_positive_infinity(arg)),
+pub(crate) fn round_float<F: num_traits::Float>(arg: F, precision: i32) -> F {
+    round_float_with(arg, precision, round_float_ties_to_positive_infinity)
+}
+
+// Round a float to a precision, using the given function to round to a
+// whole number.
+fn round_float_with<F: num_traits::Float>(arg: F, precision: i32, round: impl Fn(F) -> F) -> F {
+    if arg.is_nan() || arg.is_infinite() || arg.is_zero() {
+        return arg;
+    }
+    let ten = F::from(10).unwrap();
+    let rounded = match precision.cmp(&0) {
+        Ordering::Equal => round(arg),
         Ordering::Greater => {
-            let d = 10i32.pow(precision.unsigned_abs());
-            let d = F::from(d);
-            if let Some(d) = d {
-                Ok(round_float_ties_to_positive_infinity(arg * d) / d)
-            } else {
-                Err(error::Error::FOAR0001)
+            let d = ten.powi(precision);
+            let scaled = arg * d;
+            if !scaled.is_finite() {
+                // the precision is beyond anything the float can
+                // represent, so there is nothing to round
+                return arg;
             }
+            round(scaled) / d
         }
         Ordering::Less => {
-            let d = 10i32.pow(precision.unsigned_abs());
-            let d = F::from(d);
-            if let Some(d) = d {
-                Ok(round_float_ties_to_positive_infinity(arg / d) * d)
+            let d = ten.powi(precision.saturating_neg());
+            if d.is_infinite() {
+                // the precision is beyond the magnitude of any float
+                F::zero()
             } else {
-                Err(error::Error::FOAR0001)
+                round(arg / d) * d
             }
         }
+    };
+    // we retain negative zero
+    if rounded.is_zero() {
+        rounded.copysign(arg)
+    } else {
+        rounded
     }
 }
 
@@ -110,6 +158,18 @@ fn round_float_ties_to_positive_infinity<F: num_traits::Float>(x: F) -> F {
     }
 }
 
+fn round_float_ties_to_even<F: num_traits::Float>(x: F) -> F {
+    let rounded = x.round();
+    let half = F::from(0.5).unwrap();
+    if (rounded - x).abs() == half {
+        // a tie, which `round` sends away from zero
+        let two = F::from(2).unwrap();
+        (x / two).round() * two
+    } else {
+        rounded
+    }
+}
+
 // Round half to even
 
 pub(crate) fn round_half_to_even_atomic(
@@ -118,48 +178,54 @@ pub(crate) fn round_half_to_even_atomic(
 ) -> error::Result<atomic::Atomic> {
     match arg {
         atomic::Atomic::Integer(_, i) => round_half_to_even_integer(i, precision),
-        atomic::Atomic::Decimal(d) => Ok(round_half_to_even_decimal(*d, precision).into()),
-        // even though the spec claims we should cast to an infinite
-        // precision decimal, we don't have such a thing, so we
-        // make do with doing the operation directly on f32 and f64
-        atomic::Atomic::Float(OrderedFloat(f)) => {
-            if f.is_nan() || f.is_infinite() || f.is_zero() {
-                return Ok(f.into());
-            }
-            // turn f into a Decimal
-            // we have to retain the excess bits here, as that's what the spec
-            // says
-            let f = Decimal::from_f32_retain(f);
-            if let Some(f) = f {
-                let f = round_half_to_even_decimal(f, precision);
-                // turn f back into a float
-                let f: f32 = f.try_into().map_err(|_| error::Error::FOAR0001)?;
-                Ok(f.into())
-            } else {
-                Err(error::Error::FOCA0001)
-            }
-        }
-        atomic::Atomic::Double(OrderedFloat(d)) => {
-            if d.is_nan() || d.is_infinite() || d.is_zero() {
-                return Ok(d.into());
-            }
-            // turn d into a Decimal
-            // we have to retain the excess bits here, as that's what the spec
-            // says
-            let d = Decimal::from_f64_retain(d);
-            if let Some(d) = d {
-                let d = round_half_to_even_decimal(d, precision);
-                // turn d back into a double
-                let d: f64 = d.try_into().map_err(|_| error::Error::FOAR0001)?;
-                Ok(d.into())
-            } else {
-                Err(error::Error::FOCA0001)
-            }
-        }
+        atomic::Atomic::Decimal(d) => Ok(round_half_to_even_decimal(*d, precision)?.into()),
+        // the spec says we should cast to an infinite precision decimal.
+        // We don't have such a thing, so we use a decimal where we can, and
+        // we retain the excess bits as that's what the spec says
+        atomic::Atomic::Float(OrderedFloat(f)) => Ok(round_half_to_even_float(
+            f,
+            precision,
+            Decimal::from_f32_retain,
+            |d| d.try_into().ok(),
+        )?
+        .into()),
+        atomic::Atomic::Double(OrderedFloat(d)) => Ok(round_half_to_even_float(
+            d,
+            precision,
+            Decimal::from_f64_retain,
+            |d| d.try_into().ok(),
+        )?
+        .into()),
         _ => Err(error::Error::XPTY0004),
     }
 }
 
+fn round_half_to_even_float<F: num_traits::Float>(
+    arg: F,
+    precision: i32,
+    to_decimal: impl Fn(F) -> Option<Decimal>,
+    from_decimal: impl Fn(Decimal) -> Option<F>,
+) -> error::Result<F> {
+    if arg.is_nan() || arg.is_infinite() || arg.is_zero() {
+        return Ok(arg);
+    }
+    if let Some(decimal) = to_decimal(arg) {
+        let rounded = round_half_to_even_decimal(decimal, precision)?;
+        let rounded = from_decimal(rounded).ok_or(error::Error::FOAR0001)?;
+        // we retain negative zero
+        if rounded.is_zero() {
+            Ok(rounded.copysign(arg))
+        } else {
+            Ok(rounded)
+        }
+    } else {
+        // the float is too large to be a decimal, so we do the operation
+        // on the float directly. Such a float has no fractional part, so
+        // precision only matters if it's negative.
+        Ok(round_float_with(arg, precision, round_float_ties_to_even))
+    }
+}
+
 fn round_half_to_even_integer(i: Rc<IBig>, precision: i32) -> Result<atomic::Atomic, error::Error> {
     if precision < 0 {
         Ok(round_half_to_even_integer_negative(
@@ -172,40 +238,24 @@ fn round_half_to_even_integer(i: Rc<IBig>, precision: i32) -> Result<atomic::Ato
 }
 
 fn round_half_to_even_integer_negative(arg: IBig, precision: u32) -> atomic::Atomic {
-    let d = 10u32.pow(precision);
-    let mut divided = arg.clone() / d;
-    let remainder = arg.clone() % d;
-    let halfway = d / 2;
-
-    let remainder_abs = remainder.abs();
-    if remainder_abs > halfway.into()
-        || (remainder_abs == halfway.into() && divided.clone() % 2 != 0)
-    {
-        if arg < 0.into() {
-            divided -= 1;
+    if let Some((quotient, remainder, d)) = divide_by_power_of_ten(&arg, precision) {
+        let remainder = remainder * 2;
+        let quotient = if remainder > d || (remainder == d && &quotient % 2 != 0) {
+            quotient + 1
         } else {
-            divided += 1;
-        }
+            quotient
+        };
+        (quotient * d).into()
+    } else {
+        IBig::from(0).into()
     }
-
-    (divided * d).into()
 }
 
 // Round half to even (bankers' rounding) for decimal
 // we also support negative precision
 // in case of half-way, we go to the lowest even number
-fn round_half_to_even_decimal(x: Decimal, precision: i32) -> Decimal {
-    match precision.cmp(&0) {
-        Ordering::Equal | Ordering::Greater => {
-            x.round_dp_with_strategy(precision as u32, RoundingStrategy::MidpointNearestEven)
-        }
-        Ordering::Less => {
-            // round-half-to-even(12450.00, -2) = 12400
-            // round-half-to-even(12350.00, -2) = 12400
-            let d = Decimal::new(10i64.pow(precision.unsigned_abs()), 0);
-            let x = x / d;
-            let x = x.round_dp_with_strategy(0, RoundingStrategy::MidpointNearestEven);
-            x * d
-        }
-    }
+fn round_half_to_even_decimal(x: Decimal, precision: i32) -> error::Result<Decimal> {
+    // round-half-to-even(12450.00, -2) = 12400
+    // round-half-to-even(12350.00, -2) = 12400
+    round_decimal_with_strategy(x, precision, RoundingStrategy::MidpointNearestEven)
 }
@@ -0,0 +1,152 @@
+use ahash::{HashSet, HashSetExt};
+
+use crate::error;
+use crate::string::{decimal_digit_zero, is_decimal_digit};
+
+/// The properties of a decimal format, as used by `fn:format-number`.
+///
+/// Decimal formats are registered in the static context with
+/// [`StaticContextBuilder::decimal_format`](super::StaticContextBuilder::decimal_format).
+/// A property that is `None` isn't declared, and has its default value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecimalFormatProps {
+    /// Separates the integer part from the fractional part. Defaults to `.`.
+    pub decimal_separator: Option<char>,
+    /// Separates groups of digits. Defaults to `,`.
+    pub grouping_separator: Option<char>,
+    /// Separates the mantissa from the exponent. Defaults to `e`.
+    pub exponent_separator: Option<char>,
+    /// The string used for infinity. Defaults to `Infinity`.
+    pub infinity: Option<String>,
+    /// The sign of a negative number. Defaults to `-`.
+    pub minus_sign: Option<char>,
+    /// The string used for NaN. Defaults to `NaN`.
+    pub nan: Option<String>,
+    /// Indicates a percentage. Defaults to `%`.
+    pub percent: Option<char>,
+    /// Indicates a per-mille value. Defaults to `‰`.
+    pub per_mille: Option<char>,
+    /// The zero of the digits used. Defaults to `0`.
+    pub zero_digit: Option<char>,
+    /// An optional digit in the picture string. Defaults to `#`.
+    pub digit: Option<char>,
+    /// Separates the positive and negative sub-pictures. Defaults to `;`.
+    pub pattern_separator: Option<char>,
+}
+
+impl DecimalFormatProps {
+    // Another declaration of the same decimal format may declare more
+    // properties, but it cannot give a property a different value.
+    pub(crate) fn merge(&mut self, other: DecimalFormatProps) -> error::Result<()> {
+        merge_property(&mut self.decimal_separator, other.decimal_separator)?;
+        merge_property(&mut self.grouping_separator, other.grouping_separator)?;
+        merge_property(&mut self.exponent_separator, other.exponent_separator)?;
+        merge_property(&mut self.infinity, other.infinity)?;
+        merge_property(&mut self.minus_sign, other.minus_sign)?;
+        merge_property(&mut self.nan, other.nan)?;
+        merge_property(&mut self.percent, other.percent)?;
+        merge_property(&mut self.per_mille, other.per_mille)?;
+        merge_property(&mut self.zero_digit, other.zero_digit)?;
+        merge_property(&mut self.digit, other.digit)?;
+        merge_property(&mut self.pattern_separator, other.pattern_separator)?;
+        Ok(())
+    }
+}
+
+fn merge_property<T: PartialEq>(property: &mut Option<T>, other: Option<T>) -> error::Result<()> {
+    match (&property, other) {
+        (Some(value), Some(other)) if *value != other => Err(error::Error::XTSE1290),
+        (_, Some(other)) => {
+            *property = Some(other);
+            Ok(())
+        }
+        (_, None) => Ok(()),
+    }
+}
+
+// A decimal format with the default value for each property that isn't
+// declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DecimalFormat {
+    pub(crate) decimal_separator: char,
+    pub(crate) grouping_separator: char,
+    pub(crate) exponent_separator: char,
+    pub(crate) infinity: String,
+    pub(crate) minus_sign: char,
+    pub(crate) nan: String,
+    pub(crate) percent: char,
+    pub(crate) per_mille: char,
+    pub(crate) zero_digit: char,
+    pub(crate) digit: char,
+    pub(crate) pattern_separator: char,
+}
+
+impl Default for DecimalFormat {
+    fn default() -> Self {
+        (&DecimalFormatProps::default()).into()
+    }
+}
+
+impl From<&DecimalFormatProps> for DecimalFormat {
+    fn from(props: &DecimalFormatProps) -> Self {
+        Self {
+            decimal_separator: props.decimal_separator.unwrap_or('.'),
+            grouping_separator: props.grouping_separator.unwrap_or(','),
+            exponent_separator: props.exponent_separator.unwrap_or('e'),
+            infinity: props
+                .infinity
+                .clone()
+                .unwrap_or_else(|| "Infinity".to_string()),
+            minus_sign: props.minus_sign.unwrap_or('-'),
+            nan: props.nan.clone().unwrap_or_else(|| "NaN".to_string()),
+            percent: props.percent.unwrap_or('%'),
+            per_mille: props.per_mille.unwrap_or('‰'),
+            zero_digit: props.zero_digit.unwrap_or('0'),
+            digit: props.digit.unwrap_or('#'),
+            pattern_separator: props.pattern_separator.unwrap_or(';'),
+        }
+    }
+}
+
+impl DecimalFormat {
+    // The zero digit has to be a decimal digit with the value zero, and the
+    // characters used in a picture string have to be distinct.
+    pub(crate) fn check(&self) -> error::Result<()> {
+        if !is_decimal_digit(self.zero_digit)
+            || decimal_digit_zero(self.zero_digit) != self.zero_digit
+        {
+            return Err(error::Error::XTSE1295);
+        }
+        let mut signs = vec![
+            self.decimal_separator,
+            self.grouping_separator,
+            self.exponent_separator,
+            self.percent,
+            self.per_mille,
+            self.digit,
+            self.pattern_separator,
+        ];
+        for value in 0..10 {
+            signs.push(self.digit_char(value)?);
+        }
+        let mut seen = HashSet::new();
+        for c in signs {
+            if !seen.insert(c) {
+                return Err(error::Error::XTSE1300);
+            }
+        }
+        Ok(())
+    }
+
+    // The digit with the given value, from the digit family of the zero
+    // digit.
+    pub(crate) fn digit_char(&self, value: u8) -> error::Result<char> {
+        char::from_u32(self.zero_digit as u32 + value as u32).ok_or(error::Error::XTSE1295)
+    }
+
+    // The value of a digit from the digit family of the zero digit.
+    pub(crate) fn digit_value(&self, c: char) -> Option<u8> {
+        let value = (c as u32).checked_sub(self.zero_digit as u32)?;
+        (value < 10).then_some(value as u8)
+    }
+}
@@ -1,12 +1,16 @@
 use ahash::{AHashMap, HashMap};
 use iri_string::types::{IriStr, IriString};
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
 use crate::function::{self, Function};
-use crate::{error::Error, interpreter::Program};
+use crate::{error, error::Error, interpreter::Program};
 use crate::{interpreter, sequence};
 
-use super::{DocumentsRef, StaticContext};
+use super::{DocumentsRef, DynamicContextBuilder, Resource, StaticContext};
 
 /// A map of variables
 ///
@@ -15,6 +19,63 @@ use super::{DocumentsRef, StaticContext};
 /// The key is the name of a variable, and the value is an item.
 pub type Variables = AHashMap<xot::xmlname::OwnedName, sequence::Sequence>;
 
+type TraceFunction = dyn Fn(&str, &sequence::Sequence, &xot::Xot);
+
+/// Where the output of `fn:trace` goes.
+#[derive(Clone, Default)]
+pub(crate) enum TraceSink {
+    /// Trace output is discarded.
+    #[default]
+    Discard,
+    /// Trace output is written to standard error.
+    Stderr,
+    /// Trace output is passed to a function supplied by the host.
+    Function(Rc<TraceFunction>),
+}
+
+impl Debug for TraceSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceSink::Discard => write!(f, "Discard"),
+            TraceSink::Stderr => write!(f, "Stderr"),
+            TraceSink::Function(_) => write!(f, "Function"),
+        }
+    }
+}
+
+type XsltCompileFunction =
+    dyn Fn(&str) -> error::SpannedResult<(Program, sequence::SerializationParameters)>;
+
+/// Compiles the stylesheets that `fn:transform` runs.
+///
+/// The interpreter can't compile XSLT itself, so the host supplies this.
+#[derive(Clone)]
+pub(crate) struct XsltCompiler(Rc<XsltCompileFunction>);
+
+impl XsltCompiler {
+    pub(crate) fn new(
+        compile: impl Fn(&str) -> error::SpannedResult<(Program, sequence::SerializationParameters)>
+            + 'static,
+    ) -> Self {
+        Self(Rc::new(compile))
+    }
+
+    /// Compile a stylesheet into a program, along with the serialization
+    /// parameters of its unnamed `xsl:output`.
+    pub(crate) fn compile(
+        &self,
+        xslt: &str,
+    ) -> error::SpannedResult<(Program, sequence::SerializationParameters)> {
+        (self.0)(xslt)
+    }
+}
+
+impl Debug for XsltCompiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "XsltCompiler")
+    }
+}
+
 // a dynamic context is created for each xpath evaluation
 #[derive(Debug)]
 pub struct DynamicContext<'a> {
@@ -41,6 +102,21 @@ pub struct DynamicContext<'a> {
     uri_collections: HashMap<IriString, sequence::Sequence>,
     // environment variables
     environment_variables: HashMap<String, String>,
+    // resources that can be retrieved by URI
+    resources: HashMap<String, Resource>,
+    // the maximum amount of nested function calls
+    max_stack_depth: usize,
+    // evaluation stops when this is set
+    cancellation_flag: Option<Arc<AtomicBool>>,
+    // evaluation stops when this moment has passed
+    deadline: Option<Instant>,
+    // where the output of fn:trace goes
+    trace_sink: TraceSink,
+    // compiles the stylesheets fn:transform runs
+    xslt_compiler: Option<XsltCompiler>,
+    // the level of the tracing events fn:trace emits
+    #[cfg(feature = "tracing")]
+    trace_level: tracing::Level,
 }
 
 impl<'a> DynamicContext<'a> {
@@ -56,6 +132,13 @@ impl<'a> DynamicContext<'a> {
         default_uri_collection: Option<sequence::Sequence>,
         uri_collections: HashMap<IriString, sequence::Sequence>,
         environment_variables: HashMap<String, String>,
+        resources: HashMap<String, Resource>,
+        max_stack_depth: usize,
+        cancellation_flag: Option<Arc<AtomicBool>>,
+        deadline: Option<Instant>,
+        trace_sink: TraceSink,
+        xslt_compiler: Option<XsltCompiler>,
+        #[cfg(feature = "tracing")] trace_level: tracing::Level,
     ) -> Self {
         Self {
             program,
@@ -68,6 +151,14 @@ impl<'a> DynamicContext<'a> {
             default_uri_collection,
             uri_collections,
             environment_variables,
+            resources,
+            max_stack_depth,
+            cancellation_flag,
+            deadline,
+            trace_sink,
+            xslt_compiler,
+            #[cfg(feature = "tracing")]
+            trace_level,
         }
     }
 
@@ -77,10 +168,31 @@ impl<'a> DynamicContext<'a> {
     }
 
     /// Access the context item, if any.
+    ///
+    /// This is the context item the evaluation starts with, as returned by
+    /// `.` at the top level of an expression.
     pub fn context_item(&self) -> Option<&sequence::Item> {
         self.context_item.as_ref()
     }
 
+    /// The context position the evaluation starts with, as returned by
+    /// `fn:position()` at the top level of an expression.
+    ///
+    /// This is 1 if there is a context item. If there is no context item
+    /// the focus is absent, and this is an [`Error::XPDY0002`].
+    pub fn position(&self) -> Result<usize, Error> {
+        self.context_item.as_ref().map(|_| 1).ok_or(Error::XPDY0002)
+    }
+
+    /// The context size the evaluation starts with, as returned by
+    /// `fn:last()` at the top level of an expression.
+    ///
+    /// This is 1 if there is a context item. If there is no context item
+    /// the focus is absent, and this is an [`Error::XPDY0002`].
+    pub fn last(&self) -> Result<usize, Error> {
+        self.context_item.as_ref().map(|_| 1).ok_or(Error::XPDY0002)
+    }
+
     /// The documents in this context.
     pub fn documents(&self) -> DocumentsRef {
         self.documents.clone()
@@ -124,6 +236,110 @@ impl<'a> DynamicContext<'a> {
         self.environment_variables.keys().map(String::as_str)
     }
 
+    /// Access a resource by absolute URI
+    pub fn resource(&self, uri: &str) -> Option<&Resource> {
+        self.resources.get(uri)
+    }
+
+    /// The maximum amount of nested function calls during evaluation.
+    pub fn max_stack_depth(&self) -> usize {
+        self.max_stack_depth
+    }
+
+    /// Whether evaluation should stop, either because it was cancelled or
+    /// because the deadline has passed.
+    pub fn is_cancelled(&self) -> bool {
+        if let Some(cancellation_flag) = &self.cancellation_flag {
+            if cancellation_flag.load(Ordering::Relaxed) {
+                return true;
+            }
+        }
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// A builder for a dynamic context of another program, which starts out
+    /// with the settings of this context.
+    ///
+    /// Everything is taken over except for the context item: the documents,
+    /// the variables, collections, resources and environment variables, as
+    /// well as the limits on evaluation such as the maximum stack depth, the
+    /// cancellation flag and the deadline.
+    pub fn builder_for<'b>(&self, program: &'b Program) -> DynamicContextBuilder<'b> {
+        let mut builder = program.dynamic_context_builder();
+        builder.documents = self.documents.clone();
+        builder.variables = self.variables.clone();
+        builder.current_datetime = self.current_datetime;
+        builder.default_collection = self.default_collection.clone();
+        builder.collections = self.collections.clone();
+        builder.default_uri_collection = self.default_uri_collection.clone();
+        builder.uri_collections = self.uri_collections.clone();
+        builder.environment_variables = Some(self.environment_variables.clone());
+        builder.resources = self.resources.clone();
+        builder.max_stack_depth = self.max_stack_depth;
+        builder.cancellation_flag = self.cancellation_flag.clone();
+        builder.deadline = self.deadline;
+        builder.trace_sink = self.trace_sink.clone();
+        builder.xslt_compiler = self.xslt_compiler.clone();
+        #[cfg(feature = "tracing")]
+        {
+            builder.trace_level = self.trace_level;
+        }
+        builder
+    }
+
+    /// The compiler for the stylesheets `fn:transform` runs, if any.
+    pub(crate) fn xslt_compiler(&self) -> Option<&XsltCompiler> {
+        self.xslt_compiler.as_ref()
+    }
+
+    /// Pass a value and its label to the trace sink.
+    pub(crate) fn trace(&self, label: &str, value: &sequence::Sequence, xot: &xot::Xot) {
+        match &self.trace_sink {
+            TraceSink::Discard => {}
+            TraceSink::Stderr => {
+                let representation = value.display_representation(xot, self);
+                if label.is_empty() {
+                    eprintln!("{}", representation);
+                } else {
+                    eprintln!("{}: {}", label, representation);
+                }
+            }
+            TraceSink::Function(sink) => sink(label, value, xot),
+        }
+        #[cfg(feature = "tracing")]
+        self.trace_event(label, value, xot);
+    }
+
+    // Emit a tracing event with the label and the value. The macros of
+    // tracing need a level that's known at compile time, so we dispatch on
+    // it.
+    #[cfg(feature = "tracing")]
+    fn trace_event(&self, label: &str, value: &sequence::Sequence, xot: &xot::Xot) {
+        use tracing::Level;
+
+        macro_rules! event {
+            ($level:expr) => {
+                if tracing::enabled!($level) {
+                    let value = value.display_representation(xot, self);
+                    tracing::event!($level, label, value = value.as_str())
+                }
+            };
+        }
+
+        match self.trace_level {
+            Level::ERROR => event!(Level::ERROR),
+            Level::WARN => event!(Level::WARN),
+            Level::INFO => event!(Level::INFO),
+            Level::DEBUG => event!(Level::DEBUG),
+            Level::TRACE => event!(Level::TRACE),
+        }
+    }
+
     pub(crate) fn arguments(&self) -> Result<Vec<sequence::Sequence>, Error> {
         let mut arguments = Vec::new();
         for variable_name in self.static_context().variable_names() {
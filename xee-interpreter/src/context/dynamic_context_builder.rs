@@ -1,11 +1,20 @@
-use std::{cell::RefCell, ops::Deref, rc::Rc};
+use std::{
+    cell::RefCell,
+    ops::Deref,
+    rc::Rc,
+    sync::{atomic::AtomicBool, Arc},
+    time::Instant,
+};
 
 use ahash::{HashMap, HashMapExt};
 use iri_string::types::{IriStr, IriString};
 
-use crate::{interpreter, sequence, xml};
+use crate::{error, interpreter, sequence, xml};
 
-use super::{DynamicContext, Variables};
+use super::{
+    dynamic_context::{TraceSink, XsltCompiler},
+    DynamicContext, Resource, Variables,
+};
 
 /// A builder for constructing a [`DynamicContext`].
 ///
@@ -18,16 +27,28 @@ use super::{DynamicContext, Variables};
 pub struct DynamicContextBuilder<'a> {
     program: &'a interpreter::Program,
     context_item: Option<sequence::Item>,
-    documents: DocumentsRef,
-    variables: Variables,
-    current_datetime: chrono::DateTime<chrono::offset::FixedOffset>,
-    default_collection: Option<sequence::Sequence>,
-    collections: HashMap<IriString, sequence::Sequence>,
-    default_uri_collection: Option<sequence::Sequence>,
-    uri_collections: HashMap<IriString, sequence::Sequence>,
-    environment_variables: HashMap<String, String>,
+    pub(super) documents: DocumentsRef,
+    pub(super) variables: Variables,
+    pub(super) current_datetime: chrono::DateTime<chrono::offset::FixedOffset>,
+    pub(super) default_collection: Option<sequence::Sequence>,
+    pub(super) collections: HashMap<IriString, sequence::Sequence>,
+    pub(super) default_uri_collection: Option<sequence::Sequence>,
+    pub(super) uri_collections: HashMap<IriString, sequence::Sequence>,
+    pub(super) environment_variables: Option<HashMap<String, String>>,
+    process_environment: bool,
+    pub(super) resources: HashMap<String, Resource>,
+    pub(super) max_stack_depth: usize,
+    pub(super) cancellation_flag: Option<Arc<AtomicBool>>,
+    pub(super) deadline: Option<Instant>,
+    pub(super) trace_sink: TraceSink,
+    pub(super) xslt_compiler: Option<XsltCompiler>,
+    #[cfg(feature = "tracing")]
+    pub(super) trace_level: tracing::Level,
 }
 
+/// The default maximum amount of nested function calls.
+pub const DEFAULT_MAX_STACK_DEPTH: usize = 1000;
+
 #[derive(Debug, Clone)]
 pub struct DocumentsRef(Rc<RefCell<xml::Documents>>);
 
@@ -70,7 +91,16 @@ impl<'a> DynamicContextBuilder<'a> {
             collections: HashMap::new(),
             default_uri_collection: None,
             uri_collections: HashMap::new(),
-            environment_variables: HashMap::new(),
+            environment_variables: None,
+            process_environment: false,
+            resources: HashMap::new(),
+            max_stack_depth: DEFAULT_MAX_STACK_DEPTH,
+            cancellation_flag: None,
+            deadline: None,
+            trace_sink: TraceSink::Discard,
+            xslt_compiler: None,
+            #[cfg(feature = "tracing")]
+            trace_level: tracing::Level::DEBUG,
         }
     }
 
@@ -140,18 +170,143 @@ impl<'a> DynamicContextBuilder<'a> {
         self
     }
 
-    /// Set the environment variables
+    /// Set a virtual environment.
+    ///
+    /// `fn:environment-variable` and `fn:available-environment-variables`
+    /// see these variables only. This takes precedence over
+    /// [`initialize_env`](Self::initialize_env), so the process environment
+    /// is ignored, which makes expressions that read environment variables
+    /// reproducible.
     pub fn environment_variables(
         &mut self,
         environment_variables: HashMap<String, String>,
     ) -> &mut Self {
-        self.environment_variables = environment_variables;
+        self.environment_variables = Some(environment_variables);
         self
     }
 
     /// Initialize the environment variables from the current system environment.
+    ///
+    /// The environment is read when the context is built. If a virtual
+    /// environment is set with
+    /// [`environment_variables`](Self::environment_variables), it is used
+    /// instead.
+    ///
+    /// Without either, no environment variables are available.
     pub fn initialize_env(&mut self) -> &mut Self {
-        self.environment_variables = std::env::vars().collect();
+        self.process_environment = true;
+        self
+    }
+
+    /// Set the resources that can be retrieved by URI.
+    ///
+    /// The keys are absolute URIs; a relative URI passed to a function is
+    /// first resolved against the static base URI. `fn:doc`,
+    /// `fn:collection` and `fn:uri-collection` look up a URI here if no
+    /// document or collection was added for it, and `fn:unparsed-text`
+    /// retrieves its text from here.
+    ///
+    /// Without this, no resources are available.
+    pub fn resource_map(&mut self, resources: HashMap<String, Resource>) -> &mut Self {
+        self.resources = resources;
+        self
+    }
+
+    /// Set the maximum amount of nested function calls.
+    ///
+    /// Each call to a function defined in XPath or XSLT, such as an inline
+    /// function or a template, takes a place on the call stack until it
+    /// returns. If a call would exceed this depth, evaluation stops with
+    /// [`Error::StackOverflow`](crate::error::Error::StackOverflow) rather
+    /// than crashing the process. This protects against runaway recursion in
+    /// untrusted expressions.
+    ///
+    /// Without this, the maximum is [`DEFAULT_MAX_STACK_DEPTH`].
+    pub fn max_stack_depth(&mut self, max_stack_depth: usize) -> &mut Self {
+        self.max_stack_depth = max_stack_depth;
+        self
+    }
+
+    /// Set a flag to cancel evaluation.
+    ///
+    /// You can set this flag from another thread. Evaluation then stops with
+    /// [`Error::Cancelled`](crate::error::Error::Cancelled).
+    ///
+    /// The flag isn't checked after every step: the interpreter checks it
+    /// every [`CANCELLATION_CHECK_INTERVAL`](crate::interpreter::CANCELLATION_CHECK_INTERVAL)
+    /// instructions, which for typical expressions means within
+    /// microseconds. Note that a single function call that takes a long time
+    /// by itself, such as `count()` over a very large range, isn't
+    /// interrupted; the check happens once it returns.
+    pub fn cancellation_flag(&mut self, cancellation_flag: Arc<AtomicBool>) -> &mut Self {
+        self.cancellation_flag = Some(cancellation_flag);
+        self
+    }
+
+    /// Set a deadline for evaluation.
+    ///
+    /// Once the deadline has passed evaluation stops with
+    /// [`Error::Cancelled`](crate::error::Error::Cancelled). This is checked
+    /// with the same granularity as the
+    /// [cancellation flag](Self::cancellation_flag).
+    pub fn deadline(&mut self, deadline: Instant) -> &mut Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set a function that receives the output of `fn:trace`.
+    ///
+    /// The function is called with the label (empty if `fn:trace` is called
+    /// without one) and the traced value. It's also given the [`xot::Xot`]
+    /// that holds any nodes in the value, so that they can be serialized.
+    /// `fn:trace` itself returns the value unchanged.
+    ///
+    /// Without this, trace output is discarded.
+    pub fn trace_sink(
+        &mut self,
+        trace_sink: impl Fn(&str, &sequence::Sequence, &xot::Xot) + 'static,
+    ) -> &mut Self {
+        self.trace_sink = TraceSink::Function(Rc::new(trace_sink));
+        self
+    }
+
+    /// Write the output of `fn:trace` to standard error.
+    ///
+    /// Each traced value is written on its own line, after its label.
+    pub fn trace_to_stderr(&mut self) -> &mut Self {
+        self.trace_sink = TraceSink::Stderr;
+        self
+    }
+
+    /// Set the function that compiles the stylesheets `fn:transform` runs.
+    ///
+    /// The function is given the text of the stylesheet, and returns the
+    /// compiled program along with the serialization parameters of its
+    /// unnamed `xsl:output`, which are used for the `serialized` delivery
+    /// format.
+    ///
+    /// Without this, `fn:transform` fails with `FOXT0001`.
+    pub fn xslt_compiler(
+        &mut self,
+        xslt_compiler: impl Fn(
+                &str,
+            )
+                -> error::SpannedResult<(interpreter::Program, sequence::SerializationParameters)>
+            + 'static,
+    ) -> &mut Self {
+        self.xslt_compiler = Some(XsltCompiler::new(xslt_compiler));
+        self
+    }
+
+    /// Set the level of the events `fn:trace` emits through `tracing`.
+    ///
+    /// With the `tracing` feature, each call to `fn:trace` emits an event
+    /// with a `label` and a `value` field, in addition to passing its output
+    /// to the trace sink. The value is serialized the same way as when
+    /// tracing to standard error. The default level is `DEBUG`.
+    #[cfg(feature = "tracing")]
+    pub fn trace_level(&mut self, trace_level: tracing::Level) -> &mut Self {
+        self.trace_level = trace_level;
         self
     }
 
@@ -179,7 +334,23 @@ impl<'a> DynamicContextBuilder<'a> {
             self.collections.clone(),
             self.default_uri_collection.clone(),
             self.uri_collections.clone(),
-            self.environment_variables.clone(),
+            self.build_environment_variables(),
+            self.resources.clone(),
+            self.max_stack_depth,
+            self.cancellation_flag.clone(),
+            self.deadline,
+            self.trace_sink.clone(),
+            self.xslt_compiler.clone(),
+            #[cfg(feature = "tracing")]
+            self.trace_level,
         )
     }
+
+    fn build_environment_variables(&self) -> HashMap<String, String> {
+        match &self.environment_variables {
+            Some(environment_variables) => environment_variables.clone(),
+            None if self.process_environment => std::env::vars().collect(),
+            None => HashMap::new(),
+        }
+    }
 }
@@ -1,11 +1,16 @@
 /// The static context is used during compile time. It is then used to
 /// construct a dynamic context, which is used during runtime.
+mod decimal_format;
 mod dynamic_context;
 mod dynamic_context_builder;
+mod resource;
 mod static_context;
 mod static_context_builder;
 
+pub(crate) use decimal_format::DecimalFormat;
+pub use decimal_format::DecimalFormatProps;
 pub use dynamic_context::{DynamicContext, Variables};
-pub use dynamic_context_builder::{DocumentsRef, DynamicContextBuilder};
-pub use static_context::StaticContext;
+pub use dynamic_context_builder::{DocumentsRef, DynamicContextBuilder, DEFAULT_MAX_STACK_DEPTH};
+pub use resource::Resource;
+pub use static_context::{OrderingMode, StaticContext};
 pub use static_context_builder::StaticContextBuilder;
@@ -0,0 +1,24 @@
+/// A resource that can be retrieved by URI during evaluation.
+///
+/// Resources are supplied to the dynamic context with
+/// [`DynamicContextBuilder::resource_map`](super::DynamicContextBuilder::resource_map),
+/// so that `fn:doc`, `fn:collection`, `fn:uri-collection`,
+/// `fn:unparsed-text` and `fn:json-doc` can be used without access to a
+/// file system or network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resource {
+    /// Text, as retrieved by `fn:unparsed-text`, or JSON text, as retrieved
+    /// by `fn:json-doc`.
+    Text(String),
+    /// An XML document, as retrieved by `fn:doc`.
+    ///
+    /// It's parsed the first time it's retrieved. Its source text can also
+    /// be retrieved by `fn:unparsed-text`.
+    Xml(String),
+    /// A collection, as retrieved by `fn:collection` and
+    /// `fn:uri-collection`.
+    ///
+    /// These are the URIs of the documents in the collection. Each is
+    /// retrieved as `fn:doc` would.
+    Collection(Vec<String>),
+}
@@ -3,13 +3,16 @@ use std::fmt::Debug;
 use std::rc::Rc;
 use std::sync::LazyLock;
 
+use ahash::{HashMap, HashMapExt};
 use iri_string::types::IriAbsoluteStr;
 use iri_string::types::IriAbsoluteString;
 use iri_string::types::IriReferenceStr;
 use xee_name::{Namespaces, VariableNames};
 use xee_xpath_ast::ast;
 use xee_xpath_ast::XPathParserContext;
+use xot::xmlname::OwnedName;
 
+use crate::context::decimal_format::DecimalFormat;
 use crate::error;
 use crate::function;
 use crate::string::{Collation, Collations};
@@ -24,6 +27,30 @@ static DEFAULT_COLLATION: LazyLock<IriAbsoluteString> = LazyLock::new(|| {
         .unwrap()
 });
 
+/// Whether node sequences are delivered in document order.
+///
+/// The path operator (`/`) and the node set operators (`|`/`union`,
+/// `intersect` and `except`) remove duplicate nodes from their result. In
+/// the ordered mode, which is the default and what XPath requires, they
+/// also sort the result in document order.
+///
+/// In the unordered mode the sorting is skipped, which saves time for large
+/// node sets. Duplicates are still removed, and the nodes are delivered in
+/// the order in which they are first encountered. For a path that selects
+/// along forward axes only, such as `//a`, this is usually document order
+/// anyway, but there is no such guarantee for unions or reverse axes.
+///
+/// All other expressions, such as the comma operator, `for`, `!` and
+/// predicates, keep the order of their input in both modes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderingMode {
+    /// Nodes are sorted in document order.
+    #[default]
+    Ordered,
+    /// Nodes are delivered in the order they are encountered.
+    Unordered,
+}
+
 #[derive(Debug)]
 pub struct StaticContext {
     parser_context: XPathParserContext,
@@ -31,11 +58,22 @@ pub struct StaticContext {
     // TODO: try to make collations static
     collations: RefCell<Collations>,
     static_base_uri: Option<IriAbsoluteString>,
+    ordering_mode: OrderingMode,
+    // the unnamed decimal format is under `None`, and is always present
+    decimal_formats: HashMap<Option<OwnedName>, DecimalFormat>,
+    // whether the functions XSLT adds can be called
+    xslt_functions: bool,
 }
 
 impl Default for StaticContext {
     fn default() -> Self {
-        Self::new(Namespaces::default(), VariableNames::default(), None)
+        Self::new(
+            Namespaces::default(),
+            VariableNames::default(),
+            None,
+            OrderingMode::default(),
+            HashMap::new(),
+        )
     }
 }
 
@@ -46,6 +84,9 @@ impl From<XPathParserContext> for StaticContext {
             functions: &STATIC_FUNCTIONS,
             collations: RefCell::new(Collations::new()),
             static_base_uri: None,
+            ordering_mode: OrderingMode::default(),
+            decimal_formats: default_decimal_formats(HashMap::new()),
+            xslt_functions: false,
         }
     }
 }
@@ -55,23 +96,42 @@ impl StaticContext {
         namespaces: Namespaces,
         variable_names: VariableNames,
         static_base_uri: Option<IriAbsoluteString>,
+        ordering_mode: OrderingMode,
+        decimal_formats: HashMap<Option<OwnedName>, DecimalFormat>,
     ) -> Self {
         Self {
             parser_context: XPathParserContext::new(namespaces, variable_names),
             functions: &STATIC_FUNCTIONS,
             collations: RefCell::new(Collations::new()),
             static_base_uri,
+            ordering_mode,
+            decimal_formats: default_decimal_formats(decimal_formats),
+            xslt_functions: false,
         }
     }
 
     pub fn from_namespaces(namespaces: Namespaces) -> Self {
-        Self::new(namespaces, VariableNames::default(), None)
+        Self::new(
+            namespaces,
+            VariableNames::default(),
+            None,
+            OrderingMode::default(),
+            HashMap::new(),
+        )
+    }
+
+    /// Make the functions XSLT adds to the function library available,
+    /// such as `fn:system-property`. The XSLT compiler does this; in plain
+    /// XPath these functions don't exist.
+    pub fn enable_xslt_functions(&mut self) {
+        self.xslt_functions = true;
     }
 
     pub fn namespaces(&self) -> &Namespaces {
         &self.parser_context.namespaces
     }
 
+    /// The names of the variables that are in scope.
     pub fn variable_names(&self) -> &VariableNames {
         &self.parser_context.variable_names
     }
@@ -100,6 +160,17 @@ impl StaticContext {
         self.static_base_uri.as_deref()
     }
 
+    /// Whether node sequences are sorted in document order.
+    pub fn ordering_mode(&self) -> OrderingMode {
+        self.ordering_mode
+    }
+
+    // The decimal format with the given name, or the unnamed decimal format
+    // for `None`.
+    pub(crate) fn decimal_format(&self, name: Option<&OwnedName>) -> Option<&DecimalFormat> {
+        self.decimal_formats.get(&name.cloned())
+    }
+
     pub(crate) fn collation(&self, uri: &IriReferenceStr) -> error::Result<Rc<Collation>> {
         self.collations
             .borrow_mut()
@@ -123,6 +194,19 @@ impl StaticContext {
         self.parser_context.parse_value_template_xpath(s)
     }
 
+    /// The names and signatures of the functions that are in scope.
+    ///
+    /// A function that can be called with a different number of arguments,
+    /// such as `fn:substring`, is listed once for each arity, with the
+    /// signature for that arity. `fn:concat`, which takes any number of
+    /// arguments, is listed for each arity from 2 up to the maximum that is
+    /// supported.
+    pub fn function_signatures(
+        &self,
+    ) -> impl Iterator<Item = (&xot::xmlname::OwnedName, &function::Signature)> {
+        self.functions.signatures(self.xslt_functions)
+    }
+
     /// Get a static function by id
     pub fn function_by_id(
         &self,
@@ -137,7 +221,13 @@ impl StaticContext {
         name: &xot::xmlname::OwnedName,
         arity: u8,
     ) -> Option<function::StaticFunctionId> {
-        self.functions.get_by_name(name, arity)
+        self.functions.get_by_name(name, arity).or_else(|| {
+            if self.xslt_functions {
+                self.functions.get_xslt_by_name(name, arity)
+            } else {
+                None
+            }
+        })
     }
 
     /// Get an internal static function by name and arity
@@ -149,3 +239,10 @@ impl StaticContext {
         self.functions.get_by_internal_name(name, arity)
     }
 }
+
+fn default_decimal_formats(
+    mut decimal_formats: HashMap<Option<OwnedName>, DecimalFormat>,
+) -> HashMap<Option<OwnedName>, DecimalFormat> {
+    decimal_formats.entry(None).or_default();
+    decimal_formats
+}
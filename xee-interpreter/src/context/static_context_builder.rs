@@ -4,14 +4,18 @@ use xee_name::Namespaces;
 use xot::xmlname::OwnedName;
 
 use crate::context;
+use crate::context::decimal_format::{DecimalFormat, DecimalFormatProps};
+use crate::error;
 
 #[derive(Debug, Clone, Default)]
 pub struct StaticContextBuilder<'a> {
     variable_names: Vec<OwnedName>,
-    namespaces: HashMap<&'a str, &'a str>,
-    default_element_namespace: &'a str,
+    namespaces: HashMap<String, String>,
+    default_element_namespace: String,
     default_function_namespace: &'a str,
     static_base_uri: Option<IriAbsoluteString>,
+    ordering_mode: context::OrderingMode,
+    decimal_formats: HashMap<Option<OwnedName>, DecimalFormatProps>,
 }
 
 impl<'a> StaticContextBuilder<'a> {
@@ -48,22 +52,51 @@ impl<'a> StaticContextBuilder<'a> {
     }
 
     /// Add a namespace prefix that the XPath expression can use.
+    ///
+    /// This is the same as [`declare_namespace`](Self::declare_namespace).
     pub fn add_namespace(&mut self, prefix: &'a str, uri: &'a str) -> &mut Self {
+        self.declare_namespace(prefix, uri)
+    }
+
+    /// Declare a namespace prefix that the XPath expression can use.
+    ///
+    /// You can call this repeatedly to declare multiple prefixes. Declaring
+    /// a prefix again overrides it. If the prefix is empty, it sets the
+    /// default element namespace.
+    ///
+    /// Unlike [`add_namespace`](Self::add_namespace) the strings are
+    /// copied, so they don't need to outlive the builder.
+    pub fn declare_namespace(&mut self, prefix: &str, uri: &str) -> &mut Self {
         if prefix.is_empty() {
-            self.default_element_namespace = uri;
+            self.declare_default_element_namespace(uri)
         } else {
-            self.namespaces.insert(prefix, uri);
+            self.namespaces.insert(prefix.to_string(), uri.to_string());
+            self
         }
-        self
     }
 
     /// Set the default namespace for element references in the XPath expression.
     pub fn default_element_namespace(&mut self, default_element_namespace: &'a str) -> &mut Self {
-        self.default_element_namespace = default_element_namespace;
+        self.declare_default_element_namespace(default_element_namespace)
+    }
+
+    /// Declare the default namespace for element references in the XPath
+    /// expression.
+    ///
+    /// Declaring it again overrides it.
+    pub fn declare_default_element_namespace(
+        &mut self,
+        default_element_namespace: &str,
+    ) -> &mut Self {
+        self.default_element_namespace = default_element_namespace.to_string();
         self
     }
 
     /// Set the default namespace for function references in the XPath expression.
+    ///
+    /// Unprefixed function calls resolve into this namespace. If not set,
+    /// this is the `fn` namespace. The built-in functions remain reachable
+    /// through the explicit `fn:` prefix.
     pub fn default_function_namespace(&mut self, default_function_namespace: &'a str) -> &mut Self {
         self.default_function_namespace = default_function_namespace;
         self
@@ -75,15 +108,49 @@ impl<'a> StaticContextBuilder<'a> {
         self
     }
 
+    /// Set the ordering mode.
+    ///
+    /// By default node sequences produced by paths and node set operators
+    /// are in document order. See [`OrderingMode`](context::OrderingMode)
+    /// for what changes in the unordered mode.
+    pub fn ordering_mode(&mut self, ordering_mode: context::OrderingMode) -> &mut Self {
+        self.ordering_mode = ordering_mode;
+        self
+    }
+
+    /// Declare a decimal format, as used by `fn:format-number`.
+    ///
+    /// With a name this declares a named decimal format, which is used when
+    /// its name is passed as the third argument of `fn:format-number`.
+    /// Without a name it declares the unnamed decimal format, which is used
+    /// otherwise.
+    ///
+    /// A decimal format can be declared more than once, for instance to
+    /// declare more properties, but a property cannot be given two different
+    /// values; that's a `XTSE1290` error.
+    pub fn decimal_format(
+        &mut self,
+        name: Option<OwnedName>,
+        props: DecimalFormatProps,
+    ) -> error::Result<&mut Self> {
+        self.decimal_formats.entry(name).or_default().merge(props)?;
+        Ok(self)
+    }
+
     /// Build the static context.
     ///
     /// This will always include the default known namespaces for
     /// XPath, and the default function namespace will be the `fn` namespace
     /// if not set.
-    pub fn build(&self) -> context::StaticContext {
+    ///
+    /// Each decimal format, with the default values for the properties that
+    /// weren't declared, must be valid: the zero digit has to be a decimal
+    /// digit with the value zero (`XTSE1295`), and the characters used in a
+    /// picture string have to be distinct (`XTSE1300`).
+    pub fn build(&self) -> error::Result<context::StaticContext> {
         let mut namespaces = Namespaces::default_namespaces();
         for (prefix, uri) in &self.namespaces {
-            namespaces.insert(prefix.to_string(), uri.to_string());
+            namespaces.insert(prefix.clone(), uri.clone());
         }
         let default_function_namespace = if !self.default_function_namespace.is_empty() {
             self.default_function_namespace
@@ -92,11 +159,26 @@ impl<'a> StaticContextBuilder<'a> {
         };
         let namespaces = xee_name::Namespaces::new(
             namespaces,
-            self.default_element_namespace.to_string(),
+            self.default_element_namespace.clone(),
             default_function_namespace.to_string(),
         );
         let variable_names = self.variable_names.clone().into_iter().collect();
-        context::StaticContext::new(namespaces, variable_names, self.static_base_uri.clone())
+        let decimal_formats = self
+            .decimal_formats
+            .iter()
+            .map(|(name, props)| {
+                let decimal_format = DecimalFormat::from(props);
+                decimal_format.check()?;
+                Ok((name.clone(), decimal_format))
+            })
+            .collect::<error::Result<HashMap<_, _>>>()?;
+        Ok(context::StaticContext::new(
+            namespaces,
+            variable_names,
+            self.static_base_uri.clone(),
+            self.ordering_mode,
+            decimal_formats,
+        ))
     }
 }
 
@@ -118,7 +200,7 @@ mod tests {
     #[test]
     fn test_default_behavior() {
         let builder = StaticContextBuilder::default();
-        let static_context = builder.build();
+        let static_context = builder.build().unwrap();
         assert_eq!(static_context.namespaces().default_element_namespace(), "");
         assert_eq!(
             static_context.namespaces().default_function_namespace,
@@ -130,4 +212,77 @@ mod tests {
             Some("http://www.w3.org/XML/1998/namespace")
         );
     }
+
+    #[test]
+    fn test_function_signatures() {
+        let static_context = StaticContextBuilder::default().build().unwrap();
+        let fn_name = |local_name: &str| {
+            OwnedName::new(
+                local_name.to_string(),
+                Namespaces::FN_NAMESPACE.to_string(),
+                "fn".to_string(),
+            )
+        };
+        let signatures = |name: &OwnedName| {
+            static_context
+                .function_signatures()
+                .filter(|(n, _)| *n == name)
+                .map(|(_, signature)| signature.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let count = signatures(&fn_name("count"));
+        assert_eq!(count.len(), 1);
+        assert_eq!(count[0].arity(), 1);
+        assert_eq!(count[0].display_representation(), "(item()*) as xs:integer");
+
+        // concat is listed for every arity it supports
+        let concat_arities = signatures(&fn_name("concat"))
+            .iter()
+            .map(|signature| signature.arity())
+            .collect::<Vec<_>>();
+        assert_eq!(concat_arities, (2..=99).collect::<Vec<_>>());
+
+        // the context item variants of a function are listed too
+        let mut name_arities = signatures(&fn_name("name"))
+            .iter()
+            .map(|signature| signature.arity())
+            .collect::<Vec<_>>();
+        name_arities.sort();
+        assert_eq!(name_arities, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_default_function_namespace() {
+        let mut builder = StaticContextBuilder::default();
+        builder.default_function_namespace("http://example.com/functions");
+        let static_context = builder.build().unwrap();
+        assert_eq!(
+            static_context.namespaces().default_function_namespace,
+            "http://example.com/functions"
+        );
+        // the fn prefix is still available to reach the built-in functions
+        assert_eq!(
+            static_context.namespaces().by_prefix("fn"),
+            Some(Namespaces::FN_NAMESPACE)
+        );
+    }
+
+    #[test]
+    fn test_declare_namespace_override() {
+        let mut builder = StaticContextBuilder::default();
+        let uri = "http://example.com/a".to_string();
+        builder.declare_namespace("a", &uri);
+        builder.declare_namespace("a", "http://example.com/b");
+        builder.declare_namespace("", "http://example.com/default");
+        let static_context = builder.build().unwrap();
+        assert_eq!(
+            static_context.namespaces().by_prefix("a"),
+            Some("http://example.com/b")
+        );
+        assert_eq!(
+            static_context.namespaces().default_element_namespace(),
+            "http://example.com/default"
+        );
+    }
 }
@@ -0,0 +1,34 @@
+use ahash::HashMap;
+use xee_xpath_ast::Pattern;
+use xot::xmlname::OwnedName;
+
+use crate::{function, sequence};
+
+/// A rule of an accumulator: the pattern to match, and the function that
+/// calculates the new value.
+pub type AccumulatorRule = (
+    Pattern<function::InlineFunctionId>,
+    function::InlineFunctionId,
+);
+
+/// An `xsl:accumulator` declaration.
+#[derive(Debug)]
+pub struct Accumulator {
+    pub name: OwnedName,
+    // takes the context item, position and size
+    pub initial_value: function::InlineFunctionId,
+    // rules in document order, by phase. Each rule takes the context item,
+    // position and size, and the current value of the accumulator.
+    pub start_rules: Vec<AccumulatorRule>,
+    pub end_rules: Vec<AccumulatorRule>,
+}
+
+/// The values of an accumulator for each node of a tree, except attribute
+/// and namespace nodes.
+#[derive(Debug, Default)]
+pub(crate) struct AccumulatorValues {
+    // the value immediately after the start visit of a node
+    pub(crate) before: HashMap<xot::Node, sequence::Sequence>,
+    // the value immediately after the end visit of a node
+    pub(crate) after: HashMap<xot::Node, sequence::Sequence>,
+}
@@ -1,14 +1,32 @@
+use ahash::{HashSet, HashSetExt};
+use xee_xpath_ast::Pattern;
+
 use crate::{function, pattern::ModeLookup};
 
+use super::{Accumulator, Global};
+
 #[derive(Debug)]
 pub struct Declarations {
     pub mode_lookup: ModeLookup<function::InlineFunctionId>,
+    pub accumulators: Vec<Accumulator>,
+    // global variables and parameters, by global id
+    pub globals: Vec<Global>,
+    // patterns that are matched directly, by index, such as those
+    // used by xsl:for-each-group
+    pub patterns: Vec<Pattern<function::InlineFunctionId>>,
+    // template rules that are the identity template; a subtree that is
+    // entirely processed by these can be copied in one go
+    pub identity_rules: HashSet<function::InlineFunctionId>,
 }
 
 impl Declarations {
     pub(crate) fn new() -> Self {
         Self {
             mode_lookup: ModeLookup::new(),
+            accumulators: Vec::new(),
+            globals: Vec::new(),
+            patterns: Vec::new(),
+            identity_rules: HashSet::new(),
         }
     }
 }
@@ -0,0 +1,25 @@
+use xee_xpath_ast::ast;
+use xot::xmlname::OwnedName;
+
+use crate::function;
+
+/// A global `xsl:variable` or `xsl:param` declaration.
+#[derive(Debug)]
+pub struct Global {
+    /// Set if this is a stylesheet parameter.
+    pub param: Option<GlobalParam>,
+    // takes the global context item, position and size, and calculates the
+    // value, or for a parameter the default value
+    pub function_id: function::InlineFunctionId,
+}
+
+/// A stylesheet parameter. A value supplied for it as a variable of the
+/// dynamic context is used instead of its default value.
+#[derive(Debug)]
+pub struct GlobalParam {
+    pub name: OwnedName,
+    /// A value has to be supplied.
+    pub required: bool,
+    /// The type a supplied value is converted to.
+    pub sequence_type: Option<ast::SequenceType>,
+}
@@ -1,7 +1,12 @@
 /// XSLT has a number of things that can be declared globally, such
 /// as global variables, parameters, functions, and templates. This
 /// contains the runtime information to execute XSLT.
+mod accumulator;
 mod decl;
+mod global;
 mod globalvar;
 
+pub(crate) use accumulator::AccumulatorValues;
+pub use accumulator::{Accumulator, AccumulatorRule};
 pub use decl::Declarations;
+pub use global::{Global, GlobalParam};
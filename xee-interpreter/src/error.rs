@@ -1,11 +1,17 @@
+use std::sync::Arc;
+
 use ibig::error::OutOfBoundsError;
 use strum::EnumMessage;
-use strum_macros::{Display, EnumMessage};
+use strum_macros::{Display, EnumDiscriminants, EnumMessage, EnumString};
+use xee_schema_type::Xs;
 use xee_xpath_ast::ParserError;
 use xot::xmlname::NameStrInfo;
 
+use crate::sequence::{Item, Sequence};
 use crate::span::SourceSpan;
 
+const ERROR_NAMESPACE: &str = "http://www.w3.org/2005/xqt-errors";
+
 /// An error code with an optional source span.
 ///
 /// Also known as `SpannedError` internally.
@@ -25,14 +31,26 @@ pub struct SpannedError {
 /// Xee extends them with a few additional error codes.
 ///
 /// Also known as `Error` internally.
-#[derive(Debug, Clone, PartialEq, Display, EnumMessage)]
+///
+/// The plain error codes, without any associated data, are available as
+/// [`ErrorCode`].
+#[derive(Debug, Clone, PartialEq, Display, EnumMessage, EnumDiscriminants)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[strum_discriminants(name(ErrorCode), derive(Hash, Display, EnumString))]
 pub enum Error {
     /// Stack overflow.
     ///
-    /// Internal stack overflow.
+    /// Too many nested function calls. This is usually caused by a recursive
+    /// function that doesn't terminate. The maximum depth can be configured
+    /// on the dynamic context.
     StackOverflow,
 
+    /// Evaluation cancelled.
+    ///
+    /// Evaluation was stopped because it was cancelled or because its
+    /// deadline passed.
+    Cancelled,
+
     /// Unsupported XPath feature.
     ///
     /// This XPath feature is not supported by Xee.
@@ -43,6 +61,18 @@ pub enum Error {
     /// The query was created with a different queries collection.
     UsedQueryWithWrongQueries,
 
+    /// Internal error.
+    ///
+    /// Xee ended up in a state that should not be possible. This is a bug in
+    /// Xee.
+    Internal,
+
+    /// I/O error.
+    ///
+    /// Writing serialized output, or reading input, failed. The underlying
+    /// [`std::io::Error`] is the source of this error.
+    Io(IoError),
+
     // XPath error conditions: https://www.w3.org/TR/xpath-31/#id-errors
     /// Component absent in static context.
     ///  
@@ -239,12 +269,14 @@ pub enum Error {
     /// Unsupported normalization form.
     ///
     /// Raised by fn:normalize-unicode if the requested normalization form is
-    /// not supported by the implementation.
+    /// not supported by the implementation. Xee supports NFC, NFD, NFKC and
+    /// NFKD, but not FULLY-NORMALIZED.
     FOCH0003,
     /// Collation does not support collation units.
     ///
     /// Raised by functions such as fn:contains if the requested collation does
-    /// not operate on a character-by-character basis.
+    /// not operate on a character-by-character basis, and by
+    /// fn:collation-key if the collation cannot produce collation keys.
     FOCH0004,
     /// No context document.
     ///
@@ -556,11 +588,198 @@ pub enum Error {
     ///
     /// Shallow copy of sequence of more than one item is not allowed.
     XTTE3180,
+    /// Attribute after children
+    ///
+    /// It is a type error if the result sequence used to construct the
+    /// content of an element node contains a namespace node or attribute
+    /// node that is preceded in the sequence by a node that is neither a
+    /// namespace node nor an attribute node.
+    XTDE0410,
     /// Function item in complex content
     ///
     /// The result sequence to be added as content cannot contain a function
     /// item.
     XTDE0450,
+    /// Invalid attribute value
+    ///
+    /// It is a dynamic error if the effective value of an attribute written
+    /// using curly brackets, in a position where an attribute value template
+    /// is permitted, is a value that is not one of the permitted values for
+    /// that attribute.
+    XTDE0030,
+    /// Number value with other attributes
+    ///
+    /// It is a static error if the value attribute of xsl:number is present
+    /// unless the select, level, count, and from attributes are all absent.
+    XTSE0975,
+    /// Invalid number value
+    ///
+    /// It is a dynamic error if any undiscarded item in the atomized sequence
+    /// supplied as the value of the value attribute of xsl:number cannot be
+    /// converted to an integer, or if the resulting integer is less than 0
+    /// (zero).
+    XTDE0980,
+    /// Number select not a node
+    ///
+    /// It is a type error if the result of evaluating the select attribute of
+    /// the xsl:number instruction is anything other than a single node.
+    XTTE0990,
+    /// Number without context node
+    ///
+    /// It is a type error if the xsl:number instruction is evaluated, with no
+    /// value or select attribute, when the context item is not a node.
+    XTTE1000,
+    /// Grouping attributes
+    ///
+    /// These four attributes of xsl:for-each-group are mutually exclusive:
+    /// it is a static error if none of them is present or if more than one
+    /// of them is present: group-by, group-adjacent, group-starting-with,
+    /// group-ending-with.
+    XTSE1080,
+    /// Grouping key not a single atomic value
+    ///
+    /// It is a type error if the grouping key evaluated using the
+    /// group-adjacent attribute is an empty sequence, or a sequence containing
+    /// more than one item, unless composite="yes" is specified.
+    XTTE1100,
+    /// Group population contains non-node
+    ///
+    /// When the group-starting-with or group-ending-with attribute is used,
+    /// it is a type error if the result of evaluating the select expression
+    /// contains an item that is not a node.
+    XTTE1120,
+    /// Unknown initial template
+    ///
+    /// It is a dynamic error if the invocation of the stylesheet specifies a
+    /// template name that does not match the expanded QName of a named
+    /// template defined in the stylesheet.
+    XTDE0040,
+    /// Unknown initial mode
+    ///
+    /// It is a dynamic error if the invocation of the stylesheet specifies an
+    /// initial mode that is not a mode of the stylesheet.
+    XTDE0045,
+    /// Missing stylesheet parameter
+    ///
+    /// It is a dynamic error if a stylesheet declares a visible stylesheet
+    /// parameter that is explicitly or implicitly mandatory, and no value for
+    /// this parameter is supplied when the stylesheet is primed.
+    XTDE0050,
+    /// Unknown named template
+    ///
+    /// It is a static error if a package contains an xsl:call-template
+    /// instruction whose name attribute does not match the name attribute of
+    /// any named template visible in the containing package.
+    XTSE0650,
+    /// Unknown template parameter
+    ///
+    /// In the case of xsl:call-template, it is a static error to pass a
+    /// non-tunnel parameter named x to a template that does not have a
+    /// non-tunnel template parameter named x.
+    XTSE0680,
+    /// Missing template parameter
+    ///
+    /// It is a static error if a template that is invoked using
+    /// xsl:call-template declares a template parameter specifying
+    /// required="yes" and not specifying tunnel="yes", if no value for this
+    /// parameter is supplied by the calling instruction.
+    XTSE0690,
+    /// Missing initial template parameter
+    ///
+    /// It is a dynamic error if a template that has an explicitly mandatory
+    /// or implicitly mandatory parameter is invoked without supplying a value
+    /// for that parameter.
+    XTDE0700,
+    /// Variable type mismatch
+    ///
+    /// It is a type error if the supplied value of a variable cannot be
+    /// converted to the required type.
+    XTTE0570,
+    /// Parameter type mismatch
+    ///
+    /// It is a type error if the conversion of the supplied value of a
+    /// parameter to its required type fails.
+    XTTE0590,
+    /// No matching template rule
+    ///
+    /// It is a dynamic error if xsl:apply-templates is used to process a
+    /// node using a mode whose declaration specifies on-no-match="fail"
+    /// when there is no template rule in the stylesheet whose match pattern
+    /// matches that node.
+    XTDE0555,
+    /// Unknown accumulator
+    ///
+    /// It is a dynamic error if the value of the first argument to the
+    /// accumulator-before or accumulator-after function is not a valid
+    /// EQName, or if there is no namespace declaration in scope for the
+    /// prefix of the QName, or if the name obtained by expanding the QName
+    /// is not the same as the expanded name of any xsl:accumulator
+    /// declaration.
+    XTDE3340,
+    /// Accumulator without context item
+    ///
+    /// It is a dynamic error to call the accumulator-before or
+    /// accumulator-after function when there is no context item.
+    XTDE3350,
+    /// Accumulator context item not a node
+    ///
+    /// It is a type error to call the accumulator-before or
+    /// accumulator-after function when the context item is not a node, or
+    /// when it is an attribute or namespace node.
+    XTTE3360,
+    /// Cyclic accumulators
+    ///
+    /// It is an error if there is a cyclic set of dependencies among
+    /// accumulators such that the (pre- or post-descent) value of an
+    /// accumulator depends directly or indirectly on itself.
+    XTDE3400,
+    /// Invalid system property name
+    ///
+    /// It is a dynamic error if the argument supplied to the system-property
+    /// function is not a valid EQName, or if there is no namespace
+    /// declaration in scope for the prefix of the QName.
+    XTDE1390,
+    /// Conflicting decimal format declarations
+    ///
+    /// It is a static error if a named or unnamed decimal format contains
+    /// two conflicting values for the same attribute in different
+    /// xsl:decimal-format declarations having the same import precedence,
+    /// unless there is another definition of the same attribute with higher
+    /// import precedence.
+    XTSE1290,
+    /// Invalid zero digit
+    ///
+    /// It is a static error if the character specified in the zero-digit
+    /// attribute is not a digit or is a digit that does not have the numeric
+    /// value zero.
+    XTSE1295,
+    /// Decimal format characters not distinct
+    ///
+    /// It is a static error if, for any named or unnamed decimal format, the
+    /// properties representing characters used in a picture string do not
+    /// have distinct values. These properties are decimal-separator,
+    /// grouping-separator, exponent-separator, percent, per-mille, the family
+    /// of ten decimal digits starting with zero-digit, digit, and
+    /// pattern-separator.
+    XTSE1300,
+    /// Duplicate character map
+    ///
+    /// It is a static error if the stylesheet contains two or more character
+    /// maps with the same name and the same import precedence, unless it
+    /// also contains another character map with the same name and higher
+    /// import precedence.
+    XTSE1580,
+    /// Unknown character map
+    ///
+    /// It is a static error if a name in the use-character-maps attribute of
+    /// the xsl:output or xsl:character-map declarations does not match the
+    /// name attribute of any xsl:character-map in the stylesheet.
+    XTSE1590,
+    /// Circular character map
+    ///
+    /// It is a static error if a character map references itself, directly
+    /// or indirectly, via a name in the use-character-maps attribute.
+    XTSE1600,
 
     /// Function cannot be normalized for serialization.
     ///
@@ -714,19 +933,110 @@ pub enum Error {
     Application(Box<ApplicationError>),
 }
 
+/// An [`std::io::Error`] kept in an [`Error::Io`].
+///
+/// I/O errors can't be cloned or compared, so they're shared. Two of them
+/// are equal if they're of the same kind.
+#[derive(Debug, Clone)]
+pub struct IoError(Arc<std::io::Error>);
+
+impl IoError {
+    /// The underlying I/O error.
+    pub fn io_error(&self) -> &std::io::Error {
+        &self.0
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.kind() == other.0.kind()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for IoError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+/// An error raised by `fn:error`.
+///
+/// Besides the error code and description this carries the error object,
+/// the sequence passed as the third argument to `fn:error`, so that host
+/// code can inspect it.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ApplicationError {
     qname: xot::xmlname::OwnedName,
     description: String,
-    // FIXME: error object is not supported right now
-    // it would require storing an arbitrary sequence in here,
-    // but that's not really supported by this simple error.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    error_object: Vec<ErrorObjectItem>,
 }
 
 impl ApplicationError {
+    /// Create a new application error with an empty error object.
     pub fn new(qname: xot::xmlname::OwnedName, description: String) -> Self {
-        Self { qname, description }
+        Self {
+            qname,
+            description,
+            error_object: Vec::new(),
+        }
+    }
+
+    /// Set the error object.
+    pub fn with_error_object(mut self, error_object: &Sequence) -> Self {
+        self.error_object = error_object.iter().map(ErrorObjectItem::from).collect();
+        self
+    }
+
+    /// The error code.
+    pub fn qname(&self) -> &xot::xmlname::OwnedName {
+        &self.qname
+    }
+
+    /// The description of the error.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The items of the error object. This is empty if no error object was
+    /// given.
+    pub fn error_object(&self) -> &[ErrorObjectItem] {
+        &self.error_object
+    }
+}
+
+/// An item in the error object of an [`ApplicationError`].
+///
+/// Errors can be sent between threads but sequences cannot, so the error
+/// object is kept in this detached form.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorObjectItem {
+    /// An atomic value, as its string value and its type.
+    Atomic {
+        /// The string value
+        value: String,
+        /// The type of the atomic value, such as `xs:integer`
+        schema_type: Xs,
+    },
+    /// A node.
+    Node(xot::Node),
+    /// A function item, which includes maps and arrays. These cannot be
+    /// kept in an error.
+    Function,
+}
+
+impl From<Item> for ErrorObjectItem {
+    fn from(item: Item) -> Self {
+        match item {
+            Item::Atomic(atomic) => ErrorObjectItem::Atomic {
+                value: atomic.string_value(),
+                schema_type: atomic.schema_type(),
+            },
+            Item::Node(node) => ErrorObjectItem::Node(node),
+            Item::Function(_) => ErrorObjectItem::Function,
+        }
     }
 }
 
@@ -750,14 +1060,37 @@ impl Error {
         }
     }
 
+    /// The error code, so you can match on it.
+    ///
+    /// Application errors raised by `fn:error` usually have a code that's not
+    /// known to Xee; this returns `None` for those. Use [`Error::code_qname`]
+    /// to get their code. An application error with a standard code, such as
+    /// the default `err:FOER0000`, returns that code.
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        match self {
+            Error::Application(application_error) => {
+                if application_error.qname.namespace() == ERROR_NAMESPACE {
+                    application_error.qname.local_name().parse().ok()
+                } else {
+                    None
+                }
+            }
+            _ => Some(self.into()),
+        }
+    }
+
     pub fn code_qname(&self) -> xot::xmlname::OwnedName {
         match self {
             Error::Application(application_error) => application_error.qname.clone(),
-            _ => xot::xmlname::OwnedName::new(
-                self.code(),
-                "http://www.w3.org/2005/xqt-errors".to_string(),
-                "".to_string(),
-            ),
+            _ => ErrorCode::from(self).qname(),
+        }
+    }
+
+    /// The application error raised by `fn:error`, if this is one.
+    pub fn application_error(&self) -> Option<&ApplicationError> {
+        match self {
+            Error::Application(application_error) => Some(application_error),
+            _ => None,
         }
     }
 
@@ -780,7 +1113,14 @@ impl Error {
         }
     }
 }
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(io_error) => Some(io_error.io_error()),
+            _ => None,
+        }
+    }
+}
 
 impl std::fmt::Display for SpannedError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -793,7 +1133,11 @@ impl std::fmt::Display for SpannedError {
     }
 }
 
-impl std::error::Error for SpannedError {}
+impl std::error::Error for SpannedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        std::error::Error::source(&self.error)
+    }
+}
 
 // note: this is only used for internal conversions of names
 // for now, not the full grammar.
@@ -843,12 +1187,20 @@ impl From<xot::Error> for Error {
     fn from(e: xot::Error) -> Self {
         match e {
             xot::Error::MissingPrefix(_) => Error::XPST0081,
+            // xot only keeps the message of an I/O error
+            xot::Error::Io(message) => std::io::Error::other(message).into(),
             // TODO: are there other xot errors that need to be translated?
             _ => Error::XPST0003,
         }
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(IoError(Arc::new(e)))
+    }
+}
+
 impl From<Error> for SpannedError {
     fn from(e: Error) -> Self {
         SpannedError {
@@ -885,4 +1237,40 @@ impl SpannedError {
     pub fn value(self) -> Error {
         self.error
     }
+
+    /// The error code, so you can match on it.
+    ///
+    /// See [`Error::error_code`].
+    pub fn code(&self) -> Option<ErrorCode> {
+        self.error.error_code()
+    }
+
+    /// The error code as a qualified name, like `err:FORG0001`.
+    ///
+    /// This also works for application errors raised by `fn:error`.
+    pub fn code_qname(&self) -> xot::xmlname::OwnedName {
+        self.error.code_qname()
+    }
+
+    /// The application error raised by `fn:error`, with its description
+    /// and error object.
+    ///
+    /// See [`Error::application_error`].
+    pub fn application_error(&self) -> Option<&ApplicationError> {
+        self.error.application_error()
+    }
+}
+
+impl ErrorCode {
+    /// The error code as a qualified name, like `err:FORG0001`.
+    ///
+    /// Error codes are in the `http://www.w3.org/2005/xqt-errors` namespace,
+    /// conventionally bound to the `err` prefix.
+    pub fn qname(&self) -> xot::xmlname::OwnedName {
+        xot::xmlname::OwnedName::new(
+            self.to_string(),
+            ERROR_NAMESPACE.to_string(),
+            "err".to_string(),
+        )
+    }
 }
@@ -2,7 +2,7 @@ use std::rc::Rc;
 
 use xot::Xot;
 
-use crate::{context, error, sequence, string};
+use crate::{atomic, context, error, sequence};
 
 /// An XPath Array
 ///
@@ -109,15 +109,14 @@ impl Array {
     pub(crate) fn deep_equal(
         &self,
         other: Array,
-        collation: &string::Collation,
-        default_offset: chrono::FixedOffset,
-        xot: &Xot,
+        atomic_equal: &dyn Fn(&atomic::Atomic, &atomic::Atomic) -> bool,
+        node_equal: &dyn Fn(xot::Node, xot::Node) -> bool,
     ) -> error::Result<bool> {
         if self.0.len() != other.0.len() {
             return Ok(false);
         }
         for (a, b) in self.0.iter().zip(other.0.iter()) {
-            if !a.deep_equal(b, collation, default_offset, xot)? {
+            if !a.deep_equal_by(b, atomic_equal, node_equal)? {
                 return Ok(false);
             }
         }
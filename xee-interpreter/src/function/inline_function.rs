@@ -1,4 +1,5 @@
 use xee_schema_type::Xs;
+use xee_xpath_ast::ast;
 use xee_xpath_type::ast::SequenceType;
 
 use crate::sequence;
@@ -13,7 +14,7 @@ pub struct CastType {
     pub empty_sequence_allowed: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Name(pub(crate) String);
 
 impl Name {
@@ -31,6 +32,8 @@ pub struct InlineFunction {
     pub steps: Vec<xml::Step>,
     pub cast_types: Vec<CastType>,
     pub sequence_types: Vec<SequenceType>,
+    // for each try/catch, the name tests of each of its catch clauses
+    pub try_catches: Vec<Vec<Vec<ast::NameTest>>>,
     pub closure_names: Vec<Name>,
     // the compiled code, and the spans of each instruction
     pub chunk: Vec<u8>,
@@ -5,7 +5,7 @@ use xee_schema_type::Xs;
 use xee_xpath_ast::ast;
 use xot::Xot;
 
-use crate::{atomic, context, error, sequence, string};
+use crate::{atomic, context, error, sequence};
 
 /// An XPath Map (a collection of key-value pairs).
 #[derive(Debug, Clone, PartialEq)]
@@ -156,26 +156,17 @@ impl Map {
     pub(crate) fn deep_equal(
         &self,
         other: &Map,
-        collation: &string::Collation,
-        default_offset: chrono::FixedOffset,
-        xot: &Xot,
+        atomic_equal: &dyn Fn(&atomic::Atomic, &atomic::Atomic) -> bool,
+        node_equal: &dyn Fn(xot::Node, xot::Node) -> bool,
     ) -> error::Result<bool> {
         match (self, other) {
             (Map::Empty(_), Map::Empty(_)) => Ok(true),
             (Map::Empty(_), _) => Ok(false),
             (_, Map::Empty(_)) => Ok(false),
-            (Map::One(map), Map::One(other)) => {
-                map.deep_equal(other, collation, default_offset, xot)
-            }
-            (Map::One(map), Map::Many(other)) => {
-                map.deep_equal(other, collation, default_offset, xot)
-            }
-            (Map::Many(map), Map::Many(other)) => {
-                map.deep_equal(other, collation, default_offset, xot)
-            }
-            (Map::Many(map), Map::One(other)) => {
-                map.deep_equal(other, collation, default_offset, xot)
-            }
+            (Map::One(map), Map::One(other)) => map.deep_equal(other, atomic_equal, node_equal),
+            (Map::One(map), Map::Many(other)) => map.deep_equal(other, atomic_equal, node_equal),
+            (Map::Many(map), Map::Many(other)) => map.deep_equal(other, atomic_equal, node_equal),
+            (Map::Many(map), Map::One(other)) => map.deep_equal(other, atomic_equal, node_equal),
         }
     }
 
@@ -312,9 +303,8 @@ pub(crate) trait Mappable {
     fn deep_equal(
         &self,
         other: &impl Mappable,
-        collation: &string::Collation,
-        default_offset: chrono::FixedOffset,
-        xot: &Xot,
+        atomic_equal: &dyn Fn(&atomic::Atomic, &atomic::Atomic) -> bool,
+        node_equal: &dyn Fn(xot::Node, xot::Node) -> bool,
     ) -> error::Result<bool> {
         if self.len() != other.len() {
             return Ok(false);
@@ -322,7 +312,7 @@ pub(crate) trait Mappable {
         for (map_key, value) in self.map_key_entries() {
             let other_value = other.get_by_map_key(map_key);
             if let Some(other_value) = other_value {
-                if !value.deep_equal(other_value, collation, default_offset, xot)? {
+                if !value.deep_equal_by(other_value, atomic_equal, node_equal)? {
                     return Ok(false);
                 }
             } else {
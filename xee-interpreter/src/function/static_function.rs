@@ -9,7 +9,7 @@ use crate::context::DynamicContext;
 use crate::error;
 use crate::function;
 use crate::interpreter;
-use crate::library::static_function_descriptions;
+use crate::library::{static_function_descriptions, xslt_static_function_descriptions};
 use crate::sequence;
 use crate::stack;
 
@@ -270,22 +270,31 @@ fn into_sequences(values: &[stack::Value]) -> error::Result<Vec<sequence::Sequen
 pub struct StaticFunctions {
     by_name: HashMap<(Name, u8), function::StaticFunctionId>,
     by_internal_name: HashMap<(Name, u8), function::StaticFunctionId>,
+    // functions that only XSLT can call by name, such as fn:system-property
+    xslt_by_name: HashMap<(Name, u8), function::StaticFunctionId>,
     by_index: Vec<StaticFunction>,
+    // the functions from this index on are the XSLT functions
+    xslt_index: usize,
 }
 
 impl StaticFunctions {
     pub(crate) fn new() -> Self {
         let mut by_name = HashMap::new();
         let mut by_internal_name = HashMap::new();
-        let descriptions = static_function_descriptions();
+        let mut xslt_by_name = HashMap::new();
         let mut by_index = Vec::new();
-        for description in descriptions {
+        for description in static_function_descriptions() {
+            by_index.extend(description.functions());
+        }
+        let xslt_index = by_index.len();
+        for description in xslt_static_function_descriptions() {
             by_index.extend(description.functions());
         }
 
         for (i, static_function) in by_index.iter().enumerate() {
             let map = match static_function.function_rule {
                 Some(FunctionRule::AnonymousClosure) => &mut by_internal_name,
+                _ if i >= xslt_index => &mut xslt_by_name,
                 _ => &mut by_name,
             };
             map.insert(
@@ -296,7 +305,9 @@ impl StaticFunctions {
         Self {
             by_name,
             by_internal_name,
+            xslt_by_name,
             by_index,
+            xslt_index,
         }
     }
 
@@ -305,6 +316,11 @@ impl StaticFunctions {
         self.by_name.get(&(name.clone(), arity)).copied()
     }
 
+    pub fn get_xslt_by_name(&self, name: &Name, arity: u8) -> Option<function::StaticFunctionId> {
+        // TODO annoying clone
+        self.xslt_by_name.get(&(name.clone(), arity)).copied()
+    }
+
     pub fn get_by_internal_name(
         &self,
         name: &Name,
@@ -317,4 +333,23 @@ impl StaticFunctions {
     pub fn get_by_index(&self, static_function_id: function::StaticFunctionId) -> &StaticFunction {
         &self.by_index[static_function_id.0]
     }
+
+    /// The names and signatures of the functions that can be called by
+    /// name, in registration order. The XSLT functions are only included if
+    /// `xslt` is set.
+    pub(crate) fn signatures(
+        &self,
+        xslt: bool,
+    ) -> impl Iterator<Item = (&Name, &function::Signature)> {
+        let end = if xslt {
+            self.by_index.len()
+        } else {
+            self.xslt_index
+        };
+        self.by_index[..end].iter().filter_map(|static_function| {
+            static_function
+                .name()
+                .map(|name| (name, static_function.signature()))
+        })
+    }
 }
@@ -59,6 +59,7 @@ pub enum Instruction {
     Castable(u16),
     InstanceOf(u16),
     Treat(u16),
+    Convert(u16),
     Range,
     SequenceLen,
     SequenceGet,
@@ -76,8 +77,12 @@ pub enum Instruction {
     XmlProcessingInstruction,
     XmlAppend,
     CopyShallow,
+    CopyShallowNoNamespaces,
     CopyDeep,
     ApplyTemplates(u16),
+    MatchPattern(u16),
+    TryCatch(u16),
+    Global(u16),
     PrintTop,
     PrintStack,
 }
@@ -138,6 +143,7 @@ pub(crate) enum EncodedInstruction {
     Castable,
     InstanceOf,
     Treat,
+    Convert,
     Range,
     SequenceLen,
     SequenceGet,
@@ -155,8 +161,12 @@ pub(crate) enum EncodedInstruction {
     XmlProcessingInstruction,
     XmlAppend,
     ApplyTemplates,
+    MatchPattern,
+    TryCatch,
     CopyShallow,
+    CopyShallowNoNamespaces,
     CopyDeep,
+    Global,
     PrintTop,
     PrintStack,
 }
@@ -259,6 +269,10 @@ pub(crate) fn decode_instruction(bytes: &[u8]) -> (Instruction, usize) {
             let sequence_type_id = u16::from_le_bytes([bytes[1], bytes[2]]);
             (Instruction::Treat(sequence_type_id), 3)
         }
+        EncodedInstruction::Convert => {
+            let sequence_type_id = u16::from_le_bytes([bytes[1], bytes[2]]);
+            (Instruction::Convert(sequence_type_id), 3)
+        }
         EncodedInstruction::Return => (Instruction::Return, 1),
         EncodedInstruction::ReturnConvert => {
             let sequence_type_id = u16::from_le_bytes([bytes[1], bytes[2]]);
@@ -284,11 +298,24 @@ pub(crate) fn decode_instruction(bytes: &[u8]) -> (Instruction, usize) {
         EncodedInstruction::XmlProcessingInstruction => (Instruction::XmlProcessingInstruction, 1),
         EncodedInstruction::XmlAppend => (Instruction::XmlAppend, 1),
         EncodedInstruction::CopyShallow => (Instruction::CopyShallow, 1),
+        EncodedInstruction::CopyShallowNoNamespaces => (Instruction::CopyShallowNoNamespaces, 1),
         EncodedInstruction::CopyDeep => (Instruction::CopyDeep, 1),
         EncodedInstruction::ApplyTemplates => {
             let mode_id = u16::from_le_bytes([bytes[1], bytes[2]]);
             (Instruction::ApplyTemplates(mode_id), 3)
         }
+        EncodedInstruction::MatchPattern => {
+            let pattern_id = u16::from_le_bytes([bytes[1], bytes[2]]);
+            (Instruction::MatchPattern(pattern_id), 3)
+        }
+        EncodedInstruction::TryCatch => {
+            let try_catch_id = u16::from_le_bytes([bytes[1], bytes[2]]);
+            (Instruction::TryCatch(try_catch_id), 3)
+        }
+        EncodedInstruction::Global => {
+            let global_id = u16::from_le_bytes([bytes[1], bytes[2]]);
+            (Instruction::Global(global_id), 3)
+        }
         EncodedInstruction::PrintTop => (Instruction::PrintTop, 1),
         EncodedInstruction::PrintStack => (Instruction::PrintStack, 1),
     }
@@ -413,6 +440,10 @@ pub fn encode_instruction(instruction: Instruction, bytes: &mut Vec<u8>) {
             bytes.push(EncodedInstruction::Treat.to_u8().unwrap());
             bytes.extend_from_slice(&sequence_type_id.to_le_bytes());
         }
+        Instruction::Convert(sequence_type_id) => {
+            bytes.push(EncodedInstruction::Convert.to_u8().unwrap());
+            bytes.extend_from_slice(&sequence_type_id.to_le_bytes());
+        }
         Instruction::Range => bytes.push(EncodedInstruction::Range.to_u8().unwrap()),
         Instruction::SequenceLen => bytes.push(EncodedInstruction::SequenceLen.to_u8().unwrap()),
         Instruction::SequenceGet => bytes.push(EncodedInstruction::SequenceGet.to_u8().unwrap()),
@@ -436,11 +467,26 @@ pub fn encode_instruction(instruction: Instruction, bytes: &mut Vec<u8>) {
         ),
         Instruction::XmlAppend => bytes.push(EncodedInstruction::XmlAppend.to_u8().unwrap()),
         Instruction::CopyShallow => bytes.push(EncodedInstruction::CopyShallow.to_u8().unwrap()),
+        Instruction::CopyShallowNoNamespaces => {
+            bytes.push(EncodedInstruction::CopyShallowNoNamespaces.to_u8().unwrap())
+        }
         Instruction::CopyDeep => bytes.push(EncodedInstruction::CopyDeep.to_u8().unwrap()),
         Instruction::ApplyTemplates(mode_id) => {
             bytes.push(EncodedInstruction::ApplyTemplates.to_u8().unwrap());
             bytes.extend_from_slice(&mode_id.to_le_bytes());
         }
+        Instruction::MatchPattern(pattern_id) => {
+            bytes.push(EncodedInstruction::MatchPattern.to_u8().unwrap());
+            bytes.extend_from_slice(&pattern_id.to_le_bytes());
+        }
+        Instruction::TryCatch(try_catch_id) => {
+            bytes.push(EncodedInstruction::TryCatch.to_u8().unwrap());
+            bytes.extend_from_slice(&try_catch_id.to_le_bytes());
+        }
+        Instruction::Global(global_id) => {
+            bytes.push(EncodedInstruction::Global.to_u8().unwrap());
+            bytes.extend_from_slice(&global_id.to_le_bytes());
+        }
         Instruction::PrintTop => bytes.push(EncodedInstruction::PrintTop.to_u8().unwrap()),
         Instruction::PrintStack => bytes.push(EncodedInstruction::PrintStack.to_u8().unwrap()),
     }
@@ -510,6 +556,7 @@ pub fn instruction_size(instruction: &Instruction) -> usize {
         | Instruction::XmlProcessingInstruction
         | Instruction::XmlAppend
         | Instruction::CopyShallow
+        | Instruction::CopyShallowNoNamespaces
         | Instruction::CopyDeep
         | Instruction::PrintTop
         | Instruction::PrintStack => 1,
@@ -527,9 +574,13 @@ pub fn instruction_size(instruction: &Instruction) -> usize {
         | Instruction::Castable(_)
         | Instruction::InstanceOf(_)
         | Instruction::Treat(_)
+        | Instruction::Convert(_)
         | Instruction::ReturnConvert(_)
         | Instruction::JumpIfFalse(_) => 3,
-        Instruction::ApplyTemplates(_) => 3,
+        Instruction::ApplyTemplates(_)
+        | Instruction::MatchPattern(_)
+        | Instruction::TryCatch(_)
+        | Instruction::Global(_) => 3,
     }
 }
 
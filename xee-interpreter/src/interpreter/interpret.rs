@@ -1,19 +1,21 @@
 use std::cmp::Ordering;
 use std::rc::Rc;
 
+use ahash::{HashMap, HashMapExt};
 use ibig::{ibig, IBig};
 
 use xee_name::Name;
 use xee_schema_type::Xs;
 use xee_xpath_ast::ast;
-use xot::xmlname::NameStrInfo;
+use xot::xmlname::{NameStrInfo, OwnedName};
 use xot::Xot;
 
 use crate::atomic::{self, AtomicCompare};
 use crate::atomic::{
     op_add, op_div, op_idiv, op_mod, op_multiply, op_subtract, OpEq, OpGe, OpGt, OpLe, OpLt, OpNe,
 };
-use crate::context::DynamicContext;
+use crate::context::{DynamicContext, OrderingMode};
+use crate::declaration::{Accumulator, AccumulatorValues};
 use crate::function;
 use crate::pattern::PredicateMatcher;
 use crate::sequence;
@@ -29,6 +31,20 @@ use super::state::State;
 pub struct Interpreter<'a> {
     runnable: &'a Runnable<'a>,
     pub(crate) state: State<'a>,
+    // instructions left until we check for cancellation
+    until_cancellation_check: usize,
+    // the error object of the last error raised by fn:error, so a catch
+    // clause can bind it to err:value
+    error_object: Option<sequence::Sequence>,
+    // accumulator values by accumulator index and root node, calculated when
+    // first needed. None while they're being calculated.
+    accumulator_values: HashMap<(usize, xot::Node), Option<Rc<AccumulatorValues>>>,
+    // global variable and parameter values by global id, calculated when
+    // first needed. None while they're being calculated.
+    global_values: HashMap<usize, Option<sequence::Sequence>>,
+    // whether a node and everything in it is processed by identity templates
+    // in a mode, calculated when first needed
+    identity_subtrees: HashMap<(pattern::ModeId, xot::Node), bool>,
 }
 
 pub struct ContextInfo {
@@ -47,11 +63,25 @@ impl From<sequence::Item> for ContextInfo {
     }
 }
 
+/// The interpreter checks whether evaluation is cancelled once per this many
+/// instructions.
+pub const CANCELLATION_CHECK_INTERVAL: usize = 1024;
+
+// when less than this amount of Rust stack is left when we call back into the
+// interpreter, we allocate a new stack segment of the given size
+const STACK_RED_ZONE: usize = 128 * 1024;
+const STACK_GROW_SIZE: usize = 2 * 1024 * 1024;
+
 impl<'a> Interpreter<'a> {
     pub fn new(runnable: &'a Runnable<'a>, xot: &'a mut Xot) -> Self {
         Interpreter {
             runnable,
-            state: State::new(xot),
+            state: State::new(xot, runnable.dynamic_context().max_stack_depth()),
+            until_cancellation_check: CANCELLATION_CHECK_INTERVAL,
+            error_object: None,
+            accumulator_values: HashMap::new(),
+            global_values: HashMap::new(),
+            identity_subtrees: HashMap::new(),
         }
     }
 
@@ -97,6 +127,13 @@ impl<'a> Interpreter<'a> {
         // we can make this an infinite loop as all functions end
         // with the return instruction
         loop {
+            self.until_cancellation_check -= 1;
+            if self.until_cancellation_check == 0 {
+                self.until_cancellation_check = CANCELLATION_CHECK_INTERVAL;
+                if self.runnable.dynamic_context().is_cancelled() {
+                    return Err(error::Error::Cancelled);
+                }
+            }
             let instruction = self.read_instruction();
             match instruction {
                 EncodedInstruction::Add => {
@@ -184,6 +221,8 @@ impl<'a> Interpreter<'a> {
                 }
                 EncodedInstruction::CurlyArray => {
                     let sequence = self.state.pop()?;
+                    // each member of the array is an item of the sequence
+                    sequence.materialized_len()?;
                     let array: function::Array = sequence.into();
                     self.state.push(array);
                 }
@@ -307,37 +346,31 @@ impl<'a> Interpreter<'a> {
                 EncodedInstruction::Union => {
                     let b = self.state.pop()?;
                     let a = self.state.pop()?;
-                    let combined = a.union(
-                        b,
-                        self.runnable
-                            .documents()
-                            .borrow()
-                            .document_order_access(self.xot()),
-                    )?;
+                    let combined = {
+                        let documents = self.runnable.documents();
+                        let documents = documents.borrow();
+                        a.union(b, self.document_order_access(&documents))?
+                    };
                     self.state.push(combined);
                 }
                 EncodedInstruction::Intersect => {
                     let b = self.state.pop()?;
                     let a = self.state.pop()?;
-                    let combined = a.intersect(
-                        b,
-                        self.runnable
-                            .documents()
-                            .borrow()
-                            .document_order_access(self.xot()),
-                    )?;
+                    let combined = {
+                        let documents = self.runnable.documents();
+                        let documents = documents.borrow();
+                        a.intersect(b, self.document_order_access(&documents))?
+                    };
                     self.state.push(combined);
                 }
                 EncodedInstruction::Except => {
                     let b = self.state.pop()?;
                     let a = self.state.pop()?;
-                    let combined = a.except(
-                        b,
-                        self.runnable
-                            .documents()
-                            .borrow()
-                            .document_order_access(self.xot()),
-                    )?;
+                    let combined = {
+                        let documents = self.runnable.documents();
+                        let documents = documents.borrow();
+                        a.except(b, self.document_order_access(&documents))?
+                    };
                     self.state.push(combined);
                 }
                 EncodedInstruction::Dup => {
@@ -367,12 +400,11 @@ impl<'a> Interpreter<'a> {
                 }
                 EncodedInstruction::Deduplicate => {
                     let value = self.state.pop()?;
-                    let value = value.deduplicate(
-                        self.runnable
-                            .documents()
-                            .borrow()
-                            .document_order_access(self.xot()),
-                    )?;
+                    let value = {
+                        let documents = self.runnable.documents();
+                        let documents = documents.borrow();
+                        value.deduplicate(self.document_order_access(&documents))?
+                    };
                     self.state.push(value);
                 }
                 EncodedInstruction::Return => {
@@ -458,6 +490,24 @@ impl<'a> Interpreter<'a> {
                         Err(error::Error::XPDY0050)?;
                     }
                 }
+                EncodedInstruction::Convert => {
+                    let sequence_type_id = self.read_u16();
+                    let sequence = self.state.pop()?;
+                    let sequence_type =
+                        &(self.current_inline_function().sequence_types[sequence_type_id as usize]);
+                    let sequence = sequence
+                        .sequence_type_matching_function_conversion(
+                            sequence_type,
+                            self.runnable.static_context(),
+                            self.state.xot(),
+                            &|function| self.runnable.function_info(function).signature(),
+                        )
+                        .map_err(|error| match error {
+                            error::Error::XPTY0004 => error::Error::XTTE0570,
+                            error => error,
+                        })?;
+                    self.state.push(sequence);
+                }
                 EncodedInstruction::Range => {
                     let b = self.state.pop()?;
                     let a = self.state.pop()?;
@@ -592,7 +642,8 @@ impl<'a> Interpreter<'a> {
                     let item = sequence::Item::Node(parent_node);
                     self.state.push(item);
                 }
-                EncodedInstruction::CopyShallow => {
+                EncodedInstruction::CopyShallow | EncodedInstruction::CopyShallowNoNamespaces => {
+                    let copy_namespaces = instruction == EncodedInstruction::CopyShallow;
                     let value = &self.state.pop()?;
                     if value.is_empty() {
                         self.state.push(sequence::Sequence::default());
@@ -605,7 +656,7 @@ impl<'a> Interpreter<'a> {
                     let copy = match &item {
                         sequence::Item::Atomic(_) | sequence::Item::Function(_) => item.clone(),
                         sequence::Item::Node(node) => {
-                            let copied_node = self.shallow_copy_node(*node);
+                            let copied_node = self.shallow_copy_node(*node, copy_namespaces);
                             sequence::Item::Node(copied_node)
                         }
                     };
@@ -617,7 +668,7 @@ impl<'a> Interpreter<'a> {
                         self.state.push(sequence::Sequence::default());
                         continue;
                     }
-                    let mut new_sequence = Vec::with_capacity(value.len());
+                    let mut new_sequence = Vec::with_capacity(value.materialized_len()?);
                     for item in value.iter() {
                         let copy = match &item {
                             sequence::Item::Atomic(_) | sequence::Item::Function(_) => item.clone(),
@@ -637,6 +688,23 @@ impl<'a> Interpreter<'a> {
                     let value = self.apply_templates_sequence(mode, value)?;
                     self.state.push(value);
                 }
+                EncodedInstruction::MatchPattern => {
+                    let value = self.state.pop()?;
+                    let pattern_id = self.read_u16();
+                    let item = value.one()?;
+                    let matches = self.match_pattern(pattern_id as usize, &item);
+                    self.state.push(atomic::Atomic::from(matches));
+                }
+                EncodedInstruction::TryCatch => {
+                    let try_catch_id = self.read_u16();
+                    let value = self.try_catch(try_catch_id as usize)?;
+                    self.state.push(value);
+                }
+                EncodedInstruction::Global => {
+                    let global_id = self.read_u16();
+                    let value = self.global_value(global_id as usize)?;
+                    self.state.push(value);
+                }
                 EncodedInstruction::PrintTop => {
                     let top = self.state.top()?;
                     println!("{:#?}", top);
@@ -717,20 +785,33 @@ impl<'a> Interpreter<'a> {
         &mut self,
         function: &function::Function,
         arguments: &[sequence::Sequence],
+    ) -> error::Result<sequence::Sequence> {
+        self.call_function_with_values(function, arguments.iter().map(|arg| arg.clone().into()))
+    }
+
+    // like call_function_with_arguments, but an argument can be absent
+    fn call_function_with_values(
+        &mut self,
+        function: &function::Function,
+        arguments: impl ExactSizeIterator<Item = stack::Value>,
     ) -> error::Result<sequence::Sequence> {
         // put function onto the stack
         let item: sequence::Item = function.clone().into();
         self.state.push(item);
         // then arguments
         let arity = arguments.len() as u8;
-        for arg in arguments.iter() {
-            self.state.push(arg.clone());
+        for arg in arguments {
+            self.state.push_value(arg);
         }
         self.call_function(function, arity)?;
         if matches!(function, function::Function::Inline(_)) {
             // run interpreter until we return to the base
-            // we started in
-            self.run_actual(self.state.frame().base())?;
+            // we started in. Functions like fn:for-each call back into the
+            // interpreter this way, so recursion through them recurses on
+            // the Rust stack too; we grow it when needed so that we run
+            // into the maximum stack depth instead of a crash.
+            let base = self.state.frame().base();
+            stacker::maybe_grow(STACK_RED_ZONE, STACK_GROW_SIZE, || self.run_actual(base))?;
         }
         self.state.pop()
     }
@@ -860,6 +941,99 @@ impl<'a> Interpreter<'a> {
         sequence.cloned().ok_or(error::Error::FOAY0001)
     }
 
+    pub(crate) fn set_error_object(&mut self, error_object: sequence::Sequence) {
+        self.error_object = Some(error_object);
+    }
+
+    fn try_catch(&mut self, try_catch_id: usize) -> error::Result<sequence::Sequence> {
+        let catch_name_tests = self.current_inline_function().try_catches[try_catch_id].clone();
+        // the function for each catch clause is on the stack, after the try
+        // function
+        let mut catch_functions = Vec::with_capacity(catch_name_tests.len());
+        for _ in 0..catch_name_tests.len() {
+            let catch_function: function::Function = self.state.pop()?.try_into()?;
+            catch_functions.push(catch_function);
+        }
+        catch_functions.reverse();
+        let try_function: function::Function = self.state.pop()?.try_into()?;
+
+        let checkpoint = self.state.checkpoint();
+        let error = match self.call_function_with_arguments(&try_function, &[]) {
+            Ok(value) => return Ok(value),
+            // cancellation is not an error that the expression can handle
+            Err(error::Error::Cancelled) => return Err(error::Error::Cancelled),
+            Err(error) => error,
+        };
+        // unwind whatever the try function left behind when it failed
+        self.state.restore(checkpoint);
+
+        let code = error.code_qname();
+        for (name_tests, catch_function) in catch_name_tests.iter().zip(catch_functions) {
+            if name_tests
+                .iter()
+                .any(|name_test| name_test_matches(name_test, &code))
+            {
+                let arguments = self.catch_arguments(&error, code);
+                return self.call_function_with_arguments(&catch_function, &arguments);
+            }
+        }
+        Err(error)
+    }
+
+    // the values of err:code, err:description, err:value, err:module,
+    // err:line-number, err:column-number and err:additional
+    fn catch_arguments(
+        &mut self,
+        error: &error::Error,
+        code: xot::xmlname::OwnedName,
+    ) -> Vec<sequence::Sequence> {
+        let (description, value) = if let Some(application_error) = error.application_error() {
+            (
+                application_error.description().to_string(),
+                self.error_value(application_error),
+            )
+        } else {
+            (error.message().to_string(), sequence::Sequence::default())
+        };
+        vec![
+            atomic::Atomic::from(code).into(),
+            atomic::Atomic::from(description).into(),
+            value,
+            // we don't track the module, line and column in which the
+            // error occurred, and have no additional information
+            sequence::Sequence::default(),
+            sequence::Sequence::default(),
+            sequence::Sequence::default(),
+            sequence::Sequence::default(),
+        ]
+    }
+
+    // the error object of an application error. If it was raised by fn:error
+    // in this interpreter we have the original; otherwise, for instance when
+    // it comes from fn:transform, we rebuild it from its detached items.
+    // Function items can't be rebuilt.
+    fn error_value(&mut self, application_error: &error::ApplicationError) -> sequence::Sequence {
+        if let Some(error_object) = self.error_object.take() {
+            return error_object;
+        }
+        let static_context = self.runnable.static_context();
+        application_error
+            .error_object()
+            .iter()
+            .filter_map(|item| match item {
+                error::ErrorObjectItem::Atomic { value, schema_type } => {
+                    atomic::Atomic::from(value.as_str())
+                        .cast_to_schema_type(*schema_type, static_context)
+                        .ok()
+                        .map(sequence::Item::from)
+                }
+                error::ErrorObjectItem::Node(node) => Some(sequence::Item::Node(*node)),
+                error::ErrorObjectItem::Function => None,
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
     fn call_map(&mut self, map: &function::Map, arity: usize) -> error::Result<()> {
         if arity != 1 {
             return Err(error::Error::XPTY0004);
@@ -1108,6 +1282,17 @@ impl<'a> Interpreter<'a> {
         self.state.xot_mut()
     }
 
+    // access to document order, unless we don't need to sort nodes
+    fn document_order_access<'b>(
+        &'b self,
+        documents: &'b xml::Documents,
+    ) -> Option<xml::DocumentOrderAccess<'b>> {
+        match self.runnable.static_context().ordering_mode() {
+            OrderingMode::Ordered => Some(documents.document_order_access(self.xot())),
+            OrderingMode::Unordered => None,
+        }
+    }
+
     fn xml_append(
         &mut self,
         parent_node: xot::Node,
@@ -1167,14 +1352,28 @@ impl<'a> Interpreter<'a> {
         self.state.xot.append(parent_node, text_node).unwrap();
     }
 
-    fn shallow_copy_node(&mut self, node: xot::Node) -> xot::Node {
+    fn shallow_copy_node(&mut self, node: xot::Node, copy_namespaces: bool) -> xot::Node {
         let xot = &mut self.state.xot;
         let value = xot.value(node);
         match value {
             // root and element are shallow copies
             xot::Value::Document => xot.new_document(),
-            // TODO: work on copying prefixes
-            xot::Value::Element(element) => xot.new_element(element.name()),
+            xot::Value::Element(element) => {
+                let copy = xot.new_element(element.name());
+                if !copy_namespaces {
+                    return copy;
+                }
+                // copy the namespace declarations along with the element
+                let namespaces = xot
+                    .namespaces(node)
+                    .iter()
+                    .map(|(prefix, namespace)| (prefix, *namespace))
+                    .collect::<Vec<_>>();
+                for (prefix, namespace) in namespaces {
+                    xot.namespaces_mut(copy).insert(prefix, namespace);
+                }
+                copy
+            }
             // we can clone (deep-copy) these nodes as it's the same
             // operation as shallow copy
             _ => xot.clone_node(node),
@@ -1209,6 +1408,19 @@ impl<'a> Interpreter<'a> {
     ) -> error::Result<Option<sequence::Sequence>> {
         let function_id = self.lookup_pattern(mode, &item);
 
+        if let sequence::Item::Node(node) = item {
+            // the identity template would copy the node and apply templates
+            // to its content. If that content is processed by identity
+            // templates too, we can copy it all at once.
+            if self.is_identity_match(mode, function_id)
+                && !self.xot().is_document(node)
+                && self.has_identity_content(mode, node)
+            {
+                let copied_node = self.state.xot.clone_node(node);
+                return Ok(Some(sequence::Item::Node(copied_node).into()));
+            }
+        }
+
         if let Some(function_id) = function_id {
             let position: IBig = (position + 1).into();
             let arguments: Vec<sequence::Sequence> = vec![
@@ -1220,10 +1432,186 @@ impl<'a> Interpreter<'a> {
             self.call_function_with_arguments(&function, &arguments)
                 .map(Some)
         } else {
-            Ok(None)
+            self.apply_built_in_rule(mode, item)
         }
     }
 
+    // the built-in template rule of the mode, used when no template rule
+    // matches the item
+    fn apply_built_in_rule(
+        &mut self,
+        mode: pattern::ModeId,
+        item: sequence::Item,
+    ) -> error::Result<Option<sequence::Sequence>> {
+        use pattern::OnNoMatch;
+
+        let on_no_match = self
+            .runnable
+            .program()
+            .declarations
+            .mode_lookup
+            .on_no_match(mode);
+        if on_no_match == OnNoMatch::Fail {
+            return Err(error::Error::XTDE0555);
+        }
+        let node = match item {
+            sequence::Item::Node(node) => node,
+            sequence::Item::Atomic(atomic) => {
+                return Ok(match on_no_match {
+                    OnNoMatch::DeepCopy | OnNoMatch::ShallowCopy => Some(atomic.into()),
+                    OnNoMatch::TextOnlyCopy => {
+                        let text = self.state.xot.new_text(&atomic.string_value());
+                        Some(sequence::Item::Node(text).into())
+                    }
+                    _ => None,
+                })
+            }
+            sequence::Item::Function(_) => {
+                return Ok(match on_no_match {
+                    OnNoMatch::DeepCopy | OnNoMatch::ShallowCopy => Some(item.into()),
+                    _ => None,
+                })
+            }
+        };
+        let value_type = self.xot().value_type(node);
+        let is_container = matches!(
+            value_type,
+            xot::ValueType::Document | xot::ValueType::Element
+        );
+        Ok(match on_no_match {
+            OnNoMatch::DeepCopy => {
+                Some(sequence::Item::Node(self.state.xot.clone_node(node)).into())
+            }
+            OnNoMatch::ShallowCopy => {
+                let copy = self.shallow_copy_node(node, true);
+                if is_container {
+                    let content = self.apply_templates_content(mode, node, true)?;
+                    self.xml_append(copy, content)?;
+                }
+                Some(sequence::Item::Node(copy).into())
+            }
+            OnNoMatch::DeepSkip => {
+                if value_type == xot::ValueType::Document {
+                    Some(self.apply_templates_content(mode, node, false)?)
+                } else {
+                    None
+                }
+            }
+            OnNoMatch::ShallowSkip => {
+                if is_container {
+                    Some(self.apply_templates_content(mode, node, true)?)
+                } else {
+                    None
+                }
+            }
+            OnNoMatch::TextOnlyCopy => match value_type {
+                xot::ValueType::Document | xot::ValueType::Element => {
+                    Some(self.apply_templates_content(mode, node, false)?)
+                }
+                xot::ValueType::Text | xot::ValueType::Attribute => {
+                    let text = self.xot().string_value(node);
+                    let text = self.state.xot.new_text(&text);
+                    Some(sequence::Item::Node(text).into())
+                }
+                _ => None,
+            },
+            OnNoMatch::Fail => unreachable!(),
+        })
+    }
+
+    // apply templates to the children of a node, and to its attributes as
+    // well if requested
+    fn apply_templates_content(
+        &mut self,
+        mode: pattern::ModeId,
+        node: xot::Node,
+        attributes: bool,
+    ) -> error::Result<sequence::Sequence> {
+        let xot = self.xot();
+        let content = if attributes {
+            xot.attribute_nodes(node)
+                .chain(xot.children(node))
+                .map(sequence::Item::Node)
+                .collect::<Vec<_>>()
+        } else {
+            xot.children(node).map(sequence::Item::Node).collect()
+        };
+        self.apply_templates_sequence(mode, content.into())
+    }
+
+    // the item is processed by the identity template, or by the built-in
+    // shallow-copy rule, which does the same
+    fn is_identity_match(
+        &self,
+        mode: pattern::ModeId,
+        function_id: Option<function::InlineFunctionId>,
+    ) -> bool {
+        match function_id {
+            Some(function_id) => self
+                .runnable
+                .program()
+                .declarations
+                .identity_rules
+                .contains(&function_id),
+            None => {
+                self.runnable
+                    .program()
+                    .declarations
+                    .mode_lookup
+                    .on_no_match(mode)
+                    == pattern::OnNoMatch::ShallowCopy
+            }
+        }
+    }
+
+    // the attributes and children of the node, and everything in them, are
+    // processed by identity templates. We walk the content with an explicit
+    // stack so that deep documents don't overflow the native stack; a node
+    // is popped a second time once all its content has been found to be
+    // identity content.
+    fn has_identity_content(&mut self, mode: pattern::ModeId, node: xot::Node) -> bool {
+        let mut stack = Vec::new();
+        self.push_content(node, &mut stack);
+        while let Some((node, content_done)) = stack.pop() {
+            if content_done {
+                self.identity_subtrees.insert((mode, node), true);
+                continue;
+            }
+            let is_identity = match self.identity_subtrees.get(&(mode, node)) {
+                Some(true) => continue,
+                Some(false) => false,
+                None => {
+                    let function_id = self.lookup_pattern(mode, &sequence::Item::Node(node));
+                    self.is_identity_match(mode, function_id)
+                }
+            };
+            if !is_identity {
+                // the nodes whose content we're still checking contain this
+                // node, so they aren't identity subtrees either
+                self.identity_subtrees.insert((mode, node), false);
+                for (node, _) in stack.into_iter().filter(|(_, content_done)| *content_done) {
+                    self.identity_subtrees.insert((mode, node), false);
+                }
+                return false;
+            }
+            stack.push((node, true));
+            self.push_content(node, &mut stack);
+        }
+        true
+    }
+
+    fn push_content(&self, node: xot::Node, stack: &mut Vec<(xot::Node, bool)>) {
+        let xot = self.xot();
+        let start = stack.len();
+        stack.extend(
+            xot.attribute_nodes(node)
+                .chain(xot.children(node))
+                .map(|node| (node, false)),
+        );
+        // visit the content in document order
+        stack[start..].reverse();
+    }
+
     pub(crate) fn lookup_pattern(
         &mut self,
         mode: pattern::ModeId,
@@ -1237,6 +1625,157 @@ impl<'a> Interpreter<'a> {
             .copied()
     }
 
+    /// The value of the named accumulator at a node: the value immediately
+    /// after its start visit, or if `after` is set, after its end visit.
+    pub(crate) fn accumulator_value(
+        &mut self,
+        name: &OwnedName,
+        node: xot::Node,
+        after: bool,
+    ) -> error::Result<sequence::Sequence> {
+        let accumulators = &self.runnable.program().declarations.accumulators;
+        let index = accumulators
+            .iter()
+            .position(|accumulator| &accumulator.name == name)
+            .ok_or(error::Error::XTDE3340)?;
+        let root = self.xot().root(node);
+        let values = match self.accumulator_values.get(&(index, root)) {
+            Some(Some(values)) => values.clone(),
+            // the accumulator depends on its own value
+            Some(None) => return Err(error::Error::XTDE3400),
+            None => {
+                self.accumulator_values.insert((index, root), None);
+                let values = self.calculate_accumulator_values(&accumulators[index], root);
+                let values = match values {
+                    Ok(values) => Rc::new(values),
+                    Err(error) => {
+                        self.accumulator_values.remove(&(index, root));
+                        return Err(error);
+                    }
+                };
+                self.accumulator_values
+                    .insert((index, root), Some(values.clone()));
+                values
+            }
+        };
+        let values = if after { &values.after } else { &values.before };
+        Ok(values.get(&node).cloned().unwrap_or_default())
+    }
+
+    /// The value of a global variable or parameter.
+    fn global_value(&mut self, global_id: usize) -> error::Result<sequence::Sequence> {
+        match self.global_values.get(&global_id) {
+            Some(Some(value)) => Ok(value.clone()),
+            // the global depends on its own value
+            Some(None) => Err(error::Error::XTDE0640),
+            None => {
+                self.global_values.insert(global_id, None);
+                match self.calculate_global_value(global_id) {
+                    Ok(value) => {
+                        self.global_values.insert(global_id, Some(value.clone()));
+                        Ok(value)
+                    }
+                    Err(error) => {
+                        self.global_values.remove(&global_id);
+                        Err(error)
+                    }
+                }
+            }
+        }
+    }
+
+    fn calculate_global_value(&mut self, global_id: usize) -> error::Result<sequence::Sequence> {
+        let global = &self.runnable.program().declarations.globals[global_id];
+        if let Some(param) = &global.param {
+            let supplied = self.runnable.dynamic_context().variables().get(&param.name);
+            if let Some(supplied) = supplied {
+                return match &param.sequence_type {
+                    Some(sequence_type) => supplied
+                        .clone()
+                        .sequence_type_matching_function_conversion(
+                            sequence_type,
+                            self.runnable.static_context(),
+                            self.state.xot(),
+                            &|function| self.runnable.function_info(function).signature(),
+                        )
+                        .map_err(|error| match error {
+                            error::Error::XPTY0004 => error::Error::XTTE0590,
+                            error => error,
+                        }),
+                    None => Ok(supplied.clone()),
+                };
+            }
+            if param.required {
+                return Err(error::Error::XTDE0050);
+            }
+        }
+        // the focus is the global context item
+        let context_info = match self.runnable.dynamic_context().context_item() {
+            Some(item) => ContextInfo::from(item.clone()),
+            None => ContextInfo {
+                item: stack::Value::Absent,
+                position: stack::Value::Absent,
+                size: stack::Value::Absent,
+            },
+        };
+        let function = function::InlineFunctionData::new(global.function_id, Vec::new()).into();
+        self.call_function_with_values(
+            &function,
+            [context_info.item, context_info.position, context_info.size].into_iter(),
+        )
+    }
+
+    fn calculate_accumulator_values(
+        &mut self,
+        accumulator: &Accumulator,
+        root: xot::Node,
+    ) -> error::Result<AccumulatorValues> {
+        let edges = self.xot().traverse(root).collect::<Vec<_>>();
+        let mut value = self.call_inline_function(accumulator.initial_value, root, None)?;
+        let mut values = AccumulatorValues::default();
+        for edge in edges {
+            let (node, rules, phase_values) = match edge {
+                xot::NodeEdge::Start(node) => (node, &accumulator.start_rules, &mut values.before),
+                xot::NodeEdge::End(node) => (node, &accumulator.end_rules, &mut values.after),
+            };
+            let item: sequence::Item = node.into();
+            // if more than one rule matches, the last one is used
+            let function_id = rules
+                .iter()
+                .rev()
+                .find(|(pattern, _)| self.matches(pattern, &item))
+                .map(|(_, function_id)| *function_id);
+            if let Some(function_id) = function_id {
+                value = self.call_inline_function(function_id, node, Some(value))?;
+            }
+            phase_values.insert(node, value.clone());
+        }
+        Ok(values)
+    }
+
+    // call an inline function with a singleton focus on node, and an
+    // optional extra argument
+    fn call_inline_function(
+        &mut self,
+        function_id: function::InlineFunctionId,
+        node: xot::Node,
+        argument: Option<sequence::Sequence>,
+    ) -> error::Result<sequence::Sequence> {
+        let mut arguments: Vec<sequence::Sequence> = vec![
+            sequence::Item::from(node).into(),
+            atomic::Atomic::from(ibig!(1)).into(),
+            atomic::Atomic::from(ibig!(1)).into(),
+        ];
+        arguments.extend(argument);
+        let function = function::InlineFunctionData::new(function_id, Vec::new()).into();
+        self.call_function_with_arguments(&function, &arguments)
+    }
+
+    fn match_pattern(&mut self, pattern_id: usize, item: &sequence::Item) -> bool {
+        let pattern = &self.runnable.program().declarations.patterns[pattern_id];
+        self.matches(pattern, item)
+    }
+
     // The interpreter can return an error for any byte code, in any level of
     // nesting in the function. When this happens the interpreter stops with
     // the error code. We here wrap it in a SpannedError using the current
@@ -1287,3 +1826,15 @@ impl<'a> Interpreter<'a> {
         read_u8(chunk, &mut frame.ip)
     }
 }
+
+fn name_test_matches(name_test: &ast::NameTest, name: &xot::xmlname::OwnedName) -> bool {
+    match name_test {
+        ast::NameTest::Name(test_name) => {
+            test_name.value.namespace() == name.namespace()
+                && test_name.value.local_name() == name.local_name()
+        }
+        ast::NameTest::Star => true,
+        ast::NameTest::LocalName(local_name) => name.local_name() == local_name,
+        ast::NameTest::Namespace(namespace) => name.namespace() == namespace,
+    }
+}
@@ -6,6 +6,6 @@ mod program;
 mod runnable;
 mod state;
 
-pub use interpret::Interpreter;
+pub use interpret::{Interpreter, CANCELLATION_CHECK_INTERVAL};
 pub use program::{FunctionInfo, Program};
 pub use runnable::Runnable;
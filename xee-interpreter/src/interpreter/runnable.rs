@@ -1,3 +1,4 @@
+use std::io::Write;
 use std::rc::Rc;
 
 use ibig::ibig;
@@ -37,8 +38,12 @@ impl<'a> Runnable<'a> {
     }
 
     fn run_value(&self, xot: &'a mut Xot) -> error::SpannedResult<stack::Value> {
-        let arguments = self.dynamic_context.arguments().unwrap();
         let mut interpreter = Interpreter::new(self, xot);
+        self.run_interpreter(&mut interpreter)
+    }
+
+    fn run_interpreter(&self, interpreter: &mut Interpreter) -> error::SpannedResult<stack::Value> {
+        let arguments = self.dynamic_context.arguments().unwrap();
 
         let context_info = if let Some(context_item) = self.dynamic_context.context_item() {
             ContextInfo {
@@ -57,7 +62,7 @@ impl<'a> Runnable<'a> {
         interpreter.start(context_info, arguments);
         interpreter.run(0)?;
 
-        let state = interpreter.state();
+        let state = &interpreter.state;
         // the stack has to be 1 values and return the result of the expression
         // why 1 value if the context item is on the top of the stack? This is because
         // the outer main function will pop the context item; this code is there to
@@ -102,6 +107,34 @@ impl<'a> Runnable<'a> {
         })
     }
 
+    /// Run the program and serialize the result to a writer.
+    ///
+    /// The result of the program is computed in full, as with
+    /// [`Runnable::many`], but the serialized output is written to `writer`
+    /// as it is produced rather than built as a string first. This is useful
+    /// when a query produces a large result that goes to a file or stdout.
+    ///
+    /// The writer is flushed, also when serialization fails halfway through.
+    /// A failure to write is reported as [`error::Error::Io`], with the
+    /// [`std::io::Error`] as its source.
+    pub fn run_to_writer(
+        &self,
+        xot: &'a mut Xot,
+        writer: &mut impl Write,
+        params: sequence::SerializationParameters,
+    ) -> error::SpannedResult<()> {
+        let mut interpreter = Interpreter::new(self, xot);
+        let sequence: sequence::Sequence = self.run_interpreter(&mut interpreter)?.try_into()?;
+        let serialized = sequence.serialize_write(params, interpreter.xot_mut(), writer);
+        let flushed = writer.flush();
+        let span = Some(self.program.span().into());
+        serialized.map_err(|error| SpannedError { error, span })?;
+        flushed.map_err(|error| SpannedError {
+            error: error.into(),
+            span,
+        })
+    }
+
     pub(crate) fn program(&self) -> &'a Program {
         self.program
     }
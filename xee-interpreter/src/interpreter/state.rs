@@ -3,7 +3,6 @@ use std::rc::Rc;
 
 use ahash::HashMap;
 use ahash::HashMapExt;
-use arrayvec::ArrayVec;
 use xot::Xot;
 
 use crate::error;
@@ -11,8 +10,6 @@ use crate::function;
 use crate::sequence;
 use crate::stack;
 
-const FRAMES_MAX: usize = 64;
-
 #[derive(Debug, Clone)]
 pub(crate) struct Frame {
     function: function::InlineFunctionId,
@@ -29,6 +26,14 @@ impl Frame {
     }
 }
 
+// how far the stacks extend, so we can unwind them after an error is caught
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Checkpoint {
+    stack: usize,
+    build_stack: usize,
+    frames: usize,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct RegexKey {
     pattern: String,
@@ -39,7 +44,8 @@ struct RegexKey {
 pub struct State<'a> {
     stack: Vec<stack::Value>,
     build_stack: Vec<BuildStackEntry>,
-    frames: ArrayVec<Frame, FRAMES_MAX>,
+    frames: Vec<Frame>,
+    max_stack_depth: usize,
     regex_cache: RefCell<HashMap<RegexKey, Rc<regexml::Regex>>>,
     pub(crate) xot: &'a mut Xot,
 }
@@ -67,6 +73,10 @@ impl BuildStackEntry {
         self.item.build_stack.push(item);
     }
 
+    fn len(&self) -> usize {
+        self.item.build_stack.len()
+    }
+
     fn extend<I: Iterator<Item = sequence::Item>>(
         &mut self,
         items: impl IntoIterator<Item = sequence::Item, IntoIter = I>,
@@ -82,11 +92,12 @@ impl From<BuildStackEntry> for sequence::Sequence {
 }
 
 impl<'a> State<'a> {
-    pub(crate) fn new(xot: &'a mut Xot) -> Self {
+    pub(crate) fn new(xot: &'a mut Xot, max_stack_depth: usize) -> Self {
         Self {
             stack: vec![],
             build_stack: vec![],
-            frames: ArrayVec::new(),
+            frames: Vec::new(),
+            max_stack_depth,
             regex_cache: RefCell::new(HashMap::new()),
             xot,
         }
@@ -114,9 +125,14 @@ impl<'a> State<'a> {
     pub(crate) fn build_push(&mut self) -> error::Result<()> {
         let value = self.pop()?;
         let build = self.build_stack.last_mut().unwrap();
+        // a loop over a lazy range can build a sequence we can't hold, so we
+        // check before growing the build
+        sequence::check_materialized_len(build.len() + value.len())?;
         match value {
             sequence::Sequence::Empty(_) => {}
             sequence::Sequence::One(item) => build.push(item.into_item()),
+            // a lazy range is materialized here
+            sequence::Sequence::Range(range) => build.extend(range.to_vec()?),
             // any other sequence
             sequence => build.extend(sequence.iter()),
         }
@@ -177,7 +193,7 @@ impl<'a> State<'a> {
         function_id: function::InlineFunctionId,
         arity: usize,
     ) -> error::Result<()> {
-        if self.frames.len() >= self.frames.capacity() {
+        if self.frames.len() >= self.max_stack_depth {
             return Err(error::Error::StackOverflow);
         }
         self.frames.push(Frame {
@@ -240,6 +256,20 @@ impl<'a> State<'a> {
         base == start_base
     }
 
+    pub(crate) fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            stack: self.stack.len(),
+            build_stack: self.build_stack.len(),
+            frames: self.frames.len(),
+        }
+    }
+
+    pub(crate) fn restore(&mut self, checkpoint: Checkpoint) {
+        self.stack.truncate(checkpoint.stack);
+        self.build_stack.truncate(checkpoint.build_stack);
+        self.frames.truncate(checkpoint.frames);
+    }
+
     pub(crate) fn top(&self) -> error::Result<sequence::Sequence> {
         self.stack.last().unwrap().try_into()
     }
@@ -18,4 +18,5 @@ pub mod stack;
 pub mod string;
 pub mod xml;
 
-pub use xee_name::{Name, Namespaces, VariableNames};
+pub use library::parse_json_array_members;
+pub use xee_name::{Name, Namespaces, VariableNames, XEE_NAMESPACE};
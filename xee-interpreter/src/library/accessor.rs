@@ -1,4 +1,5 @@
 // https://www.w3.org/TR/xpath-functions-31/#accessors
+use iri_string::types::IriString;
 use xee_xpath_ast::ast;
 use xee_xpath_macros::xpath_fn;
 
@@ -11,6 +12,8 @@ use crate::sequence;
 use crate::wrap_xpath_fn;
 use crate::xml::BaseUriResolver;
 
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
 #[xpath_fn("fn:node-name($arg as node()?) as xs:QName?", context_first)]
 fn node_name(
     interpreter: &Interpreter,
@@ -23,6 +26,22 @@ fn node_name(
     })
 }
 
+// Without schema validation we can't tell whether an element is valid, so
+// we consider any element with `xsi:nil="true"` to be nilled.
+#[xpath_fn("fn:nilled($arg as node()?) as xs:boolean?", context_first)]
+fn nilled(interpreter: &Interpreter, arg: Option<xot::Node>) -> Option<bool> {
+    let node = arg?;
+    let xot = interpreter.xot();
+    if !xot.is_element(node) {
+        return None;
+    }
+    let nil = xot
+        .namespace(XSI_NAMESPACE)
+        .and_then(|namespace| xot.name_ns("nil", namespace))
+        .and_then(|name| xot.get_attribute(node, name));
+    Some(matches!(nil.map(str::trim), Some("true" | "1")))
+}
+
 #[xpath_fn("fn:string($arg as item()?) as xs:string", context_first)]
 fn string(interpreter: &Interpreter, arg: Option<sequence::Item>) -> error::Result<String> {
     if let Some(arg) = arg {
@@ -34,6 +53,7 @@ fn string(interpreter: &Interpreter, arg: Option<sequence::Item>) -> error::Resu
 
 #[xpath_fn("fn:data($arg as item()*) as xs:anyAtomicType*", context_first)]
 fn data(interpreter: &Interpreter, arg: &sequence::Sequence) -> error::Result<Vec<sequence::Item>> {
+    arg.materialized_len()?;
     let data = arg
         .atomized(interpreter.xot())
         .map(|atom| atom.map(|a| a.into()))
@@ -48,31 +68,41 @@ fn base_uri(
     arg: Option<xot::Node>,
 ) -> error::Result<Option<atomic::Atomic>> {
     Ok(if let Some(node) = arg {
-        // root node of the document
-        let root = interpreter.xot().root(node);
+        node_base_uri(context, interpreter, node)?.map(|i| i.into())
+    } else {
+        None
+    })
+}
 
-        let base_uri = if matches!(interpreter.xot().value(root), xot::Value::Document) {
-            // the base uri of the document is the one we can find registered, if available
-            let documents = context.documents();
-            let documents = documents.borrow();
-            documents.get_uri_by_document_node(root)
-        } else {
-            None
-        };
+pub(crate) fn node_base_uri(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    node: xot::Node,
+) -> error::Result<Option<IriString>> {
+    // root node of the document
+    let root = interpreter.xot().root(node);
+
+    // the base uri of the document, or of a copied node, is the one we can
+    // find registered, if available
+    let base_uri = context
+        .documents()
+        .borrow()
+        .get_base_uri_by_document_node(root);
 
-        // if we don't have a registered URI, use the static base uri
-        let base_uri = base_uri.or_else(|| {
+    // if we don't have a registered URI for a document, use the static base
+    // uri
+    let base_uri = if matches!(interpreter.xot().value(root), xot::Value::Document) {
+        base_uri.or_else(|| {
             context
                 .static_context()
                 .static_base_uri()
                 .map(|u| u.to_owned().into())
-        });
-        let resolver = BaseUriResolver::new(base_uri.as_deref(), interpreter.state.xot_mut());
-        let base_iri = resolver.base_uri(node)?;
-        base_iri.map(|i| i.into())
+        })
     } else {
-        None
-    })
+        base_uri
+    };
+    let resolver = BaseUriResolver::new(base_uri.as_deref(), interpreter.state.xot_mut());
+    resolver.base_uri(node)
 }
 
 #[xpath_fn("fn:document-uri($arg as node()?) as xs:anyURI?", context_first)]
@@ -102,6 +132,7 @@ fn document_uri(
 pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
     vec![
         wrap_xpath_fn!(node_name),
+        wrap_xpath_fn!(nilled),
         wrap_xpath_fn!(string),
         wrap_xpath_fn!(data),
         wrap_xpath_fn!(base_uri),
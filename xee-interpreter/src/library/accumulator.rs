@@ -0,0 +1,69 @@
+// https://www.w3.org/TR/xslt-30/#accumulator-functions
+
+use xee_xpath_ast::parse_name;
+use xee_xpath_macros::xpath_fn;
+
+use crate::context::DynamicContext;
+use crate::error;
+use crate::function::StaticFunctionDescription;
+use crate::interpreter::Interpreter;
+use crate::sequence;
+use crate::wrap_xpath_fn;
+
+// The last argument, $arg, isn't part of the official signature; it's
+// the context item, if any.
+#[xpath_fn(
+    "fn:accumulator-before($name as xs:string, $arg as item()?) as item()*",
+    context_last_optional
+)]
+fn accumulator_before(
+    context: &DynamicContext,
+    interpreter: &mut Interpreter,
+    name: &str,
+    arg: Option<sequence::Item>,
+) -> error::Result<sequence::Sequence> {
+    accumulator_value(context, interpreter, name, arg, false)
+}
+
+#[xpath_fn(
+    "fn:accumulator-after($name as xs:string, $arg as item()?) as item()*",
+    context_last_optional
+)]
+fn accumulator_after(
+    context: &DynamicContext,
+    interpreter: &mut Interpreter,
+    name: &str,
+    arg: Option<sequence::Item>,
+) -> error::Result<sequence::Sequence> {
+    accumulator_value(context, interpreter, name, arg, true)
+}
+
+fn accumulator_value(
+    context: &DynamicContext,
+    interpreter: &mut Interpreter,
+    name: &str,
+    arg: Option<sequence::Item>,
+    after: bool,
+) -> error::Result<sequence::Sequence> {
+    let node = match arg {
+        Some(sequence::Item::Node(node)) => node,
+        Some(_) => return Err(error::Error::XTTE3360),
+        None => return Err(error::Error::XTDE3350),
+    };
+    let xot = interpreter.xot();
+    if xot.is_attribute_node(node) || xot.is_namespace_node(node) {
+        return Err(error::Error::XTTE3360);
+    }
+    // no prefix means no namespace
+    let name = parse_name(name, context.static_context().namespaces())
+        .map_err(|_| error::Error::XTDE3340)?
+        .value;
+    interpreter.accumulator_value(&name, node, after)
+}
+
+pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
+    vec![
+        wrap_xpath_fn!(accumulator_before),
+        wrap_xpath_fn!(accumulator_after),
+    ]
+}
@@ -5,7 +5,7 @@ use ibig::IBig;
 use rust_decimal::Decimal;
 use xee_xpath_macros::xpath_fn;
 
-use crate::atomic::ToDateTimeStamp;
+use crate::atomic::{self, ToDateTimeStamp};
 use crate::function::StaticFunctionDescription;
 use crate::{
     atomic::NaiveDateTimeWithOffset, atomic::NaiveDateWithOffset, atomic::NaiveTimeWithOffset,
@@ -329,10 +329,7 @@ fn duration_to_offset(
 #[xpath_fn("fn:parse-ietf-date($value as xs:string?) as xs:dateTime?")]
 fn parse_ietf_date(value: Option<&str>) -> error::Result<Option<NaiveDateTimeWithOffset>> {
     if let Some(value) = value {
-        match chrono::DateTime::parse_from_rfc2822(value.trim()) {
-            Ok(date_time) => Ok(Some(date_time.into())),
-            Err(_) => Err(error::Error::FORG0010),
-        }
+        Ok(Some(atomic::parse_ietf_date(value)?))
     } else {
         Ok(None)
     }
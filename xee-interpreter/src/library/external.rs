@@ -2,39 +2,71 @@ use iri_string::types::{IriReferenceStr, IriString};
 use xee_xpath_macros::xpath_fn;
 
 use crate::{
-    context::DynamicContext, error, function::StaticFunctionDescription, sequence::Sequence,
+    atomic,
+    context::{DynamicContext, Resource},
+    error,
+    function::StaticFunctionDescription,
+    interpreter::Interpreter,
+    sequence::{self, Sequence},
     wrap_xpath_fn,
 };
 
+use super::string::is_valid_xml_char;
+
 #[xpath_fn("fn:doc($uri as xs:string?) as document-node()?")]
-fn doc(context: &DynamicContext, uri: Option<&str>) -> error::Result<Option<xot::Node>> {
+fn doc(
+    context: &DynamicContext,
+    interpreter: &mut Interpreter,
+    uri: Option<&str>,
+) -> error::Result<Option<xot::Node>> {
     if let Some(uri) = uri {
-        document_node(context, uri)
+        document_node(context, interpreter, uri).map(Some)
     } else {
         Ok(None)
     }
 }
 
 #[xpath_fn("fn:doc-available($uri as xs:string?) as xs:boolean")]
-fn doc_available(context: &DynamicContext, uri: Option<&str>) -> bool {
+fn doc_available(
+    context: &DynamicContext,
+    interpreter: &mut Interpreter,
+    uri: Option<&str>,
+) -> bool {
     if let Some(uri) = uri {
-        document_node(context, uri).is_ok()
+        document_node(context, interpreter, uri).is_ok()
     } else {
         false
     }
 }
 
-fn document_node(context: &DynamicContext, uri: &str) -> error::Result<Option<xot::Node>> {
+fn document_node(
+    context: &DynamicContext,
+    interpreter: &mut Interpreter,
+    uri: &str,
+) -> error::Result<xot::Node> {
     let iri_reference: &IriReferenceStr = uri.try_into().map_err(|_| error::Error::FODC0005)?;
     let uri = absolute_uri(context, iri_reference)?;
 
     // first check whether a document is there at all, if so, return it
     let documents = context.documents();
-    let documents = documents.borrow();
-    let document = documents.get_by_uri(&uri);
-
-    if let Some(document) = document {
-        Ok(Some(document.root()))
+    let root = documents
+        .borrow()
+        .get_by_uri(&uri)
+        .map(|document| document.root());
+    if let Some(root) = root {
+        return Ok(root);
+    }
+    // otherwise parse it from the resources. We add it to the documents
+    // under its URI, so that retrieving it again gives the same node
+    if let Some(Resource::Xml(xml)) = context.resource(uri.as_str()) {
+        let handle = documents
+            .borrow_mut()
+            .add_string(interpreter.xot_mut(), Some(&uri), xml)
+            .map_err(|_| error::Error::FODC0002)?;
+        documents
+            .borrow()
+            .get_node_by_handle(handle)
+            .ok_or(error::Error::FODC0002)
     } else {
         // The document doesn't exist, so return an error
         Err(error::Error::FODC0002)
@@ -51,12 +83,22 @@ fn collection(context: &DynamicContext) -> error::Result<Sequence> {
 }
 
 #[xpath_fn("fn:collection($uri as xs:string?) as item()*")]
-fn collection_by_uri(context: &DynamicContext, uri: Option<&str>) -> error::Result<Sequence> {
+fn collection_by_uri(
+    context: &DynamicContext,
+    interpreter: &mut Interpreter,
+    uri: Option<&str>,
+) -> error::Result<Sequence> {
     if let Some(uri) = uri {
         let iri_reference: &IriReferenceStr = uri.try_into().map_err(|_| error::Error::FODC0004)?;
         let uri = absolute_uri(context, iri_reference)?;
         if let Some(collection) = context.collection(&uri) {
             Ok(collection.clone())
+        } else if let Some(Resource::Collection(uris)) = context.resource(uri.as_str()) {
+            let nodes = uris
+                .iter()
+                .map(|uri| document_node(context, interpreter, uri).map(sequence::Item::Node))
+                .collect::<error::Result<Vec<_>>>()?;
+            Ok(nodes.into())
         } else {
             Err(error::Error::FODC0002)
         }
@@ -83,6 +125,19 @@ fn uri_collection_by_uri(context: &DynamicContext, uri: Option<&str>) -> error::
         let uri = absolute_uri(context, iri_reference)?;
         if let Some(collection) = context.uri_collection(&uri) {
             Ok(collection.clone())
+        } else if let Some(Resource::Collection(uris)) = context.resource(uri.as_str()) {
+            let items = uris
+                .iter()
+                .map(|uri| {
+                    let iri_reference: &IriReferenceStr = uri
+                        .as_str()
+                        .try_into()
+                        .map_err(|_| error::Error::FODC0004)?;
+                    let uri = absolute_uri(context, iri_reference)?;
+                    Ok(atomic::Atomic::from(uri).into())
+                })
+                .collect::<error::Result<Vec<sequence::Item>>>()?;
+            Ok(items.into())
         } else {
             Err(error::Error::FODC0002)
         }
@@ -108,6 +163,89 @@ fn absolute_uri(context: &DynamicContext, uri: &IriReferenceStr) -> error::Resul
     Ok(uri)
 }
 
+#[xpath_fn("fn:unparsed-text($href as xs:string?) as xs:string?")]
+fn unparsed_text1(context: &DynamicContext, href: Option<&str>) -> error::Result<Option<String>> {
+    href.map(|href| text_resource(context, href).map(|text| text.to_string()))
+        .transpose()
+}
+
+#[xpath_fn("fn:unparsed-text($href as xs:string?, $encoding as xs:string) as xs:string?")]
+fn unparsed_text2(
+    context: &DynamicContext,
+    href: Option<&str>,
+    _encoding: &str,
+) -> error::Result<Option<String>> {
+    // resources are already decoded, so the encoding doesn't apply
+    unparsed_text1(context, href)
+}
+
+#[xpath_fn("fn:unparsed-text-lines($href as xs:string?) as xs:string*")]
+fn unparsed_text_lines1(
+    context: &DynamicContext,
+    href: Option<&str>,
+) -> error::Result<Vec<String>> {
+    if let Some(href) = href {
+        Ok(lines(text_resource(context, href)?))
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+#[xpath_fn("fn:unparsed-text-lines($href as xs:string?, $encoding as xs:string) as xs:string*")]
+fn unparsed_text_lines2(
+    context: &DynamicContext,
+    href: Option<&str>,
+    _encoding: &str,
+) -> error::Result<Vec<String>> {
+    unparsed_text_lines1(context, href)
+}
+
+#[xpath_fn("fn:unparsed-text-available($href as xs:string?) as xs:boolean")]
+fn unparsed_text_available1(context: &DynamicContext, href: Option<&str>) -> bool {
+    if let Some(href) = href {
+        text_resource(context, href).is_ok()
+    } else {
+        false
+    }
+}
+
+#[xpath_fn("fn:unparsed-text-available($href as xs:string?, $encoding as xs:string) as xs:boolean")]
+fn unparsed_text_available2(context: &DynamicContext, href: Option<&str>, _encoding: &str) -> bool {
+    unparsed_text_available1(context, href)
+}
+
+pub(super) fn text_resource<'a>(context: &'a DynamicContext, href: &str) -> error::Result<&'a str> {
+    let iri_reference: &IriReferenceStr = href.try_into().map_err(|_| error::Error::FOUT1170)?;
+    if iri_reference.fragment().is_some() {
+        return Err(error::Error::FOUT1170);
+    }
+    let uri = absolute_uri(context, iri_reference).map_err(|_| error::Error::FOUT1170)?;
+    let text = match context.resource(uri.as_str()) {
+        Some(Resource::Text(text)) | Some(Resource::Xml(text)) => text,
+        _ => return Err(error::Error::FOUT1170),
+    };
+    // a byte order mark isn't part of the text
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+    if !text.chars().all(is_valid_xml_char) {
+        return Err(error::Error::FOUT1190);
+    }
+    Ok(text)
+}
+
+// like tokenizing on newlines, except that a trailing newline doesn't give
+// an empty last line
+fn lines(text: &str) -> Vec<String> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut lines = text.split('\n').map(String::from).collect::<Vec<_>>();
+    if lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
 #[xpath_fn("fn:environment-variable($name as xs:string) as xs:string?")]
 fn environment_variable(context: &DynamicContext, name: &str) -> Option<String> {
     context.environment_variable(name).map(|s| s.to_string())
@@ -115,10 +253,14 @@ fn environment_variable(context: &DynamicContext, name: &str) -> Option<String>
 
 #[xpath_fn("fn:available-environment-variables() as xs:string*")]
 fn available_environment_variables(context: &DynamicContext) -> Vec<String> {
-    context
+    // the order is implementation dependent; we sort the names so it is
+    // stable between runs
+    let mut names = context
         .environment_variable_names()
         .map(|s| s.to_string())
-        .collect()
+        .collect::<Vec<_>>();
+    names.sort();
+    names
 }
 
 // https://www.w3.org/TR/xpath-functions-31/#fns-on-docs
@@ -130,6 +272,12 @@ pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
         wrap_xpath_fn!(collection_by_uri),
         wrap_xpath_fn!(uri_collection),
         wrap_xpath_fn!(uri_collection_by_uri),
+        wrap_xpath_fn!(unparsed_text1),
+        wrap_xpath_fn!(unparsed_text2),
+        wrap_xpath_fn!(unparsed_text_lines1),
+        wrap_xpath_fn!(unparsed_text_lines2),
+        wrap_xpath_fn!(unparsed_text_available1),
+        wrap_xpath_fn!(unparsed_text_available2),
         wrap_xpath_fn!(environment_variable),
         wrap_xpath_fn!(available_environment_variables),
     ]
@@ -3,8 +3,10 @@ use ibig::IBig;
 use xee_name::Name;
 use xee_xpath_macros::xpath_fn;
 
+use crate::context;
 use crate::error;
 use crate::function::StaticFunctionDescription;
+use crate::interpreter::Interpreter;
 use crate::sequence;
 use crate::wrap_xpath_fn;
 
@@ -44,47 +46,59 @@ fn error_with_code_and_description(
     code: Option<Name>,
     description: &str,
 ) -> error::Result<sequence::Sequence> {
-    error_helper(code, description)
+    error_helper(code, description, &sequence::Sequence::default())
 }
 
 #[xpath_fn(
     "fn:error($code as xs:QName?, $description as xs:string, $error_object as item()*) as item()*"
 )]
 fn error_with_code_and_description_and_sequence(
+    interpreter: &mut Interpreter,
     code: Option<Name>,
     description: &str,
-    _error_object: &sequence::Sequence,
+    error_object: &sequence::Sequence,
 ) -> error::Result<sequence::Sequence> {
-    // FIXME: we are not doing anything with _error_object
-    error_helper(code, description)
+    // the error itself only keeps a detached copy of the error object; a
+    // catch clause gets the original
+    interpreter.set_error_object(error_object.clone());
+    error_helper(code, description, error_object)
 }
 
-fn error_helper(code: Option<Name>, description: &str) -> error::Result<sequence::Sequence> {
-    if let Some(code) = code {
-        Err(error::Error::Application(Box::new(
-            error::ApplicationError::new(code, description.to_string()),
-        )))
-    } else {
-        let unknown_error_qname = Name::new(
+fn error_helper(
+    code: Option<Name>,
+    description: &str,
+    error_object: &sequence::Sequence,
+) -> error::Result<sequence::Sequence> {
+    let code = code.unwrap_or_else(|| {
+        Name::new(
             "FOER0000".to_string(),
             "http://www.w3.org/2005/xqt-errors".to_string(),
             "".to_string(),
-        );
-        Err(error::Error::Application(Box::new(
-            error::ApplicationError::new(unknown_error_qname, description.to_string()),
-        )))
-    }
+        )
+    });
+    Err(error::Error::Application(Box::new(
+        error::ApplicationError::new(code, description.to_string()).with_error_object(error_object),
+    )))
 }
 
 #[xpath_fn("fn:trace($value as item()*) as item()*")]
-fn trace(value: &sequence::Sequence) -> sequence::Sequence {
-    // TODO: direct values to the "trace data set".
+fn trace(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    value: &sequence::Sequence,
+) -> sequence::Sequence {
+    context.trace("", value, interpreter.xot());
     value.clone()
 }
 
 #[xpath_fn("fn:trace($value as item()*,$label as xs:string) as item()*")]
-fn trace_with_label(value: &sequence::Sequence, _label: &str) -> sequence::Sequence {
-    // TODO: direct values + label to the "trace data set".
+fn trace_with_label(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    value: &sequence::Sequence,
+    label: &str,
+) -> sequence::Sequence {
+    context.trace(label, value, interpreter.xot());
     value.clone()
 }
 
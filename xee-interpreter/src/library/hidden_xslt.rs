@@ -1,12 +1,17 @@
 // functions used to implement the XSLT that aren't supposed to be
 // exposed to XPath
+use ahash::{HashSet, HashSetExt};
+use ibig::IBig;
 use xee_xpath_macros::xpath_fn;
 use xot::Xot;
 
+use crate::atomic::Atomic;
+use crate::context::DynamicContext;
 use crate::error;
-use crate::function::StaticFunctionDescription;
+use crate::function::{self, StaticFunctionDescription};
 use crate::interpreter::Interpreter;
 use crate::sequence;
+use crate::string::{Collation, Grouping, NumberFormat};
 use crate::wrap_xpath_fn;
 
 // TODO: Things should really be hidden from XPath, and not be in the fn prefix
@@ -76,8 +81,418 @@ fn simple_content_text_nodes(
     Ok(r.into())
 }
 
+// https://www.w3.org/TR/xslt-30/#grouping
+
+// The grouping functions take the population and a parallel sequence of
+// per-item values (keys wrapped in arrays, or pattern match results), and
+// return a sequence of arrays, one per group, in order of first appearance.
+// Each array has two members: the items in the group and the grouping key.
+
+#[xpath_fn(
+    "fn:group-by($population as item()*, $keys as array(*)*, $composite as xs:boolean, $collation as xs:string) as array(*)*",
+    collation
+)]
+fn group_by(
+    context: &DynamicContext,
+    interpreter: &Interpreter,
+    population: &sequence::Sequence,
+    keys: impl Iterator<Item = error::Result<function::Array>>,
+    composite: bool,
+    collation: &str,
+) -> error::Result<Vec<function::Array>> {
+    let collation = context
+        .static_context()
+        .resolve_collation_str(Some(collation))?;
+    let default_offset = context.implicit_timezone();
+    let mut groups: Vec<(Vec<Atomic>, Vec<sequence::Item>)> = Vec::new();
+    for (item, key) in population.iter().zip(keys) {
+        let key = grouping_key(&key?, interpreter.xot())?;
+        if composite {
+            add_to_group(&mut groups, key, item, &collation, default_offset);
+        } else {
+            add_to_groups(&mut groups, key, item, &collation, default_offset);
+        }
+    }
+    Ok(groups_to_arrays(groups))
+}
+
+#[xpath_fn(
+    "fn:group-adjacent($population as item()*, $keys as array(*)*, $composite as xs:boolean, $collation as xs:string) as array(*)*",
+    collation
+)]
+fn group_adjacent(
+    context: &DynamicContext,
+    interpreter: &Interpreter,
+    population: &sequence::Sequence,
+    keys: impl Iterator<Item = error::Result<function::Array>>,
+    composite: bool,
+    collation: &str,
+) -> error::Result<Vec<function::Array>> {
+    let collation = context
+        .static_context()
+        .resolve_collation_str(Some(collation))?;
+    let default_offset = context.implicit_timezone();
+    let mut groups: Vec<(Vec<Atomic>, Vec<sequence::Item>)> = Vec::new();
+    for (item, key) in population.iter().zip(keys) {
+        let key = grouping_key(&key?, interpreter.xot())?;
+        if !composite && key.len() != 1 {
+            return Err(error::Error::XTTE1100);
+        }
+        match groups.last_mut() {
+            Some((last_key, items)) if keys_equal(last_key, &key, &collation, default_offset) => {
+                items.push(item)
+            }
+            _ => groups.push((key, vec![item])),
+        }
+    }
+    Ok(groups_to_arrays(groups))
+}
+
+#[xpath_fn("fn:group-starting-with($population as item()*, $matches as xs:boolean*) as array(*)*")]
+fn group_starting_with(
+    population: &sequence::Sequence,
+    matches: impl Iterator<Item = error::Result<bool>>,
+) -> error::Result<Vec<function::Array>> {
+    let mut groups: Vec<Vec<sequence::Item>> = Vec::new();
+    for (item, matches) in population.iter().zip(matches) {
+        if !matches!(item, sequence::Item::Node(_)) {
+            return Err(error::Error::XTTE1120);
+        }
+        match groups.last_mut() {
+            Some(items) if !matches? => items.push(item),
+            _ => groups.push(vec![item]),
+        }
+    }
+    Ok(groups_to_arrays(
+        groups.into_iter().map(|items| (Vec::new(), items)),
+    ))
+}
+
+#[xpath_fn("fn:group-ending-with($population as item()*, $matches as xs:boolean*) as array(*)*")]
+fn group_ending_with(
+    population: &sequence::Sequence,
+    matches: impl Iterator<Item = error::Result<bool>>,
+) -> error::Result<Vec<function::Array>> {
+    let mut groups: Vec<Vec<sequence::Item>> = Vec::new();
+    let mut ended = true;
+    for (item, matches) in population.iter().zip(matches) {
+        if !matches!(item, sequence::Item::Node(_)) {
+            return Err(error::Error::XTTE1120);
+        }
+        match groups.last_mut() {
+            Some(items) if !ended => items.push(item),
+            _ => groups.push(vec![item]),
+        }
+        ended = matches?;
+    }
+    Ok(groups_to_arrays(
+        groups.into_iter().map(|items| (Vec::new(), items)),
+    ))
+}
+
+// The grouping key is the atomized first member of the array. Untyped
+// atomic values are compared as strings.
+fn grouping_key(key: &function::Array, xot: &Xot) -> error::Result<Vec<Atomic>> {
+    let key = match key.index(0) {
+        Some(key) => key,
+        None => return Ok(Vec::new()),
+    };
+    key.atomized(xot)
+        .map(|atom| {
+            let atom = atom?;
+            Ok(if atom.is_untyped() {
+                atom.cast_to_string()
+            } else {
+                atom
+            })
+        })
+        .collect()
+}
+
+// Add the item to the group of each of its distinct keys.
+pub(super) fn add_to_groups(
+    groups: &mut Vec<(Vec<Atomic>, Vec<sequence::Item>)>,
+    keys: Vec<Atomic>,
+    item: sequence::Item,
+    collation: &Collation,
+    default_offset: chrono::FixedOffset,
+) {
+    let mut seen: Vec<Atomic> = Vec::new();
+    for atom in keys {
+        if seen
+            .iter()
+            .any(|s| s.deep_equal(&atom, collation, default_offset))
+        {
+            continue;
+        }
+        seen.push(atom.clone());
+        add_to_group(groups, vec![atom], item.clone(), collation, default_offset);
+    }
+}
+
+fn keys_equal(
+    a: &[Atomic],
+    b: &[Atomic],
+    collation: &Collation,
+    default_offset: chrono::FixedOffset,
+) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b.iter())
+            .all(|(a, b)| a.deep_equal(b, collation, default_offset))
+}
+
+fn add_to_group(
+    groups: &mut Vec<(Vec<Atomic>, Vec<sequence::Item>)>,
+    key: Vec<Atomic>,
+    item: sequence::Item,
+    collation: &Collation,
+    default_offset: chrono::FixedOffset,
+) {
+    for (group_key, items) in groups.iter_mut() {
+        if keys_equal(group_key, &key, collation, default_offset) {
+            items.push(item);
+            return;
+        }
+    }
+    groups.push((key, vec![item]));
+}
+
+fn groups_to_arrays(
+    groups: impl IntoIterator<Item = (Vec<Atomic>, Vec<sequence::Item>)>,
+) -> Vec<function::Array> {
+    groups
+        .into_iter()
+        .map(|(key, items)| function::Array::new(vec![items.into(), key.into()]))
+        .collect()
+}
+
+// https://www.w3.org/TR/xslt-30/#numbering-based-on-position
+
+// The nodes that need to be matched against the count and from patterns of
+// xsl:number to determine the position of $node: its ancestors-or-self and
+// their preceding siblings, or for level any all nodes that come before it.
+//
+// $node is the result of the select expression if $selected is true, and the
+// context item otherwise; it has to be a single node.
+#[xpath_fn(
+    "fn:number-candidates($node as item()*, $level as xs:string, $selected as xs:boolean) as node()*"
+)]
+fn number_candidates(
+    interpreter: &Interpreter,
+    node: &sequence::Sequence,
+    level: &str,
+    selected: bool,
+) -> error::Result<Vec<xot::Node>> {
+    let node = match node.clone().one() {
+        Ok(sequence::Item::Node(node)) => node,
+        _ if selected => return Err(error::Error::XTTE0990),
+        _ => return Err(error::Error::XTTE1000),
+    };
+    let xot = interpreter.xot();
+    Ok(if level == "any" {
+        // the nodes before node in document order are exactly the nodes
+        // on its preceding and ancestor axes. Attribute and namespace nodes
+        // aren't descendants, so for those we go up to their parent.
+        let anchor = if xot.is_attribute_node(node) || xot.is_namespace_node(node) {
+            xot.parent(node).unwrap_or(node)
+        } else {
+            node
+        };
+        let mut candidates = xot
+            .descendants(xot.root(node))
+            .take_while(|n| *n != anchor)
+            .collect::<Vec<_>>();
+        candidates.push(anchor);
+        if anchor != node {
+            candidates.push(node);
+        }
+        candidates
+    } else {
+        xot.ancestors(node)
+            .flat_map(|ancestor| xot.preceding_siblings(ancestor).chain([ancestor]))
+            .collect()
+    })
+}
+
+// Determine the place of $node given the candidates and a parallel sequence
+// of whether they match the count and from patterns. If there is no count
+// pattern, $count is empty; likewise for $from.
+#[xpath_fn("fn:number-node($node as node(), $level as xs:string, $candidates as node()*, $count as xs:boolean*, $from as xs:boolean*) as xs:integer*")]
+fn number_node(
+    interpreter: &Interpreter,
+    node: xot::Node,
+    level: &str,
+    candidates: impl Iterator<Item = error::Result<xot::Node>>,
+    count: impl Iterator<Item = error::Result<bool>>,
+    from: impl Iterator<Item = error::Result<bool>>,
+) -> error::Result<Vec<IBig>> {
+    let xot = interpreter.xot();
+    let candidates = candidates.collect::<error::Result<Vec<_>>>()?;
+    let count_matches = matching_nodes(&candidates, count)?;
+    let from_matches = matching_nodes(&candidates, from)?;
+    let counts = |n: xot::Node| match &count_matches {
+        Some(count_matches) => count_matches.contains(&n),
+        None => default_count_matches(xot, node, n),
+    };
+    let froms = |n: xot::Node| {
+        from_matches
+            .as_ref()
+            .is_some_and(|from_matches| from_matches.contains(&n))
+    };
+    let sibling_number = |n: xot::Node| {
+        let preceding = xot
+            .preceding_siblings(n)
+            .filter(|sibling| *sibling != n && counts(*sibling))
+            .count();
+        IBig::from(preceding + 1)
+    };
+    Ok(match level {
+        "single" => {
+            // the first ancestor-or-self that is counted, unless we
+            // run into a from node before that
+            let mut numbers = Vec::new();
+            for ancestor in xot.ancestors(node) {
+                if counts(ancestor) {
+                    numbers.push(sibling_number(ancestor));
+                    break;
+                }
+                if froms(ancestor) {
+                    break;
+                }
+            }
+            numbers
+        }
+        "multiple" => {
+            let mut numbers = Vec::new();
+            for ancestor in xot.ancestors(node) {
+                if counts(ancestor) {
+                    numbers.push(sibling_number(ancestor));
+                }
+                if froms(ancestor) {
+                    break;
+                }
+            }
+            numbers.reverse();
+            numbers
+        }
+        _ => {
+            // candidates are in document order, so we go backward from
+            // node until we encounter a from node
+            let mut number = 0usize;
+            for candidate in candidates.iter().rev() {
+                if counts(*candidate) {
+                    number += 1;
+                }
+                if froms(*candidate) {
+                    break;
+                }
+            }
+            if number > 0 {
+                vec![IBig::from(number)]
+            } else {
+                vec![]
+            }
+        }
+    })
+}
+
+fn matching_nodes(
+    candidates: &[xot::Node],
+    matches: impl Iterator<Item = error::Result<bool>>,
+) -> error::Result<Option<HashSet<xot::Node>>> {
+    let mut nodes = HashSet::new();
+    let mut any = false;
+    for (candidate, matches) in candidates.iter().zip(matches) {
+        any = true;
+        if matches? {
+            nodes.insert(*candidate);
+        }
+    }
+    Ok(any.then_some(nodes))
+}
+
+// Without a count pattern, we count nodes of the same kind, and with the
+// same name if the node has one.
+fn default_count_matches(xot: &Xot, node: xot::Node, other: xot::Node) -> bool {
+    xot.value_type(node) == xot.value_type(other) && xot.node_name(node) == xot.node_name(other)
+}
+
+// https://www.w3.org/TR/xslt-30/#convert
+
+#[xpath_fn("fn:number-format($numbers as xs:anyAtomicType*, $format as xs:string, $grouping_separator as xs:string?, $grouping_size as xs:string?, $start_at as xs:string) as xs:string")]
+fn number_format(
+    numbers: impl Iterator<Item = error::Result<Atomic>>,
+    format: &str,
+    grouping_separator: Option<&str>,
+    grouping_size: Option<&str>,
+    start_at: &str,
+) -> error::Result<String> {
+    let numbers = numbers
+        .map(|number| number_value(number?))
+        .collect::<error::Result<Vec<_>>>()?;
+    let start_at = start_at
+        .split_ascii_whitespace()
+        .map(|s| s.parse::<IBig>().map_err(|_| error::Error::XTDE0030))
+        .collect::<error::Result<Vec<_>>>()?;
+    // each number is offset by its start-at value; the last one repeats
+    let numbers = numbers
+        .into_iter()
+        .enumerate()
+        .map(|(i, number)| match start_at.get(i).or(start_at.last()) {
+            Some(start) => number + start - IBig::from(1),
+            None => number,
+        })
+        .collect::<Vec<_>>();
+    // grouping only takes place if both attributes are present
+    let grouping = match (grouping_separator, grouping_size) {
+        (Some(separator), Some(size)) => {
+            let mut chars = separator.chars();
+            let separator = match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => return Err(error::Error::XTDE0030),
+            };
+            Some(Grouping {
+                separator,
+                size: size.trim().parse().map_err(|_| error::Error::XTDE0030)?,
+            })
+        }
+        _ => None,
+    };
+    Ok(NumberFormat::parse(format).format(&numbers, grouping.as_ref()))
+}
+
+// A number supplied to xsl:number is rounded to an integer; it may not be
+// negative.
+fn number_value(atomic: Atomic) -> error::Result<IBig> {
+    if let Atomic::Integer(_, i) = &atomic {
+        if i.as_ref() < &IBig::from(0) {
+            return Err(error::Error::XTDE0980);
+        }
+        return Ok(i.as_ref().clone());
+    }
+    let d: f64 = match atomic.cast_to_double() {
+        Ok(Atomic::Double(d)) => d.into_inner(),
+        _ => return Err(error::Error::XTDE0980),
+    };
+    let d = (d + 0.5).floor();
+    if !d.is_finite() || d < 0.0 {
+        return Err(error::Error::XTDE0980);
+    }
+    Ok(IBig::from(d as u128))
+}
+
 pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
-    vec![wrap_xpath_fn!(simple_content)]
+    vec![
+        wrap_xpath_fn!(simple_content),
+        wrap_xpath_fn!(group_by),
+        wrap_xpath_fn!(group_adjacent),
+        wrap_xpath_fn!(group_starting_with),
+        wrap_xpath_fn!(group_ending_with),
+        wrap_xpath_fn!(number_candidates),
+        wrap_xpath_fn!(number_node),
+        wrap_xpath_fn!(number_format),
+    ]
 }
 
 #[cfg(test)]
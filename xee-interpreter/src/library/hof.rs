@@ -4,7 +4,7 @@ use std::rc::Rc;
 
 use ibig::IBig;
 
-use xee_name::Name;
+use xee_name::{Name, XEE_NAMESPACE};
 use xee_xpath_macros::xpath_fn;
 
 use crate::atomic;
@@ -17,6 +17,8 @@ use crate::sequence;
 use crate::string::Collation;
 use crate::wrap_xpath_fn;
 
+use super::hidden_xslt::add_to_groups;
+
 // we use the special marker context_last_optional here. The last node
 // argument, $arg, is not part of the official signature, but it is
 // required to create a static closure from the context, bind it to
@@ -66,7 +68,7 @@ fn for_each(
     seq: &sequence::Sequence,
     action: sequence::Item,
 ) -> error::Result<sequence::Sequence> {
-    let mut result: Vec<sequence::Item> = Vec::with_capacity(seq.len());
+    let mut result: Vec<sequence::Item> = Vec::with_capacity(seq.materialized_len()?);
     let function = action.to_function()?;
 
     for item in seq.iter() {
@@ -84,6 +86,7 @@ fn filter(
     seq: &sequence::Sequence,
     predicate: sequence::Item,
 ) -> error::Result<sequence::Sequence> {
+    seq.materialized_len()?;
     let mut result: Vec<sequence::Item> = Vec::new();
     let function = predicate.to_function()?;
 
@@ -126,7 +129,7 @@ fn fold_right(
 
     let mut accumulator = zero.clone();
     // TODO: do not have reverse iterator, so have to collect first
-    let seq = seq.iter().collect::<Vec<_>>();
+    let seq = seq.to_vec()?;
     for item in seq.into_iter().rev() {
         accumulator =
             interpreter.call_function_with_arguments(&function, &[item.into(), accumulator])?;
@@ -141,7 +144,9 @@ fn for_each_pair(
     seq2: &sequence::Sequence,
     action: sequence::Item,
 ) -> error::Result<sequence::Sequence> {
-    let mut result: Vec<sequence::Item> = Vec::with_capacity(seq1.len());
+    // we stop at the shorter sequence, so the longer one is never fully
+    // iterated
+    let mut result: Vec<sequence::Item> = Vec::with_capacity(seq1.len().min(seq2.len()));
     let function = action.to_function()?;
 
     for (item1, item2) in seq1.iter().zip(seq2.iter()) {
@@ -245,6 +250,138 @@ where
     Ok(items.into())
 }
 
+// A Xee extension, not in the XPath function library: group items by the
+// keys $key gives for them, like xsl:for-each-group with group-by does. An
+// item is in the group of each of its distinct keys, so an item without a
+// key isn't in any group. Each group is a map with the key and the items,
+// and the groups are in order of first appearance.
+#[xpath_fn(
+    "xee:group-by($seq as item()*, $key as function(item()) as xs:anyAtomicType*) as map(*)*"
+)]
+fn group_by2(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    seq: &sequence::Sequence,
+    key: sequence::Item,
+) -> error::Result<Vec<function::Map>> {
+    let collation = context.static_context().default_collation()?;
+    group_by(context, interpreter, seq, key, collation)
+}
+
+#[xpath_fn("xee:group-by($seq as item()*, $key as function(item()) as xs:anyAtomicType*, $collation as xs:string?) as map(*)*")]
+fn group_by3(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    seq: &sequence::Sequence,
+    key: sequence::Item,
+    collation: Option<&str>,
+) -> error::Result<Vec<function::Map>> {
+    let collation = context.static_context().resolve_collation_str(collation)?;
+    group_by(context, interpreter, seq, key, collation)
+}
+
+fn group_by(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    seq: &sequence::Sequence,
+    key: sequence::Item,
+    collation: Rc<Collation>,
+) -> error::Result<Vec<function::Map>> {
+    let function = key.to_function()?;
+    let default_offset = context.implicit_timezone();
+    let mut groups = Vec::new();
+    for item in seq.iter() {
+        let value = interpreter.call_function_with_arguments(&function, &[item.clone().into()])?;
+        // as in xsl:for-each-group, untyped keys are compared as strings
+        let keys = value
+            .atomized(interpreter.xot())
+            .map(|atom| {
+                let atom = atom?;
+                Ok(if atom.is_untyped() {
+                    atom.cast_to_string()
+                } else {
+                    atom
+                })
+            })
+            .collect::<error::Result<Vec<_>>>()?;
+        add_to_groups(&mut groups, keys, item, &collation, default_offset);
+    }
+    groups
+        .into_iter()
+        .map(|(key, items)| {
+            function::Map::new(vec![
+                ("key".to_string().into(), key.into()),
+                ("items".to_string().into(), items.into()),
+            ])
+        })
+        .collect()
+}
+
+// A Xee extension, not in the XPath function library: turn a function that
+// takes n arguments into a chain of functions that each take one argument.
+// Each application gives back a function that takes the next argument,
+// until all arguments are supplied and the original function is called. A
+// function that takes fewer than two arguments is returned as is.
+#[xpath_fn("xee:curry($f as function(*)) as function(*)")]
+fn curry(
+    context: &context::DynamicContext,
+    interpreter: &Interpreter,
+    f: sequence::Item,
+) -> error::Result<sequence::Item> {
+    let function = f.to_function()?;
+    if interpreter.function_arity(&function) < 2 {
+        return Ok(f);
+    }
+    curried_function(context, function, Vec::new())
+}
+
+// A curried function is an anonymous closure over the original function
+// and the arguments applied so far, in an array.
+fn curried_function(
+    context: &context::DynamicContext,
+    function: function::Function,
+    arguments: Vec<sequence::Sequence>,
+) -> error::Result<sequence::Item> {
+    let name = Name::new(
+        "_curried".to_string(),
+        XEE_NAMESPACE.to_string(),
+        String::new(),
+    );
+    let curried_id = context
+        .static_context()
+        .function_id_by_internal_name(&name, 1)
+        .unwrap();
+    let closure: sequence::Sequence = vec![
+        sequence::Item::from(function),
+        sequence::Item::from(function::Array::from(arguments)),
+    ]
+    .into();
+    let curried =
+        Interpreter::create_static_closure(context, curried_id, || Some(closure.clone().into()))?;
+    Ok(curried.into())
+}
+
+#[xpath_fn(
+    "xee:_curried($arg as item()*, $closure as item()*) as item()*",
+    anonymous_closure
+)]
+fn curried(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    arg: &sequence::Sequence,
+    closure: &sequence::Sequence,
+) -> error::Result<sequence::Sequence> {
+    let mut closure = closure.iter();
+    let function = closure.next().unwrap().to_function()?;
+    let mut arguments = closure.next().unwrap().to_array()?.0.as_ref().clone();
+    arguments.push(arg.clone());
+    if arguments.len() == interpreter.function_arity(&function) {
+        interpreter.call_function_with_arguments(&function, &arguments)
+    } else {
+        Ok(curried_function(context, function, arguments)?.into())
+    }
+}
+
 #[xpath_fn("fn:apply($function as function(*), $array as array(*)) as item()*")]
 fn apply(
     interpreter: &mut Interpreter,
@@ -272,6 +409,10 @@ pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
         wrap_xpath_fn!(sort1),
         wrap_xpath_fn!(sort2),
         wrap_xpath_fn!(sort3),
+        wrap_xpath_fn!(group_by2),
+        wrap_xpath_fn!(group_by3),
+        wrap_xpath_fn!(curry),
+        wrap_xpath_fn!(curried),
         wrap_xpath_fn!(apply),
     ]
 }
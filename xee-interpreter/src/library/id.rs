@@ -18,15 +18,7 @@ fn id(
     arg: impl Iterator<Item = Result<String, Error>>,
     node: Node,
 ) -> Result<Vec<Node>, Error> {
-    ids_helper(
-        arg,
-        node,
-        interpreter.xot(),
-        context
-            .documents()
-            .borrow()
-            .document_order_access(interpreter.xot()),
-    )
+    ids_helper(arg, node, interpreter.xot(), &context.documents().borrow())
 }
 
 #[xpath_fn(
@@ -39,26 +31,19 @@ fn element_with_id(
     arg: impl Iterator<Item = Result<String, Error>>,
     node: Node,
 ) -> Result<Vec<Node>, Error> {
-    // we only support xml:id so in the absence of schema information that
-    // identifies an ID element, the behavior is the same as for fn:id
-    ids_helper(
-        arg,
-        node,
-        interpreter.xot(),
-        context
-            .documents()
-            .borrow()
-            .document_order_access(interpreter.xot()),
-    )
+    // we only support ID attributes, not elements, so in the absence of
+    // schema information that identifies an ID element, the behavior is
+    // the same as for fn:id
+    ids_helper(arg, node, interpreter.xot(), &context.documents().borrow())
 }
 
 fn ids_helper(
     arg: impl Iterator<Item = Result<String, Error>>,
     node: Node,
     xot: &Xot,
-    annotations: xml::DocumentOrderAccess,
+    documents: &xml::Documents,
 ) -> Result<Vec<Node>, Error> {
-    let document_node = xot.root(node);
+    let index = documents.id_index(xot, document_node(node, xot)?);
     let mut result: Vec<Node> = Vec::new();
     let mut seen = HashSet::new();
     for idrefs in arg {
@@ -72,15 +57,53 @@ fn ids_helper(
             // find the element with the given id
             // if found, return it
             // if not found, return an empty sequence
-            if let Some(node) = xot.xml_id_node(document_node, idref) {
+            if let Some(node) = index.element(idref) {
                 result.push(node);
             }
         }
     }
+    let annotations = documents.document_order_access(xot);
     result.sort_by_key(|n| annotations.get(*n));
+    result.dedup();
     Ok(result)
 }
 
+#[xpath_fn(
+    "fn:idref($arg as xs:string*, $node as node()) as node()*",
+    context_last
+)]
+fn idref(
+    context: &DynamicContext,
+    interpreter: &Interpreter,
+    arg: impl Iterator<Item = Result<String, Error>>,
+    node: Node,
+) -> Result<Vec<Node>, Error> {
+    let xot = interpreter.xot();
+    let documents = context.documents();
+    let documents = documents.borrow();
+    let index = documents.id_index(xot, document_node(node, xot)?);
+    let mut result: Vec<Node> = Vec::new();
+    for id in arg {
+        // unlike fn:id, each string is a single id
+        let id = id?;
+        result.extend(index.referring_attributes(id.trim()));
+    }
+    let annotations = documents.document_order_access(xot);
+    result.sort_by_key(|n| annotations.get(*n));
+    result.dedup();
+    Ok(result)
+}
+
+// the root of the tree containing node, which has to be a document node
+fn document_node(node: Node, xot: &Xot) -> Result<Node, Error> {
+    let root = xot.root(node);
+    if xot.is_document(root) {
+        Ok(root)
+    } else {
+        Err(Error::FODC0001)
+    }
+}
+
 #[xpath_fn("fn:generate-id($arg as node()?) as xs:string", context_first)]
 fn generate_id(
     context: &DynamicContext,
@@ -102,6 +125,7 @@ pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
     vec![
         wrap_xpath_fn!(id),
         wrap_xpath_fn!(element_with_id),
+        wrap_xpath_fn!(idref),
         wrap_xpath_fn!(generate_id),
     ]
 }
@@ -1,19 +1,26 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::io::{BufReader, Read};
+
+use xee_name::FN_NAMESPACE;
 use xee_schema_type::Xs;
 use xee_xpath_macros::xpath_fn;
 use xot::Xot;
 
 use crate::{atomic, context, error, function, interpreter::Interpreter, sequence, wrap_xpath_fn};
 
+use super::external::text_resource;
+use super::string::is_valid_xml_char;
 use super::StaticFunctionDescription;
 
 #[xpath_fn("fn:parse-json($json_text as xs:string?) as item()?")]
 fn parse_json1(json_text: Option<&str>) -> error::Result<Option<sequence::Item>> {
     if let Some(json_text) = json_text {
-        let value = json::parse(json_text).map_err(|_| error::Error::FOJS0001)?;
-        // the spec seems to imply escape should be true by default, but then
-        // various tests fail (and escape false by default seems more
-        // reasonable) See https://github.com/w3c/qt3tests/issues/65
-        Ok(parse_json_value(&value, false)?)
+        parse_json(
+            json_text,
+            &ParseJsonParameters::without_options(),
+            &mut |_| Ok(REPLACEMENT.to_string()),
+        )
     } else {
         Ok(None)
     }
@@ -26,17 +33,72 @@ fn parse_json2(
     json_text: Option<&str>,
     options: function::Map,
 ) -> error::Result<Option<sequence::Item>> {
-    let parameters =
-        ParseJsonParameters::from_map(&options, context.static_context(), interpreter.xot())?;
-
     if let Some(json_text) = json_text {
-        let value = json::parse(json_text).map_err(|_| error::Error::FOJS0001)?;
-        Ok(parse_json_value(&value, parameters.escape)?)
+        parse_json_with_options(context, interpreter, json_text, &options)
+    } else {
+        Ok(None)
+    }
+}
+
+#[xpath_fn("fn:json-doc($href as xs:string?) as item()?")]
+fn json_doc1(
+    context: &context::DynamicContext,
+    href: Option<&str>,
+) -> error::Result<Option<sequence::Item>> {
+    if let Some(href) = href {
+        let json_text = text_resource(context, href)?;
+        parse_json(
+            json_text,
+            &ParseJsonParameters::without_options(),
+            &mut |_| Ok(REPLACEMENT.to_string()),
+        )
+    } else {
+        Ok(None)
+    }
+}
+
+#[xpath_fn("fn:json-doc($href as xs:string?, $options as map(*)) as item()?")]
+fn json_doc2(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    href: Option<&str>,
+    options: function::Map,
+) -> error::Result<Option<sequence::Item>> {
+    if let Some(href) = href {
+        let json_text = text_resource(context, href)?;
+        parse_json_with_options(context, interpreter, json_text, &options)
     } else {
         Ok(None)
     }
 }
 
+fn parse_json_with_options(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    json_text: &str,
+    options: &function::Map,
+) -> error::Result<Option<sequence::Item>> {
+    let parameters =
+        ParseJsonParameters::from_map(options, context.static_context(), interpreter.xot())?;
+
+    if let Some(fallback) = &parameters.fallback {
+        if interpreter.function_arity(fallback) != 1 {
+            return Err(error::Error::XPTY0004);
+        }
+        parse_json(json_text, &parameters, &mut |escape_sequence| {
+            let escape_sequence: atomic::Atomic = escape_sequence.to_string().into();
+            let replacement =
+                interpreter.call_function_with_arguments(fallback, &[escape_sequence.into()])?;
+            Ok(replacement.one()?.to_atomic()?.to_str()?.to_string())
+        })
+    } else {
+        parse_json(json_text, &parameters, &mut |_| Ok(REPLACEMENT.to_string()))
+    }
+}
+
+// what an invalid character is replaced with if there is no fallback function
+const REPLACEMENT: char = '\u{FFFD}';
+
 enum Duplicates {
     Reject,
     UseFirst,
@@ -46,18 +108,30 @@ enum Duplicates {
 struct ParseJsonParameters {
     // liberal is entirely ignored. we don't have a more liberal JSON parser
     liberal: bool,
-    // We cannot actually handle duplicates, as the Rust json crate
-    // does not report duplicate information and effectively implements
-    // `use-last` semantics (most common according to the JSON RFC)
     duplicates: Duplicates,
     // I don't understand why escape=true even exists, as it imports JSON
     // escaping rules into XML land where they have no meaning? But it's the
-    // default! we implement it by re-escaping...
+    // default!
     escape: bool,
-    // TODO: fallback
+    // called with the escape sequence for any escaped character that isn't
+    // allowed in XML. Cannot be combined with escape.
+    fallback: Option<function::Function>,
 }
 
 impl ParseJsonParameters {
+    // the parameters of fn:parse-json without an options map
+    fn without_options() -> Self {
+        // the spec seems to imply escape should be true by default, but then
+        // various tests fail (and escape false by default seems more
+        // reasonable) See https://github.com/w3c/qt3tests/issues/65
+        Self {
+            liberal: false,
+            duplicates: Duplicates::UseFirst,
+            escape: false,
+            fallback: None,
+        }
+    }
+
     fn from_map(
         map: &function::Map,
         static_context: &context::StaticContext,
@@ -82,25 +156,139 @@ impl ParseJsonParameters {
         // case the map isn't passed at all.
         // See https://github.com/w3c/qt3tests/issues/65
         let escape = c
-            .option_with_default("escape", Xs::Boolean, true)
+            .option("escape", Xs::Boolean)
             .map_err(|_| error::Error::FOJS0005)?;
+        let fallback = c.function("fallback")?;
+        let escape = match (escape, &fallback) {
+            (Some(true), Some(_)) => return Err(error::Error::FOJS0005),
+            (Some(escape), _) => escape,
+            // supplying a fallback implies that we don't escape
+            (None, Some(_)) => false,
+            (None, None) => true,
+        };
 
         Ok(Self {
             liberal,
             duplicates,
             escape,
+            fallback,
         })
     }
 }
 
+fn parse_json(
+    json_text: &str,
+    parameters: &ParseJsonParameters,
+    fallback: &mut dyn FnMut(&str) -> error::Result<String>,
+) -> error::Result<Option<sequence::Item>> {
+    // The json crate decodes escape sequences itself, and refuses unpaired
+    // surrogates, but we need to control how each escape sequence is
+    // handled. So we take out the strings and decode them ourselves, and let
+    // the json crate parse the rest, with each string replaced by its index.
+    // As a bonus, the json crate doesn't see duplicate keys, so we can
+    // handle those according to the duplicates option.
+    let (json_text, raw_strings) = extract_strings(json_text)?;
+    let strings = raw_strings
+        .into_iter()
+        .map(|raw| decode_string(raw, parameters.escape, fallback))
+        .collect::<error::Result<Vec<_>>>()?;
+    let value = json::parse(&json_text).map_err(|_| error::Error::FOJS0001)?;
+    parse_json_value(&value, &strings, &parameters.duplicates)
+}
+
+/// Parse a JSON array from `reader`, calling `f` with each of its members in
+/// turn.
+///
+/// Each member is parsed like `fn:parse-json` without options would, so
+/// JSON `null` is `None`. Only a single member is held in memory at a time,
+/// which makes this suitable for huge JSON documents that are an array of
+/// records, where building the whole array would take too much memory.
+///
+/// It is an error (`FOJS0001`) if the JSON text isn't an array. An error
+/// returned by `f` stops the parse and is returned. Failing to read from
+/// `reader` is an [`error::Error::Io`].
+pub fn parse_json_array_members(
+    reader: impl Read,
+    mut f: impl FnMut(Option<sequence::Item>) -> error::SpannedResult<()>,
+) -> error::SpannedResult<()> {
+    let mut bytes = BufReader::new(reader).bytes();
+    let mut next = || bytes.next().transpose().map_err(error::Error::from);
+    let parameters = ParseJsonParameters::without_options();
+
+    // skip to the opening bracket
+    loop {
+        match next()? {
+            Some(b) if is_json_whitespace(b) => {}
+            Some(b'[') => break,
+            _ => return Err(error::Error::FOJS0001.into()),
+        }
+    }
+    // collect the text of each member until the comma or closing bracket
+    // that ends it; the member is then checked and parsed as a whole, so we
+    // only need to track enough to find its end
+    let mut member = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut first = true;
+    loop {
+        let b = next()?.ok_or(error::Error::FOJS0001)?;
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                in_string = false;
+            }
+            member.push(b);
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' if depth > 0 => depth -= 1,
+            b',' | b']' if depth == 0 => {
+                let text = std::str::from_utf8(&member).map_err(|_| error::Error::FOJS0001)?;
+                // an empty array has no members
+                if !(b == b']' && first && text.bytes().all(is_json_whitespace)) {
+                    f(parse_json(text, &parameters, &mut |_| {
+                        Ok(REPLACEMENT.to_string())
+                    })?)?;
+                }
+                if b == b']' {
+                    break;
+                }
+                member.clear();
+                first = false;
+                continue;
+            }
+            _ => {}
+        }
+        member.push(b);
+    }
+    // only whitespace may follow the array
+    while let Some(b) = next()? {
+        if !is_json_whitespace(b) {
+            return Err(error::Error::FOJS0001.into());
+        }
+    }
+    Ok(())
+}
+
+fn is_json_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
 fn parse_json_value(
     value: &json::JsonValue,
-    escape: bool,
+    strings: &[String],
+    duplicates: &Duplicates,
 ) -> error::Result<Option<sequence::Item>> {
     match value {
         json::JsonValue::Null => Ok(None),
-        json::JsonValue::Short(s) => Ok(Some(parse_json_string(s.to_string(), escape).into())),
-        json::JsonValue::String(s) => Ok(Some(parse_json_string(s.to_string(), escape).into())),
+        json::JsonValue::Short(s) => Ok(Some(lookup_string(s.as_str(), strings)?.into())),
+        json::JsonValue::String(s) => Ok(Some(lookup_string(s.as_str(), strings)?.into())),
         json::JsonValue::Number(n) => {
             let f: f64 = (*n).into();
             let atomic: atomic::Atomic = f.into();
@@ -113,7 +301,7 @@ fn parse_json_value(
         json::JsonValue::Array(a) => {
             let mut entries = Vec::with_capacity(a.len());
             for value in a.iter() {
-                let value = parse_json_value(value, escape)?;
+                let value = parse_json_value(value, strings, duplicates)?;
                 let sequence: sequence::Sequence = value.into();
                 entries.push(sequence);
             }
@@ -122,13 +310,24 @@ fn parse_json_value(
             Ok(Some(function.into()))
         }
         json::JsonValue::Object(o) => {
-            let mut entries = Vec::with_capacity(o.len());
+            let mut entries: Vec<(atomic::Atomic, sequence::Sequence)> =
+                Vec::with_capacity(o.len());
+            let mut seen: HashMap<&str, usize> = HashMap::new();
 
             for (key, value) in o.iter() {
-                let key = parse_json_string(key.to_string(), escape);
-                let value = parse_json_value(value, escape)?;
+                let key = lookup_string(key, strings)?;
+                let value = parse_json_value(value, strings, duplicates)?;
                 let sequence: sequence::Sequence = value.into();
-                entries.push((key.clone(), sequence));
+                if let Some(&index) = seen.get(key) {
+                    match duplicates {
+                        Duplicates::Reject => return Err(error::Error::FOJS0003),
+                        Duplicates::UseFirst => {}
+                        Duplicates::UseLast => entries[index].1 = sequence,
+                    }
+                } else {
+                    seen.insert(key, entries.len());
+                    entries.push((key.to_string().into(), sequence));
+                }
             }
             let map = function::Map::new(entries)?;
             let function = function::Function::Map(map);
@@ -137,17 +336,452 @@ fn parse_json_value(
     }
 }
 
-fn parse_json_string(s: String, escape: bool) -> atomic::Atomic {
-    let s = s.to_string();
-    let s = if escape {
-        v_jsonescape::escape(&s).to_string()
-    } else {
-        s
+fn lookup_string<'a>(index: &str, strings: &'a [String]) -> error::Result<&'a str> {
+    // we put the indexes in ourselves in extract_strings, so they must be
+    // there
+    let index: usize = index.parse().map_err(|_| error::Error::FOJS0001)?;
+    Ok(strings[index].as_str())
+}
+
+// Replace each string in the JSON text by its index (as a string), and
+// return the raw content of the strings, without quotes and with escape
+// sequences still in place.
+fn extract_strings(json_text: &str) -> error::Result<(String, Vec<&str>)> {
+    let mut rewritten = String::with_capacity(json_text.len());
+    let mut strings = Vec::new();
+    let mut chars = json_text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '"' {
+            rewritten.push(c);
+            continue;
+        }
+        let start = i + 1;
+        loop {
+            match chars.next() {
+                Some((_, '\\')) => {
+                    // skip whatever is escaped; we check it in decode_string
+                    chars.next();
+                }
+                Some((end, '"')) => {
+                    strings.push(&json_text[start..end]);
+                    write!(rewritten, "\"{}\"", strings.len() - 1).unwrap();
+                    break;
+                }
+                Some(_) => {}
+                // an unterminated string
+                None => return Err(error::Error::FOJS0001),
+            }
+        }
+    }
+    Ok((rewritten, strings))
+}
+
+// Decode the raw content of a JSON string.
+//
+// If escape is true, special characters are represented by JSON escape
+// sequences, whether they were escaped in the input or not. Otherwise, the
+// escape sequence of any escaped character that isn't allowed in XML is
+// passed to fallback, which returns its replacement.
+fn decode_string(
+    raw: &str,
+    escape: bool,
+    fallback: &mut dyn FnMut(&str) -> error::Result<String>,
+) -> error::Result<String> {
+    let mut result = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(c) = rest.chars().next() {
+        if c != '\\' {
+            // control characters must be escaped in JSON
+            if c < '\u{20}' {
+                return Err(error::Error::FOJS0001);
+            }
+            if escape && is_special(c) {
+                push_escaped(&mut result, c as u32);
+            } else {
+                result.push(c);
+            }
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let (decoded, len) = decode_escape_sequence(rest)?;
+        let escape_sequence = &rest[..len];
+        match decoded {
+            Some(c) if escape && is_special(c) => push_escaped(&mut result, c as u32),
+            Some(c) if escape || is_valid_xml_char(c) => result.push(c),
+            // an unpaired surrogate
+            None if escape => push_escaped(&mut result, code_unit(escape_sequence)?),
+            _ => result.push_str(&fallback(escape_sequence)?),
+        }
+        rest = &rest[len..];
+    }
+    Ok(result)
+}
+
+// Decode the escape sequence at the start of s. Returns the character, or
+// None for an unpaired surrogate, and the length of the escape sequence.
+fn decode_escape_sequence(s: &str) -> error::Result<(Option<char>, usize)> {
+    let c = match s.as_bytes().get(1) {
+        Some(b'"') => '"',
+        Some(b'\\') => '\\',
+        Some(b'/') => '/',
+        Some(b'b') => '\u{08}',
+        Some(b'f') => '\u{0C}',
+        Some(b'n') => '\n',
+        Some(b'r') => '\r',
+        Some(b't') => '\t',
+        Some(b'u') => {
+            let high = code_unit(s)?;
+            if (0xD800..0xDC00).contains(&high) {
+                // a high surrogate needs to be followed by a low surrogate
+                if let Some(low) = s.get(6..).and_then(|s| code_unit(s).ok()) {
+                    if (0xDC00..0xE000).contains(&low) {
+                        let c = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                        return Ok((char::from_u32(c), 12));
+                    }
+                }
+            }
+            return Ok((char::from_u32(high), 6));
+        }
+        _ => return Err(error::Error::FOJS0001),
+    };
+    Ok((Some(c), 2))
+}
+
+// The code unit of a `\uXXXX` escape sequence at the start of s.
+fn code_unit(s: &str) -> error::Result<u32> {
+    let hex = s
+        .strip_prefix("\\u")
+        .and_then(|s| s.get(..4))
+        .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or(error::Error::FOJS0001)?;
+    u32::from_str_radix(hex, 16).map_err(|_| error::Error::FOJS0001)
+}
+
+fn is_special(c: char) -> bool {
+    c < '\u{20}' || ('\u{7F}'..='\u{9F}').contains(&c) || c == '\\' || !is_valid_xml_char(c)
+}
+
+fn push_escaped(s: &mut String, code_unit: u32) {
+    match code_unit {
+        0x08 => s.push_str("\\b"),
+        0x0C => s.push_str("\\f"),
+        0x0A => s.push_str("\\n"),
+        0x0D => s.push_str("\\r"),
+        0x09 => s.push_str("\\t"),
+        0x5C => s.push_str("\\\\"),
+        _ => write!(s, "\\u{:04X}", code_unit).unwrap(),
+    }
+}
+
+#[xpath_fn("fn:xml-to-json($input as node()?) as xs:string?")]
+fn xml_to_json1(
+    interpreter: &Interpreter,
+    input: Option<xot::Node>,
+) -> error::Result<Option<String>> {
+    input
+        .map(|node| xml_to_json(interpreter.xot(), node, false))
+        .transpose()
+}
+
+#[xpath_fn("fn:xml-to-json($input as node()?, $options as map(*)) as xs:string?")]
+fn xml_to_json2(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    input: Option<xot::Node>,
+    options: function::Map,
+) -> error::Result<Option<String>> {
+    let c = sequence::OptionParameterConverter::new(
+        &options,
+        context.static_context(),
+        interpreter.xot(),
+    );
+    let indent = c.option_with_default("indent", Xs::Boolean, false)?;
+    input
+        .map(|node| xml_to_json(interpreter.xot(), node, indent))
+        .transpose()
+}
+
+fn xml_to_json(xot: &Xot, node: xot::Node, indent: bool) -> error::Result<String> {
+    let element = match xot.value(node) {
+        xot::Value::Element(_) => node,
+        // a document node must have exactly one element child, and no text
+        xot::Value::Document => {
+            let mut elements = Vec::new();
+            for child in xot.children(node) {
+                match xot.value(child) {
+                    xot::Value::Element(_) => elements.push(child),
+                    xot::Value::Text(_) => return Err(error::Error::FOJS0006),
+                    _ => {}
+                }
+            }
+            match elements.as_slice() {
+                [element] => *element,
+                _ => return Err(error::Error::FOJS0006),
+            }
+        }
+        _ => return Err(error::Error::FOJS0006),
     };
-    let atomic: atomic::Atomic = s.into();
-    atomic
+    let mut writer = JsonWriter {
+        xot,
+        indent,
+        depth: 0,
+        output: String::new(),
+    };
+    writer.write_value(element)?;
+    Ok(writer.output)
+}
+
+// Writes the XML representation of JSON, as produced by fn:json-to-xml, as
+// JSON text.
+//
+// https://www.w3.org/TR/xpath-functions-31/#json-to-xml-mapping
+struct JsonWriter<'a> {
+    xot: &'a Xot,
+    indent: bool,
+    depth: usize,
+    output: String,
+}
+
+impl<'a> JsonWriter<'a> {
+    fn write_value(&mut self, node: xot::Node) -> error::Result<()> {
+        let element = self.xot.element(node).ok_or(error::Error::FOJS0006)?;
+        let (local_name, namespace) = self.xot.name_ns_str(element.name());
+        if namespace != FN_NAMESPACE {
+            return Err(error::Error::FOJS0006);
+        }
+        self.check_attributes(node)?;
+        match local_name {
+            "null" => {
+                if self.xot.children(node).any(|child| {
+                    matches!(
+                        self.xot.value(child),
+                        xot::Value::Element(_) | xot::Value::Text(_)
+                    )
+                }) {
+                    return Err(error::Error::FOJS0006);
+                }
+                self.output.push_str("null");
+            }
+            "boolean" => {
+                let content = self.simple_content(node)?;
+                let b = atomic::Atomic::parse_boolean(trim_whitespace(&content))
+                    .map_err(|_| error::Error::FOJS0006)?;
+                self.output.push_str(if b { "true" } else { "false" });
+            }
+            "number" => {
+                let content: atomic::Atomic = self.simple_content(node)?.into();
+                // the number is output in the canonical lexical form of
+                // xs:double, but infinity and NaN can't be represented in
+                // JSON
+                match content.cast_to_double() {
+                    Ok(number @ atomic::Atomic::Double(d)) if d.is_finite() => {
+                        self.output.push_str(&number.string_value())
+                    }
+                    _ => return Err(error::Error::FOJS0006),
+                }
+            }
+            "string" => {
+                let content = self.simple_content(node)?;
+                let escaped = self.boolean_attribute(node, "escaped")?;
+                self.write_string(&content, escaped)?;
+            }
+            "array" => {
+                let members = self.members(node)?;
+                self.output.push('[');
+                self.depth += 1;
+                for (i, member) in members.iter().enumerate() {
+                    self.write_separator(i);
+                    self.write_value(*member)?;
+                }
+                self.depth -= 1;
+                self.write_close(members.is_empty(), ']');
+            }
+            "map" => {
+                let members = self.members(node)?;
+                let mut keys = HashSet::new();
+                self.output.push('{');
+                self.depth += 1;
+                for (i, member) in members.iter().enumerate() {
+                    let key = self
+                        .attribute(*member, "key")
+                        .ok_or(error::Error::FOJS0006)?;
+                    let escaped_key = self.boolean_attribute(*member, "escaped-key")?;
+                    // keys are compared after unescaping
+                    let unescaped_key = if escaped_key {
+                        unescape(key)?
+                    } else {
+                        key.to_string()
+                    };
+                    if !keys.insert(unescaped_key) {
+                        return Err(error::Error::FOJS0006);
+                    }
+                    self.write_separator(i);
+                    self.write_string(key, escaped_key)?;
+                    self.output.push(':');
+                    if self.indent {
+                        self.output.push(' ');
+                    }
+                    self.write_value(*member)?;
+                }
+                self.depth -= 1;
+                self.write_close(members.is_empty(), '}');
+            }
+            _ => return Err(error::Error::FOJS0006),
+        }
+        Ok(())
+    }
+
+    // Attributes in no namespace are restricted to the ones we know, and
+    // their values must be valid. Attributes in other namespaces, such as
+    // xml:base or xsi:type, are ignored, except for the functions namespace.
+    fn check_attributes(&self, node: xot::Node) -> error::Result<()> {
+        for attribute_node in self.xot.attribute_nodes(node) {
+            let attribute = self.xot.attribute_node(attribute_node).unwrap();
+            let (local_name, namespace) = self.xot.name_ns_str(attribute.name());
+            match (namespace, local_name) {
+                ("", "key") => {}
+                ("", "escaped" | "escaped-key") => {
+                    atomic::Atomic::parse_boolean(trim_whitespace(attribute.value()))
+                        .map_err(|_| error::Error::FOJS0006)?;
+                }
+                ("", _) | (FN_NAMESPACE, _) => return Err(error::Error::FOJS0006),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn attribute(&self, node: xot::Node, local_name: &str) -> Option<&'a str> {
+        self.xot.attribute_nodes(node).find_map(|attribute_node| {
+            let attribute = self.xot.attribute_node(attribute_node).unwrap();
+            (self.xot.name_ns_str(attribute.name()) == (local_name, "")).then(|| attribute.value())
+        })
+    }
+
+    fn boolean_attribute(&self, node: xot::Node, local_name: &str) -> error::Result<bool> {
+        if let Some(value) = self.attribute(node, local_name) {
+            atomic::Atomic::parse_boolean(trim_whitespace(value))
+                .map_err(|_| error::Error::FOJS0006)
+        } else {
+            Ok(false)
+        }
+    }
+
+    // The text content of a string, number or boolean. Comments and
+    // processing instructions are ignored, but elements aren't allowed.
+    fn simple_content(&self, node: xot::Node) -> error::Result<String> {
+        let mut content = String::new();
+        for child in self.xot.children(node) {
+            match self.xot.value(child) {
+                xot::Value::Text(text) => content.push_str(text.get()),
+                xot::Value::Element(_) => return Err(error::Error::FOJS0006),
+                _ => {}
+            }
+        }
+        Ok(content)
+    }
+
+    // The element children of an array or map. Whitespace, comments and
+    // processing instructions in between are ignored, but other text isn't
+    // allowed.
+    fn members(&self, node: xot::Node) -> error::Result<Vec<xot::Node>> {
+        let mut members = Vec::new();
+        for child in self.xot.children(node) {
+            match self.xot.value(child) {
+                xot::Value::Element(_) => members.push(child),
+                xot::Value::Text(text) if !trim_whitespace(text.get()).is_empty() => {
+                    return Err(error::Error::FOJS0006)
+                }
+                _ => {}
+            }
+        }
+        Ok(members)
+    }
+
+    fn write_separator(&mut self, index: usize) {
+        if index > 0 {
+            self.output.push(',');
+        }
+        self.write_newline();
+    }
+
+    fn write_close(&mut self, empty: bool, close: char) {
+        if !empty {
+            self.write_newline();
+        }
+        self.output.push(close);
+    }
+
+    fn write_newline(&mut self) {
+        if self.indent {
+            self.output.push('\n');
+            for _ in 0..self.depth {
+                self.output.push_str("  ");
+            }
+        }
+    }
+
+    // If escaped is true, the string may already contain JSON escape
+    // sequences, which are copied unchanged; they must be valid. Any other
+    // special character is escaped.
+    fn write_string(&mut self, s: &str, escaped: bool) -> error::Result<()> {
+        self.output.push('"');
+        let mut rest = s;
+        while let Some(c) = rest.chars().next() {
+            if c == '\\' && escaped {
+                let (_, len) = decode_escape_sequence(rest).map_err(|_| error::Error::FOJS0007)?;
+                self.output.push_str(&rest[..len]);
+                rest = &rest[len..];
+                continue;
+            }
+            match c {
+                '"' => self.output.push_str("\\\""),
+                '/' => self.output.push_str("\\/"),
+                '\\' => self.output.push_str("\\\\"),
+                c if c < '\u{20}' || ('\u{7F}'..='\u{9F}').contains(&c) => {
+                    push_escaped(&mut self.output, c as u32)
+                }
+                c => self.output.push(c),
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+        self.output.push('"');
+        Ok(())
+    }
+}
+
+// Decode the escape sequences in a string that has escaped="true" or
+// escaped-key="true". Unpaired surrogates are kept as escape sequences.
+fn unescape(s: &str) -> error::Result<String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        if c != '\\' {
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let (decoded, len) = decode_escape_sequence(rest).map_err(|_| error::Error::FOJS0007)?;
+        match decoded {
+            Some(c) => result.push(c),
+            None => result.push_str(&rest[..len].to_uppercase()),
+        }
+        rest = &rest[len..];
+    }
+    Ok(result)
+}
+
+fn trim_whitespace(s: &str) -> &str {
+    s.trim_matches(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
 }
 
 pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
-    vec![wrap_xpath_fn!(parse_json1), wrap_xpath_fn!(parse_json2)]
+    vec![
+        wrap_xpath_fn!(parse_json1),
+        wrap_xpath_fn!(parse_json2),
+        wrap_xpath_fn!(json_doc1),
+        wrap_xpath_fn!(json_doc2),
+        wrap_xpath_fn!(xml_to_json1),
+        wrap_xpath_fn!(xml_to_json2),
+    ]
 }
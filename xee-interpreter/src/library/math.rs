@@ -53,8 +53,13 @@ fn pow(x: Option<f64>, y: Atomic) -> error::Result<Option<f64>> {
     if let Some(x) = x {
         match y {
             Atomic::Integer(_, i) => {
-                let i: i32 = i.as_ref().try_into()?;
-                Ok(Some(x.powi(i)))
+                // an exponent this large overflows (or underflows) anyway,
+                // except for the special cases which powf handles too
+                if let Ok(i) = i32::try_from(i.as_ref()) {
+                    Ok(Some(x.powi(i)))
+                } else {
+                    Ok(Some(x.powf(i.to_f64())))
+                }
             }
             Atomic::Decimal(_) => {
                 let f = Atomic::parse_atomic::<f64>(&y.into_canonical())?;
@@ -136,9 +141,9 @@ fn atan(arg: Option<f64>) -> error::Result<Option<f64>> {
     }
 }
 
-#[xpath_fn("math:atan2($y as xs:double, $x as xs:double) as xs:double?")]
-fn atan2(y: f64, x: f64) -> error::Result<Option<f64>> {
-    Ok(Some(y.atan2(x)))
+#[xpath_fn("math:atan2($y as xs:double, $x as xs:double) as xs:double")]
+fn atan2(y: f64, x: f64) -> f64 {
+    y.atan2(x)
 }
 
 pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
@@ -1,6 +1,7 @@
 /// XPath defines a standard function library, and this module implements
 /// it.
 mod accessor;
+mod accumulator;
 mod array;
 mod boolean;
 mod context;
@@ -20,8 +21,12 @@ mod parse;
 mod qname;
 mod sequence;
 mod string;
+mod transform;
 mod uri;
 mod xs;
+mod xslt;
+
+pub use json::parse_json_array_members;
 
 use crate::function::StaticFunctionDescription;
 
@@ -49,5 +54,14 @@ pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
     descriptions.extend(parse::static_function_descriptions());
     descriptions.extend(json::static_function_descriptions());
     descriptions.extend(id::static_function_descriptions());
+    descriptions.extend(transform::static_function_descriptions());
+    descriptions
+}
+
+/// The functions XSLT adds to the standard function library. These are only
+/// available to stylesheets.
+pub(crate) fn xslt_static_function_descriptions() -> Vec<StaticFunctionDescription> {
+    let mut descriptions = xslt::static_function_descriptions();
+    descriptions.extend(accumulator::static_function_descriptions());
     descriptions
 }
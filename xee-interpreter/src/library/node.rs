@@ -5,10 +5,14 @@ use ahash::HashSetExt;
 use xee_xpath_macros::xpath_fn;
 
 use crate::atomic;
+use crate::context;
 use crate::error;
 use crate::function::StaticFunctionDescription;
 use crate::interpreter::Interpreter;
+use crate::library::accessor::node_base_uri;
+use crate::sequence;
 use crate::wrap_xpath_fn;
+use crate::xml;
 
 #[xpath_fn("fn:name($arg as node()?) as xs:string", context_first)]
 fn name(interpreter: &Interpreter, arg: Option<xot::Node>) -> error::Result<String> {
@@ -152,109 +156,130 @@ fn outermost(
 
 #[xpath_fn("fn:path($arg as node()?) as xs:string?", context_first)]
 fn path(interpreter: &Interpreter, arg: Option<xot::Node>) -> Option<String> {
-    if let Some(node) = arg {
-        if interpreter.xot().is_document(node) {
-            Some("/".to_string())
-        } else {
-            Some(path_helper(node, interpreter.xot()))
-        }
-    } else {
-        None
-    }
+    arg.map(|node| xml::node_path(node, interpreter.xot()))
 }
 
-fn path_helper(node: xot::Node, xot: &xot::Xot) -> String {
-    match xot.value(node) {
-        xot::Value::Document => "".to_string(),
-        xot::Value::Element(e) => {
-            let name = e.name();
-            let (local, ns) = xot.name_ns_str(name);
-            let position = position_by_type(node, xot, |child, xot| {
-                if let Some(element) = xot.element(child) {
-                    element.name() == name
-                } else {
-                    false
-                }
-            });
-            let path = parent_path(node, xot);
-            format!("{}/Q{{{}}}{}[{}]", path, ns, local, position)
-        }
-        xot::Value::Text(_) => {
-            let position = position_by_type(node, xot, |child, xot| xot.is_text(child));
-            format!("{}/text()[{}]", parent_path(node, xot), position)
-        }
-        xot::Value::Comment(_) => {
-            let position = position_by_type(node, xot, |child, xot| xot.is_comment(child));
-            format!("{}/comment()[{}]", parent_path(node, xot), position)
-        }
-        xot::Value::ProcessingInstruction(p) => {
-            let target = p.target();
-            let position = position_by_type(node, xot, |child, xot| {
-                if let Some(processing_instruction) = xot.processing_instruction(child) {
-                    processing_instruction.target() == target
-                } else {
-                    false
-                }
-            });
-            let (local, _) = xot.name_ns_str(target);
+#[xpath_fn("fn:copy-of($input as item()*) as item()*", context_first)]
+fn copy_of(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    input: &sequence::Sequence,
+) -> error::Result<Vec<sequence::Item>> {
+    input
+        .iter()
+        .map(|item| match item {
+            sequence::Item::Node(node) => {
+                let copy = deep_copy_node(interpreter.xot_mut(), node);
+                preserve_base_uri(context, interpreter, node, copy)?;
+                Ok(sequence::Item::Node(copy))
+            }
+            _ => Ok(item),
+        })
+        .collect()
+}
 
-            format!(
-                "{}/processing-instruction({})[{}]",
-                parent_path(node, xot),
-                local,
-                position
-            )
-        }
-        xot::Value::Attribute(attribute) => {
-            let name = attribute.name();
-            let (local, ns) = xot.name_ns_str(name);
-            let s = if ns.is_empty() {
-                local.to_string()
-            } else {
-                format!("Q{{{}}}{}", ns, local)
-            };
-            format!("{}/@{}", parent_path(node, xot), s)
-        }
-        xot::Value::Namespace(n) => {
-            let prefix = n.prefix();
-            let s = if xot.empty_prefix() != prefix {
-                xot.prefix_str(prefix)
-            } else {
-                "*[Q{http://www.w3.org/2005/xpath-functions}local-name()=\"\"]"
-            };
-            format!("{}/namespace::{}", parent_path(node, xot), s)
+#[xpath_fn("fn:snapshot($input as item()*) as item()*", context_first)]
+fn snapshot(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    input: &sequence::Sequence,
+) -> error::Result<Vec<sequence::Item>> {
+    input
+        .iter()
+        .map(|item| match item {
+            sequence::Item::Node(node) => {
+                let copy = snapshot_node(interpreter.xot_mut(), node)?;
+                let root = interpreter.xot().root(node);
+                let root_copy = interpreter.xot().root(copy);
+                preserve_base_uri(context, interpreter, root, root_copy)?;
+                Ok(sequence::Item::Node(copy))
+            }
+            _ => Ok(item),
+        })
+        .collect()
+}
+
+// Deep copy the node. Unlike a plain clone, an element copy keeps all the
+// namespaces that were in scope for the original.
+fn deep_copy_node(xot: &mut xot::Xot, node: xot::Node) -> xot::Node {
+    let copy = xot.clone_node(node);
+    if !xot.is_element(copy) {
+        return copy;
+    }
+    if let Some(parent) = xot.parent(node) {
+        let in_scope = xot.namespaces_in_scope(parent).collect::<Vec<_>>();
+        let mut namespaces = xot.namespaces_mut(copy);
+        for (prefix, namespace) in in_scope {
+            if !namespaces.contains_key(prefix) {
+                namespaces.insert(prefix, namespace);
+            }
         }
     }
+    copy
 }
 
-fn parent_path(node: xot::Node, xot: &xot::Xot) -> String {
-    if let Some(parent) = xot.parent(node) {
-        path_helper(parent, xot)
-    } else {
-        "Q{http://www.w3.org/2005/xpath-functions}root()".to_string()
+// Deep copy the node, and then wrap it in shallow copies of its ancestors,
+// including their attributes and namespaces. Returns the copy of the node
+// itself.
+fn snapshot_node(xot: &mut xot::Xot, node: xot::Node) -> error::Result<xot::Node> {
+    let mut result = xot.clone_node(node);
+    let mut copy = result;
+    let mut current = node;
+    while let Some(parent) = xot.parent(current) {
+        let parent_copy = shallow_copy_node(xot, parent);
+        let appended = xot.any_append(parent_copy, copy)?;
+        // attributes and namespaces that already exist are updated
+        // in place, so we have to find our result again
+        if current == node {
+            result = appended;
+        }
+        copy = parent_copy;
+        current = parent;
     }
+    Ok(result)
 }
 
-fn position_by_type(
-    node: xot::Node,
-    xot: &xot::Xot,
-    is_type: impl Fn(xot::Node, &xot::Xot) -> bool,
-) -> usize {
-    let mut position = 1;
-    let parent = xot.parent(node);
-    if let Some(parent) = parent {
-        for child in xot.children(parent) {
-            if child == node {
-                return position;
-            }
-            if is_type(child, xot) {
-                position += 1;
-            }
+fn shallow_copy_node(xot: &mut xot::Xot, node: xot::Node) -> xot::Node {
+    if let Some(element) = xot.element(node) {
+        let copy = xot.new_element(element.name());
+        for (prefix, namespace) in xot.prefixes(node) {
+            xot.namespaces_mut(copy).insert(prefix, namespace);
         }
-        unreachable!()
+        let attributes = xot.attribute_nodes(node).collect::<Vec<_>>();
+        for attribute in attributes {
+            let attribute_copy = xot.clone_node(attribute);
+            xot.any_append(copy, attribute_copy).unwrap();
+        }
+        copy
     } else {
-        1
+        // only documents and elements can be ancestors
+        xot.new_document()
+    }
+}
+
+// A copy is no longer part of the original document, so it loses its
+// document URI and any inherited xml:base. To keep the base URI the same we
+// register the base URI the copy inherited as that of its root.
+fn preserve_base_uri(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    original: xot::Node,
+    copy: xot::Node,
+) -> error::Result<()> {
+    let base_uri = match interpreter.xot().parent(original) {
+        Some(parent) => node_base_uri(context, interpreter, parent)?,
+        None => context
+            .documents()
+            .borrow()
+            .get_base_uri_by_document_node(original),
+    };
+    if let Some(base_uri) = base_uri {
+        context
+            .documents()
+            .borrow_mut()
+            .set_base_uri(copy, base_uri);
     }
+    Ok(())
 }
 
 pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
@@ -268,5 +293,7 @@ pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
         wrap_xpath_fn!(innermost),
         wrap_xpath_fn!(outermost),
         wrap_xpath_fn!(path),
+        wrap_xpath_fn!(copy_of),
+        wrap_xpath_fn!(snapshot),
     ]
 }
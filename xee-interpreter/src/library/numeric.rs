@@ -7,6 +7,7 @@ use rand::prelude::*;
 use rand_xoshiro::SplitMix64;
 
 use xee_name::{Name, FN_NAMESPACE};
+use xee_xpath_ast::parse_name;
 use xee_xpath_macros::xpath_fn;
 
 use crate::atomic::round_atomic;
@@ -18,6 +19,7 @@ use crate::function;
 use crate::function::StaticFunctionDescription;
 use crate::interpreter::Interpreter;
 use crate::sequence;
+use crate::string::{IntegerPicture, NumberPicture};
 use crate::wrap_xpath_fn;
 
 #[xpath_fn("fn:abs($arg as xs:numeric?) as xs:numeric?")]
@@ -77,8 +79,7 @@ fn round1(arg: Option<Atomic>) -> error::Result<Option<Atomic>> {
 #[xpath_fn("fn:round($arg as xs:numeric?, $precision as xs:integer) as xs:numeric?")]
 fn round2(arg: Option<Atomic>, precision: IBig) -> error::Result<Option<Atomic>> {
     if let Some(arg) = arg {
-        let precision: i32 = precision.try_into().map_err(|_| error::Error::FOAR0002)?;
-        round_atomic(arg, precision).map(Some)
+        round_atomic(arg, clamp_precision(&precision)).map(Some)
     } else {
         Ok(None)
     }
@@ -96,13 +97,89 @@ fn round_half_to_even1(arg: Option<Atomic>) -> error::Result<Option<Atomic>> {
 #[xpath_fn("fn:round-half-to-even($arg as xs:numeric?, $precision as xs:integer) as xs:numeric?")]
 fn round_half_to_even2(arg: Option<Atomic>, precision: IBig) -> error::Result<Option<Atomic>> {
     if let Some(arg) = arg {
-        let precision: i32 = precision.try_into().map_err(|_| error::Error::FOAR0002)?;
-        round_half_to_even_atomic(arg, precision).map(Some)
+        round_half_to_even_atomic(arg, clamp_precision(&precision)).map(Some)
     } else {
         Ok(None)
     }
 }
 
+// a precision beyond what fits in an i32 is far beyond what any number we
+// support can represent, so it rounds the same as the nearest i32
+fn clamp_precision(precision: &IBig) -> i32 {
+    precision
+        .try_into()
+        .unwrap_or(if precision < &IBig::from(0) {
+            i32::MIN
+        } else {
+            i32::MAX
+        })
+}
+
+#[xpath_fn("fn:format-integer($value as xs:integer?, $picture as xs:string) as xs:string")]
+fn format_integer2(value: Option<IBig>, picture: &str) -> error::Result<String> {
+    format_integer(value, picture)
+}
+
+// We only support English, so the language is ignored, as allowed by the
+// specification for languages that aren't supported.
+#[xpath_fn(
+    "fn:format-integer($value as xs:integer?, $picture as xs:string, $lang as xs:string?) as xs:string"
+)]
+fn format_integer3(
+    value: Option<IBig>,
+    picture: &str,
+    _lang: Option<&str>,
+) -> error::Result<String> {
+    format_integer(value, picture)
+}
+
+fn format_integer(value: Option<IBig>, picture: &str) -> error::Result<String> {
+    // the picture is checked even if there is no value
+    let picture = IntegerPicture::parse(picture)?;
+    Ok(if let Some(value) = value {
+        picture.format(&value)
+    } else {
+        String::new()
+    })
+}
+
+#[xpath_fn("fn:format-number($value as xs:numeric?, $picture as xs:string) as xs:string")]
+fn format_number2(
+    context: &context::DynamicContext,
+    value: Option<Atomic>,
+    picture: &str,
+) -> error::Result<String> {
+    format_number3(context, value, picture, None)
+}
+
+#[xpath_fn(
+    "fn:format-number($value as xs:numeric?, $picture as xs:string, $decimal_format_name as xs:string?) as xs:string"
+)]
+fn format_number3(
+    context: &context::DynamicContext,
+    value: Option<Atomic>,
+    picture: &str,
+    decimal_format_name: Option<&str>,
+) -> error::Result<String> {
+    let static_context = context.static_context();
+    // the name is an EQName or a lexical QName, where no prefix means no
+    // namespace
+    let name = decimal_format_name
+        .map(|name| {
+            parse_name(name.trim(), static_context.namespaces())
+                .map(|name| name.value)
+                .map_err(|_| error::Error::FODF1280)
+        })
+        .transpose()?;
+    let decimal_format = static_context
+        .decimal_format(name.as_ref())
+        .ok_or(error::Error::FODF1280)?;
+    let picture = NumberPicture::parse(picture, decimal_format)?;
+    // the empty sequence is formatted like NaN
+    let value = value.unwrap_or_else(|| f64::NAN.into());
+    picture.format(&value, decimal_format)
+}
+
 #[xpath_fn("fn:number($arg as xs:anyAtomicType?) as xs:double", context_first)]
 fn number(arg: Option<Atomic>) -> error::Result<Atomic> {
     if let Some(arg) = arg {
@@ -183,15 +260,15 @@ fn rng_next(context: &context::DynamicContext, seed: u64) -> error::Result<funct
     "fn:_rng-permute($arg as item()*, $seed as xs:unsignedLong) as item()*",
     anonymous_closure
 )]
-fn rng_permute(arg: &sequence::Sequence, seed: u64) -> sequence::Sequence {
+fn rng_permute(arg: &sequence::Sequence, seed: u64) -> error::Result<sequence::Sequence> {
     // don't use the seed directly, since rejection sampling can cause
     // consecutive seeds in a next() sequence to produce the same shuffle.
     // Adding a level of indirection breaks up this correlation.
     // TODO: mix an argument-based hash into the seed for better randomness.
     let shuffle_seed = SplitMix64::seed_from_u64(seed).next_u64();
-    let mut items = arg.iter().collect::<Vec<_>>();
+    let mut items = arg.to_vec()?;
     items.shuffle(&mut SplitMix64::seed_from_u64(shuffle_seed));
-    items.into()
+    Ok(items.into())
 }
 
 pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
@@ -203,6 +280,10 @@ pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
         wrap_xpath_fn!(round2),
         wrap_xpath_fn!(round_half_to_even1),
         wrap_xpath_fn!(round_half_to_even2),
+        wrap_xpath_fn!(format_integer2),
+        wrap_xpath_fn!(format_integer3),
+        wrap_xpath_fn!(format_number2),
+        wrap_xpath_fn!(format_number3),
         wrap_xpath_fn!(number),
         wrap_xpath_fn!(random_number_generator0),
         wrap_xpath_fn!(random_number_generator1),
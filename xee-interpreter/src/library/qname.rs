@@ -145,17 +145,15 @@ fn namespace_uri_for_prefix(
     prefix: Option<&str>,
     node: xot::Node,
 ) -> error::Result<Option<atomic::Atomic>> {
-    if let Some(prefix) = prefix {
-        // TODO: efficiency could be made faster if we used NameSpaceLookup, see
-        // resolve-QName
-
-        let namespaces = element_namespaces(node, interpreter.xot());
-        Ok(namespaces
-            .by_prefix(prefix)
-            .map(|s| atomic::Atomic::String(atomic::StringType::AnyURI, s.to_string().into())))
-    } else {
-        Ok(None)
-    }
+    // the empty sequence, like the empty string, stands for the default
+    // namespace
+    let prefix = prefix.unwrap_or("");
+    // TODO: efficiency could be made faster if we used NameSpaceLookup, see
+    // resolve-QName
+    let namespaces = element_namespaces(node, interpreter.xot());
+    Ok(namespaces
+        .by_prefix(prefix)
+        .map(|s| atomic::Atomic::String(atomic::StringType::AnyURI, s.to_string().into())))
 }
 
 #[xpath_fn("fn:in-scope-prefixes($element as element()) as xs:string*")]
@@ -1,7 +1,8 @@
 // https://www.w3.org/TR/xpath-functions-31/#sequence-functions
 
-use ahash::HashMap;
+use ahash::{HashMap, HashMapExt};
 use ibig::IBig;
+use xee_schema_type::Xs;
 use xee_xpath_macros::xpath_fn;
 
 use crate::atomic::op_add;
@@ -13,6 +14,7 @@ use crate::atomic::OpLt;
 use crate::atomic::StringType;
 use crate::context::DynamicContext;
 use crate::error;
+use crate::function;
 use crate::function::StaticFunctionDescription;
 use crate::interpreter::Interpreter;
 use crate::sequence;
@@ -39,6 +41,10 @@ fn tail(arg: &sequence::Sequence) -> sequence::Sequence {
     if arg.is_empty() {
         return sequence::Sequence::default();
     }
+    // the tail of a range is a range, so we don't have to materialize it
+    if let sequence::Sequence::Range(range) = arg {
+        return range.subrange(1, arg.len()).into();
+    }
     let mut items = arg.iter();
     // skip first item
     items.next();
@@ -71,7 +77,7 @@ fn insert_before(
     };
 
     let mut target_items = target.iter();
-    let mut result = Vec::with_capacity(target.len() + inserts.len());
+    let mut result = Vec::with_capacity(target.materialized_len()? + inserts.materialized_len()?);
     let mut i = 0;
     if position > 0 {
         for item in target_items.by_ref() {
@@ -103,30 +109,30 @@ fn remove(target: &sequence::Sequence, position: IBig) -> error::Result<sequence
         return Ok(target.clone());
     }
     let position = position.saturating_sub(1);
-    let mut target = target.clone().iter().collect::<Vec<_>>();
+    let mut target = target.to_vec()?;
     target.remove(position);
     Ok(target.into())
 }
 
 #[xpath_fn("fn:reverse($arg as item()*) as item()*")]
-fn reverse(arg: &sequence::Sequence) -> sequence::Sequence {
+fn reverse(arg: &sequence::Sequence) -> error::Result<sequence::Sequence> {
     if arg.is_empty() {
-        return arg.clone();
+        return Ok(arg.clone());
     }
-    let mut items = arg.clone().iter().collect::<Vec<_>>();
+    let mut items = arg.to_vec()?;
     items.reverse();
-    items.into()
+    Ok(items.into())
 }
 
 #[xpath_fn("fn:subsequence($sourceSeq as item()*, $startingLoc as xs:double) as item()*")]
-fn subsequence2(source_seq: &sequence::Sequence, starting_loc: f64) -> Vec<sequence::Item> {
+fn subsequence2(source_seq: &sequence::Sequence, starting_loc: f64) -> sequence::Sequence {
     if starting_loc.is_nan() {
-        return Vec::new();
+        return sequence::Sequence::default();
     }
-    let starting_loc = starting_loc - 1.0;
+    let starting_loc = starting_loc.round() - 1.0;
     let starting_loc = starting_loc.clamp(0.0, (source_seq.len()) as f64);
     let starting_loc = starting_loc as usize;
-    source_seq.iter().skip(starting_loc).collect()
+    subsequence(source_seq, starting_loc, source_seq.len())
 }
 
 #[xpath_fn(
@@ -136,24 +142,33 @@ fn subsequence3(
     source_seq: &sequence::Sequence,
     starting_loc: f64,
     length: f64,
-) -> Vec<sequence::Item> {
+) -> sequence::Sequence {
     let starting_loc = starting_loc.round();
     let starting_loc = starting_loc - 1.0;
     let length = length.round();
     let end = starting_loc + length;
     if end.is_nan() {
-        return Vec::new();
+        return sequence::Sequence::default();
     }
     let starting_loc = starting_loc.clamp(0.0, (source_seq.len()) as f64);
     let end = end.clamp(starting_loc, (source_seq.len()) as f64);
     let starting_loc = starting_loc as usize;
     let end = end as usize;
+    subsequence(source_seq, starting_loc, end)
+}
 
-    source_seq
-        .iter()
-        .skip(starting_loc)
-        .take(end - starting_loc)
-        .collect()
+fn subsequence(source_seq: &sequence::Sequence, start: usize, end: usize) -> sequence::Sequence {
+    match source_seq {
+        // we don't want to materialize a large range, so we take a range out
+        // of it instead
+        sequence::Sequence::Range(range) if end - start > 1 => range.subrange(start, end).into(),
+        _ => source_seq
+            .iter()
+            .skip(start)
+            .take(end - start)
+            .collect::<Vec<_>>()
+            .into(),
+    }
 }
 
 #[xpath_fn("fn:unordered($sourceSeq as item()*) as item()*")]
@@ -167,20 +182,24 @@ fn unordered(source_seq: &sequence::Sequence) -> sequence::Sequence {
 )]
 fn distinct_values(
     context: &DynamicContext,
-    arg: impl Iterator<Item = error::Result<Atomic>>,
+    interpreter: &Interpreter,
+    arg: &sequence::Sequence,
     collation: &str,
 ) -> error::Result<Vec<Atomic>> {
+    // the distinct values of a range are all its values
+    arg.materialized_len()?;
     let collation = context
         .static_context()
         .resolve_collation_str(Some(collation))?;
     let default_offset = context.implicit_timezone();
     // we use a HashMap first to remove items to compare. It removes easy
     // duplicates. It can't generate false positives as the default
-    // string compare is in use. We store the order in the value.
-    let distinct_set = arg
-        .enumerate()
-        .map(|(i, atom)| Ok((atom?, i)))
-        .collect::<error::Result<HashMap<_, _>>>()?;
+    // string compare is in use. We store the order of first appearance in
+    // the value, so that the result retains the order of the input.
+    let mut distinct_set = HashMap::new();
+    for (i, atom) in arg.atomized(interpreter.xot()).enumerate() {
+        distinct_set.entry(atom?).or_insert(i);
+    }
     if distinct_set.is_empty() {
         return Ok(Vec::new());
     }
@@ -242,11 +261,77 @@ fn deep_equal(
     parameter1.deep_equal(parameter2, &collation, default_offset, interpreter.xot())
 }
 
+// A Xee extension, not in the XPath function library: deep-equal, but with
+// floats and doubles within $epsilon of each other considered equal. This
+// is useful to compare results of floating point calculations.
+#[xpath_fn("xee:deep-equal-approx($parameter1 as item()*, $parameter2 as item()*, $epsilon as xs:double) as xs:boolean")]
+fn deep_equal_approx(
+    context: &DynamicContext,
+    interpreter: &Interpreter,
+    parameter1: &sequence::Sequence,
+    parameter2: &sequence::Sequence,
+    epsilon: f64,
+) -> error::Result<bool> {
+    let collation = context.static_context().default_collation()?;
+    let default_offset = context.implicit_timezone();
+    let xot = interpreter.xot();
+    parameter1.deep_equal_by(
+        parameter2,
+        &|a, b| a.approx_equal(b, epsilon, &collation, default_offset),
+        &|a, b| xot.deep_equal_xpath(a, b, |a, b| collation.compare(a, b).is_eq()),
+    )
+}
+
+// A Xee extension, not in the XPath function library: deep-equal, but with
+// options for comparing documents. With `ignore-whitespace`, text nodes
+// that consist of whitespace only, such as indentation, are ignored in the
+// content of an element or document. With `ignore-comments` set to false,
+// comments in the content of an element or document are compared; they're
+// ignored by default, as fn:deep-equal does.
+#[xpath_fn("xee:deep-equal($parameter1 as item()*, $parameter2 as item()*, $options as map(*)) as xs:boolean")]
+fn deep_equal_options(
+    context: &DynamicContext,
+    interpreter: &Interpreter,
+    parameter1: &sequence::Sequence,
+    parameter2: &sequence::Sequence,
+    options: function::Map,
+) -> error::Result<bool> {
+    let xot = interpreter.xot();
+    let c = sequence::OptionParameterConverter::new(&options, context.static_context(), xot);
+    let ignore_whitespace = c.option_with_default("ignore-whitespace", Xs::Boolean, false)?;
+    let ignore_comments = c.option_with_default("ignore-comments", Xs::Boolean, true)?;
+
+    let collation = context.static_context().default_collation()?;
+    let default_offset = context.implicit_timezone();
+    let text_compare = |a: &str, b: &str| collation.compare(a, b).is_eq();
+    let is_relevant = |node| match xot.value(node) {
+        xot::Value::Element(_) => true,
+        xot::Value::Text(text) => !(ignore_whitespace && is_whitespace(text.get())),
+        xot::Value::Comment(_) => !ignore_comments,
+        _ => false,
+    };
+    parameter1.deep_equal_by(
+        parameter2,
+        &|a, b| a.deep_equal(b, &collation, default_offset),
+        &|a, b| match (xot.value(a), xot.value(b)) {
+            (xot::Value::Element(_), xot::Value::Element(_))
+            | (xot::Value::Document, xot::Value::Document) => {
+                xot.advanced_deep_equal(a, b, is_relevant, text_compare)
+            }
+            _ => xot.deep_equal_xpath(a, b, text_compare),
+        },
+    )
+}
+
+fn is_whitespace(s: &str) -> bool {
+    s.chars().all(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+}
+
 #[xpath_fn("fn:zero-or-one($arg as item()*) as item()?")]
 fn zero_or_one(arg: &sequence::Sequence) -> error::Result<Option<sequence::Item>> {
     match arg.len() {
         0 => Ok(None),
-        1 => Ok(arg.iter().next()),
+        1 => Ok(arg.get(0)),
         _ => Err(error::Error::FORG0003),
     }
 }
@@ -262,10 +347,9 @@ fn one_or_more(arg: &sequence::Sequence) -> error::Result<sequence::Sequence> {
 
 #[xpath_fn("fn:exactly-one($arg as item()*) as item()")]
 fn exactly_one(arg: &sequence::Sequence) -> error::Result<sequence::Item> {
-    if arg.len() == 1 {
-        Ok(arg.iter().next().unwrap().clone())
-    } else {
-        Err(error::Error::FORG0005)
+    match arg.len() {
+        1 => Ok(arg.get(0).unwrap()),
+        _ => Err(error::Error::FORG0005),
     }
 }
 
@@ -348,6 +432,7 @@ where
 {
     let mut float_seen: bool = false;
     let mut double_seen: bool = false;
+    let mut decimal_seen: bool = false;
     let mut any_uri_seen: bool = false;
     let mut string_seen: bool = false;
 
@@ -361,6 +446,7 @@ where
         match r {
             Atomic::Float(_) => float_seen = true,
             Atomic::Double(_) => double_seen = true,
+            Atomic::Decimal(_) => decimal_seen = true,
             Atomic::String(StringType::AnyURI, _) => any_uri_seen = true,
             Atomic::String(StringType::String, _) => string_seen = true,
             _ => {}
@@ -406,6 +492,10 @@ where
             Ok(Some(extreme.cast_to_double()?))
         } else if float_seen {
             Ok(Some(extreme.cast_to_float()?))
+        } else if decimal_seen && matches!(extreme, Atomic::Integer(..)) {
+            // an integer max is promoted to decimal if there are decimals
+            // in the input
+            Ok(Some(extreme.cast_to_decimal()?))
         } else if any_uri_seen && string_seen {
             // this will only cast any AnyURI max to string,
             // otherwise the max is already string
@@ -494,6 +584,8 @@ pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
         wrap_xpath_fn!(distinct_values),
         wrap_xpath_fn!(index_of),
         wrap_xpath_fn!(deep_equal),
+        wrap_xpath_fn!(deep_equal_approx),
+        wrap_xpath_fn!(deep_equal_options),
         wrap_xpath_fn!(zero_or_one),
         wrap_xpath_fn!(one_or_more),
         wrap_xpath_fn!(exactly_one),
@@ -16,7 +16,6 @@ use xot::Xot;
 use crate::context::DynamicContext;
 use crate::function::{self, StaticFunctionDescription};
 use crate::interpreter::Interpreter;
-use crate::string::Collation;
 use crate::{atomic, error, interpreter, occurrence, sequence, wrap_xpath_fn};
 
 // we don't accept concat() invocations with an arity greater than this
@@ -37,7 +36,7 @@ fn codepoints_to_string(arg: impl Iterator<Item = error::Result<IBig>>) -> error
     .collect::<error::Result<String>>()
 }
 
-fn is_valid_xml_char(c: char) -> bool {
+pub(crate) fn is_valid_xml_char(c: char) -> bool {
     // Char ::= #x9 | #xA | #xD | [#x20-#xD7FF] | [#xE000-#xFFFD] | [#x10000-#x10FFFF]
     c == '\t'
         || c == '\n'
@@ -85,6 +84,24 @@ fn compare(
     }
 }
 
+#[xpath_fn(
+    "fn:collation-key($value as xs:string, $collation as xs:string) as xs:base64Binary",
+    collation
+)]
+fn collation_key(
+    context: &DynamicContext,
+    value: &str,
+    collation: &str,
+) -> error::Result<atomic::Atomic> {
+    let collation = context
+        .static_context()
+        .resolve_collation_str(Some(collation))?;
+    Ok(atomic::Atomic::Binary(
+        atomic::BinaryType::Base64,
+        collation.key(value)?.into(),
+    ))
+}
+
 #[xpath_fn(
     "fn:codepoint-equal($comparand1 as xs:string?, $comparand2 as xs:string?) as xs:boolean?"
 )]
@@ -109,11 +126,18 @@ fn contains_token(
     let collation = context
         .static_context()
         .resolve_collation_str(Some(collation))?;
-    let token = token.trim();
+    // only XML whitespace counts, both for trimming the token and for
+    // separating the tokens in the input
+    let xml_whitespace = [' ', '\t', '\r', '\n'];
+    let token = token.trim_matches(xml_whitespace);
+    if token.is_empty() {
+        return Ok(false);
+    }
     for s in input {
         let s = s?;
         // if any token in s, tokenized, is token, then we return true
-        if s.split_ascii_whitespace()
+        if s.split(xml_whitespace)
+            .filter(|t| !t.is_empty())
             .any(|t| collation.compare(t, token).is_eq())
         {
             return Ok(true);
@@ -149,8 +173,10 @@ fn concat(
 }
 
 #[xpath_fn("fn:string-join($arg1 as xs:anyAtomicType*) as xs:string")]
-fn string_join(arg1: impl Iterator<Item = error::Result<atomic::Atomic>>) -> error::Result<String> {
+fn string_join(interpreter: &Interpreter, arg1: &sequence::Sequence) -> error::Result<String> {
+    arg1.materialized_len()?;
     let arg1 = arg1
+        .atomized(interpreter.xot())
         .map(|a| Ok(a?.string_value()))
         .collect::<error::Result<Vec<String>>>()?;
     Ok(arg1.concat())
@@ -158,10 +184,13 @@ fn string_join(arg1: impl Iterator<Item = error::Result<atomic::Atomic>>) -> err
 
 #[xpath_fn("fn:string-join($arg1 as xs:anyAtomicType*, $arg2 as xs:string) as xs:string")]
 fn string_join_sep(
-    arg1: impl Iterator<Item = error::Result<atomic::Atomic>>,
+    interpreter: &Interpreter,
+    arg1: &sequence::Sequence,
     arg2: &str,
 ) -> error::Result<String> {
+    arg1.materialized_len()?;
     let arg1 = arg1
+        .atomized(interpreter.xot())
         .map(|a| Ok(a?.string_value()))
         .collect::<error::Result<Vec<String>>>()?;
     Ok(arg1.join(arg2))
@@ -188,32 +217,29 @@ fn substring3(source_string: Option<&str>, start: f64, length: f64) -> String {
 fn substring_with_length(source_string: &str, start: f64, length: f64) -> String {
     // we deliberately do the calculations with floats as long as possible
     // to handle infinities and such, as those are part of the spec, as well
-    // as avoid overflows.
-    if source_string.is_empty() {
-        return "".to_string();
-    }
-    if start.is_nan() || length.is_nan() {
-        return "".to_string();
-    }
-    let start = start.round();
-    let length = length.round();
-
-    // we calculate the end point
+    // as avoid overflows. The arguments are rounded as by fn:round, so
+    // halfway cases round towards positive infinity: -1.5 becomes -1.
+    let start = atomic::round_float(start, 0);
+    let length = atomic::round_float(length, 0);
+
+    // we calculate the end point (exclusive). If any argument is NaN, or
+    // we add infinities with different signs, this results in NaN, and no
+    // position can be selected.
     let end = start + length;
-    // if this results in a NaN we're done
     if end.is_nan() {
         return "".to_string();
     }
-    // we say the start should not be less than 1
-    let start = start.round().max(1f64);
-    // now we say the end should not more than the total length of the string
-    // the end position is one beyond the end of the string, due to the starting
-    // at 1.
-    let end = end.min((source_string.len() + 1) as f64);
+    // we say the start should not be less than 1, and the end should not
+    // be more than one beyond the last character
+    let start = start.max(1f64);
+    let end = end.min((source_string.chars().count() + 1) as f64);
+    if start >= end {
+        return "".to_string();
+    }
 
-    // now turn into integers and substract 1 to get
-    let length: usize = (end - start) as usize;
-    let start: usize = start as usize - 1;
+    // now turn into integers and substract 1 to get a zero-based index
+    let length = (end - start) as usize;
+    let start = start as usize - 1;
 
     source_string
         .chars()
@@ -281,7 +307,10 @@ fn normalize_unicode(arg: Option<&str>, normalization_form: &str) -> error::Resu
                 let normalizer = DecomposingNormalizer::new_nfkd();
                 Ok(normalizer.normalize(arg))
             }
-            // TODO: FULLY-NORMALIZED
+            // this includes FULLY-NORMALIZED: it also requires checking that
+            // the string doesn't start with a composing character, which we
+            // don't know how to do, so we refuse rather than return a string
+            // that isn't fully normalized
             _ => Err(error::Error::FOCH0003),
         }
     } else {
@@ -289,8 +318,6 @@ fn normalize_unicode(arg: Option<&str>, normalization_form: &str) -> error::Resu
     }
 }
 
-// TODO: fn:normalize-unicode
-
 #[xpath_fn("fn:upper-case($arg as xs:string?) as xs:string")]
 fn upper_case(arg: Option<&str>) -> String {
     if let Some(arg) = arg {
@@ -312,8 +339,11 @@ fn lower_case(arg: Option<&str>) -> String {
 #[xpath_fn("fn:tokenize($input as xs:string?) as xs:string*")]
 fn tokenize1(input: Option<&str>) -> error::Result<Vec<String>> {
     if let Some(input) = input {
+        // this is tokenize(normalize-space($input), ' '), so only XML
+        // whitespace separates tokens, and there are no empty tokens
         Ok(input
-            .split_ascii_whitespace()
+            .split([' ', '\t', '\r', '\n'])
+            .filter(|s| !s.is_empty())
             .map(|s| s.to_string())
             .collect())
     } else {
@@ -365,26 +395,12 @@ fn contains(
 ) -> error::Result<bool> {
     let arg1 = arg1.unwrap_or("");
     let arg2 = arg2.unwrap_or("");
-    if arg2.is_empty() {
-        return Ok(true);
-    }
-    if arg1.is_empty() {
-        return Ok(false);
-    }
     let collation = context
         .static_context()
         .resolve_collation_str(Some(collation))?;
-    match collation.as_ref() {
-        Collation::CodePoint => Ok(arg1.contains(arg2)),
-        Collation::HtmlAscii => {
-            let arg1 = arg1.to_ascii_lowercase();
-            let arg2 = arg2.to_ascii_lowercase();
-            Ok(arg1.contains(&arg2))
-        }
-        // for now, icu4x does not yet support collation units (actually named collation elements)
-        // https://github.com/unicode-org/icu4x/discussions/3981
-        Collation::Uca(_) => Err(error::Error::FOCH0004),
-    }
+    // an empty search string, or one that only contains ignorable
+    // characters, is always found
+    Ok(collation.find(arg1, arg2).is_some())
 }
 
 #[xpath_fn("fn:starts-with($arg1 as xs:string?, $arg2 as xs:string?, $collation as xs:string) as xs:boolean", collation)]
@@ -396,26 +412,10 @@ fn starts_with(
 ) -> error::Result<bool> {
     let arg1 = arg1.unwrap_or("");
     let arg2 = arg2.unwrap_or("");
-    if arg2.is_empty() {
-        return Ok(true);
-    }
-    if arg1.is_empty() {
-        return Ok(false);
-    }
     let collation = context
         .static_context()
         .resolve_collation_str(Some(collation))?;
-    match collation.as_ref() {
-        Collation::CodePoint => Ok(arg1.starts_with(arg2)),
-        Collation::HtmlAscii => {
-            let arg1 = arg1.to_lowercase();
-            let arg2 = arg2.to_lowercase();
-            Ok(arg1.starts_with(&arg2))
-        }
-        // for now, icu4x does not yet support collation units (actually named collation elements)
-        // https://github.com/unicode-org/icu4x/discussions/3981
-        Collation::Uca(_) => Err(error::Error::FOCH0004),
-    }
+    Ok(collation.starts_with(arg1, arg2))
 }
 
 #[xpath_fn(
@@ -430,26 +430,10 @@ fn ends_with(
 ) -> error::Result<bool> {
     let arg1 = arg1.unwrap_or("");
     let arg2 = arg2.unwrap_or("");
-    if arg2.is_empty() {
-        return Ok(true);
-    }
-    if arg1.is_empty() {
-        return Ok(false);
-    }
     let collation = context
         .static_context()
         .resolve_collation_str(Some(collation))?;
-    match collation.as_ref() {
-        Collation::CodePoint => Ok(arg1.ends_with(arg2)),
-        Collation::HtmlAscii => {
-            let arg1 = arg1.to_lowercase();
-            let arg2 = arg2.to_lowercase();
-            Ok(arg1.ends_with(&arg2))
-        }
-        // for now, icu4x does not yet support collation units (actually named collation elements)
-        // https://github.com/unicode-org/icu4x/discussions/3981
-        Collation::Uca(_) => Err(error::Error::FOCH0004),
-    }
+    Ok(collation.ends_with(arg1, arg2))
 }
 
 #[xpath_fn("fn:substring-before($arg1 as xs:string?, $arg2 as xs:string?, $collation as xs:string) as xs:string", collation)]
@@ -461,28 +445,14 @@ fn substring_before(
 ) -> error::Result<String> {
     let arg1 = arg1.unwrap_or("");
     let arg2 = arg2.unwrap_or("");
-    if arg2.is_empty() {
-        return Ok("".to_string());
-    }
-    // find substring in arg1 that comes before arg2
     let collation = context
         .static_context()
         .resolve_collation_str(Some(collation))?;
-    match collation.as_ref() {
-        Collation::CodePoint => {
-            let idx = arg1.find(arg2).unwrap_or(0);
-            Ok(arg1[..idx].to_string())
-        }
-        Collation::HtmlAscii => {
-            let arg1_l = arg1.to_lowercase();
-            let arg2_l = arg2.to_lowercase();
-            let idx = arg1_l.find(&arg2_l).unwrap_or(0);
-            Ok(arg1[..idx].to_string())
-        }
-        // for now, icu4x does not yet support collation units (actually named collation elements)
-        // https://github.com/unicode-org/icu4x/discussions/3981
-        Collation::Uca(_) => Err(error::Error::FOCH0004),
-    }
+    // an empty search string is found at the start
+    Ok(match collation.find(arg1, arg2) {
+        Some(found) => arg1[..found.start].to_string(),
+        None => "".to_string(),
+    })
 }
 
 #[xpath_fn("fn:substring-after($arg1 as xs:string?, $arg2 as xs:string?, $collation as xs:string) as xs:string", collation)]
@@ -494,34 +464,14 @@ fn substring_after(
 ) -> error::Result<String> {
     let arg1 = arg1.unwrap_or("");
     let arg2 = arg2.unwrap_or("");
-    if arg2.is_empty() {
-        return Ok(arg1.to_string());
-    }
-    // find substring in arg1 that comes before arg2
     let collation = context
         .static_context()
         .resolve_collation_str(Some(collation))?;
-    match collation.as_ref() {
-        Collation::CodePoint => {
-            if let Some(idx) = arg1.find(arg2) {
-                Ok(arg1[(idx + arg2.len())..].to_string())
-            } else {
-                Ok("".to_string())
-            }
-        }
-        Collation::HtmlAscii => {
-            let arg1_l = arg1.to_lowercase();
-            let arg2_l = arg2.to_lowercase();
-            if let Some(idx) = arg1_l.find(&arg2_l) {
-                Ok(arg1[(idx + arg2.len())..].to_string())
-            } else {
-                Ok("".to_string())
-            }
-        }
-        // for now, icu4x does not yet support collation units (actually named collation elements)
-        // https://github.com/unicode-org/icu4x/discussions/3981
-        Collation::Uca(_) => Err(error::Error::FOCH0004),
-    }
+    // an empty search string is found at the start
+    Ok(match collation.find(arg1, arg2) {
+        Some(found) => arg1[found.end..].to_string(),
+        None => "".to_string(),
+    })
 }
 
 #[xpath_fn(
@@ -556,15 +506,28 @@ fn matches(
     Ok(regex.is_match(input))
 }
 
-#[xpath_fn("fn:replace($input as xs:string?, $pattern as xs:string, $replacement as xs:string, $flags as xs:string) as xs:string")]
+// The $replacement can also be a function, which is called for each match
+// with the matched substring and the captured groups; its result is used as
+// the replacement. As functions are items too, we declare $replacement as
+// item() and apply the function conversion rules to xs:string ourselves
+// when we get anything else.
+#[xpath_fn("fn:replace($input as xs:string?, $pattern as xs:string, $replacement as item(), $flags as xs:string) as xs:string")]
 fn replace4(
     interpreter: &mut Interpreter,
     input: Option<&str>,
     pattern: &str,
-    replacement: &str,
+    replacement: sequence::Item,
     flags: &str,
 ) -> error::Result<String> {
-    replace(interpreter, input, pattern, replacement, flags)
+    match replacement {
+        sequence::Item::Function(
+            function @ (function::Function::Static(_) | function::Function::Inline(_)),
+        ) => replace_with_function(interpreter, input, pattern, function, flags),
+        replacement => {
+            let replacement = replacement_string(interpreter, replacement)?;
+            replace(interpreter, input, pattern, &replacement, flags)
+        }
+    }
 }
 
 #[xpath_fn("fn:replace($input as xs:string?, $pattern as xs:string, $replacement as xs:string) as xs:string")]
@@ -577,6 +540,108 @@ fn replace3(
     replace(interpreter, input, pattern, replacement, "")
 }
 
+fn replacement_string(
+    interpreter: &Interpreter,
+    replacement: sequence::Item,
+) -> error::Result<String> {
+    let sequence_type = ast::SequenceType::Item(ast::Item {
+        occurrence: ast::Occurrence::One,
+        item_type: ast::ItemType::AtomicOrUnionType(Xs::String),
+    });
+    let runnable = interpreter.runnable();
+    let replacement = sequence::Sequence::from(replacement)
+        .sequence_type_matching_function_conversion(
+            &sequence_type,
+            runnable.static_context(),
+            interpreter.xot(),
+            &|function| runnable.program().function_info(function).signature(),
+        )?;
+    let atomic: atomic::Atomic = replacement.one()?.to_atomic()?;
+    atomic.to_string()
+}
+
+fn replace_with_function(
+    interpreter: &mut Interpreter,
+    input: Option<&str>,
+    pattern: &str,
+    function: function::Function,
+    flags: &str,
+) -> error::Result<String> {
+    let regex = interpreter.regex(pattern, flags)?;
+    let group_count = capture_group_count(pattern, flags);
+    let input = input.unwrap_or("");
+    let mut result = String::new();
+    for entry in regex.analyze(input)? {
+        match entry {
+            AnalyzeEntry::Match(match_entries) => {
+                // groups that didn't participate in the match are the empty
+                // string
+                let mut groups = vec![String::new(); group_count];
+                let matched = match_entries_string(&match_entries, &mut groups);
+                let matched: sequence::Sequence = atomic::Atomic::Untyped(matched.into()).into();
+                let groups: sequence::Sequence = groups
+                    .into_iter()
+                    .map(|group| atomic::Atomic::Untyped(group.into()))
+                    .collect();
+                let value =
+                    interpreter.call_function_with_arguments(&function, &[matched, groups])?;
+                for atom in value.atomized(interpreter.xot()) {
+                    result.push_str(&atom?.string_value());
+                }
+            }
+            AnalyzeEntry::NonMatch(s) => result.push_str(&s),
+        }
+    }
+    Ok(result)
+}
+
+// Get the string of the whole match, and store the strings of the captured
+// groups by group number.
+fn match_entries_string(match_entries: &[MatchEntry], groups: &mut [String]) -> String {
+    let mut s = String::new();
+    for match_entry in match_entries {
+        match match_entry {
+            MatchEntry::String(text) => s.push_str(text),
+            MatchEntry::Group { nr, value } => {
+                let group = match_entries_string(value, groups);
+                groups[*nr - 1].clone_from(&group);
+                s.push_str(&group);
+            }
+        }
+    }
+    s
+}
+
+// The number of capturing groups in a pattern that we know compiles. Every
+// opening parenthesis starts one, except when it's escaped, in a character
+// class, or starts a non-capturing group (?:...). regexml doesn't expose the
+// number of groups of a compiled regex, so we count them ourselves.
+fn capture_group_count(pattern: &str, flags: &str) -> usize {
+    if flags.contains('q') {
+        return 0;
+    }
+    // with the x flag whitespace is ignored, so "( ?:" is non-capturing too
+    let ignore_whitespace = flags.contains('x');
+    let mut count = 0;
+    let mut class_depth = 0;
+    let mut chars = pattern
+        .chars()
+        .filter(|c| !(ignore_whitespace && matches!(c, '\t' | '\n' | '\r' | ' ')))
+        .peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => class_depth += 1,
+            ']' if class_depth > 0 => class_depth -= 1,
+            '(' if class_depth == 0 && chars.peek() != Some(&'?') => count += 1,
+            _ => {}
+        }
+    }
+    count
+}
+
 fn replace(
     interpreter: &mut Interpreter,
     input: Option<&str>,
@@ -735,6 +800,7 @@ pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
         wrap_xpath_fn!(codepoints_to_string),
         wrap_xpath_fn!(string_to_codepoints),
         wrap_xpath_fn!(compare),
+        wrap_xpath_fn!(collation_key),
         wrap_xpath_fn!(codepoint_equal),
         wrap_xpath_fn!(contains_token),
         wrap_xpath_fn!(string_join),
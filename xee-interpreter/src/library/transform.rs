@@ -0,0 +1,145 @@
+// https://www.w3.org/TR/xpath-functions-31/#func-transform
+
+use xee_schema_type::Xs;
+use xee_xpath_macros::xpath_fn;
+use xot::Xot;
+
+use crate::context;
+use crate::error;
+use crate::function;
+use crate::function::StaticFunctionDescription;
+use crate::interpreter::Interpreter;
+use crate::sequence;
+use crate::wrap_xpath_fn;
+
+/// How the principal result of a transformation is delivered.
+enum DeliveryFormat {
+    // the result is wrapped in a document node
+    Document,
+    // the result sequence is returned as-is
+    Raw,
+    // the result is serialized to a string
+    Serialized,
+}
+
+impl DeliveryFormat {
+    fn from_str(s: &str) -> error::Result<Self> {
+        match s {
+            "document" => Ok(DeliveryFormat::Document),
+            "raw" => Ok(DeliveryFormat::Raw),
+            "serialized" => Ok(DeliveryFormat::Serialized),
+            _ => Err(error::Error::FOXT0002),
+        }
+    }
+}
+
+#[xpath_fn("fn:transform($options as map(*)) as map(*)")]
+fn transform(
+    context: &context::DynamicContext,
+    interpreter: &mut Interpreter,
+    options: function::Map,
+) -> error::Result<function::Map> {
+    let c = sequence::OptionParameterConverter::new(
+        &options,
+        context.static_context(),
+        interpreter.xot(),
+    );
+    // we only support stylesheets given as text or as a node, and exactly
+    // one of them has to be there
+    let stylesheet_text: Option<String> = c.option("stylesheet-text", Xs::String)?;
+    let stylesheet_node = c.node("stylesheet-node")?;
+    let source_node = c.node("source-node")?;
+    let delivery_format = c.option_with_default(
+        "delivery-format",
+        Xs::String,
+        "document".to_string(),
+    )?;
+    let delivery_format = DeliveryFormat::from_str(&delivery_format)?;
+
+    let stylesheet = match (stylesheet_text, stylesheet_node) {
+        (Some(text), None) => text,
+        (None, Some(node)) => interpreter.xot().to_string(node)?,
+        _ => return Err(error::Error::FOXT0002),
+    };
+
+    let xslt_compiler = context.xslt_compiler().ok_or(error::Error::FOXT0001)?;
+    let (program, parameters) = xslt_compiler
+        .compile(&stylesheet)
+        .map_err(|error| error.error)?;
+
+    // the transformation runs under the same limits as the caller, such as
+    // its deadline, but none of the caller's variables are in scope there
+    let mut dynamic_context_builder = context.builder_for(&program);
+    dynamic_context_builder.variables(context::Variables::new());
+    if let Some(source_node) = source_node {
+        dynamic_context_builder.context_node(source_node);
+    }
+    let transform_context = dynamic_context_builder.build();
+    let result = program
+        .runnable(&transform_context)
+        .many(interpreter.xot_mut())
+        .map_err(|error| error.error)?;
+
+    let output: sequence::Sequence = match delivery_format {
+        DeliveryFormat::Document => {
+            sequence::Item::Node(result_document(&result, interpreter.xot_mut())?).into()
+        }
+        DeliveryFormat::Raw => result,
+        DeliveryFormat::Serialized => {
+            let serialized = result.serialize(parameters, interpreter.xot_mut())?;
+            sequence::Item::from(serialized).into()
+        }
+    };
+    function::Map::new(vec![("output".to_string().into(), output)])
+}
+
+// wrap the result in a document node, following the rules for the content
+// of a document node: the children of document nodes are copied, and
+// adjacent atomic values become a single text node, separated by spaces
+fn result_document(result: &sequence::Sequence, xot: &mut Xot) -> error::Result<xot::Node> {
+    let document = xot.new_document();
+    let mut string_values = Vec::new();
+    for item in result.iter() {
+        match item {
+            sequence::Item::Node(node) => {
+                append_string_values(xot, document, &mut string_values)?;
+                if xot.is_document(node) {
+                    let children = xot.children(node).collect::<Vec<_>>();
+                    for child in children {
+                        let child = xot.clone_node(child);
+                        xot.append(document, child)?;
+                    }
+                } else {
+                    let node = if xot.parent(node).is_some() {
+                        xot.clone_node(node)
+                    } else {
+                        node
+                    };
+                    xot.append(document, node)?;
+                }
+            }
+            sequence::Item::Atomic(atomic) => string_values.push(atomic.string_value()),
+            sequence::Item::Function(_) => return Err(error::Error::XTDE0450),
+        }
+    }
+    append_string_values(xot, document, &mut string_values)?;
+    Ok(document)
+}
+
+fn append_string_values(
+    xot: &mut Xot,
+    parent: xot::Node,
+    string_values: &mut Vec<String>,
+) -> error::Result<()> {
+    if string_values.is_empty() {
+        return Ok(());
+    }
+    let text = xot.new_text(&string_values.join(" "));
+    string_values.clear();
+    xot.append(parent, text)?;
+    Ok(())
+}
+
+pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
+    vec![wrap_xpath_fn!(transform)]
+}
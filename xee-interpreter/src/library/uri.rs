@@ -56,21 +56,20 @@ fn resolve_uri1(
     relative: Option<&str>,
 ) -> error::Result<Option<atomic::Atomic>> {
     let base = context.static_context().static_base_uri();
-    if let Some(base) = base {
-        Ok(resolve_uri(relative, base.as_str())?
-            .map(|resolved| atomic::Atomic::String(atomic::StringType::AnyURI, resolved.into())))
-    } else {
-        Err(error::Error::FONS0005)
-    }
+    Ok(resolve_uri(relative, base.map(|base| base.as_str()))?
+        .map(|resolved| atomic::Atomic::String(atomic::StringType::AnyURI, resolved.into())))
 }
 
 #[xpath_fn("fn:resolve-uri($relative as xs:string?, $base as xs:string) as xs:anyURI?")]
 fn resolve_uri2(relative: Option<&str>, base: &str) -> error::Result<Option<atomic::Atomic>> {
-    Ok(resolve_uri(relative, base)?
+    Ok(resolve_uri(relative, Some(base))?
         .map(|resolved| atomic::Atomic::String(atomic::StringType::AnyURI, resolved.into())))
 }
 
-pub(crate) fn resolve_uri(relative: Option<&str>, base: &str) -> error::Result<Option<String>> {
+pub(crate) fn resolve_uri(
+    relative: Option<&str>,
+    base: Option<&str>,
+) -> error::Result<Option<String>> {
     if let Some(relative) = relative {
         let iri_reference: &IriReferenceStr =
             relative.try_into().map_err(|_e| error::Error::FORG0002)?;
@@ -87,8 +86,11 @@ pub(crate) fn resolve_uri(relative: Option<&str>, base: &str) -> error::Result<O
         };
 
         // note that this means base isn't validated if it's not needed
+        let base = base.ok_or(error::Error::FONS0005)?;
+        // the spec requires the base to be an absolute URI, which means
+        // it may not have a fragment
         let base: &IriAbsoluteStr = base.try_into().map_err(|_| error::Error::FORG0002)?;
-        // now resolve the iri against base
+        // now resolve the iri against base; this also removes dot segments
         let resolved_iri = relative_iri.resolve_against(base);
 
         Ok(Some(resolved_iri.to_string()))
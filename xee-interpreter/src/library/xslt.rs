@@ -0,0 +1,73 @@
+// functions XSLT adds to the function library, which can't be called from
+// plain XPath
+// https://www.w3.org/TR/xslt-30/#add-func
+
+use xee_name::Name;
+use xee_xpath_ast::parse_name;
+use xee_xpath_macros::xpath_fn;
+use xot::xmlname::NameStrInfo;
+
+use crate::atomic;
+use crate::context::DynamicContext;
+use crate::error;
+use crate::function::StaticFunctionDescription;
+use crate::wrap_xpath_fn;
+
+const XSL_NAMESPACE: &str = "http://www.w3.org/1999/XSL/Transform";
+
+// https://www.w3.org/TR/xslt-30/#system-property
+const SYSTEM_PROPERTIES: &[(&str, &str)] = &[
+    ("version", "3.0"),
+    ("vendor", "The Xee Project Developers"),
+    ("vendor-url", env!("CARGO_PKG_HOMEPAGE")),
+    ("product-name", "Xee"),
+    ("product-version", env!("CARGO_PKG_VERSION")),
+    ("is-schema-aware", "no"),
+    ("supports-serialization", "yes"),
+    ("supports-backwards-compatibility", "no"),
+    ("supports-namespace-axis", "no"),
+    ("supports-streaming", "no"),
+    ("supports-dynamic-evaluation", "no"),
+    ("supports-higher-order-functions", "yes"),
+    ("xpath-version", "3.1"),
+    ("xsd-version", "1.1"),
+];
+
+#[xpath_fn("fn:system-property($property_name as xs:string) as xs:string")]
+fn system_property(context: &DynamicContext, property_name: &str) -> error::Result<String> {
+    // no prefix means no namespace, and only properties in the XSLT
+    // namespace are known
+    let name = parse_name(property_name, context.static_context().namespaces())
+        .map_err(|_| error::Error::XTDE1390)?
+        .value;
+    if name.namespace() != XSL_NAMESPACE {
+        return Ok(String::new());
+    }
+    Ok(SYSTEM_PROPERTIES
+        .iter()
+        .find(|(local_name, _)| *local_name == name.local_name())
+        .map(|(_, value)| value.to_string())
+        .unwrap_or_default())
+}
+
+#[xpath_fn("fn:available-system-properties() as xs:QName*")]
+fn available_system_properties() -> Vec<atomic::Atomic> {
+    SYSTEM_PROPERTIES
+        .iter()
+        .map(|(local_name, _)| {
+            Name::new(
+                local_name.to_string(),
+                XSL_NAMESPACE.to_string(),
+                "xsl".to_string(),
+            )
+            .into()
+        })
+        .collect()
+}
+
+pub(crate) fn static_function_descriptions() -> Vec<StaticFunctionDescription> {
+    vec![
+        wrap_xpath_fn!(system_property),
+        wrap_xpath_fn!(available_system_properties),
+    ]
+}
@@ -19,15 +19,41 @@ impl ModeId {
     }
 }
 
+/// What happens when no template rule in a mode matches an item, as
+/// declared by the `on-no-match` attribute of `xsl:mode`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnNoMatch {
+    /// Copy the item, including all its content.
+    DeepCopy,
+    /// Copy a document or element node without content, and apply templates
+    /// to its attributes and children. Other items are copied.
+    ShallowCopy,
+    /// Apply templates to the children of a document node. Other items are
+    /// skipped.
+    DeepSkip,
+    /// Apply templates to the attributes and children of a document or
+    /// element node. Other items are skipped.
+    ShallowSkip,
+    /// Apply templates to the children of a document or element node. Text
+    /// nodes, attributes and atomic values are copied as text. Other items
+    /// are skipped.
+    #[default]
+    TextOnlyCopy,
+    /// Raise XTDE0555.
+    Fail,
+}
+
 #[derive(Debug, Default)]
 pub struct ModeLookup<V: Clone> {
     pub(crate) modes: HashMap<ModeId, PatternLookup<V>>,
+    pub(crate) on_no_match: HashMap<ModeId, OnNoMatch>,
 }
 
 impl<V: Clone> ModeLookup<V> {
     pub(crate) fn new() -> Self {
         Self {
             modes: HashMap::new(),
+            on_no_match: HashMap::new(),
         }
     }
 
@@ -49,4 +75,13 @@ impl<V: Clone> ModeLookup<V> {
 
         pattern_lookup.add_rules(rules);
     }
+
+    /// Set what happens when no template rule in the mode matches.
+    pub fn set_on_no_match(&mut self, mode: ModeId, on_no_match: OnNoMatch) {
+        self.on_no_match.insert(mode, on_no_match);
+    }
+
+    pub(crate) fn on_no_match(&self, mode: ModeId) -> OnNoMatch {
+        self.on_no_match.get(&mode).copied().unwrap_or_default()
+    }
 }
@@ -251,6 +251,11 @@ pub(crate) trait PredicateMatcher {
     }
 
     fn matches_name_test(name_test: &pattern::NameTest, node: xot::Node, xot: &Xot) -> bool {
+        // a name test only matches the principal node kind of the axis; we
+        // already know the axis is appropriate for elements or attributes
+        if !xot.is_element(node) && !xot.is_attribute_node(node) {
+            return false;
+        }
         match name_test {
             pattern::NameTest::Name(expected_name) => {
                 // TODO: unwrap - what if prefix couldn't be identified?
@@ -372,6 +377,22 @@ mod tests {
         assert!(pm.matches(&pattern, &item));
     }
 
+    #[test]
+    fn test_not_match_star_other_node_kinds() {
+        let mut xot = Xot::new();
+        let root = xot.parse(r#"<root>text<?pi?></root>"#).unwrap();
+        let document_element = xot.document_element(root).unwrap();
+        let text = xot.first_child(document_element).unwrap();
+        let pi = xot.next_sibling(text).unwrap();
+
+        let pattern = parse_pattern("*");
+
+        let mut pm = BasicPredicateMatcher::new(&xot);
+        assert!(!pm.matches(&pattern, &root.into()));
+        assert!(!pm.matches(&pattern, &text.into()));
+        assert!(!pm.matches(&pattern, &pi.into()));
+    }
+
     #[test]
     fn test_match_local_name() {
         let mut xot = Xot::new();
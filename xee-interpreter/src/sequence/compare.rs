@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 
 use xot::Xot;
 
-use crate::{error, function, string::Collation};
+use crate::{atomic, error, function, string::Collation};
 
 use super::{core::Sequence, item::Item};
 
@@ -16,6 +16,22 @@ impl Sequence {
         collation: &Collation,
         default_offset: chrono::FixedOffset,
         xot: &Xot,
+    ) -> error::Result<bool> {
+        self.deep_equal_by(
+            other,
+            &|a, b| a.deep_equal(b, collation, default_offset),
+            &|a, b| xot.deep_equal_xpath(a, b, |a, b| collation.compare(a, b).is_eq()),
+        )
+    }
+
+    /// Compare two sequences using XPath deep equal rules, but with
+    /// `atomic_equal` to compare atomic values and `node_equal` to compare
+    /// nodes, including those in arrays and map values.
+    pub(crate) fn deep_equal_by(
+        &self,
+        other: &Self,
+        atomic_equal: &dyn Fn(&atomic::Atomic, &atomic::Atomic) -> bool,
+        node_equal: &dyn Fn(xot::Node, xot::Node) -> bool,
     ) -> error::Result<bool> {
         // https://www.w3.org/TR/xpath-functions-31/#func-deep-equal
         if self.is_empty() && other.is_empty() {
@@ -27,23 +43,23 @@ impl Sequence {
         for (a, b) in self.iter().zip(other.iter()) {
             match (a, b) {
                 (Item::Atomic(a), Item::Atomic(b)) => {
-                    if !a.deep_equal(&b, collation, default_offset) {
+                    if !atomic_equal(&a, &b) {
                         return Ok(false);
                     }
                 }
                 (Item::Node(a), Item::Node(b)) => {
-                    if !xot.deep_equal_xpath(a, b, |a, b| collation.compare(a, b).is_eq()) {
+                    if !node_equal(a, b) {
                         return Ok(false);
                     }
                 }
                 (Item::Function(a), Item::Function(b)) => match (a, b) {
                     (function::Function::Array(a), function::Function::Array(b)) => {
-                        if !a.deep_equal(b.clone(), collation, default_offset, xot)? {
+                        if !a.deep_equal(b.clone(), atomic_equal, node_equal)? {
                             return Ok(false);
                         }
                     }
                     (function::Function::Map(a), function::Function::Map(b)) => {
-                        if !a.deep_equal(&b, collation, default_offset, xot)? {
+                        if !a.deep_equal(&b, atomic_equal, node_equal)? {
                             return Ok(false);
                         }
                     }
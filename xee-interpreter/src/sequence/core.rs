@@ -16,6 +16,7 @@ use crate::{
 
 use super::{
     item::Item,
+    iter::AtomizedIter,
     traits::{BoxedItemIter, SequenceCompare, SequenceCore, SequenceExt, SequenceOrder},
     variant::{Empty, Many, One, Range},
 };
@@ -48,6 +49,8 @@ impl Default for Sequence {
 
 impl Sequence {
     /// Check whether the sequence is empty
+    ///
+    /// This takes constant time; no items are evaluated.
     pub fn is_empty(&self) -> bool {
         match self {
             Sequence::Empty(inner) => inner.is_empty(),
@@ -67,6 +70,17 @@ impl Sequence {
         }
     }
 
+    /// Get the bounds on the sequence length, like
+    /// [`Iterator::size_hint`].
+    ///
+    /// This takes constant time; a range such as `1 to 1000000` isn't
+    /// expanded into its items. The sequence variants we have all know their
+    /// length, so currently the bounds are always exact.
+    pub fn len_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+
     /// Get an item in the index, if it exists
     pub fn get(&self, index: usize) -> Option<Item> {
         match self {
@@ -111,6 +125,27 @@ impl Sequence {
         }
     }
 
+    /// The sequence length, as the number of items it takes in memory.
+    ///
+    /// A range is lazy; one that is too large to materialize gives a
+    /// FOAR0002 error.
+    pub(crate) fn materialized_len(&self) -> error::Result<usize> {
+        match self {
+            Sequence::Range(inner) => inner.materialized_len(),
+            _ => Ok(self.len()),
+        }
+    }
+
+    /// Get the items from the sequence as a vector
+    ///
+    /// A range that is too large to materialize gives a FOAR0002 error.
+    pub(crate) fn to_vec(&self) -> error::Result<Vec<Item>> {
+        match self {
+            Sequence::Range(inner) => inner.to_vec(),
+            _ => Ok(self.iter().collect()),
+        }
+    }
+
     /// Effective boolean value
     pub fn effective_boolean_value(&self) -> error::Result<bool> {
         match self {
@@ -144,16 +179,11 @@ impl Sequence {
     }
 
     /// Iterator for the atomized values in the sequence
-    pub fn atomized<'a>(
-        &'a self,
-        xot: &'a xot::Xot,
-    ) -> Box<dyn Iterator<Item = error::Result<atomic::Atomic>> + 'a> {
-        match self {
-            Sequence::Empty(inner) => Box::new(inner.atomized(xot)),
-            Sequence::One(inner) => Box::new(inner.atomized(xot)),
-            Sequence::Many(inner) => Box::new(inner.atomized(xot)),
-            Sequence::Range(inner) => Box::new(inner.atomized(xot)),
-        }
+    ///
+    /// The sequence is atomized lazily, item by item. See [`AtomizedIter`]
+    /// for the rules.
+    pub fn atomized<'a>(&'a self, xot: &'a xot::Xot) -> AtomizedIter<'a, BoxedItemIter<'a>> {
+        AtomizedIter::new(xot, self.iter())
     }
 
     /// Get just one atomized value from the sequence
@@ -424,3 +454,38 @@ impl Sequence {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ibig::ibig;
+
+    use super::*;
+
+    #[test]
+    fn test_len_hint_empty() {
+        let sequence = Sequence::default();
+        assert!(sequence.is_empty());
+        assert_eq!(sequence.len_hint(), (0, Some(0)));
+    }
+
+    #[test]
+    fn test_len_hint_one() {
+        let sequence: Sequence = vec![ibig!(1)].into();
+        assert!(!sequence.is_empty());
+        assert_eq!(sequence.len_hint(), (1, Some(1)));
+    }
+
+    #[test]
+    fn test_len_hint_range() {
+        let sequence: Sequence = Range::new(ibig!(1), ibig!(1_000_001)).unwrap().into();
+        assert!(!sequence.is_empty());
+        assert_eq!(sequence.len_hint(), (1_000_000, Some(1_000_000)));
+    }
+
+    #[test]
+    fn test_len_hint_empty_range() {
+        let sequence: Sequence = Range::new(ibig!(5), ibig!(5)).unwrap().into();
+        assert!(sequence.is_empty());
+        assert_eq!(sequence.len_hint(), (0, Some(0)));
+    }
+}
@@ -8,7 +8,10 @@ use super::{
     core::Sequence,
     item::Item,
     normalization::normalize,
-    serialization::{serialize_sequence, SerializationParameters},
+    serialization::{
+        serialize_sequence, serialize_sequence_chunks, serialize_sequence_write,
+        SerializationParameters, SerializedChunks,
+    },
     traits::SequenceCore,
     variant::{Empty, Range, RangeIterator},
 };
@@ -67,7 +70,7 @@ impl Sequence {
                     Self::Range(Range::new(b.start().clone(), a.end().clone())?)
                 } else {
                     // otherwise unfortunately we have to construct the sequence
-                    let mut v = Vec::with_capacity(a.len() + b.len());
+                    let mut v = Vec::with_capacity(a.materialized_len()? + b.materialized_len()?);
                     for i in RangeIterator::new(a.start().clone(), a.end().clone()) {
                         v.push(i);
                     }
@@ -79,7 +82,7 @@ impl Sequence {
             }
             // handle other cases in less efficient way
             (a, b) => {
-                let mut v = Vec::with_capacity(a.len() + b.len());
+                let mut v = Vec::with_capacity(a.materialized_len()? + b.materialized_len()?);
                 for item in a.iter() {
                     v.push(item);
                 }
@@ -92,8 +95,16 @@ impl Sequence {
     }
 
     // https://www.w3.org/TR/xpath-31/#id-path-operator
-    pub(crate) fn deduplicate(self, annotations: xml::DocumentOrderAccess) -> error::Result<Self> {
+    //
+    // The set operations below remove duplicate nodes. If document order
+    // access is given, the result is sorted in document order; otherwise the
+    // nodes stay in the order in which they were first encountered.
+    pub(crate) fn deduplicate(
+        self,
+        document_order: Option<xml::DocumentOrderAccess>,
+    ) -> error::Result<Self> {
         let mut s = HashSet::new();
+        let mut nodes = Vec::new();
         let mut non_node_seen = false;
 
         for item in self.iter() {
@@ -102,7 +113,9 @@ impl Sequence {
                     if non_node_seen {
                         return Err(error::Error::XPTY0004);
                     }
-                    s.insert(n);
+                    if s.insert(n) {
+                        nodes.push(n);
+                    }
                 }
                 _ => {
                     if !s.is_empty() {
@@ -115,17 +128,18 @@ impl Sequence {
         if non_node_seen {
             Ok(self)
         } else {
-            Ok(Self::process_set_result(s, annotations))
+            Ok(Self::process_set_result(nodes, document_order))
         }
     }
 
     pub(crate) fn process_set_result(
-        s: HashSet<xot::Node>,
-        annotations: xml::DocumentOrderAccess,
+        mut nodes: Vec<xot::Node>,
+        document_order: Option<xml::DocumentOrderAccess>,
     ) -> Self {
         // sort nodes by document order
-        let mut nodes = s.into_iter().collect::<Vec<_>>();
-        nodes.sort_by_key(|n| annotations.get(*n));
+        if let Some(document_order) = document_order {
+            nodes.sort_by_key(|n| document_order.get(*n));
+        }
         nodes.into()
     }
 
@@ -154,7 +168,7 @@ impl Sequence {
         // see also sort_by_sequence in array.rs. The signatures are
         // sufficiently different we don't want to try to unify them.
 
-        let items = self.iter().collect::<Vec<_>>();
+        let items = self.to_vec()?;
         let keys = self.iter().map(get).collect::<error::Result<Vec<_>>>()?;
 
         let mut keys_and_items = keys.into_iter().zip(items).collect::<Vec<_>>();
@@ -179,7 +193,7 @@ impl Sequence {
 
     /// Flatten all arrays in this sequence
     pub fn flatten(&self) -> error::Result<Self> {
-        let mut result = vec![];
+        let mut result = Vec::with_capacity(self.materialized_len()?);
         for item in self.iter() {
             if let Ok(array) = item.to_array() {
                 for sequence in array.iter() {
@@ -197,51 +211,57 @@ impl Sequence {
     pub(crate) fn union(
         self,
         other: Self,
-        annotations: xml::DocumentOrderAccess,
+        document_order: Option<xml::DocumentOrderAccess>,
     ) -> error::Result<Self> {
         let mut s = HashSet::new();
-        for node in self.nodes() {
-            s.insert(node?);
-        }
-        for node in other.nodes() {
-            s.insert(node?);
+        let mut nodes = Vec::new();
+        for node in self.nodes().chain(other.nodes()) {
+            let node = node?;
+            if s.insert(node) {
+                nodes.push(node);
+            }
         }
-
-        Ok(Self::process_set_result(s, annotations))
+        Ok(Self::process_set_result(nodes, document_order))
     }
 
     pub(crate) fn intersect(
         self,
         other: Self,
-        annotations: xml::DocumentOrderAccess,
+        document_order: Option<xml::DocumentOrderAccess>,
     ) -> error::Result<Self> {
         let mut s = HashSet::new();
-        let mut r = HashSet::new();
-        for node in self.nodes() {
+        for node in other.nodes() {
             s.insert(node?);
         }
-        for node in other.nodes() {
+        let mut nodes = Vec::new();
+        for node in self.nodes() {
             let node = node?;
-            if s.contains(&node) {
-                r.insert(node);
+            // removing it makes sure we only add it once
+            if s.remove(&node) {
+                nodes.push(node);
             }
         }
-        Ok(Self::process_set_result(r, annotations))
+        Ok(Self::process_set_result(nodes, document_order))
     }
 
     pub(crate) fn except(
         self,
         other: Self,
-        annotations: xml::DocumentOrderAccess,
+        document_order: Option<xml::DocumentOrderAccess>,
     ) -> error::Result<Self> {
         let mut s = HashSet::new();
-        for node in self.nodes() {
+        for node in other.nodes() {
             s.insert(node?);
         }
-        for node in other.nodes() {
-            s.remove(&node?);
+        let mut nodes = Vec::new();
+        for node in self.nodes() {
+            let node = node?;
+            // inserting it makes sure we only add it once
+            if s.insert(node) {
+                nodes.push(node);
+            }
         }
-        Ok(Self::process_set_result(s, annotations))
+        Ok(Self::process_set_result(nodes, document_order))
     }
 
     /// Normalize this sequence into a document node, according to
@@ -259,6 +279,37 @@ impl Sequence {
         serialize_sequence(self, params, xot)
     }
 
+    /// Serialize this sequence according to serialization parameters,
+    /// writing the output to a [`Write`](std::io::Write).
+    ///
+    /// Unlike [`Sequence::serialize`] this doesn't build the output as a
+    /// string in memory first.
+    pub fn serialize_write(
+        &self,
+        params: SerializationParameters,
+        xot: &mut Xot,
+        w: &mut impl std::io::Write,
+    ) -> error::Result<()> {
+        serialize_sequence_write(self, params, xot, w)
+    }
+
+    /// Serialize this sequence according to serialization parameters,
+    /// as an iterator of string chunks.
+    ///
+    /// This is useful when the consumer pulls the output piece by piece,
+    /// such as a streaming HTTP response. With the XML output method each
+    /// top-level node of the sequence is serialized only when its chunk is
+    /// requested; other output is produced as a single chunk.
+    /// Concatenated, the chunks are the same as the output of
+    /// [`Sequence::serialize`].
+    pub fn serialize_iter<'a>(
+        &self,
+        params: SerializationParameters,
+        xot: &'a mut Xot,
+    ) -> SerializedChunks<'a> {
+        serialize_sequence_chunks(self, params, xot)
+    }
+
     /// Display representation of the sequence
     pub fn display_representation(&self, xot: &Xot, context: &context::DynamicContext) -> String {
         // TODO: various unwraps
@@ -273,7 +324,7 @@ impl Sequence {
                 format!("(\n{}\n)", representations.join(",\n"))
             }
             Sequence::Range(range) => {
-                format!("{} to {}", range.start(), range.end())
+                format!("{} to {}", range.start(), range.end() - ibig::IBig::from(1))
             }
         }
     }
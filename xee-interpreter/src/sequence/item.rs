@@ -137,6 +137,33 @@ impl Item {
         }
     }
 
+    /// The name of the type of the item, for diagnostics.
+    ///
+    /// - For an atomic value, it's the name of its type, such as
+    ///   `xs:integer`.
+    ///
+    /// - For a node, it's the kind test that matches it, such as `element()`
+    ///   or `attribute()`.
+    ///
+    /// - For a function, it's `map(*)`, `array(*)` or `function(*)`.
+    pub fn type_name(&self, xot: &Xot) -> &'static str {
+        match self {
+            Item::Atomic(atomic) => atomic.type_name(),
+            Item::Node(node) => match xot.value(*node) {
+                xot::Value::Document => "document-node()",
+                xot::Value::Element(_) => "element()",
+                xot::Value::Attribute(_) => "attribute()",
+                xot::Value::Text(_) => "text()",
+                xot::Value::Comment(_) => "comment()",
+                xot::Value::ProcessingInstruction(_) => "processing-instruction()",
+                xot::Value::Namespace(_) => "namespace-node()",
+            },
+            Item::Function(function::Function::Map(_)) => "map(*)",
+            Item::Function(function::Function::Array(_)) => "array(*)",
+            Item::Function(_) => "function(*)",
+        }
+    }
+
     /// Check whether this item is represents an XPath Map.
     pub(crate) fn is_map(&self) -> bool {
         match self {
@@ -154,6 +181,19 @@ impl Item {
     }
 }
 
+// The typed value of a node. Without schema types, it's the string value,
+// as xs:untypedAtomic; comments, processing instructions and namespace nodes
+// have xs:string as their type instead.
+pub(crate) fn typed_value(node: xot::Node, xot: &Xot) -> atomic::Atomic {
+    let s = xot.string_value(node);
+    match xot.value(node) {
+        xot::Value::Comment(_)
+        | xot::Value::ProcessingInstruction(_)
+        | xot::Value::Namespace(_) => s.into(),
+        _ => atomic::Atomic::Untyped(s.into()),
+    }
+}
+
 fn node_display_representation(node: xot::Node, xot: &Xot) -> error::Result<String> {
     match xot.value(node) {
         xot::Value::Attribute(attribute) => {
@@ -298,11 +338,7 @@ impl<'a> AtomizedItemIter<'a> {
     pub(crate) fn new(item: &'a Item, xot: &'a Xot) -> Self {
         match item {
             Item::Atomic(a) => Self::Atomic(std::iter::once(a.clone())),
-            Item::Node(n) => {
-                let s = xot.string_value(*n);
-                let value = atomic::Atomic::Untyped(s.into());
-                Self::Node(std::iter::once(value))
-            }
+            Item::Node(n) => Self::Node(std::iter::once(typed_value(*n, xot))),
             Item::Function(function) => match function {
                 function::Function::Array(a) => Self::Array(AtomizedArrayIter::new(a, xot)),
                 _ => Self::Erroring(std::iter::once(Err(error::Error::FOTY0013))),
@@ -2,7 +2,7 @@ use xot::Xot;
 
 use crate::{atomic, error, function};
 
-use super::item::Item;
+use super::item::{typed_value, Item};
 
 /// An iterator over the nodes in a sequence.
 pub struct NodeIter<I>
@@ -38,6 +38,49 @@ where
 }
 
 /// An iterator atomizing a sequence.
+///
+/// This follows the XPath
+/// [atomization](https://www.w3.org/TR/xpath-31/#id-atomization) rules,
+/// yielding a result for each atomized value:
+///
+/// - An atomic value is itself.
+///
+/// - A node is atomized to its typed value. As Xee doesn't do schema
+///   validation, this is always the string value of the node as an
+///   `xs:untypedAtomic`, for elements, text nodes and attributes alike.
+///
+/// - An array is atomized to the atomized values of its members, in order.
+///
+/// - Any other function item, including a map, can't be atomized. It
+///   yields a `FOTY0013` error in its place, after which you may continue
+///   with the remaining items.
+///
+/// Items are atomized as they are reached, so you can stop early without
+/// atomizing the whole sequence. You get one with
+/// [`Sequence::atomized`](super::Sequence::atomized).
+///
+/// ```rust
+/// use xee_interpreter::atomic::Atomic;
+/// use xee_interpreter::function::Array;
+/// use xee_interpreter::sequence::{Item, Sequence};
+/// use xot::Xot;
+///
+/// let mut xot = Xot::new();
+/// let doc = xot.parse("<root>x</root>").unwrap();
+/// let root = xot.document_element(doc).unwrap();
+/// let array = Array::from(vec![Sequence::from(3i64), Sequence::from(4i64)]);
+/// let sequence: Sequence = vec![Item::from(root), Item::from(2i64), Item::from(array)].into();
+///
+/// let mut atomized = sequence.atomized(&xot);
+/// // nodes are atomized to their string value, as xs:untypedAtomic
+/// assert_eq!(atomized.next().unwrap()?, Atomic::Untyped("x".into()));
+/// assert_eq!(atomized.next().unwrap()?.string_value(), "2");
+/// // arrays are flattened
+/// assert_eq!(atomized.next().unwrap()?.string_value(), "3");
+/// assert_eq!(atomized.next().unwrap()?.string_value(), "4");
+/// assert!(atomized.next().is_none());
+/// # Ok::<(), xee_interpreter::error::Error>(())
+/// ```
 pub struct AtomizedIter<'a, I>
 where
     I: Iterator<Item = Item> + 'a,
@@ -150,8 +193,7 @@ pub struct AtomizedNodeIter {
 
 impl AtomizedNodeIter {
     fn new(node: xot::Node, xot: &Xot) -> Self {
-        let s = xot.string_value(node);
-        let typed_value = vec![atomic::Atomic::Untyped(s.into())];
+        let typed_value = vec![typed_value(node, xot)];
         Self {
             typed_value,
             typed_value_index: 0,
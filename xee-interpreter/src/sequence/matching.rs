@@ -214,6 +214,12 @@ impl Sequence {
         Ok(self)
     }
 
+    // a range only contains integers, which match these types as they are,
+    // so we don't need to materialize it
+    fn is_lazy_integer_match(&self, xs: Xs) -> bool {
+        matches!(self, Sequence::Range(_)) && matches!(xs, Xs::Integer | Xs::AnyAtomicType)
+    }
+
     fn atomic_occurrence_item_matching(
         self,
         occurrence_item: &ast::Item,
@@ -237,7 +243,10 @@ impl Sequence {
                 }
             }
             ast::Occurrence::Many => {
-                let mut atoms = Vec::with_capacity(self.len());
+                if self.is_lazy_integer_match(xs) {
+                    return Ok(self);
+                }
+                let mut atoms = Vec::with_capacity(self.materialized_len()?);
                 for atom in self.atomized(xot) {
                     atoms.push(atom?.atomic_type_matching(xs, cast_or_promote_atomic)?);
                 }
@@ -247,7 +256,10 @@ impl Sequence {
                 if self.is_empty() {
                     return Err(error::Error::XPTY0004);
                 }
-                let mut atoms = Vec::with_capacity(self.len());
+                if self.is_lazy_integer_match(xs) {
+                    return Ok(self);
+                }
+                let mut atoms = Vec::with_capacity(self.materialized_len()?);
                 for atom in self.atomized(xot) {
                     atoms.push(atom?.atomic_type_matching(xs, cast_or_promote_atomic)?);
                 }
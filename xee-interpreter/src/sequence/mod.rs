@@ -27,5 +27,5 @@ pub use item::{AtomizedItemIter, Item};
 pub use iter::AtomizedIter;
 pub(crate) use iter::{one, option};
 pub(crate) use opc::OptionParameterConverter;
-pub use serialization::SerializationParameters;
-pub(crate) use variant::Range;
+pub use serialization::{SerializationParameters, SerializedChunks};
+pub(crate) use variant::{check_materialized_len, Range};
@@ -6,7 +6,10 @@ use xee_xpath_ast::ast;
 use xot::xmlname::{NameStrInfo, OwnedName};
 use xot::Xot;
 
-use crate::{atomic, context, error, function::Map};
+use crate::{
+    atomic, context, error,
+    function::{Function, Map},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum QNameOrString {
@@ -114,6 +117,36 @@ impl<'a> OptionParameterConverter<'a> {
         Ok(values)
     }
 
+    pub(crate) fn function(&self, name: &str) -> error::Result<Option<Function>> {
+        let name: atomic::Atomic = name.to_string().into();
+        let value = if let Some(value) = self.map.get(&name) {
+            value.clone().option()?
+        } else {
+            return Ok(None);
+        };
+        value.map(|item| item.to_function()).transpose()
+    }
+
+    pub(crate) fn map(&self, name: &str) -> error::Result<Option<Map>> {
+        let name: atomic::Atomic = name.to_string().into();
+        let value = if let Some(value) = self.map.get(&name) {
+            value.clone().option()?
+        } else {
+            return Ok(None);
+        };
+        value.map(|item| item.to_map()).transpose()
+    }
+
+    pub(crate) fn node(&self, name: &str) -> error::Result<Option<xot::Node>> {
+        let name: atomic::Atomic = name.to_string().into();
+        let value = if let Some(value) = self.map.get(&name) {
+            value.clone().option()?
+        } else {
+            return Ok(None);
+        };
+        value.map(|item| item.to_node()).transpose()
+    }
+
     pub(crate) fn qname_or_string(
         &self,
         name: &str,
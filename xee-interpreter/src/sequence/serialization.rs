@@ -1,6 +1,12 @@
+use std::borrow::Cow;
+use std::io::Write;
+
 use ahash::HashMap;
 use rust_decimal::Decimal;
-use xot::{xmlname::OwnedName, Xot};
+use xot::{
+    xmlname::{NameStrInfo, OwnedName},
+    Xot,
+};
 
 use xee_schema_type::Xs;
 
@@ -15,6 +21,7 @@ use super::{
     opc::{OptionParameterConverter, QNameOrString},
 };
 
+#[derive(Debug, Clone)]
 pub struct SerializationParameters {
     pub allow_duplicate_names: bool,
     pub byte_order_mark: bool,
@@ -123,7 +130,7 @@ impl SerializationParameters {
 
         let undeclare_prefixes = c.option_with_default("undeclare-prefixes", Xs::Boolean, false)?;
 
-        // TODO: use-character-maps
+        let use_character_maps = character_map(c.map("use-character-maps")?)?;
 
         let version = c.option_with_default("version", Xs::String, "1.0".to_string())?;
 
@@ -147,7 +154,7 @@ impl SerializationParameters {
             standalone,
             suppress_indentation,
             undeclare_prefixes,
-            use_character_maps: HashMap::default(),
+            use_character_maps,
             version,
         })
     }
@@ -183,6 +190,25 @@ impl SerializationParameters {
     }
 }
 
+// A character map as given to fn:serialize maps single characters to
+// strings.
+fn character_map(map: Option<Map>) -> error::Result<HashMap<char, String>> {
+    let mut character_map = HashMap::default();
+    if let Some(map) = map {
+        for (key, value) in map.entries() {
+            let key: String = key.clone().try_into()?;
+            let mut chars = key.chars();
+            let character = match (chars.next(), chars.next()) {
+                (Some(character), None) => character,
+                _ => return Err(error::Error::SEPM0016),
+            };
+            let value: String = value.clone().one()?.to_atomic()?.try_into()?;
+            character_map.insert(character, value);
+        }
+    }
+    Ok(character_map)
+}
+
 impl Default for SerializationParameters {
     fn default() -> Self {
         Self::new()
@@ -194,11 +220,23 @@ pub(crate) fn serialize_sequence(
     parameters: SerializationParameters,
     xot: &mut Xot,
 ) -> error::Result<String> {
+    let mut buf = Vec::new();
+    serialize_sequence_write(arg, parameters, xot, &mut buf)?;
+    // all serialization methods produce UTF-8
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+pub(crate) fn serialize_sequence_write(
+    arg: &Sequence,
+    parameters: SerializationParameters,
+    xot: &mut Xot,
+    w: &mut impl Write,
+) -> error::Result<()> {
     if let Some(local_name) = parameters.method.local_name() {
         match local_name {
-            "xml" => serialize_xml(arg, parameters, xot),
-            "html" => serialize_html(arg, parameters, xot),
-            "json" => serialize_json(arg, parameters, xot),
+            "xml" => serialize_xml(arg, parameters, xot, w),
+            "html" => serialize_html(arg, parameters, xot, w),
+            "json" => serialize_json(arg, parameters, xot, w),
             _ => Err(error::Error::SEPM0016),
         }
     } else {
@@ -206,13 +244,108 @@ pub(crate) fn serialize_sequence(
     }
 }
 
+/// The serialization of a sequence, as an iterator of string chunks.
+///
+/// Created by [`Sequence::serialize_iter`]. Concatenated, the chunks are
+/// the same as the output of [`Sequence::serialize`].
+pub struct SerializedChunks<'a> {
+    state: ChunksState<'a>,
+}
+
+enum ChunksState<'a> {
+    // the whole serialization as a single chunk, or an error
+    Whole(Option<error::Result<String>>),
+    // a chunk per top-level node of the normalized sequence
+    Nodes {
+        xot: &'a Xot,
+        parameters: Box<SerializationParameters>,
+        output_parameters: xot::output::xml::Parameters,
+        nodes: std::vec::IntoIter<xot::Node>,
+    },
+}
+
+impl Iterator for SerializedChunks<'_> {
+    type Item = error::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.state {
+            ChunksState::Whole(whole) => whole.take(),
+            ChunksState::Nodes {
+                xot,
+                parameters,
+                output_parameters,
+                nodes,
+            } => {
+                let node = nodes.next()?;
+                let mut buf = Vec::new();
+                // only the first chunk gets the XML declaration
+                let serialized =
+                    write_xml(node, output_parameters.clone(), parameters, xot, &mut buf);
+                output_parameters.declaration = None;
+                // all serialization methods produce UTF-8
+                Some(serialized.map(|_| String::from_utf8(buf).unwrap()))
+            }
+        }
+    }
+}
+
+pub(crate) fn serialize_sequence_chunks<'a>(
+    arg: &Sequence,
+    parameters: SerializationParameters,
+    xot: &'a mut Xot,
+) -> SerializedChunks<'a> {
+    // Only XML output without indentation or doctype can be serialized a
+    // top-level node at a time and still be the same as the serialization
+    // as a whole. We produce anything else as a single chunk.
+    let chunked = parameters.method.local_name() == Some("xml")
+        && !parameters.indent
+        && parameters.doctype_system.is_none()
+        && parameters.doctype_public.is_none();
+    if !chunked {
+        return SerializedChunks {
+            state: ChunksState::Whole(Some(serialize_sequence(arg, parameters, xot))),
+        };
+    }
+    let document = match arg.normalize(&parameters.item_separator, xot) {
+        Ok(document) => document,
+        Err(error) => {
+            return SerializedChunks {
+                state: ChunksState::Whole(Some(Err(error))),
+            }
+        }
+    };
+    let output_parameters = xot_xml_parameters(&parameters, xot);
+    let mut nodes = xot.children(document).collect::<Vec<_>>();
+    // an empty document still has a declaration to serialize
+    if nodes.is_empty() {
+        nodes.push(document);
+    }
+    SerializedChunks {
+        state: ChunksState::Nodes {
+            xot,
+            parameters: Box::new(parameters),
+            output_parameters,
+            nodes: nodes.into_iter(),
+        },
+    }
+}
+
 fn serialize_xml(
     arg: &Sequence,
     parameters: SerializationParameters,
     xot: &mut Xot,
-) -> Result<String, error::Error> {
+    w: &mut impl Write,
+) -> Result<(), error::Error> {
     let node = arg.normalize(&parameters.item_separator, xot)?;
-    let indentation = xot_indentation(&parameters, xot);
+    let output_parameters = xot_xml_parameters(&parameters, xot);
+    write_xml(node, output_parameters, &parameters, xot, w)
+}
+
+fn xot_xml_parameters(
+    parameters: &SerializationParameters,
+    xot: &mut Xot,
+) -> xot::output::xml::Parameters {
+    let indentation = xot_indentation(parameters, xot);
     let cdata_section_elements = xot_names(&parameters.cdata_section_elements, xot);
     let declaration = if !parameters.omit_xml_declaration {
         Some(xot::output::xml::Declaration {
@@ -222,7 +355,10 @@ fn serialize_xml(
     } else {
         None
     };
-    let doctype = match (parameters.doctype_public, parameters.doctype_system) {
+    let doctype = match (
+        parameters.doctype_public.clone(),
+        parameters.doctype_system.clone(),
+    ) {
         (Some(public), Some(system)) => Some(xot::output::xml::DocType::Public { public, system }),
         (None, Some(system)) => Some(xot::output::xml::DocType::System { system }),
         // TODO: this should really not happen?
@@ -232,22 +368,77 @@ fn serialize_xml(
         }),
         (None, None) => None,
     };
-    let output_parameters = xot::output::xml::Parameters {
+    xot::output::xml::Parameters {
         indentation,
         cdata_section_elements,
         declaration,
         doctype,
         ..Default::default()
-    };
+    }
+}
+
+fn write_xml(
+    node: xot::Node,
+    output_parameters: xot::output::xml::Parameters,
+    parameters: &SerializationParameters,
+    xot: &Xot,
+    w: &mut impl Write,
+) -> Result<(), error::Error> {
+    if parameters.use_character_maps.is_empty() {
+        return write_with_xot(w, |w| xot.serialize_xml_write(output_parameters, node, w));
+    }
+    write_character_mapped(
+        node,
+        &output_parameters,
+        &parameters.use_character_maps,
+        xot,
+        w,
+    )
+}
 
-    Ok(xot.serialize_xml_string(output_parameters, node)?)
+// Xot only keeps the message of an error it gets from the writer, so we
+// hold on to the error itself to report that instead.
+fn write_with_xot<W: Write>(
+    w: &mut W,
+    write: impl FnOnce(&mut IoErrorWriter<W>) -> Result<(), xot::Error>,
+) -> error::Result<()> {
+    let mut w = IoErrorWriter { w, error: None };
+    match write(&mut w) {
+        Ok(()) => Ok(()),
+        Err(xot::Error::Io(_)) if w.error.is_some() => Err(w.error.unwrap().into()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+struct IoErrorWriter<'a, W: Write> {
+    w: &'a mut W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: Write> IoErrorWriter<'_, W> {
+    fn keep(&mut self, error: std::io::Error) -> std::io::Error {
+        let reported = std::io::Error::new(error.kind(), error.to_string());
+        self.error = Some(error);
+        reported
+    }
+}
+
+impl<W: Write> Write for IoErrorWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.w.write(buf).map_err(|error| self.keep(error))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.w.flush().map_err(|error| self.keep(error))
+    }
 }
 
 fn serialize_html(
     arg: &Sequence,
     parameters: SerializationParameters,
     xot: &mut Xot,
-) -> Result<String, error::Error> {
+    w: &mut impl Write,
+) -> Result<(), error::Error> {
     let node = arg.normalize(&parameters.item_separator, xot)?;
     // TODO: no check yet for html version rejecting versions that aren't 5
     let cdata_section_elements = xot_names(&parameters.cdata_section_elements, xot);
@@ -257,16 +448,275 @@ fn serialize_html(
         indentation,
         cdata_section_elements,
     };
-    Ok(html5.serialize_string(output_parameters, node)?)
+    if parameters.use_character_maps.is_empty() {
+        return write_with_xot(w, |w| html5.serialize_write(output_parameters, node, w));
+    }
+    // Xot has no tokens for html5 output, so we serialize with placeholders
+    // for the mapped characters and replace those afterwards
+    let mut buf = Vec::new();
+    html5.serialize_write_with_normalizer(
+        output_parameters,
+        node,
+        &mut buf,
+        PlaceholderNormalizer {
+            character_map: &parameters.use_character_maps,
+        },
+    )?;
+    write_placeholders_mapped(&buf, &parameters.use_character_maps, w)
+}
+
+// Xot only lets us normalize text and attribute values before it escapes
+// them, so we replace each mapped character by a placeholder that escaping
+// leaves alone: its code point between NUL characters. NUL can't occur in XML
+// text, so once serialized we can safely replace the placeholders.
+struct PlaceholderNormalizer<'a> {
+    character_map: &'a HashMap<char, String>,
+}
+
+impl xot::output::Normalizer for PlaceholderNormalizer<'_> {
+    fn normalize<'a>(&self, content: Cow<'a, str>) -> Cow<'a, str> {
+        if !content.chars().any(|c| self.character_map.contains_key(&c)) {
+            return content;
+        }
+        let mut normalized = String::with_capacity(content.len());
+        for c in content.chars() {
+            if self.character_map.contains_key(&c) {
+                normalized.push('\0');
+                normalized.push_str(&(c as u32).to_string());
+                normalized.push('\0');
+            } else {
+                normalized.push(c);
+            }
+        }
+        Cow::Owned(normalized)
+    }
+}
+
+fn write_placeholders_mapped(
+    buf: &[u8],
+    character_map: &HashMap<char, String>,
+    w: &mut impl Write,
+) -> error::Result<()> {
+    // all serialization methods produce UTF-8
+    let serialized = std::str::from_utf8(buf).unwrap();
+    // the placeholders are the odd parts between the NUL characters
+    for (i, part) in serialized.split('\0').enumerate() {
+        let part = if i.is_multiple_of(2) {
+            part
+        } else {
+            let c = part.parse().ok().and_then(char::from_u32).unwrap();
+            character_map[&c].as_str()
+        };
+        w.write_all(part.as_bytes())?;
+    }
+    Ok(())
+}
+
+// Character maps apply to text and attribute values, and the string a
+// character maps to is written as is, without escaping, so that a character
+// can be mapped to an entity reference for instance. Xot escapes values
+// before we get to see them, so we write its tokens ourselves, and escape the
+// values that contain mapped characters while we map them.
+fn write_character_mapped(
+    node: xot::Node,
+    output_parameters: &xot::output::xml::Parameters,
+    character_map: &HashMap<char, String>,
+    xot: &Xot,
+    w: &mut impl Write,
+) -> error::Result<()> {
+    write_prolog(node, output_parameters, xot, w)?;
+    let token_parameters = xot::output::TokenSerializeParameters {
+        cdata_section_elements: output_parameters.cdata_section_elements.clone(),
+        unescaped_gt: output_parameters.unescaped_gt,
+    };
+    let mut token_writer = MappedTokenWriter {
+        xot,
+        cdata_section_elements: &output_parameters.cdata_section_elements,
+        character_map,
+    };
+    if let Some(indentation) = &output_parameters.indentation {
+        for (node, output, token) in xot.pretty_tokens(
+            node,
+            token_parameters,
+            &indentation.suppress,
+            xot::output::NoopNormalizer,
+        ) {
+            if token.indentation > 0 {
+                w.write_all(" ".repeat(token.indentation * 2).as_bytes())?;
+            }
+            token_writer.write(node, &output, token.space, &token.text, w)?;
+            if token.newline {
+                w.write_all(b"\n")?;
+            }
+        }
+    } else {
+        for (node, output, token) in xot.tokens(node, token_parameters, xot::output::NoopNormalizer)
+        {
+            token_writer.write(node, &output, token.space, &token.text, w)?;
+        }
+    }
+    Ok(())
+}
+
+// The XML declaration and doctype declaration, as Xot writes them
+fn write_prolog(
+    node: xot::Node,
+    output_parameters: &xot::output::xml::Parameters,
+    xot: &Xot,
+    w: &mut impl Write,
+) -> error::Result<()> {
+    if let Some(declaration) = &output_parameters.declaration {
+        w.write_all(b"<?xml version=\"1.0\"")?;
+        if let Some(encoding) = &declaration.encoding {
+            write!(w, " encoding=\"{}\"", encoding)?;
+        }
+        if let Some(standalone) = declaration.standalone {
+            write!(
+                w,
+                " standalone=\"{}\"",
+                if standalone { "yes" } else { "no" }
+            )?;
+        }
+        w.write_all(b"?>\n")?;
+    }
+    if let Some(doctype) = &output_parameters.doctype {
+        let element = if xot.is_document(node) {
+            xot.document_element(node)?
+        } else {
+            node
+        };
+        let name = xot
+            .node_name_ref(element)?
+            .ok_or(xot::Error::NotElement(element))?;
+        write!(w, "<!DOCTYPE {}", name.full_name())?;
+        match doctype {
+            xot::output::xml::DocType::Public { public, system } => {
+                write!(w, " PUBLIC \"{}\" \"{}\"", public, system)?
+            }
+            xot::output::xml::DocType::System { system } => write!(w, " SYSTEM \"{}\"", system)?,
+        }
+        w.write_all(b">\n")?;
+    }
+    Ok(())
+}
+
+struct MappedTokenWriter<'a> {
+    xot: &'a Xot,
+    cdata_section_elements: &'a [xot::NameId],
+    character_map: &'a HashMap<char, String>,
+}
+
+impl MappedTokenWriter<'_> {
+    fn write(
+        &mut self,
+        node: xot::Node,
+        output: &xot::output::Output,
+        space: bool,
+        text: &str,
+        w: &mut impl Write,
+    ) -> error::Result<()> {
+        if space {
+            w.write_all(b" ")?;
+        }
+        match output {
+            xot::output::Output::Text(value) if self.is_mapped(value) => {
+                let in_cdata_section = self
+                    .xot
+                    .parent(node)
+                    .and_then(|parent| self.xot.element(parent))
+                    .is_some_and(|element| self.cdata_section_elements.contains(&element.name()));
+                let text = if in_cdata_section {
+                    self.map_cdata(value)
+                } else {
+                    self.map(value, escape_text)
+                };
+                w.write_all(text.as_bytes())?;
+            }
+            xot::output::Output::Attribute(_, value) if self.is_mapped(value) => {
+                // the token is the name followed by the escaped value, and a
+                // name can't contain `=`
+                let name = text.split_once('=').map_or(text, |(name, _)| name);
+                write!(w, "{}=\"{}\"", name, self.map(value, escape_attribute))?;
+            }
+            _ => w.write_all(text.as_bytes())?,
+        }
+        Ok(())
+    }
+
+    fn is_mapped(&self, value: &str) -> bool {
+        value.chars().any(|c| self.character_map.contains_key(&c))
+    }
+
+    fn map(&self, value: &str, escape: fn(char) -> Option<&'static str>) -> String {
+        let mut mapped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if let Some(s) = self.character_map.get(&c) {
+                mapped.push_str(s);
+            } else if let Some(escaped) = escape(c) {
+                mapped.push_str(escaped);
+            } else {
+                mapped.push(c);
+            }
+        }
+        mapped
+    }
+
+    // a mapped character can't be inside a CDATA section, so we end the
+    // section before it and start a new one after it
+    fn map_cdata(&self, value: &str) -> String {
+        let mut mapped = String::with_capacity(value.len());
+        let mut section = String::new();
+        for c in value.chars() {
+            if let Some(s) = self.character_map.get(&c) {
+                push_cdata_section(&mut mapped, &section);
+                section.clear();
+                mapped.push_str(s);
+            } else {
+                section.push(c);
+            }
+        }
+        push_cdata_section(&mut mapped, &section);
+        mapped
+    }
+}
+
+fn push_cdata_section(s: &mut String, section: &str) {
+    if section.is_empty() {
+        return;
+    }
+    s.push_str("<![CDATA[");
+    s.push_str(&section.replace("]]>", "]]]]><![CDATA[>"));
+    s.push_str("]]>");
+}
+
+// the same escaping Xot uses for text and attribute values
+fn escape_text(c: char) -> Option<&'static str> {
+    match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        _ => None,
+    }
+}
+
+fn escape_attribute(c: char) -> Option<&'static str> {
+    match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '\'' => Some("&apos;"),
+        '"' => Some("&quot;"),
+        _ => None,
+    }
 }
 
 fn serialize_json(
     arg: &Sequence,
     parameters: SerializationParameters,
     xot: &mut Xot,
-) -> Result<String, error::Error> {
+    w: &mut impl Write,
+) -> Result<(), error::Error> {
     let r = serialize_json_sequence(arg, &parameters, xot)?;
-    Ok(r.dump())
+    Ok(r.write(w)?)
 }
 
 fn serialize_json_sequence(
@@ -493,6 +943,27 @@ mod tests {
         assert_eq!(params.cdata_section_elements[1], script);
     }
 
+    #[test]
+    fn test_character_map_in_cdata_section() {
+        let mut xot = Xot::new();
+        let document = xot.parse("<doc><code>aé]]&gt;</code></doc>").unwrap();
+        let mut parameters = SerializationParameters::new();
+        parameters.omit_xml_declaration = true;
+        parameters.cdata_section_elements = vec![OwnedName::new(
+            "code".to_string(),
+            "".to_string(),
+            "".to_string(),
+        )];
+        parameters
+            .use_character_maps
+            .insert('é', "&eacute;".to_string());
+        let sequence = Sequence::from(vec![Item::Node(document)]);
+        assert_eq!(
+            serialize_sequence(&sequence, parameters, &mut xot).unwrap(),
+            "<doc><code><![CDATA[a]]>&eacute;<![CDATA[]]]]><![CDATA[>]]></code></doc>"
+        );
+    }
+
     #[test]
     fn test_qname_or_string_string() {
         let json: atomic::Atomic = "json".to_string().into();
@@ -555,4 +1026,45 @@ mod tests {
             QNameOrString::String("xml".to_string())
         );
     }
+
+    #[test]
+    fn test_use_character_maps() {
+        let character_map = Map::new(vec![(
+            "\u{a0}".to_string().into(),
+            sequence::Sequence::from(vec![atomic::Atomic::from("&nbsp;".to_string())]),
+        )])
+        .unwrap();
+        let map = Map::new(vec![(
+            "use-character-maps".to_string().into(),
+            sequence::Sequence::from(vec![sequence::Item::from(character_map)]),
+        )])
+        .unwrap();
+        let static_context = context::StaticContext::default();
+        let mut xot = Xot::new();
+        let params = SerializationParameters::from_map(map, &static_context, &xot).unwrap();
+        assert_eq!(params.use_character_maps.len(), 1);
+
+        let root = xot.parse("<p a=\"1\u{a0}2\">3\u{a0}&lt;4</p>").unwrap();
+        let sequence = sequence::Sequence::from(vec![root]);
+        let serialized = serialize_sequence(&sequence, params, &mut xot).unwrap();
+        assert_eq!(serialized, "<p a=\"1&nbsp;2\">3&nbsp;&lt;4</p>");
+    }
+
+    #[test]
+    fn test_use_character_maps_key_not_single_character() {
+        let character_map = Map::new(vec![(
+            "ab".to_string().into(),
+            sequence::Sequence::from(vec![atomic::Atomic::from("c".to_string())]),
+        )])
+        .unwrap();
+        let map = Map::new(vec![(
+            "use-character-maps".to_string().into(),
+            sequence::Sequence::from(vec![sequence::Item::from(character_map)]),
+        )])
+        .unwrap();
+        let static_context = context::StaticContext::default();
+        let xot = Xot::new();
+        let params = SerializationParameters::from_map(map, &static_context, &xot);
+        assert!(matches!(params, Err(error::Error::SEPM0016)));
+    }
 }
@@ -6,4 +6,5 @@ mod range;
 pub use empty::Empty;
 pub use many::Many;
 pub use one::One;
+pub(crate) use range::check_materialized_len;
 pub use range::{Range, RangeIterator};
@@ -2,11 +2,29 @@ use ibig::IBig;
 
 use crate::atomic::AtomicCompareValue;
 use crate::error;
+use crate::function;
 use crate::sequence::traits::{SequenceCompare, SequenceCore, SequenceExt, SequenceOrder};
-use crate::sequence::Item;
+use crate::sequence::{Item, Sequence};
 
-// this size should be below a usize
-const MAXIMUM_RANGE_SIZE: i64 = 2_i64.pow(25);
+// this size should be below a usize, also on 32 bit platforms. A range
+// isn't materialized, so functions like fn:exists and fn:head can work on
+// large ranges cheaply.
+const MAXIMUM_RANGE_SIZE: i64 = u32::MAX as i64;
+
+// materializing a range larger than this is a FOAR0002 error, rather than
+// an attempt to allocate all its items
+const MAXIMUM_MATERIALIZED_RANGE_SIZE: usize = 2_usize.pow(25);
+
+/// Check that this many items can be materialized.
+///
+/// Only a lazy range can cheaply have more items than that, so this is the
+/// error of a range that's too large.
+pub(crate) fn check_materialized_len(len: usize) -> error::Result<usize> {
+    if len > MAXIMUM_MATERIALIZED_RANGE_SIZE {
+        return Err(error::Error::FOAR0002);
+    }
+    Ok(len)
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Range {
@@ -34,6 +52,26 @@ impl Range {
         &self.end
     }
 
+    // a range from start (inclusive) to end (exclusive), relative to the start
+    // of this range
+    pub(crate) fn subrange(&self, start: usize, end: usize) -> Range {
+        Range {
+            start: Box::new(self.start.as_ref() + start),
+            end: Box::new(self.start.as_ref() + end),
+        }
+    }
+
+    // the length of this range once it's materialized
+    pub(crate) fn materialized_len(&self) -> error::Result<usize> {
+        check_materialized_len(self.len())
+    }
+
+    pub(crate) fn to_vec(&self) -> error::Result<Vec<Item>> {
+        let mut items = Vec::with_capacity(self.materialized_len()?);
+        items.extend(self.iter());
+        Ok(items)
+    }
+
     pub(crate) fn contains(&self, index: &IBig) -> bool {
         index >= self.start.as_ref() && index < self.end.as_ref()
     }
@@ -172,8 +210,15 @@ impl Iterator for RangeIterator {
         }
     }
 
+    // skipping ahead is cheap, as we can calculate the index
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.index += n;
+        self.next()
+    }
+
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = &self.end - &self.start;
+        let len = &self.end - &self.start - &self.index;
+        let len = len.max(IBig::from(0));
         // we know that we don't have a range that's > usize as we cannot construct
         // any
         let len: usize = len.try_into().expect("range size is within usize");
@@ -186,6 +231,15 @@ where
     I: Iterator<Item = Item> + 'a,
     Range: SequenceCore<'a, I>,
 {
+    fn to_array(&'a self) -> error::Result<function::Array> {
+        // unlike the other sequences, a range may be too large to materialize
+        let array = self
+            .to_vec()?
+            .into_iter()
+            .map(|item| item.into())
+            .collect::<Vec<Sequence>>();
+        Ok(array.into())
+    }
 }
 
 impl<'a, I> SequenceCompare<'a, I> for Range
@@ -1,5 +1,6 @@
 use std::cmp::Ordering;
 use std::collections::hash_map::Entry;
+use std::ops::Range;
 use std::rc::Rc;
 
 use ahash::{HashMap, HashMapExt};
@@ -232,6 +233,122 @@ impl Collation {
             Collation::HtmlAscii => a.to_ascii_lowercase().cmp(&b.to_ascii_lowercase()),
         }
     }
+
+    // Find the first and shortest match of `search` in `s`, and give back its
+    // byte range in `s`.
+    //
+    // icu4x doesn't expose collation elements, so for the UCA we look for
+    // the first substring that compares as equal to `search` and that
+    // doesn't start with an ignorable character. A search string that
+    // contains only ignorable characters matches at the start.
+    pub(crate) fn find(&self, s: &str, search: &str) -> Option<Range<usize>> {
+        match self {
+            Collation::CodePoint => s.find(search).map(|start| start..start + search.len()),
+            // ASCII lowercasing doesn't change byte offsets
+            Collation::HtmlAscii => s
+                .to_ascii_lowercase()
+                .find(&search.to_ascii_lowercase())
+                .map(|start| start..start + search.len()),
+            Collation::Uca(collator) => {
+                if collator.compare(search, "") == Ordering::Equal {
+                    return Some(0..0);
+                }
+                s.char_indices().find_map(|(start, c)| {
+                    let end = start + c.len_utf8();
+                    if collator.compare(&s[start..end], "") == Ordering::Equal {
+                        return None;
+                    }
+                    uca_match_ends(collator, s, start, search)
+                        .next()
+                        .map(|end| start..end)
+                })
+            }
+        }
+    }
+
+    // Whether `s` starts with `search`. As with `find`, for the UCA we look
+    // for a prefix of `s` that compares as equal to `search`, extending it
+    // only while it can still match.
+    pub(crate) fn starts_with(&self, s: &str, search: &str) -> bool {
+        match self {
+            Collation::CodePoint => s.starts_with(search),
+            Collation::HtmlAscii => s
+                .to_ascii_lowercase()
+                .starts_with(&search.to_ascii_lowercase()),
+            Collation::Uca(collator) => {
+                collator.compare(search, "") == Ordering::Equal
+                    || uca_match_ends(collator, s, 0, search).next().is_some()
+            }
+        }
+    }
+
+    // Whether `s` ends with `search`. As with `find`, for the UCA we look
+    // for a suffix of `s` that compares as equal to `search`.
+    pub(crate) fn ends_with(&self, s: &str, search: &str) -> bool {
+        match self {
+            Collation::CodePoint => s.ends_with(search),
+            Collation::HtmlAscii => s
+                .to_ascii_lowercase()
+                .ends_with(&search.to_ascii_lowercase()),
+            Collation::Uca(collator) => {
+                collator.compare(search, "") == Ordering::Equal
+                    || s.char_indices().any(|(start, _)| {
+                        uca_match_ends(collator, s, start, search).any(|end| end == s.len())
+                    })
+            }
+        }
+    }
+
+    // A key that is equal for two strings exactly when they compare as
+    // equal under this collation.
+    pub(crate) fn key(&self, s: &str) -> error::Result<Vec<u8>> {
+        match self {
+            Collation::CodePoint => Ok(s.as_bytes().to_vec()),
+            Collation::HtmlAscii => Ok(s.to_ascii_lowercase().into_bytes()),
+            // icu4x doesn't expose sort keys, so we cannot produce them
+            Collation::Uca(_) => Err(error::Error::FOCH0004),
+        }
+    }
+}
+
+// The ends of the substrings of `s` from `start` that compare as equal to
+// `search`, shortest first.
+//
+// We extend the candidate a character at a time, and stop once extending it
+// further can't give a match: when it compares as greater than `search`, or
+// when even followed by U+FFFF, which has the highest primary weight, it
+// compares as less. A character can still form a contraction with the next
+// one, so we only stop when this holds for two candidates in a row.
+fn uca_match_ends<'a>(
+    collator: &'a Collator,
+    s: &'a str,
+    start: usize,
+    search: &'a str,
+) -> impl Iterator<Item = usize> + 'a {
+    let mut previous_dead = false;
+    s[start..]
+        .char_indices()
+        .map(move |(i, c)| start + i + c.len_utf8())
+        .map_while(move |end| {
+            let candidate = &s[start..end];
+            let ordering = collator.compare(candidate, search);
+            let dead = match ordering {
+                Ordering::Equal => false,
+                Ordering::Greater => true,
+                Ordering::Less => {
+                    let mut bound = String::with_capacity(candidate.len() + 3);
+                    bound.push_str(candidate);
+                    bound.push('\u{FFFF}');
+                    collator.compare(&bound, search) == Ordering::Less
+                }
+            };
+            if dead && previous_dead {
+                return None;
+            }
+            previous_dead = dead;
+            Some((end, ordering))
+        })
+        .filter_map(|(end, ordering)| (ordering == Ordering::Equal).then_some(end))
 }
 
 #[derive(Debug)]
@@ -0,0 +1,598 @@
+// Formatting of an integer using a picture string, as done by
+// fn:format-integer.
+// https://www.w3.org/TR/xpath-functions-31/#func-format-integer
+use ibig::{ops::Abs, IBig};
+use icu::properties::{maps, GeneralCategory};
+
+use crate::error;
+
+use super::number_format::Grouping;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IntegerPicture {
+    token: PrimaryToken,
+    ordinal: bool,
+    // used for a number we cannot express with the token
+    fallback: DecimalPattern,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PrimaryToken {
+    Decimal(DecimalPattern),
+    Alphabetic(u8),
+    Roman { lowercase: bool },
+    Words(WordsCase),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WordsCase {
+    Lower,
+    Upper,
+    Title,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DecimalPattern {
+    // the zero digit of the decimal digit family
+    zero: char,
+    mandatory_digits: usize,
+    grouping: DecimalGrouping,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DecimalGrouping {
+    None,
+    // the same separator at regular intervals, which is repeated for
+    // digits beyond the pattern
+    Regular { separator: char, size: usize },
+    // separators at the given positions only, counted in digits from the
+    // right
+    Positions(Vec<(usize, char)>),
+}
+
+impl IntegerPicture {
+    // The picture consists of a primary format token, optionally followed by
+    // a semicolon and a format modifier.
+    pub(crate) fn parse(picture: &str) -> error::Result<Self> {
+        let (primary, modifier) = match picture.rfind(';') {
+            Some(i) => (&picture[..i], &picture[i + 1..]),
+            None => (picture, ""),
+        };
+        if primary.is_empty() {
+            return Err(error::Error::FODF1310);
+        }
+        Ok(Self {
+            token: PrimaryToken::parse(primary)?,
+            ordinal: parse_modifier(modifier)?,
+            fallback: DecimalPattern::one(),
+        })
+    }
+
+    // A format token of xsl:number. Unlike a picture, this has no format
+    // modifier, and a token we don't support is treated as 1 rather than
+    // being an error. Decimal numbers are grouped as given by the
+    // grouping-separator and grouping-size attributes.
+    pub(super) fn number_token(token: &str, grouping: Option<&Grouping>) -> Self {
+        let group = |pattern: DecimalPattern| match grouping {
+            Some(grouping) => pattern.with_grouping(grouping.separator, grouping.size),
+            None => pattern,
+        };
+        let token = match DecimalPattern::number_token(token) {
+            Some(pattern) => PrimaryToken::Decimal(group(pattern)),
+            None => match PrimaryToken::parse(token) {
+                Ok(PrimaryToken::Decimal(_)) | Err(_) => {
+                    PrimaryToken::Decimal(group(DecimalPattern::one()))
+                }
+                Ok(token) => token,
+            },
+        };
+        Self {
+            token,
+            ordinal: false,
+            fallback: group(DecimalPattern::one()),
+        }
+    }
+
+    pub(crate) fn format(&self, number: &IBig) -> String {
+        let mut s = String::new();
+        if number < &IBig::from(0) {
+            s.push('-');
+        }
+        let number = number.abs();
+        let small: Option<u64> = (&number).try_into().ok();
+        match (&self.token, small) {
+            (PrimaryToken::Alphabetic(start), Some(n)) if n > 0 => {
+                s.push_str(&alphabetic(n, *start))
+            }
+            (PrimaryToken::Roman { lowercase }, Some(n)) if n > 0 && n < 4000 => {
+                if *lowercase {
+                    s.push_str(&roman(n).to_lowercase())
+                } else {
+                    s.push_str(&roman(n))
+                }
+            }
+            (PrimaryToken::Words(case), Some(n)) => s.push_str(&case.words(n, self.ordinal)),
+            (PrimaryToken::Decimal(pattern), _) => {
+                s.push_str(&pattern.format(&number));
+                if self.ordinal {
+                    s.push_str(ordinal_suffix(&number));
+                }
+            }
+            // a number we cannot express with the token is formatted as if
+            // the token were 1
+            _ => {
+                s.push_str(&self.fallback.format(&number));
+                if self.ordinal {
+                    s.push_str(ordinal_suffix(&number));
+                }
+            }
+        }
+        s
+    }
+}
+
+impl PrimaryToken {
+    fn parse(token: &str) -> error::Result<Self> {
+        if token.chars().any(is_decimal_digit) {
+            return Ok(PrimaryToken::Decimal(DecimalPattern::parse(token)?));
+        }
+        Ok(match token {
+            "a" => PrimaryToken::Alphabetic(b'a'),
+            "A" => PrimaryToken::Alphabetic(b'A'),
+            "i" => PrimaryToken::Roman { lowercase: true },
+            "I" => PrimaryToken::Roman { lowercase: false },
+            _ => match WordsCase::parse(token) {
+                Some(case) => PrimaryToken::Words(case),
+                // any token we don't support is treated as 1
+                None => PrimaryToken::Decimal(DecimalPattern::one()),
+            },
+        })
+    }
+}
+
+impl DecimalPattern {
+    // A decimal digit pattern consists of optional digit signs (#),
+    // followed by mandatory digit signs from a single decimal digit family.
+    // Any other character that isn't a letter or a number is a grouping
+    // separator; these cannot be at the start or the end, or next to each
+    // other.
+    fn parse(token: &str) -> error::Result<Self> {
+        let mut zero = None;
+        let mut mandatory_digits = 0;
+        let mut digits = 0;
+        // separators with the amount of digits to their left
+        let mut separators = Vec::new();
+        let mut after_separator = true;
+        for c in token.chars() {
+            if c == '#' {
+                if mandatory_digits > 0 {
+                    return Err(error::Error::FODF1310);
+                }
+                digits += 1;
+                after_separator = false;
+            } else if is_decimal_digit(c) {
+                let digit_zero = decimal_digit_zero(c);
+                if *zero.get_or_insert(digit_zero) != digit_zero {
+                    return Err(error::Error::FODF1310);
+                }
+                mandatory_digits += 1;
+                digits += 1;
+                after_separator = false;
+            } else if c.is_alphabetic() || c.is_numeric() {
+                return Err(error::Error::FODF1310);
+            } else {
+                if after_separator {
+                    return Err(error::Error::FODF1310);
+                }
+                separators.push((digits, c));
+                after_separator = true;
+            }
+        }
+        if after_separator {
+            return Err(error::Error::FODF1310);
+        }
+        let zero = zero.ok_or(error::Error::FODF1310)?;
+        let positions = separators
+            .into_iter()
+            .rev()
+            .map(|(left, c)| (digits - left, c))
+            .collect::<Vec<_>>();
+        Ok(Self {
+            zero,
+            mandatory_digits,
+            grouping: DecimalGrouping::new(positions, digits),
+        })
+    }
+
+    // An xsl:number format token is decimal if it's a run of digits from a
+    // single decimal digit family, all zero except for the last one, which
+    // is one. It has no grouping; that comes from the grouping-separator and
+    // grouping-size attributes instead.
+    fn number_token(token: &str) -> Option<Self> {
+        let (zeros, last) = token.split_at(token.char_indices().last()?.0);
+        let pattern = Self::parse(token).ok()?;
+        let one = char::from_u32(pattern.zero as u32 + 1)?;
+        (zeros.chars().all(|c| c == pattern.zero) && last.starts_with(one)).then_some(pattern)
+    }
+
+    // the pattern of the token 1 in the ASCII digit family
+    fn one() -> Self {
+        Self {
+            zero: '0',
+            mandatory_digits: 1,
+            grouping: DecimalGrouping::None,
+        }
+    }
+
+    fn with_grouping(self, separator: char, size: usize) -> Self {
+        Self {
+            grouping: if size > 0 {
+                DecimalGrouping::Regular { separator, size }
+            } else {
+                DecimalGrouping::None
+            },
+            ..self
+        }
+    }
+
+    fn format(&self, number: &IBig) -> String {
+        let digits = format!(
+            "{:0>width$}",
+            number.to_string(),
+            width = self.mandatory_digits
+        );
+        let len = digits.len();
+        let mut s = String::new();
+        for (i, digit) in digits.bytes().enumerate() {
+            if i > 0 {
+                if let Some(separator) = self.grouping.separator(len - i) {
+                    s.push(separator);
+                }
+            }
+            s.push(char::from_u32(self.zero as u32 + (digit - b'0') as u32).unwrap());
+        }
+        s
+    }
+}
+
+impl DecimalGrouping {
+    // The grouping is regular if the same separator is at every multiple of
+    // the position of the first one, up to the amount of digit signs.
+    fn new(positions: Vec<(usize, char)>, digits: usize) -> Self {
+        let (size, separator) = match positions.first() {
+            Some(first) => *first,
+            None => return DecimalGrouping::None,
+        };
+        let regular = positions
+            .iter()
+            .enumerate()
+            .all(|(i, (position, c))| *position == (i + 1) * size && *c == separator)
+            && (positions.len() + 1) * size >= digits;
+        if regular {
+            DecimalGrouping::Regular { separator, size }
+        } else {
+            DecimalGrouping::Positions(positions)
+        }
+    }
+
+    // the separator to put before the digit with this many digits to the
+    // right of it, including itself
+    fn separator(&self, position: usize) -> Option<char> {
+        match self {
+            DecimalGrouping::None => None,
+            DecimalGrouping::Regular { separator, size } => {
+                position.is_multiple_of(*size).then_some(*separator)
+            }
+            DecimalGrouping::Positions(positions) => positions
+                .iter()
+                .find(|(p, _)| *p == position)
+                .map(|(_, c)| *c),
+        }
+    }
+}
+
+// The format modifier is `c` (cardinal, the default) or `o` (ordinal),
+// optionally followed by a variant in parentheses, and then optionally `a`
+// (alphabetic) or `t` (traditional). We only support English, so the variant
+// is ignored, as is the distinction between alphabetic and traditional.
+fn parse_modifier(modifier: &str) -> error::Result<bool> {
+    let (ordinal, rest) = match modifier.strip_prefix('o') {
+        Some(rest) => (true, Some(rest)),
+        None => (false, modifier.strip_prefix('c')),
+    };
+    let rest = match rest {
+        Some(rest) => rest,
+        None => modifier,
+    };
+    let has_variant = rest.starts_with('(') && rest != modifier;
+    let rest = if has_variant {
+        let rest = rest
+            .strip_suffix('a')
+            .or_else(|| rest.strip_suffix('t'))
+            .unwrap_or(rest);
+        match rest.strip_suffix(')') {
+            Some(variant) if variant.len() > 1 => "",
+            _ => return Err(error::Error::FODF1310),
+        }
+    } else {
+        rest
+    };
+    match rest {
+        "" | "a" | "t" => Ok(ordinal),
+        _ => Err(error::Error::FODF1310),
+    }
+}
+
+pub(crate) fn is_decimal_digit(c: char) -> bool {
+    maps::general_category().get(c) == GeneralCategory::DecimalNumber
+}
+
+// Decimal digits in Unicode come in consecutive runs of ten, from zero to
+// nine, so we find the zero digit by looking at where the run starts.
+pub(crate) fn decimal_digit_zero(c: char) -> char {
+    let mut start = c as u32;
+    while let Some(previous) = start.checked_sub(1).and_then(char::from_u32) {
+        if !is_decimal_digit(previous) {
+            break;
+        }
+        start -= 1;
+    }
+    char::from_u32(start + (c as u32 - start) / 10 * 10).unwrap()
+}
+
+// a, b, ..., z, aa, ab, ...
+fn alphabetic(mut n: u64, start: u8) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push((start + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+fn roman(mut n: u64) -> String {
+    const NUMERALS: [(u64, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut s = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            s.push_str(numeral);
+            n -= value;
+        }
+    }
+    s
+}
+
+fn ordinal_suffix(number: &IBig) -> &'static str {
+    let last_two: u8 = (number % IBig::from(100)).try_into().unwrap();
+    match (last_two % 10, last_two / 10) {
+        (_, 1) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+impl WordsCase {
+    // the w, W and Ww format tokens
+    fn parse(token: &str) -> Option<Self> {
+        match token {
+            "w" => Some(WordsCase::Lower),
+            "W" => Some(WordsCase::Upper),
+            "Ww" => Some(WordsCase::Title),
+            _ => None,
+        }
+    }
+
+    fn words(&self, n: u64, ordinal: bool) -> String {
+        let words = if ordinal {
+            ordinal_words(n)
+        } else {
+            cardinal_words(n)
+        };
+        self.apply(&words)
+    }
+
+    fn apply(&self, words: &str) -> String {
+        match self {
+            WordsCase::Lower => words.to_string(),
+            WordsCase::Upper => words.to_uppercase(),
+            WordsCase::Title => words
+                .split(' ')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars).collect(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const SCALES: [(u64, &str); 6] = [
+    (1_000_000_000_000_000_000, "quintillion"),
+    (1_000_000_000_000_000, "quadrillion"),
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+// English words for a number, such as "one hundred twenty-three".
+fn cardinal_words(n: u64) -> String {
+    if n == 0 {
+        return ONES[0].to_string();
+    }
+    let mut words = Vec::new();
+    let mut rest = n;
+    for (scale, name) in SCALES {
+        if rest >= scale {
+            words.push(below_thousand_words(rest / scale));
+            words.push(name.to_string());
+            rest %= scale;
+        }
+    }
+    if rest > 0 {
+        words.push(below_thousand_words(rest));
+    }
+    words.join(" ")
+}
+
+fn below_thousand_words(n: u64) -> String {
+    let mut words = Vec::new();
+    if n >= 100 {
+        words.push(ONES[(n / 100) as usize].to_string());
+        words.push("hundred".to_string());
+    }
+    let rest = n % 100;
+    if rest >= 20 {
+        if rest.is_multiple_of(10) {
+            words.push(TENS[(rest / 10) as usize].to_string());
+        } else {
+            words.push(format!(
+                "{}-{}",
+                TENS[(rest / 10) as usize],
+                ONES[(rest % 10) as usize]
+            ));
+        }
+    } else if rest > 0 {
+        words.push(ONES[rest as usize].to_string());
+    }
+    words.join(" ")
+}
+
+// English ordinal words, such as "twenty-first". Only the last word
+// changes.
+fn ordinal_words(n: u64) -> String {
+    let words = cardinal_words(n);
+    let split = words.rfind([' ', '-']).map(|i| i + 1).unwrap_or(0);
+    let (start, last) = words.split_at(split);
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        last => match last.strip_suffix('y') {
+            Some(stem) => format!("{}ieth", stem),
+            None => format!("{}th", last),
+        },
+    };
+    format!("{}{}", start, last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(picture: &str, number: i64) -> String {
+        IntegerPicture::parse(picture)
+            .unwrap()
+            .format(&IBig::from(number))
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(format("1", 123), "123");
+        assert_eq!(format("00001", 123), "00123");
+        assert_eq!(format("99999", -123), "-00123");
+        assert_eq!(format("#0", 123), "123");
+        assert_eq!(format("\u{661}", 20), "\u{662}\u{660}");
+    }
+
+    #[test]
+    fn test_grouping() {
+        assert_eq!(format("#,##0", 1500000), "1,500,000");
+        assert_eq!(format("#(000)000-000", 602347826), "602)347-826");
+        assert_eq!(format("00,00,00", 123456789), "1,23,45,67,89");
+        assert_eq!(format("000,00,00", 123456789), "12345,67,89");
+    }
+
+    #[test]
+    fn test_invalid_picture() {
+        for picture in [
+            "", ";", "0,000,", ",123", "0,,000", "0#", "1o", "1;o(", "1;x",
+        ] {
+            assert_eq!(
+                IntegerPicture::parse(picture),
+                Err(error::Error::FODF1310),
+                "{:?}",
+                picture
+            );
+        }
+    }
+
+    #[test]
+    fn test_ordinal() {
+        assert_eq!(format("1;o", 1), "1st");
+        assert_eq!(format("1;o", 12), "12th");
+        assert_eq!(format("1;o", 22), "22nd");
+        assert_eq!(format("1;o", 113), "113th");
+        assert_eq!(format("#,##1;o", -8500), "-8,500th");
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(format("w", 0), "zero");
+        assert_eq!(format("w", 123), "one hundred twenty-three");
+        assert_eq!(format("w", 1_000_017), "one million seventeen");
+        assert_eq!(format("W", 40), "FORTY");
+        assert_eq!(format("Ww", 21), "Twenty-one");
+    }
+
+    #[test]
+    fn test_ordinal_words() {
+        assert_eq!(format("w;o", 0), "zeroth");
+        assert_eq!(format("w;o", 21), "twenty-first");
+        assert_eq!(format("w;o", 40), "fortieth");
+        assert_eq!(format("w;o", 100), "one hundredth");
+        assert_eq!(format("Ww;o", -5), "-Fifth");
+    }
+}
@@ -0,0 +1,561 @@
+// Formatting of a number using a picture string and a decimal format, as
+// done by fn:format-number.
+// https://www.w3.org/TR/xpath-functions-31/#func-format-number
+use ibig::IBig;
+
+use crate::atomic::Atomic;
+use crate::context::DecimalFormat;
+use crate::error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NumberPicture {
+    positive: SubPicture,
+    negative: SubPicture,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SubPicture {
+    prefix: String,
+    suffix: String,
+    integer_grouping: IntegerGrouping,
+    // positions of the grouping separators in the fractional part, counted
+    // in digits from the decimal separator
+    fractional_grouping: Vec<usize>,
+    minimum_integer_size: usize,
+    minimum_fractional_size: usize,
+    maximum_fractional_size: usize,
+    // the amount of digits before the decimal separator of the mantissa
+    scaling_factor: usize,
+    // the minimum amount of exponent digits, if there is an exponent
+    exponent_size: Option<usize>,
+    // the power of ten the number is multiplied by, for percent and per-mille
+    scale: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum IntegerGrouping {
+    // separators at every multiple of the size, also beyond the picture
+    Regular(usize),
+    // separators at the given positions only, counted in digits from the
+    // decimal separator
+    Positions(Vec<usize>),
+}
+
+// A non-negative decimal number as its significant digits, so that it can be
+// rounded without loss of precision. The value is
+// `0.d1d2...dn * 10^exponent`; zero has no digits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Digits {
+    digits: Vec<u8>,
+    exponent: i64,
+}
+
+impl NumberPicture {
+    // The picture consists of a positive sub-picture, optionally followed by
+    // a pattern separator and a negative sub-picture.
+    pub(crate) fn parse(picture: &str, format: &DecimalFormat) -> error::Result<Self> {
+        let chars = picture.chars().collect::<Vec<_>>();
+        let mut sub_pictures = chars.split(|c| *c == format.pattern_separator);
+        let positive = SubPicture::parse(sub_pictures.next().unwrap(), format)?;
+        let negative = match sub_pictures.next() {
+            Some(sub_picture) => SubPicture::parse(sub_picture, format)?,
+            None => {
+                let mut negative = positive.clone();
+                negative.prefix = format!("{}{}", format.minus_sign, positive.prefix);
+                negative
+            }
+        };
+        if sub_pictures.next().is_some() {
+            return Err(error::Error::FODF1310);
+        }
+        Ok(Self { positive, negative })
+    }
+
+    // Format a numeric value. Negative zero is formatted with the negative
+    // sub-picture for xs:double and xs:float.
+    pub(crate) fn format(&self, value: &Atomic, format: &DecimalFormat) -> error::Result<String> {
+        let negative = match value {
+            Atomic::Double(d) if d.is_nan() => return Ok(format.nan.clone()),
+            Atomic::Float(f) if f.is_nan() => return Ok(format.nan.clone()),
+            Atomic::Double(d) => d.is_sign_negative(),
+            Atomic::Float(f) => f.is_sign_negative(),
+            Atomic::Decimal(d) => d.is_sign_negative() && !d.is_zero(),
+            Atomic::Integer(_, i) => i.as_ref() < &IBig::from(0),
+            _ => unreachable!(),
+        };
+        let sub_picture = if negative {
+            &self.negative
+        } else {
+            &self.positive
+        };
+        // percent and per-mille multiply in the type of the value, so a
+        // double can overflow into infinity
+        let digits = match value {
+            Atomic::Double(d) => {
+                let d = d.0 * 10f64.powi(sub_picture.scale as i32);
+                if d.is_infinite() {
+                    return Ok(sub_picture.format_infinity(format));
+                }
+                // the shortest representation that round-trips, so that 0.1
+                // is formatted as 0.1 and not as 0.1000000000000000055511151231257827
+                Digits::parse(&d.to_string())
+            }
+            Atomic::Float(f) => {
+                let f = f.0 * 10f32.powi(sub_picture.scale as i32);
+                if f.is_infinite() {
+                    return Ok(sub_picture.format_infinity(format));
+                }
+                Digits::parse(&f.to_string())
+            }
+            // decimals and integers are scaled exactly
+            Atomic::Decimal(d) => {
+                let mut digits = Digits::parse(&d.to_string());
+                digits.scale(sub_picture.scale);
+                digits
+            }
+            Atomic::Integer(_, i) => {
+                let mut digits = Digits::parse(&i.to_string());
+                digits.scale(sub_picture.scale);
+                digits
+            }
+            _ => unreachable!(),
+        };
+        sub_picture.format(digits, format)
+    }
+}
+
+impl SubPicture {
+    fn parse(chars: &[char], format: &DecimalFormat) -> error::Result<Self> {
+        let is_mandatory_digit = |c: &char| format.digit_value(*c).is_some();
+        let is_digit = |c: &char| *c == format.digit || is_mandatory_digit(c);
+        let is_active = |c: &char| {
+            is_digit(c) || *c == format.grouping_separator || *c == format.decimal_separator
+        };
+        // the exponent separator is only active if it's between other active
+        // characters; otherwise it's passive, like a letter in the suffix
+        let exponent_position = chars.iter().enumerate().position(|(i, c)| {
+            *c == format.exponent_separator
+                && chars[..i].iter().any(is_active)
+                && chars[i + 1..].iter().any(is_active)
+        });
+        let active = |i: usize| is_active(&chars[i]) || Some(i) == exponent_position;
+
+        let first = (0..chars.len()).find(|i| active(*i));
+        let last = (0..chars.len()).rev().find(|i| active(*i));
+        let (first, last) = match (first, last) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(error::Error::FODF1310),
+        };
+        // passive characters are only allowed in the prefix and suffix
+        if !(first..=last).all(active) {
+            return Err(error::Error::FODF1310);
+        }
+
+        let percents = chars.iter().filter(|c| **c == format.percent).count();
+        let per_milles = chars.iter().filter(|c| **c == format.per_mille).count();
+        let scale = match (percents, per_milles) {
+            (0, 0) => 0,
+            (1, 0) => 2,
+            (0, 1) => 3,
+            _ => return Err(error::Error::FODF1310),
+        };
+
+        let (mantissa, exponent) = match exponent_position {
+            Some(i) => (&chars[first..i], Some(&chars[i + 1..=last])),
+            None => (&chars[first..=last], None),
+        };
+        let exponent_size = match exponent {
+            Some(exponent) => {
+                if scale != 0 || !exponent.iter().all(is_mandatory_digit) {
+                    return Err(error::Error::FODF1310);
+                }
+                Some(exponent.len())
+            }
+            None => None,
+        };
+
+        let mut decimal_separators = mantissa
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c == format.decimal_separator)
+            .map(|(i, _)| i);
+        let (integer, fractional) = match decimal_separators.next() {
+            Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+            None => (mantissa, &mantissa[mantissa.len()..]),
+        };
+        if decimal_separators.next().is_some() || !mantissa.iter().any(is_digit) {
+            return Err(error::Error::FODF1310);
+        }
+        let grouping = format.grouping_separator;
+        if mantissa
+            .windows(2)
+            .any(|w| w[0] == grouping && w[1] == grouping)
+            || integer.last() == Some(&grouping)
+            || fractional.first() == Some(&grouping)
+        {
+            return Err(error::Error::FODF1310);
+        }
+        // optional digits come before mandatory digits in the integer part,
+        // and after them in the fractional part
+        if let Some(i) = integer.iter().position(is_mandatory_digit) {
+            if integer[i..].contains(&format.digit) {
+                return Err(error::Error::FODF1310);
+            }
+        }
+        if let Some(i) = fractional.iter().position(|c| *c == format.digit) {
+            if fractional[i..].iter().any(is_mandatory_digit) {
+                return Err(error::Error::FODF1310);
+            }
+        }
+
+        let integer_digits = integer.iter().filter(|c| is_digit(c)).count();
+        let integer_grouping = IntegerGrouping::new(
+            grouping_positions(integer.iter().rev(), grouping),
+            integer_digits,
+        );
+        let fractional_grouping = grouping_positions(fractional.iter(), grouping);
+        let mut minimum_integer_size = integer.iter().filter(|c| is_mandatory_digit(c)).count();
+        let scaling_factor = minimum_integer_size;
+        let mut minimum_fractional_size =
+            fractional.iter().filter(|c| is_mandatory_digit(c)).count();
+        let mut maximum_fractional_size = fractional.iter().filter(|c| is_digit(c)).count();
+        if minimum_integer_size == 0 && maximum_fractional_size == 0 {
+            if exponent_size.is_some() {
+                minimum_fractional_size = 1;
+                maximum_fractional_size = 1;
+            } else {
+                minimum_integer_size = 1;
+            }
+        }
+        if exponent_size.is_some() && minimum_integer_size == 0 && integer_digits > 0 {
+            minimum_integer_size = 1;
+        }
+        if minimum_integer_size == 0 && minimum_fractional_size == 0 {
+            minimum_fractional_size = 1;
+        }
+
+        Ok(Self {
+            prefix: chars[..first].iter().collect(),
+            suffix: chars[last + 1..].iter().collect(),
+            integer_grouping,
+            fractional_grouping,
+            minimum_integer_size,
+            minimum_fractional_size,
+            maximum_fractional_size,
+            scaling_factor,
+            exponent_size,
+            scale,
+        })
+    }
+
+    fn format_infinity(&self, format: &DecimalFormat) -> String {
+        format!("{}{}{}", self.prefix, format.infinity, self.suffix)
+    }
+
+    fn format(&self, mut digits: Digits, format: &DecimalFormat) -> error::Result<String> {
+        let exponent = match self.exponent_size {
+            Some(_) => digits.normalize(self.scaling_factor),
+            None => 0,
+        };
+        // if rounding adds an integer digit to the mantissa, the exponent
+        // isn't adjusted; 0.99 is formatted as 10.0e-1 with 0.0e0
+        digits.round(self.maximum_fractional_size);
+
+        let mut integer = digits.integer_digits();
+        if integer.len() < self.minimum_integer_size {
+            let padding = self.minimum_integer_size - integer.len();
+            integer.splice(0..0, std::iter::repeat_n(0, padding));
+        }
+        let mut fractional = digits.fractional_digits();
+        if fractional.len() < self.minimum_fractional_size {
+            fractional.resize(self.minimum_fractional_size, 0);
+        }
+
+        let mut s = self.prefix.clone();
+        for (i, digit) in integer.iter().enumerate() {
+            if i > 0 && self.integer_grouping.is_position(integer.len() - i) {
+                s.push(format.grouping_separator);
+            }
+            s.push(format.digit_char(*digit)?);
+        }
+        if !fractional.is_empty() {
+            s.push(format.decimal_separator);
+        }
+        for (i, digit) in fractional.iter().enumerate() {
+            if self.fractional_grouping.contains(&i) {
+                s.push(format.grouping_separator);
+            }
+            s.push(format.digit_char(*digit)?);
+        }
+        if let Some(exponent_size) = self.exponent_size {
+            s.push(format.exponent_separator);
+            if exponent < 0 {
+                s.push(format.minus_sign);
+            }
+            let exponent = exponent.unsigned_abs().to_string();
+            for _ in exponent.len()..exponent_size {
+                s.push(format.digit_char(0)?);
+            }
+            for digit in exponent.bytes() {
+                s.push(format.digit_char(digit - b'0')?);
+            }
+        }
+        s.push_str(&self.suffix);
+        Ok(s)
+    }
+}
+
+// The positions of the grouping separators, counted in digits from the start
+// of the iteration.
+fn grouping_positions<'a>(chars: impl Iterator<Item = &'a char>, grouping: char) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut digits = 0;
+    for c in chars {
+        if *c == grouping {
+            positions.push(digits);
+        } else {
+            digits += 1;
+        }
+    }
+    positions
+}
+
+impl IntegerGrouping {
+    // The grouping is regular if the separators are at every multiple of the
+    // position of the first one, up to the amount of digits in the picture.
+    fn new(positions: Vec<usize>, digits: usize) -> Self {
+        let size = match positions.first() {
+            Some(size) => *size,
+            None => return IntegerGrouping::Positions(positions),
+        };
+        let regular = positions
+            .iter()
+            .enumerate()
+            .all(|(i, position)| *position == (i + 1) * size)
+            && (positions.len() + 1) * size >= digits;
+        if regular {
+            IntegerGrouping::Regular(size)
+        } else {
+            IntegerGrouping::Positions(positions)
+        }
+    }
+
+    // whether there is a separator with this many digits to the right of it
+    fn is_position(&self, position: usize) -> bool {
+        match self {
+            IntegerGrouping::Regular(size) => position.is_multiple_of(*size),
+            IntegerGrouping::Positions(positions) => positions.contains(&position),
+        }
+    }
+}
+
+impl Digits {
+    // Parse a number in decimal notation. The sign is ignored.
+    fn parse(s: &str) -> Self {
+        let s = s.trim_start_matches('-');
+        let (integer, fractional) = s.split_once('.').unwrap_or((s, ""));
+        let digits = integer
+            .bytes()
+            .chain(fractional.bytes())
+            .map(|b| b - b'0')
+            .collect();
+        let mut digits = Self {
+            digits,
+            exponent: integer.len() as i64,
+        };
+        let leading_zeros = digits.digits.iter().take_while(|d| **d == 0).count();
+        digits.digits.drain(..leading_zeros);
+        digits.exponent -= leading_zeros as i64;
+        digits.trim();
+        digits
+    }
+
+    // multiply by a power of ten
+    fn scale(&mut self, power: u32) {
+        if !self.digits.is_empty() {
+            self.exponent += power as i64;
+        }
+    }
+
+    fn trim(&mut self) {
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+        if self.digits.is_empty() {
+            self.exponent = 0;
+        }
+    }
+
+    // Scale to have the given amount of integer digits, returning the power
+    // of ten of the scaling.
+    fn normalize(&mut self, integer_digits: usize) -> i64 {
+        if self.digits.is_empty() {
+            return 0;
+        }
+        let power = self.exponent - integer_digits as i64;
+        self.exponent = integer_digits as i64;
+        power
+    }
+
+    // Round half to even to the given amount of fractional digits.
+    fn round(&mut self, fractional_digits: usize) {
+        let keep = self.exponent + fractional_digits as i64;
+        if keep >= self.digits.len() as i64 {
+            return;
+        }
+        if keep < 0 {
+            self.digits.clear();
+            self.trim();
+            return;
+        }
+        let keep = keep as usize;
+        let first_dropped = self.digits[keep];
+        let rest_dropped = self.digits[keep + 1..].iter().any(|d| *d != 0);
+        let last_odd = keep > 0 && !self.digits[keep - 1].is_multiple_of(2);
+        self.digits.truncate(keep);
+        if first_dropped > 5 || (first_dropped == 5 && (rest_dropped || last_odd)) {
+            let mut i = keep;
+            loop {
+                if i == 0 {
+                    self.digits.insert(0, 1);
+                    self.exponent += 1;
+                    break;
+                }
+                i -= 1;
+                if self.digits[i] == 9 {
+                    self.digits[i] = 0;
+                } else {
+                    self.digits[i] += 1;
+                    break;
+                }
+            }
+        }
+        self.trim();
+    }
+
+    // the digits before the decimal point, without leading zeros
+    fn integer_digits(&self) -> Vec<u8> {
+        (0..self.exponent.max(0) as usize)
+            .map(|i| self.digits.get(i).copied().unwrap_or(0))
+            .collect()
+    }
+
+    // the digits after the decimal point, without trailing zeros
+    fn fractional_digits(&self) -> Vec<u8> {
+        if self.exponent >= 0 {
+            self.digits
+                .get(self.exponent as usize..)
+                .unwrap_or_default()
+                .to_vec()
+        } else {
+            std::iter::repeat_n(0, self.exponent.unsigned_abs() as usize)
+                .chain(self.digits.iter().copied())
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_with(picture: &str, number: f64, format: &DecimalFormat) -> String {
+        NumberPicture::parse(picture, format)
+            .unwrap()
+            .format(&number.into(), format)
+            .unwrap()
+    }
+
+    fn format(picture: &str, number: f64) -> String {
+        format_with(picture, number, &DecimalFormat::default())
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(format("#", 12345.6), "12346");
+        assert_eq!(format("0.00", 1.005), "1.00");
+        assert_eq!(format("#.##", 0.125), ".12");
+        assert_eq!(format("#.#", 0.0), ".0");
+        assert_eq!(format("000.0#", 2.5), "002.5");
+        assert_eq!(format(".00", 0.5), ".50");
+        assert_eq!(format("#", 0.1), "0");
+        assert_eq!(format("0.0", 0.1), "0.1");
+    }
+
+    #[test]
+    fn test_grouping() {
+        assert_eq!(format("#,##0.00", 1234567.891), "1,234,567.89");
+        assert_eq!(format("#,###,##0", 1234567.0), "1,234,567");
+        assert_eq!(format("##,##,##0", 12345678.0), "123,45,678");
+        assert_eq!(format("0.000,0", 1.2345), "1.234,5");
+    }
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(format("#,##0.00", -1234.5), "-1,234.50");
+        assert_eq!(format("0;(0)", -12.0), "(12)");
+        assert_eq!(format("0;(0)", 12.0), "12");
+        assert_eq!(format("0", -0.0), "-0");
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(format("0%", 0.25), "25%");
+        assert_eq!(format("0.0‰", 0.0123), "12.3‰");
+    }
+
+    #[test]
+    fn test_exponent() {
+        assert_eq!(format("0.000e0", 1234.5), "1.234e3");
+        assert_eq!(format("00.0e00", 0.000123), "12.3e-05");
+        assert_eq!(format("0.0e0", 9.99), "10.0e0");
+        assert_eq!(format(".00e0", 1234.5), ".12e4");
+    }
+
+    #[test]
+    fn test_special_values() {
+        assert_eq!(format("0", f64::NAN), "NaN");
+        assert_eq!(format("0 kg", f64::INFINITY), "Infinity kg");
+        assert_eq!(format("0", f64::NEG_INFINITY), "-Infinity");
+    }
+
+    #[test]
+    fn test_decimal_format() {
+        let format = DecimalFormat {
+            decimal_separator: ',',
+            grouping_separator: '.',
+            zero_digit: '\u{660}',
+            ..Default::default()
+        };
+        assert_eq!(
+            format_with("#.##\u{660},\u{660}", 1234.5, &format),
+            "\u{661}.\u{662}\u{663}\u{664},\u{665}"
+        );
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        let picture = NumberPicture::parse("#,##0", &DecimalFormat::default()).unwrap();
+        let number: IBig = "123456789012345678901234567890".parse().unwrap();
+        assert_eq!(
+            picture
+                .format(&number.into(), &DecimalFormat::default())
+                .unwrap(),
+            "123,456,789,012,345,678,901,234,567,890"
+        );
+    }
+
+    #[test]
+    fn test_invalid_picture() {
+        for picture in [
+            "", "0;0;0", "#.#.#", "#%%", "#%‰", "0,,0", "0,.0", "0.,0", "0,", "0#", "#.0#0", "0x0",
+            "0.0e0%", "0e#", "kg",
+        ] {
+            assert_eq!(
+                NumberPicture::parse(picture, &DecimalFormat::default()),
+                Err(error::Error::FODF1310),
+                "{picture}"
+            );
+        }
+    }
+}
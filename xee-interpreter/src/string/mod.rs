@@ -1,6 +1,12 @@
 /// String support for XPath. XPath allows strings to be compared
 /// using collations.
 mod collation;
+mod format_integer;
+mod format_number;
+mod number_format;
 
 pub use collation::Collation;
 pub(crate) use collation::Collations;
+pub(crate) use format_integer::{decimal_digit_zero, is_decimal_digit, IntegerPicture};
+pub(crate) use format_number::NumberPicture;
+pub(crate) use number_format::{Grouping, NumberFormat};
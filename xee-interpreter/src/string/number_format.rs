@@ -0,0 +1,154 @@
+// Formatting of a sequence of integers using a format string, as done by
+// xsl:number.
+// https://www.w3.org/TR/xslt-30/#convert
+use ibig::IBig;
+
+use super::format_integer::IntegerPicture;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NumberFormat {
+    prefix: String,
+    // each format token with the separator that precedes it. The separator
+    // of the first token is unused, as the prefix comes before it.
+    tokens: Vec<(String, String)>,
+    suffix: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Grouping {
+    pub(crate) separator: char,
+    pub(crate) size: usize,
+}
+
+impl NumberFormat {
+    // The format string is split into alternating format tokens (runs of
+    // alphanumeric characters) and separators. A leading separator is the
+    // prefix, a trailing one the suffix.
+    pub(crate) fn parse(format: &str) -> Self {
+        let mut prefix = String::new();
+        let mut tokens = Vec::new();
+        let mut separator = String::new();
+        let mut token = String::new();
+        for c in format.chars() {
+            if c.is_alphanumeric() {
+                token.push(c);
+            } else {
+                if !token.is_empty() {
+                    tokens.push((std::mem::take(&mut separator), std::mem::take(&mut token)));
+                }
+                separator.push(c);
+            }
+        }
+        if !token.is_empty() {
+            tokens.push((std::mem::take(&mut separator), token));
+        }
+        let suffix = separator;
+        if let Some((first_separator, _)) = tokens.first_mut() {
+            prefix = std::mem::take(first_separator);
+        }
+        if tokens.is_empty() {
+            // without any format tokens we use the default token
+            tokens.push((String::new(), "1".to_string()));
+            return Self {
+                prefix: suffix,
+                tokens,
+                suffix: String::new(),
+            };
+        }
+        Self {
+            prefix,
+            tokens,
+            suffix,
+        }
+    }
+
+    pub(crate) fn format(&self, numbers: &[IBig], grouping: Option<&Grouping>) -> String {
+        if numbers.is_empty() {
+            return String::new();
+        }
+        let mut s = self.prefix.clone();
+        for (i, number) in numbers.iter().enumerate() {
+            if i > 0 {
+                s.push_str(self.separator(i));
+            }
+            let (_, token) = &self.tokens[i.min(self.tokens.len() - 1)];
+            s.push_str(&IntegerPicture::number_token(token, grouping).format(number));
+        }
+        s.push_str(&self.suffix);
+        s
+    }
+
+    // the separator to put before the number at index i
+    fn separator(&self, i: usize) -> &str {
+        if i < self.tokens.len() {
+            &self.tokens[i].0
+        } else if self.tokens.len() > 1 {
+            &self.tokens[self.tokens.len() - 1].0
+        } else {
+            "."
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(format_string: &str, numbers: &[u64]) -> String {
+        let numbers = numbers.iter().map(|n| IBig::from(*n)).collect::<Vec<_>>();
+        NumberFormat::parse(format_string).format(&numbers, None)
+    }
+
+    #[test]
+    fn test_default_separator() {
+        assert_eq!(format("1", &[1, 2, 3]), "1.2.3");
+    }
+
+    #[test]
+    fn test_prefix_suffix_separators() {
+        assert_eq!(format("(1.a) ", &[3, 2]), "(3.b) ");
+        assert_eq!(format("1.a-i", &[3, 2, 4, 5]), "3.b-iv-v");
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(format("a", &[28]), "ab");
+        assert_eq!(format("A", &[26]), "Z");
+        assert_eq!(format("i", &[1994]), "mcmxciv");
+        assert_eq!(format("I", &[4]), "IV");
+        assert_eq!(format("001", &[7]), "007");
+        assert_eq!(format("01", &[123]), "123");
+        assert_eq!(format("\u{661}", &[20]), "\u{662}\u{660}");
+        assert_eq!(format("\u{660}\u{661}", &[7]), "\u{660}\u{667}");
+        assert_eq!(format("w", &[21]), "twenty-one");
+        assert_eq!(format("W", &[3]), "THREE");
+        assert_eq!(format("Ww", &[112]), "One Hundred Twelve");
+    }
+
+    #[test]
+    fn test_fallback() {
+        // zero cannot be expressed alphabetically or in roman numerals
+        assert_eq!(format("a", &[0]), "0");
+        assert_eq!(format("I", &[0]), "0");
+        // unsupported tokens use 1
+        assert_eq!(format("x", &[5]), "5");
+        // no tokens at all
+        assert_eq!(format("", &[5]), "5");
+    }
+
+    #[test]
+    fn test_grouping() {
+        let grouping = Grouping {
+            separator: ',',
+            size: 3,
+        };
+        assert_eq!(
+            NumberFormat::parse("1").format(&[IBig::from(1234567)], Some(&grouping)),
+            "1,234,567"
+        );
+        assert_eq!(
+            NumberFormat::parse("0001").format(&[IBig::from(12)], Some(&grouping)),
+            "0,012"
+        );
+    }
+}
@@ -4,16 +4,18 @@ use xot;
 use crate::error;
 
 pub(crate) struct BaseUriResolver<'a> {
-    document_base_uri: Option<&'a IriStr>,
+    // the base URI of the document node, or of a root node that isn't a
+    // document, such as a copy
+    root_base_uri: Option<&'a IriStr>,
     xml_base_name: xot::NameId,
     xot: &'a xot::Xot,
 }
 
 impl<'a> BaseUriResolver<'a> {
-    pub(crate) fn new(document_base_uri: Option<&'a IriStr>, xot: &'a mut xot::Xot) -> Self {
+    pub(crate) fn new(root_base_uri: Option<&'a IriStr>, xot: &'a mut xot::Xot) -> Self {
         let xml_base_name = xot.add_name_ns("base", xot.xml_namespace());
         Self {
-            document_base_uri,
+            root_base_uri,
             xml_base_name,
             xot,
         }
@@ -29,7 +31,7 @@ impl<'a> BaseUriResolver<'a> {
     // that behavior.
     pub(crate) fn base_uri(&self, node: xot::Node) -> Result<Option<IriString>, error::Error> {
         Ok(match self.xot.value(node) {
-            xot::Value::Document => self.document_base_uri.map(|u| u.to_owned()),
+            xot::Value::Document => self.root_base_uri.map(|u| u.to_owned()),
             xot::Value::Element(_) => {
                 let base = self.xot.attributes(node).get(self.xml_base_name);
 
@@ -43,27 +45,23 @@ impl<'a> BaseUriResolver<'a> {
                         Err(iri) => {
                             // iri is relative, so resolve against the
                             // parent's base uri
-                            if let Some(parent) = self.xot.parent(node) {
-                                let base = self.base_uri(parent)?;
-                                if let Some(base) = base {
-                                    let base: IriAbsoluteString =
-                                        base.try_into().map_err(|_| error::Error::FORG0002)?;
-                                    let iri = iri.resolve_against(&base);
-                                    Some(iri.into())
-                                } else {
-                                    // no base URI, so how to resolve?
-                                    return Err(error::Error::FORG0009);
-                                }
+                            let base = self.parent_base_uri(node)?;
+                            if let Some(base) = base {
+                                let base: IriAbsoluteString =
+                                    base.try_into().map_err(|_| error::Error::FORG0002)?;
+                                let iri = iri.resolve_against(&base);
+                                Some(iri.into())
+                            } else if self.xot.parent(node).is_some() {
+                                // no base URI, so how to resolve?
+                                return Err(error::Error::FORG0009);
                             } else {
                                 // no parent, so how to resolve?
                                 return Err(error::Error::FORG0002);
                             }
                         }
                     }
-                } else if let Some(parent) = self.xot.parent(node) {
-                    self.base_uri(parent)?
                 } else {
-                    None
+                    self.parent_base_uri(node)?
                 }
             }
             // NOTE: Processing instruction is defined to have a base URI by
@@ -73,16 +71,20 @@ impl<'a> BaseUriResolver<'a> {
             xot::Value::Attribute(_)
             | xot::Value::Comment(_)
             | xot::Value::Text(_)
-            | xot::Value::ProcessingInstruction(_) => {
-                if let Some(parent) = self.xot.parent(node) {
-                    self.base_uri(parent)?
-                } else {
-                    None
-                }
-            }
+            | xot::Value::ProcessingInstruction(_) => self.parent_base_uri(node)?,
             xot::Value::Namespace(_) => None,
         })
     }
+
+    // the base URI of the parent, or for a root node, the base URI it was
+    // given
+    fn parent_base_uri(&self, node: xot::Node) -> Result<Option<IriString>, error::Error> {
+        if let Some(parent) = self.xot.parent(node) {
+            self.base_uri(parent)
+        } else {
+            Ok(self.root_base_uri.map(|u| u.to_owned()))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -240,6 +242,23 @@ mod tests {
         assert_eq!(base_uri, Some(base.to_owned()));
     }
 
+    #[test]
+    fn test_base_uri_element_with_document_base_composes() {
+        let mut xot = Xot::new();
+        let doc = xot
+            .parse(r#"<foo xml:base="a/"><bar xml:base="b/"><baz/></bar></foo>"#)
+            .unwrap();
+        let foo = xot.document_element(doc).unwrap();
+        let bar = xot.first_child(foo).unwrap();
+        let baz = xot.first_child(bar).unwrap();
+        let base: &IriStr = "http://example.com/doc/".try_into().unwrap();
+        let resolver = BaseUriResolver::new(Some(base), &mut xot);
+        // the nearest xml:base wins, resolved against those of its ancestors
+        let base_uri = resolver.base_uri(baz).unwrap();
+        let expected: IriString = "http://example.com/doc/a/b/".try_into().unwrap();
+        assert_eq!(base_uri, Some(expected));
+    }
+
     #[test]
     fn test_base_uri_attribute_without_base() {
         let mut xot = Xot::new();
@@ -1,3 +1,4 @@
+use std::rc::Rc;
 use std::sync::atomic;
 
 use ahash::{HashMap, HashMapExt};
@@ -5,7 +6,8 @@ use iri_string::types::{IriStr, IriString};
 use xot::Xot;
 
 use super::document_order::DocumentOrderAnnotations;
-use super::DocumentOrderAccess;
+use super::id::{IdAttributes, IdIndex, IdIndexes};
+use super::{strip_whitespace, DocumentOrderAccess, LoadOptions};
 
 static DOCUMENTS_COUNTER: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
 
@@ -19,7 +21,18 @@ pub enum DocumentsError {
     /// An attempt as made to add a document with a URI that was already known.
     DuplicateUri(String),
     /// An error occurred loading the document XML (using the [`xot`] crate).
-    Parse(xot::ParseError),
+    Parse(DocumentParseError),
+}
+
+impl DocumentsError {
+    /// The line and column (both 1-based) in the XML source where the
+    /// document failed to parse, if this is a parse error.
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            DocumentsError::DuplicateUri(_) => None,
+            DocumentsError::Parse(e) => Some((e.line, e.column)),
+        }
+    }
 }
 
 impl std::error::Error for DocumentsError {}
@@ -28,14 +41,86 @@ impl std::fmt::Display for DocumentsError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             DocumentsError::DuplicateUri(uri) => write!(f, "Duplicate URI: {}", uri),
-            DocumentsError::Parse(e) => write!(f, "Parse error: {}", e),
+            DocumentsError::Parse(e) => write!(f, "{}", e),
         }
     }
 }
 
-impl From<xot::ParseError> for DocumentsError {
-    fn from(e: xot::ParseError) -> Self {
-        DocumentsError::Parse(e)
+/// An XML document failed to parse.
+///
+/// Besides the underlying [`xot::ParseError`] this records where in the XML
+/// source the error occurred, so it can be reported without access to the
+/// source.
+#[derive(Debug)]
+pub struct DocumentParseError {
+    error: xot::ParseError,
+    line: usize,
+    column: usize,
+    snippet: String,
+}
+
+impl DocumentParseError {
+    fn new(error: xot::ParseError, xml: &str) -> Self {
+        // xot reports byte offsets into the source; be defensive so we never
+        // slice outside of it or in the middle of a character
+        let offset = (0..=error.span().start.min(xml.len()))
+            .rev()
+            .find(|offset| xml.is_char_boundary(*offset))
+            .unwrap_or(0);
+        let before = &xml[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = xml[offset..]
+            .find('\n')
+            .map(|i| offset + i)
+            .unwrap_or(xml.len());
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            snippet: xml[line_start..line_end].trim_end_matches('\r').to_string(),
+            error,
+        }
+    }
+
+    /// The underlying parse error.
+    pub fn error(&self) -> &xot::ParseError {
+        &self.error
+    }
+
+    /// The byte range in the XML source where the error occurred.
+    pub fn span(&self) -> std::ops::Range<usize> {
+        self.error.span().range()
+    }
+
+    /// The line (1-based) in the XML source where the error occurred.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column (1-based, in characters) in the XML source where the error
+    /// occurred.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The line of XML source in which the error occurred.
+    pub fn snippet(&self) -> &str {
+        &self.snippet
+    }
+}
+
+impl std::error::Error for DocumentParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl std::fmt::Display for DocumentParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Parse error at line {}, column {}: {}",
+            self.line, self.column, self.error
+        )
     }
 }
 
@@ -70,30 +155,50 @@ impl Document {
 pub struct Documents {
     id: usize,
     annotations: DocumentOrderAnnotations,
+    ids: IdIndexes,
     documents: Vec<Document>,
     by_uri: HashMap<IriString, DocumentHandle>,
     uri_by_document_node: HashMap<xot::Node, IriString>,
+    base_uri_by_document_node: HashMap<xot::Node, IriString>,
 }
 
 /// A handle to a document.
 ///
 /// This is an identifier into a [`Documents`] collection. You can
 /// freely copy it.
+///
+/// A handle is stable for the lifetime of the collection it came from: it
+/// keeps referring to the same document until the collection is cleaned up.
+/// It can't be used with any other collection.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub struct DocumentHandle {
     pub(crate) documents_id: usize,
     pub(crate) id: usize,
 }
 
+impl DocumentHandle {
+    /// The index of the document in its collection.
+    ///
+    /// Documents are numbered from 0 in the order they were added, so
+    /// adding the same documents in the same order to a new collection
+    /// gives them the same indexes. Use [`Documents::get_handle_by_index`]
+    /// to turn an index back into a handle.
+    pub fn as_index(&self) -> usize {
+        self.id
+    }
+}
+
 impl Documents {
     /// Create a new empty collection of documents.
     pub fn new() -> Self {
         Self {
             id: get_documents_id(),
             annotations: DocumentOrderAnnotations::new(),
+            ids: IdIndexes::new(IdAttributes::default()),
             documents: Vec::new(),
             by_uri: HashMap::new(),
             uri_by_document_node: HashMap::new(),
+            base_uri_by_document_node: HashMap::new(),
         }
     }
 
@@ -113,7 +218,25 @@ impl Documents {
         uri: Option<&IriStr>,
         xml: &str,
     ) -> Result<DocumentHandle, DocumentsError> {
-        let root = xot.parse(xml)?;
+        let root = xot
+            .parse(xml)
+            .map_err(|e| DocumentsError::Parse(DocumentParseError::new(e, xml)))?;
+        self.add_root(uri, root)
+    }
+
+    /// Add a string as an XML document, loaded with the given options. It
+    /// can be designated with a URI.
+    pub fn add_string_with_options(
+        &mut self,
+        xot: &mut Xot,
+        uri: Option<&IriStr>,
+        xml: &str,
+        options: &LoadOptions,
+    ) -> Result<DocumentHandle, DocumentsError> {
+        let root = xot
+            .parse(xml)
+            .map_err(|e| DocumentsError::Parse(DocumentParseError::new(e, xml)))?;
+        strip_whitespace(xot, root, options);
         self.add_root(uri, root)
     }
 
@@ -123,7 +246,9 @@ impl Documents {
         xot: &mut Xot,
         xml: &str,
     ) -> Result<DocumentHandle, DocumentsError> {
-        let root = xot.parse_fragment(xml)?;
+        let root = xot
+            .parse_fragment(xml)
+            .map_err(|e| DocumentsError::Parse(DocumentParseError::new(e, xml)))?;
         self.add_root(None, root)
     }
 
@@ -157,6 +282,30 @@ impl Documents {
         Ok(handle)
     }
 
+    /// Add a deep copy of a document in the collection.
+    ///
+    /// The copy has the same structure but new node identities, so changing
+    /// it leaves the original untouched. It has the same base URI as the
+    /// original, but no document URI: that keeps designating the original.
+    ///
+    /// Returns `None` if the handle isn't from this collection.
+    pub fn clone_document(
+        &mut self,
+        xot: &mut Xot,
+        handle: DocumentHandle,
+    ) -> Option<DocumentHandle> {
+        let document_root = self.get_by_handle(handle)?.root;
+        // a clone of a clone has no URI, but we still know its base URI
+        let base_uri = self.get_base_uri_by_document_node(document_root);
+        let root = xot.clone_node(document_root);
+        // this cannot fail, as there is no URI to clash
+        let handle = self.add_root(None, root).unwrap();
+        if let Some(base_uri) = base_uri {
+            self.base_uri_by_document_node.insert(root, base_uri);
+        }
+        Some(handle)
+    }
+
     /// Obtain a document by handle
     pub fn get_by_handle(&self, handle: DocumentHandle) -> Option<&Document> {
         // only works if the handle is from this collection
@@ -184,6 +333,23 @@ impl Documents {
         Some(self.get_by_uri(uri)?.root)
     }
 
+    /// Obtain a handle by the index of a document, as given by
+    /// [`DocumentHandle::as_index`].
+    pub fn get_handle_by_index(&self, index: usize) -> Option<DocumentHandle> {
+        (index < self.documents.len()).then_some(DocumentHandle {
+            documents_id: self.id,
+            id: index,
+        })
+    }
+
+    /// Obtain a handle by URI
+    ///
+    /// It's only possible to obtain a handle by URI if the document was added
+    /// with a URI.
+    pub fn get_handle_by_uri(&self, uri: &IriStr) -> Option<DocumentHandle> {
+        self.by_uri.get(uri).copied()
+    }
+
     /// Obtain document URI by document node.
     ///
     /// This only returns a URI if the document was added with a URI.
@@ -191,6 +357,24 @@ impl Documents {
         self.uri_by_document_node.get(&node).cloned()
     }
 
+    /// Obtain the base URI of a document by document node.
+    ///
+    /// This is the document URI, or for a clone of a document, the URI of
+    /// the original. For the root of a copied node it's the base URI the
+    /// node inherited.
+    pub fn get_base_uri_by_document_node(&self, node: xot::Node) -> Option<IriString> {
+        self.uri_by_document_node
+            .get(&node)
+            .or_else(|| self.base_uri_by_document_node.get(&node))
+            .cloned()
+    }
+
+    /// Set the base URI of a root node that isn't a document we loaded, such
+    /// as a copy of a node. Its own `xml:base` attributes resolve against it.
+    pub(crate) fn set_base_uri(&mut self, root: xot::Node, base_uri: IriString) {
+        self.base_uri_by_document_node.insert(root, base_uri);
+    }
+
     /// How many documents are stored.
     pub fn len(&self) -> usize {
         self.documents.len()
@@ -209,6 +393,23 @@ impl Documents {
     pub(crate) fn document_order_access<'a>(&'a self, xot: &'a Xot) -> DocumentOrderAccess<'a> {
         self.annotations.access(xot)
     }
+
+    /// Set which attributes are treated as ID and IDREFS attributes by
+    /// `fn:id`, `fn:element-with-id` and `fn:idref`.
+    pub fn set_id_attributes(&mut self, id_attributes: IdAttributes) {
+        self.ids = IdIndexes::new(id_attributes);
+    }
+
+    /// The index of ID and IDREFS attributes of the document with this root.
+    pub(crate) fn id_index(&self, xot: &Xot, root: xot::Node) -> Rc<IdIndex> {
+        self.ids.get(xot, root)
+    }
+
+    /// Forget the ID and IDREFS attributes indexed so far. Call this when
+    /// the documents have changed, so that they are indexed again.
+    pub fn clear_id_indexes(&mut self) {
+        self.ids.clear();
+    }
 }
 
 impl Default for Documents {
@@ -26,9 +26,11 @@ pub(crate) struct DocumentOrder(usize, usize);
 
 impl DocumentOrder {
     pub(crate) fn generate_id(&self) -> String {
-        // must be alphanumeric and start with alphabetic character, so we
-        // cannot use _ or - as separators
-        format!("id{}s{}", self.0, self.1)
+        // this must be an NCName, and it's convenient if it's also
+        // alphanumeric so it can be used in contexts that are more
+        // restrictive still. So we prefix the document id with `d` and the
+        // preorder position of the node within it with `e`.
+        format!("d{}e{}", self.0, self.1)
     }
 }
 
@@ -0,0 +1,153 @@
+// We don't process DTDs or schemas, so we don't know which attributes have
+// type ID or IDREF(S). Instead we treat attributes with particular names as
+// such, and index them on the fly as we need them, per document.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ahash::{HashMap, HashMapExt};
+use xot::xmlname::{NameStrInfo, OwnedName};
+use xot::Xot;
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Which attributes are treated as having type ID or IDREFS.
+///
+/// These are used by `fn:id`, `fn:element-with-id` and `fn:idref`.
+///
+/// `xml:id` is always an ID attribute. By default, `id` attributes (without
+/// namespace) are also ID attributes, and there are no IDREFS attributes.
+#[derive(Debug, Clone)]
+pub struct IdAttributes {
+    ids: Vec<OwnedName>,
+    idrefs: Vec<OwnedName>,
+}
+
+impl Default for IdAttributes {
+    fn default() -> Self {
+        Self {
+            ids: vec![OwnedName::name("id")],
+            idrefs: Vec::new(),
+        }
+    }
+}
+
+impl IdAttributes {
+    /// Only `xml:id` is an ID attribute, and there are no IDREFS
+    /// attributes.
+    pub fn empty() -> Self {
+        Self {
+            ids: Vec::new(),
+            idrefs: Vec::new(),
+        }
+    }
+
+    /// Treat attributes with this name as ID attributes.
+    pub fn add_id(&mut self, name: OwnedName) -> &mut Self {
+        self.ids.push(name);
+        self
+    }
+
+    /// Treat attributes with this name as IDREFS attributes. Their value
+    /// is a whitespace separated list of ids.
+    pub fn add_idref(&mut self, name: OwnedName) -> &mut Self {
+        self.idrefs.push(name);
+        self
+    }
+
+    fn is_id(&self, xot: &Xot, name: xot::NameId) -> bool {
+        (xot.local_name_str(name) == "id" && xot.uri_str(name) == XML_NAMESPACE)
+            || matches(&self.ids, xot, name)
+    }
+
+    fn is_idref(&self, xot: &Xot, name: xot::NameId) -> bool {
+        matches(&self.idrefs, xot, name)
+    }
+}
+
+fn matches(names: &[OwnedName], xot: &Xot, name: xot::NameId) -> bool {
+    names.iter().any(|candidate| {
+        candidate.local_name() == xot.local_name_str(name)
+            && candidate.namespace() == xot.uri_str(name)
+    })
+}
+
+/// The ID and IDREFS attributes of a single document.
+#[derive(Debug, Default)]
+pub(crate) struct IdIndex {
+    // the first element in document order with each id
+    elements: HashMap<String, xot::Node>,
+    // the IDREFS attributes that refer to each id, in document order
+    referring_attributes: HashMap<String, Vec<xot::Node>>,
+}
+
+impl IdIndex {
+    fn new(xot: &Xot, root: xot::Node, id_attributes: &IdAttributes) -> Self {
+        let mut index = Self::default();
+        for element in xot.descendants(root).filter(|node| xot.is_element(*node)) {
+            for attribute_node in xot.attribute_nodes(element) {
+                let attribute = xot.attribute_node(attribute_node).unwrap();
+                let name = attribute.name();
+                if id_attributes.is_id(xot, name) {
+                    index
+                        .elements
+                        .entry(attribute.value().trim().to_string())
+                        .or_insert(element);
+                }
+                if id_attributes.is_idref(xot, name) {
+                    for id in attribute.value().split_whitespace() {
+                        let attributes = index.referring_attributes.entry(id.to_string());
+                        let attributes = attributes.or_default();
+                        // an id may be repeated in the same attribute
+                        if attributes.last() != Some(&attribute_node) {
+                            attributes.push(attribute_node);
+                        }
+                    }
+                }
+            }
+        }
+        index
+    }
+
+    /// The element with the given id, if any.
+    pub(crate) fn element(&self, id: &str) -> Option<xot::Node> {
+        self.elements.get(id).copied()
+    }
+
+    /// The IDREFS attributes that refer to the given id, in document
+    /// order.
+    pub(crate) fn referring_attributes(&self, id: &str) -> &[xot::Node] {
+        self.referring_attributes
+            .get(id)
+            .map(|attributes| attributes.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct IdIndexes {
+    id_attributes: IdAttributes,
+    // indexed by root node; created when first needed
+    indexes: RefCell<HashMap<xot::Node, Rc<IdIndex>>>,
+}
+
+impl IdIndexes {
+    pub(crate) fn new(id_attributes: IdAttributes) -> Self {
+        Self {
+            id_attributes,
+            indexes: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn get(&self, xot: &Xot, root: xot::Node) -> Rc<IdIndex> {
+        self.indexes
+            .borrow_mut()
+            .entry(root)
+            .or_insert_with(|| Rc::new(IdIndex::new(xot, root, &self.id_attributes)))
+            .clone()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.indexes.get_mut().clear();
+    }
+}
@@ -1,13 +1,21 @@
-/// XML integration.
-mod document_order;
+//! XML integration.
+
 mod base;
 mod document;
+mod document_order;
+mod id;
 mod kind_test;
+mod path;
 mod step;
+mod whitespace;
 
-pub(crate) use document_order::DocumentOrderAccess;
 pub(crate) use base::BaseUriResolver;
-pub use document::{Document, DocumentHandle, Documents, DocumentsError};
+pub use document::{Document, DocumentHandle, DocumentParseError, Documents, DocumentsError};
+pub(crate) use document_order::DocumentOrderAccess;
+pub use id::IdAttributes;
 pub(crate) use kind_test::kind_test;
+pub use path::{node_at_path, node_path};
 pub(crate) use step::resolve_step;
 pub use step::Step;
+pub(crate) use whitespace::strip_whitespace;
+pub use whitespace::{strip_whitespace_text, LoadOptions};
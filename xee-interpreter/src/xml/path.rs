@@ -0,0 +1,195 @@
+/// The path of a node, as returned by `fn:path`.
+///
+/// <https://www.w3.org/TR/xpath-functions-31/#func-path>
+pub fn node_path(node: xot::Node, xot: &xot::Xot) -> String {
+    if xot.is_document(node) {
+        "/".to_string()
+    } else {
+        path_helper(node, xot)
+    }
+}
+
+fn path_helper(node: xot::Node, xot: &xot::Xot) -> String {
+    match xot.value(node) {
+        xot::Value::Document => "".to_string(),
+        xot::Value::Element(e) => {
+            let name = e.name();
+            let (local, ns) = xot.name_ns_str(name);
+            let position = position_by_type(node, xot, |child, xot| {
+                if let Some(element) = xot.element(child) {
+                    element.name() == name
+                } else {
+                    false
+                }
+            });
+            let path = parent_path(node, xot);
+            format!("{}/Q{{{}}}{}[{}]", path, ns, local, position)
+        }
+        xot::Value::Text(_) => {
+            let position = position_by_type(node, xot, |child, xot| xot.is_text(child));
+            format!("{}/text()[{}]", parent_path(node, xot), position)
+        }
+        xot::Value::Comment(_) => {
+            let position = position_by_type(node, xot, |child, xot| xot.is_comment(child));
+            format!("{}/comment()[{}]", parent_path(node, xot), position)
+        }
+        xot::Value::ProcessingInstruction(p) => {
+            let target = p.target();
+            let position = position_by_type(node, xot, |child, xot| {
+                if let Some(processing_instruction) = xot.processing_instruction(child) {
+                    processing_instruction.target() == target
+                } else {
+                    false
+                }
+            });
+            let (local, _) = xot.name_ns_str(target);
+
+            format!(
+                "{}/processing-instruction({})[{}]",
+                parent_path(node, xot),
+                local,
+                position
+            )
+        }
+        xot::Value::Attribute(attribute) => {
+            let name = attribute.name();
+            let (local, ns) = xot.name_ns_str(name);
+            let s = if ns.is_empty() {
+                local.to_string()
+            } else {
+                format!("Q{{{}}}{}", ns, local)
+            };
+            format!("{}/@{}", parent_path(node, xot), s)
+        }
+        xot::Value::Namespace(n) => {
+            let prefix = n.prefix();
+            let s = if xot.empty_prefix() != prefix {
+                xot.prefix_str(prefix)
+            } else {
+                "*[Q{http://www.w3.org/2005/xpath-functions}local-name()=\"\"]"
+            };
+            format!("{}/namespace::{}", parent_path(node, xot), s)
+        }
+    }
+}
+
+fn parent_path(node: xot::Node, xot: &xot::Xot) -> String {
+    if let Some(parent) = xot.parent(node) {
+        path_helper(parent, xot)
+    } else {
+        "Q{http://www.w3.org/2005/xpath-functions}root()".to_string()
+    }
+}
+
+fn position_by_type(
+    node: xot::Node,
+    xot: &xot::Xot,
+    is_type: impl Fn(xot::Node, &xot::Xot) -> bool,
+) -> usize {
+    let mut position = 1;
+    let parent = xot.parent(node);
+    if let Some(parent) = parent {
+        for child in xot.children(parent) {
+            if child == node {
+                return position;
+            }
+            if is_type(child, xot) {
+                position += 1;
+            }
+        }
+        unreachable!()
+    } else {
+        1
+    }
+}
+
+/// The node at a path as returned by [`node_path`], starting at `root`, a
+/// document node.
+///
+/// This is the inverse of [`node_path`]. Returns `None` if the path isn't in
+/// the format that `fn:path` produces, or if there is no node at that path.
+/// Since the namespace axis isn't supported, a path to a namespace node
+/// doesn't resolve.
+pub fn node_at_path(root: xot::Node, path: &str, xot: &xot::Xot) -> Option<xot::Node> {
+    if !xot.is_document(root) {
+        return None;
+    }
+    if path == "/" {
+        return Some(root);
+    }
+    let mut rest = path.strip_prefix('/')?;
+    let mut node = root;
+    loop {
+        let (step, after) = split_step(rest);
+        node = path_step(node, step, xot)?;
+        match after {
+            Some(after) => rest = after,
+            None => return Some(node),
+        }
+    }
+}
+
+// split off the first step of a path. A namespace URI in a step may contain
+// '/', so we skip over anything in braces.
+fn split_step(path: &str) -> (&str, Option<&str>) {
+    let mut in_braces = false;
+    for (i, c) in path.char_indices() {
+        match c {
+            '{' => in_braces = true,
+            '}' => in_braces = false,
+            '/' if !in_braces => return (&path[..i], Some(&path[i + 1..])),
+            _ => {}
+        }
+    }
+    (path, None)
+}
+
+fn path_step(node: xot::Node, step: &str, xot: &xot::Xot) -> Option<xot::Node> {
+    if let Some(name) = step.strip_prefix('@') {
+        let (local, ns) = if name.starts_with("Q{") {
+            eqname(name)?
+        } else {
+            (name, "")
+        };
+        return xot.attribute_nodes(node).find(|attribute| {
+            xot.attribute_node(*attribute)
+                .is_some_and(|attribute| xot.name_ns_str(attribute.name()) == (local, ns))
+        });
+    }
+    let (test, position) = step.strip_suffix(']')?.rsplit_once('[')?;
+    let index = position.parse::<usize>().ok()?.checked_sub(1)?;
+    let children = xot.children(node);
+    if test == "text()" {
+        children.filter(|child| xot.is_text(*child)).nth(index)
+    } else if test == "comment()" {
+        children.filter(|child| xot.is_comment(*child)).nth(index)
+    } else if let Some(target) = test
+        .strip_prefix("processing-instruction(")
+        .and_then(|test| test.strip_suffix(')'))
+    {
+        children
+            .filter(|child| {
+                xot.processing_instruction(*child)
+                    .is_some_and(|pi| xot.name_ns_str(pi.target()).0 == target)
+            })
+            .nth(index)
+    } else {
+        let name = eqname(test)?;
+        children
+            .filter(|child| {
+                xot.element(*child)
+                    .is_some_and(|element| xot.name_ns_str(element.name()) == name)
+            })
+            .nth(index)
+    }
+}
+
+// parse a name in the `Q{namespace}local` form into its local name and
+// namespace
+fn eqname(s: &str) -> Option<(&str, &str)> {
+    let (ns, local) = s.strip_prefix("Q{")?.split_once('}')?;
+    if local.is_empty() {
+        return None;
+    }
+    Some((local, ns))
+}
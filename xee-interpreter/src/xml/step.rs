@@ -13,12 +13,39 @@ pub struct Step {
 }
 
 pub(crate) fn resolve_step(step: &Step, node: xot::Node, xot: &Xot) -> sequence::Sequence {
-    let mut new_items = Vec::new();
-    for axis_node in node_take_axis(&step.axis, xot, node) {
-        if node_test(&step.node_test, &step.axis, xot, axis_node) {
-            new_items.push(sequence::Item::Node(axis_node));
+    let node_test = match &step.node_test {
+        ast::NodeTest::KindTest(kind_test) => NodeTest::Kind(kind_test),
+        ast::NodeTest::NameTest(name_test) => {
+            let Some(name_test) = NameTest::new(name_test, xot) else {
+                // if name isn't present in any XML document nothing can
+                // match
+                return sequence::Sequence::default();
+            };
+            NodeTest::Name(name_test, principal_node_kind(&step.axis))
         }
-    }
+    };
+    let new_items: Vec<sequence::Item> = match (&step.axis, &node_test) {
+        // Children are typically elements interspersed with text nodes. If
+        // only elements can match, we skip the other children right away,
+        // and we avoid the boxed iterator of the generic axis.
+        (ast::Axis::Child, NodeTest::Name(name_test, _)) => xot
+            .children(node)
+            .filter(|child| match xot.value(*child) {
+                xot::Value::Element(element) => name_test.matches(element.name(), xot),
+                _ => false,
+            })
+            .map(sequence::Item::Node)
+            .collect(),
+        (ast::Axis::Child, NodeTest::Kind(kt @ ast::KindTest::Element(_))) => xot
+            .children(node)
+            .filter(|child| xot.is_element(*child) && kind_test(kt, xot, *child))
+            .map(sequence::Item::Node)
+            .collect(),
+        _ => node_take_axis(&step.axis, xot, node)
+            .filter(|axis_node| node_test.matches(xot, *axis_node))
+            .map(sequence::Item::Node)
+            .collect(),
+    };
     new_items.into()
 }
 
@@ -49,58 +76,59 @@ fn node_take_axis<'a>(
     xot.axis(axis, node)
 }
 
-fn node_test(node_test: &ast::NodeTest, axis: &ast::Axis, xot: &Xot, node: xot::Node) -> bool {
-    match node_test {
-        ast::NodeTest::KindTest(kt) => kind_test(kt, xot, node),
-        ast::NodeTest::NameTest(name_test) => {
-            if xot.value_type(node) != principal_node_kind(axis) {
-                return false;
-            }
-            match name_test {
-                ast::NameTest::Name(name) => {
-                    let name = name.value.maybe_to_ref(xot);
-                    if let Some(name) = name {
-                        let name_id = name.name_id();
-                        match xot.value(node) {
-                            xot::Value::Element(element) => element.name() == name_id,
-                            xot::Value::Attribute(attribute) => attribute.name() == name_id,
-                            _ => false,
-                        }
-                    } else {
-                        // if name isn't present in any XML document it's certainly
-                        // false
-                        false
-                    }
+// A node test, with any name in it resolved against the Xot arena so that
+// we don't have to look it up for each node.
+enum NodeTest<'a> {
+    Kind(&'a ast::KindTest),
+    Name(NameTest<'a>, ValueType),
+}
+
+impl NodeTest<'_> {
+    fn matches(&self, xot: &Xot, node: xot::Node) -> bool {
+        match self {
+            NodeTest::Kind(kt) => kind_test(kt, xot, node),
+            NodeTest::Name(name_test, principal_node_kind) => {
+                if xot.value_type(node) != *principal_node_kind {
+                    return false;
                 }
-                ast::NameTest::Star => true,
-                ast::NameTest::LocalName(local_name) => match xot.value(node) {
-                    xot::Value::Element(element) => {
-                        let name_id = element.name();
-                        let (name_str, _) = xot.name_ns_str(name_id);
-                        name_str == local_name
-                    }
-                    xot::Value::Attribute(attribute) => {
-                        xot.local_name_str(attribute.name()) == local_name
-                    }
-                    _ => false,
-                },
-                ast::NameTest::Namespace(uri) => match xot.value(node) {
-                    xot::Value::Element(element) => {
-                        let name_id = element.name();
-                        let namespace_str = xot.uri_str(name_id);
-                        namespace_str == uri
-                    }
-                    xot::Value::Attribute(attribute) => {
-                        let namespace_str = xot.uri_str(attribute.name());
-                        namespace_str == uri
-                    }
+                match xot.value(node) {
+                    xot::Value::Element(element) => name_test.matches(element.name(), xot),
+                    xot::Value::Attribute(attribute) => name_test.matches(attribute.name(), xot),
                     _ => false,
-                },
+                }
             }
         }
     }
 }
 
+enum NameTest<'a> {
+    Name(xot::NameId),
+    Star,
+    LocalName(&'a str),
+    Namespace(&'a str),
+}
+
+impl<'a> NameTest<'a> {
+    // Gives back None if the name doesn't exist in the Xot arena.
+    fn new(name_test: &'a ast::NameTest, xot: &Xot) -> Option<Self> {
+        Some(match name_test {
+            ast::NameTest::Name(name) => NameTest::Name(name.value.maybe_to_ref(xot)?.name_id()),
+            ast::NameTest::Star => NameTest::Star,
+            ast::NameTest::LocalName(local_name) => NameTest::LocalName(local_name),
+            ast::NameTest::Namespace(uri) => NameTest::Namespace(uri),
+        })
+    }
+
+    fn matches(&self, name_id: xot::NameId, xot: &Xot) -> bool {
+        match self {
+            NameTest::Name(name) => *name == name_id,
+            NameTest::Star => true,
+            NameTest::LocalName(local_name) => xot.local_name_str(name_id) == *local_name,
+            NameTest::Namespace(uri) => xot.uri_str(name_id) == *uri,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum PrincipalNodeKind {
     Element,
@@ -163,4 +191,34 @@ mod tests {
         assert_eq!(value, xot_nodes_to_value(&[a]));
         Ok(())
     }
+
+    #[test]
+    fn test_child_axis_skips_other_node_kinds() -> Result<(), xot::Error> {
+        let mut xot = Xot::new();
+        let doc = xot
+            .parse(r#"<root> <a/> <!--a--> <?a?> <b/> <a/> </root>"#)
+            .unwrap();
+        let doc_el = xot.document_element(doc)?;
+        let elements = xot
+            .children(doc_el)
+            .filter(|node| xot.is_element(*node))
+            .collect::<Vec<_>>();
+
+        let step = Step {
+            axis: ast::Axis::Child,
+            node_test: ast::NodeTest::NameTest(ast::NameTest::Name(
+                ast::Name::name("a").with_empty_span(),
+            )),
+        };
+        let value = resolve_step(&step, doc_el, &xot);
+        assert_eq!(value, xot_nodes_to_value(&[elements[0], elements[2]]));
+
+        let step = Step {
+            axis: ast::Axis::Child,
+            node_test: ast::NodeTest::KindTest(ast::KindTest::Element(None)),
+        };
+        let value = resolve_step(&step, doc_el, &xot);
+        assert_eq!(value, xot_nodes_to_value(&elements));
+        Ok(())
+    }
 }
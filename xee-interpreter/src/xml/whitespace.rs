@@ -0,0 +1,160 @@
+// Whitespace stripping as described for the data model: text nodes that
+// consist of whitespace only are removed from elements they're stripped in,
+// unless `xml:space="preserve"` is in scope.
+
+use xot::xmlname::{NameStrInfo, OwnedName};
+use xot::{NodeEdge, Value, Xot};
+
+/// Options for loading an XML document.
+///
+/// By default a document is loaded as is.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Strip text nodes that consist of whitespace only, such as
+    /// indentation, from all elements.
+    pub strip_boundary_whitespace: bool,
+    /// Also strip whitespace-only text nodes from elements with these names,
+    /// even if `strip_boundary_whitespace` isn't set.
+    pub strip_elements: Vec<OwnedName>,
+    /// Never strip whitespace-only text nodes from elements with these names.
+    pub preserve_elements: Vec<OwnedName>,
+}
+
+impl LoadOptions {
+    fn is_stripping(&self) -> bool {
+        self.strip_boundary_whitespace || !self.strip_elements.is_empty()
+    }
+
+    fn strips(&self, xot: &Xot, name: xot::NameId) -> bool {
+        if matches(&self.preserve_elements, xot, name) {
+            return false;
+        }
+        self.strip_boundary_whitespace || matches(&self.strip_elements, xot, name)
+    }
+}
+
+fn matches(names: &[OwnedName], xot: &Xot, name: xot::NameId) -> bool {
+    names.iter().any(|candidate| {
+        candidate.local_name() == xot.local_name_str(name)
+            && candidate.namespace() == xot.uri_str(name)
+    })
+}
+
+pub(crate) fn strip_whitespace(xot: &mut Xot, root: xot::Node, options: &LoadOptions) {
+    if !options.is_stripping() {
+        return;
+    }
+    strip_whitespace_text(xot, root, |xot, node, preserve| {
+        let parent = xot.parent(node).and_then(|parent| xot.element(parent));
+        !preserve && parent.is_some_and(|parent| options.strips(xot, parent.name()))
+    });
+}
+
+/// Remove text nodes that consist of whitespace only.
+///
+/// `strip` decides for each such text node whether it's removed. Besides the
+/// text node it's told whether `xml:space="preserve"` is in effect for it.
+pub fn strip_whitespace_text(
+    xot: &mut Xot,
+    root: xot::Node,
+    strip: impl Fn(&Xot, xot::Node, bool) -> bool,
+) {
+    let mut to_remove = vec![];
+    // the xml:space in effect for each open element; true means preserve
+    let mut xml_space_preserve = vec![false];
+    for edge in xot.traverse(root) {
+        match edge {
+            NodeEdge::Start(node) => match xot.value(node) {
+                Value::Element(_) => {
+                    let preserve = match xot.attributes(node).get(xot.xml_space_name()) {
+                        Some(value) if value == "preserve" => true,
+                        Some(value) if value == "default" => false,
+                        _ => *xml_space_preserve.last().unwrap(),
+                    };
+                    xml_space_preserve.push(preserve);
+                }
+                Value::Text(text)
+                    if text.get().chars().all(is_xml_whitespace_char)
+                        && strip(xot, node, *xml_space_preserve.last().unwrap()) =>
+                {
+                    to_remove.push(node);
+                }
+                _ => {}
+            },
+            NodeEdge::End(node) => {
+                if xot.is_element(node) {
+                    xml_space_preserve.pop();
+                }
+            }
+        }
+    }
+    for node in to_remove {
+        let _ = xot.remove(node);
+    }
+}
+
+fn is_xml_whitespace_char(c: char) -> bool {
+    matches!(c, '\u{9}' | '\u{A}' | '\u{D}' | '\u{20}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(xml: &str, options: &LoadOptions) -> String {
+        let mut xot = Xot::new();
+        let root = xot.parse(xml).unwrap();
+        strip_whitespace(&mut xot, root, options);
+        xot.to_string(root).unwrap()
+    }
+
+    #[test]
+    fn test_no_stripping_by_default() {
+        assert_eq!(
+            strip("<a>\n  <b> </b>\n</a>", &LoadOptions::default()),
+            "<a>\n  <b> </b>\n</a>"
+        );
+    }
+
+    #[test]
+    fn test_strip_boundary_whitespace() {
+        let options = LoadOptions {
+            strip_boundary_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            strip("<a>\n  <b> x </b>\n  <c> </c>\n</a>", &options),
+            "<a><b> x </b><c/></a>"
+        );
+    }
+
+    #[test]
+    fn test_strip_xml_space_preserve() {
+        let options = LoadOptions {
+            strip_boundary_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            strip(
+                r#"<a> <b xml:space="preserve"> <c> </c><d xml:space="default"> </d></b></a>"#,
+                &options
+            ),
+            r#"<a><b xml:space="preserve"> <c> </c><d xml:space="default"/></b></a>"#
+        );
+    }
+
+    #[test]
+    fn test_strip_and_preserve_elements() {
+        let options = LoadOptions {
+            strip_elements: vec![OwnedName::name("a")],
+            ..Default::default()
+        };
+        assert_eq!(strip("<a> <b> </b></a>", &options), "<a><b> </b></a>");
+        let options = LoadOptions {
+            strip_boundary_whitespace: true,
+            preserve_elements: vec![OwnedName::name("b")],
+            ..Default::default()
+        };
+        assert_eq!(strip("<a> <b> </b></a>", &options), "<a><b> </b></a>");
+    }
+}
@@ -1,4 +1,4 @@
-use xee_xpath_ast::ast;
+use xee_xpath_ast::{ast, Pattern};
 
 use xee_interpreter::interpreter::instruction::{
     encode_instruction, instruction_size, Instruction,
@@ -30,6 +30,7 @@ pub struct FunctionBuilder<'a> {
     steps: Vec<xml::Step>,
     cast_types: Vec<function::CastType>,
     sequence_types: Vec<ast::SequenceType>,
+    try_catches: Vec<Vec<Vec<ast::NameTest>>>,
     closure_names: Vec<ir::Name>,
 }
 
@@ -43,6 +44,7 @@ impl<'a> FunctionBuilder<'a> {
             steps: Vec::new(),
             cast_types: Vec::new(),
             sequence_types: Vec::new(),
+            try_catches: Vec::new(),
             closure_names: Vec::new(),
         }
     }
@@ -107,6 +109,25 @@ impl<'a> FunctionBuilder<'a> {
         sequence_type_id
     }
 
+    pub(crate) fn add_try_catch(&mut self, name_tests: Vec<Vec<ast::NameTest>>) -> usize {
+        let try_catch_id = self.try_catches.len();
+        self.try_catches.push(name_tests);
+        if try_catch_id > (u16::MAX as usize) {
+            panic!("too many try/catch expressions");
+        }
+        try_catch_id
+    }
+
+    pub(crate) fn add_pattern(&mut self, pattern: Pattern<function::InlineFunctionId>) -> usize {
+        let patterns = &mut self.program.declarations.patterns;
+        let pattern_id = patterns.len();
+        patterns.push(pattern);
+        if pattern_id > (u16::MAX as usize) {
+            panic!("too many patterns");
+        }
+        pattern_id
+    }
+
     pub(crate) fn loop_start(&self) -> BackwardJumpRef {
         BackwardJumpRef(self.compiled.len())
     }
@@ -185,6 +206,7 @@ impl<'a> FunctionBuilder<'a> {
             steps: self.steps,
             cast_types: self.cast_types,
             sequence_types: self.sequence_types,
+            try_catches: self.try_catches,
         }
     }
 
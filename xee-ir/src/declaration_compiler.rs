@@ -6,7 +6,7 @@ use xee_xpath_ast::Pattern;
 use crate::function_compiler::Scopes;
 use crate::{ir, FunctionBuilder, FunctionCompiler};
 
-use xee_interpreter::{error, function, interpreter};
+use xee_interpreter::{declaration, error, function, interpreter};
 use xee_xpath_ast::pattern::transform_pattern;
 
 #[derive(Debug, Clone)]
@@ -29,6 +29,7 @@ impl RuleBuilder {
 }
 
 pub type ModeIds = HashMap<ir::ApplyTemplatesModeValue, ModeId>;
+pub type GlobalIds = HashMap<ir::Name, usize>;
 
 pub struct DeclarationCompiler<'a> {
     program: &'a mut interpreter::Program,
@@ -36,6 +37,7 @@ pub struct DeclarationCompiler<'a> {
     rule_declaration_order: i64,
     rule_builders: HashMap<ir::ModeValue, Vec<RuleBuilder>>,
     mode_ids: ModeIds,
+    global_ids: GlobalIds,
 }
 
 impl<'a> DeclarationCompiler<'a> {
@@ -46,12 +48,18 @@ impl<'a> DeclarationCompiler<'a> {
             rule_declaration_order: 0,
             rule_builders: HashMap::new(),
             mode_ids: HashMap::new(),
+            global_ids: HashMap::new(),
         }
     }
 
     fn function_compiler(&mut self) -> FunctionCompiler<'_> {
         let function_builder = FunctionBuilder::new(self.program);
-        FunctionCompiler::new(function_builder, &mut self.scopes, &self.mode_ids)
+        FunctionCompiler::new(
+            function_builder,
+            &mut self.scopes,
+            &self.mode_ids,
+            &self.global_ids,
+        )
     }
 
     pub fn compile_declarations(
@@ -61,17 +69,38 @@ impl<'a> DeclarationCompiler<'a> {
         // first keep track of what modes exist, to create a ModeId for them. We do
         // this early so any mode reference within apply-templates will resolve.
         self.compile_modes(declarations);
+        // globals can be referenced from anywhere, including other globals,
+        // so we give them their ids first too
+        for (global_id, global) in declarations.globals.iter().enumerate() {
+            self.global_ids.insert(global.name.clone(), global_id);
+        }
+        for global in &declarations.globals {
+            self.compile_global(global)?;
+        }
 
         for rule in &declarations.rules {
             self.compile_rule(rule)?;
         }
         // now add compiled rules from builder to the program
         self.add_rules();
+        self.compile_mode_settings(declarations);
+        for accumulator in &declarations.accumulators {
+            self.compile_accumulator(accumulator)?;
+        }
         let mut function_compiler = self.function_compiler();
         function_compiler.compile_function_definition(&declarations.main, (0..0).into())
     }
 
     fn compile_modes(&mut self, declarations: &ir::Declarations) {
+        // the unnamed mode always exists, as do declared modes, even without
+        // any rules
+        self.register_mode(ir::ApplyTemplatesModeValue::Unnamed);
+        for name in declarations.modes.keys() {
+            self.register_mode(match name {
+                Some(name) => ir::ApplyTemplatesModeValue::Named(name.clone()),
+                None => ir::ApplyTemplatesModeValue::Unnamed,
+            });
+        }
         for rule in &declarations.rules {
             for mode_value in &rule.modes {
                 // we don't register All modes
@@ -83,16 +112,34 @@ impl<'a> DeclarationCompiler<'a> {
                     ir::ModeValue::Named(name) => ir::ApplyTemplatesModeValue::Named(name.clone()),
                     ir::ModeValue::Unnamed => ir::ApplyTemplatesModeValue::Unnamed,
                 };
-                // we want the mode id to be unique and not overwritten
-                if self.mode_ids.contains_key(&apply_templates_mode_value) {
-                    continue;
-                }
-                let mode_id = ModeId::new(self.mode_ids.len());
-                self.mode_ids.insert(apply_templates_mode_value, mode_id);
+                self.register_mode(apply_templates_mode_value);
             }
         }
     }
 
+    fn register_mode(&mut self, apply_templates_mode_value: ir::ApplyTemplatesModeValue) {
+        // we want the mode id to be unique and not overwritten
+        if self.mode_ids.contains_key(&apply_templates_mode_value) {
+            return;
+        }
+        let mode_id = ModeId::new(self.mode_ids.len());
+        self.mode_ids.insert(apply_templates_mode_value, mode_id);
+    }
+
+    fn compile_mode_settings(&mut self, declarations: &ir::Declarations) {
+        for (name, mode) in &declarations.modes {
+            let apply_templates_mode_value = match name {
+                Some(name) => ir::ApplyTemplatesModeValue::Named(name.clone()),
+                None => ir::ApplyTemplatesModeValue::Unnamed,
+            };
+            let mode_id = self.mode_ids[&apply_templates_mode_value];
+            self.program
+                .declarations
+                .mode_lookup
+                .set_on_no_match(mode_id, mode.on_no_match);
+        }
+    }
+
     fn compile_rule(&mut self, rule: &ir::Rule) -> error::SpannedResult<()> {
         let mut function_compiler = self.function_compiler();
         let function_id =
@@ -103,9 +150,65 @@ impl<'a> DeclarationCompiler<'a> {
         })?;
 
         self.add_rule(&rule.modes, rule.priority, &pattern, function_id);
+        if rule.identity {
+            self.program.declarations.identity_rules.insert(function_id);
+        }
+        Ok(())
+    }
+
+    fn compile_global(&mut self, global: &ir::Global) -> error::SpannedResult<()> {
+        let mut function_compiler = self.function_compiler();
+        let function_id =
+            function_compiler.compile_function_id(&global.function_definition, (0..0).into())?;
+        let param = global.param.as_ref().map(|param| declaration::GlobalParam {
+            name: param.name.clone(),
+            required: param.required,
+            sequence_type: param.sequence_type.clone(),
+        });
+        self.program
+            .declarations
+            .globals
+            .push(declaration::Global { param, function_id });
+        Ok(())
+    }
+
+    fn compile_accumulator(&mut self, accumulator: &ir::Accumulator) -> error::SpannedResult<()> {
+        let mut function_compiler = self.function_compiler();
+        let initial_value =
+            function_compiler.compile_function_id(&accumulator.initial_value, (0..0).into())?;
+        let start_rules =
+            Self::compile_accumulator_rules(&mut function_compiler, &accumulator.start_rules)?;
+        let end_rules =
+            Self::compile_accumulator_rules(&mut function_compiler, &accumulator.end_rules)?;
+        self.program
+            .declarations
+            .accumulators
+            .push(declaration::Accumulator {
+                name: accumulator.name.clone(),
+                initial_value,
+                start_rules,
+                end_rules,
+            });
         Ok(())
     }
 
+    fn compile_accumulator_rules(
+        function_compiler: &mut FunctionCompiler,
+        rules: &[ir::AccumulatorRule],
+    ) -> error::SpannedResult<Vec<declaration::AccumulatorRule>> {
+        rules
+            .iter()
+            .map(|rule| {
+                let function_id = function_compiler
+                    .compile_function_id(&rule.function_definition, (0..0).into())?;
+                let pattern = transform_pattern(&rule.pattern, |function_definition| {
+                    function_compiler.compile_function_id(function_definition, (0..0).into())
+                })?;
+                Ok((pattern, function_id))
+            })
+            .collect()
+    }
+
     fn add_rule(
         &mut self,
         modes: &[ir::ModeValue],
@@ -143,6 +246,15 @@ impl<'a> DeclarationCompiler<'a> {
         // all modes. We do this before the final registration so we benefit
         // from priority sorting later
         if let Some(all_rule_builders) = all_rule_builders {
+            // modes without rules of their own still get the #all rules
+            for apply_templates_mode_value in self.mode_ids.keys() {
+                let mode = match apply_templates_mode_value {
+                    ir::ApplyTemplatesModeValue::Named(name) => ir::ModeValue::Named(name.clone()),
+                    ir::ApplyTemplatesModeValue::Unnamed => ir::ModeValue::Unnamed,
+                    ir::ApplyTemplatesModeValue::Current => continue,
+                };
+                self.rule_builders.entry(mode).or_default();
+            }
             for rule_builders in self.rule_builders.values_mut() {
                 for all_rule_builder in &all_rule_builders {
                     rule_builders.push(all_rule_builder.clone());
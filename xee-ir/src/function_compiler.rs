@@ -5,8 +5,9 @@ use xee_interpreter::function::FunctionRule;
 use xee_interpreter::interpreter::instruction::Instruction;
 use xee_interpreter::span::SourceSpan;
 use xee_interpreter::{error, function, sequence};
+use xee_xpath_ast::pattern::transform_pattern;
 
-use crate::declaration_compiler::ModeIds;
+use crate::declaration_compiler::{GlobalIds, ModeIds};
 use crate::ir;
 
 use super::builder::{BackwardJumpRef, ForwardJumpRef, FunctionBuilder, JumpCondition};
@@ -17,6 +18,7 @@ pub(crate) type Scopes = scope::Scopes<ir::Name>;
 pub struct FunctionCompiler<'a> {
     pub(crate) scopes: &'a mut Scopes,
     pub(crate) mode_ids: &'a ModeIds,
+    pub(crate) global_ids: &'a GlobalIds,
     pub(crate) builder: FunctionBuilder<'a>,
 }
 
@@ -25,11 +27,13 @@ impl<'a> FunctionCompiler<'a> {
         builder: FunctionBuilder<'a>,
         scopes: &'a mut Scopes,
         mode_ids: &'a ModeIds,
+        global_ids: &'a GlobalIds,
     ) -> Self {
         Self {
             builder,
             scopes,
             mode_ids,
+            global_ids,
         }
     }
 
@@ -59,10 +63,12 @@ impl<'a> FunctionCompiler<'a> {
                 self.compile_pattern_predicate(pattern_predicate, span)
             }
             ir::Expr::Quantified(quantified) => self.compile_quantified(quantified, span),
+            ir::Expr::TryCatch(try_catch) => self.compile_try_catch(try_catch, span),
             ir::Expr::Cast(cast) => self.compile_cast(cast, span),
             ir::Expr::Castable(castable) => self.compile_castable(castable, span),
             ir::Expr::InstanceOf(instance_of) => self.compile_instance_of(instance_of, span),
             ir::Expr::Treat(treat) => self.compile_treat(treat, span),
+            ir::Expr::Convert(convert) => self.compile_convert(convert, span),
             ir::Expr::MapConstructor(map_constructor) => {
                 self.compile_map_constructor(map_constructor, span)
             }
@@ -85,6 +91,9 @@ impl<'a> FunctionCompiler<'a> {
             }
             ir::Expr::CopyShallow(copy_shallow) => self.compile_copy_shallow(copy_shallow, span),
             ir::Expr::CopyDeep(copy_deep) => self.compile_copy_deep(copy_deep, span),
+            ir::Expr::MatchPattern(match_pattern) => {
+                self.compile_match_pattern(match_pattern, span)
+            }
         }
     }
 
@@ -139,6 +148,13 @@ impl<'a> FunctionCompiler<'a> {
                 self.builder
                     .emit(Instruction::ClosureVar(index as u16), span);
                 Ok(())
+            } else if let Some(global_id) = self.global_ids.get(name) {
+                if *global_id > u16::MAX as usize {
+                    return Err(Error::XPDY0130.with_span(span));
+                }
+                self.builder
+                    .emit(Instruction::Global(*global_id as u16), span);
+                Ok(())
             } else {
                 // TODO: this should be unreachable but
                 // the XSLT test suite for some reason triggers
@@ -335,6 +351,7 @@ impl<'a> FunctionCompiler<'a> {
             builder: nested_builder,
             scopes: self.scopes,
             mode_ids: self.mode_ids,
+            global_ids: self.global_ids,
         };
 
         for param in &function_definition.params {
@@ -431,6 +448,27 @@ impl<'a> FunctionCompiler<'a> {
         Ok(())
     }
 
+    fn compile_try_catch(
+        &mut self,
+        try_catch: &ir::TryCatch,
+        span: SourceSpan,
+    ) -> error::SpannedResult<()> {
+        // the try function, followed by a function for each catch clause
+        self.compile_function_definition(&try_catch.try_, span)?;
+        for catch_clause in &try_catch.catch_clauses {
+            self.compile_function_definition(&catch_clause.function_definition, span)?;
+        }
+        let name_tests = try_catch
+            .catch_clauses
+            .iter()
+            .map(|catch_clause| catch_clause.name_tests.clone())
+            .collect();
+        let try_catch_id = self.builder.add_try_catch(name_tests);
+        self.builder
+            .emit(Instruction::TryCatch(try_catch_id as u16), span);
+        Ok(())
+    }
+
     fn compile_lookup(
         &mut self,
         lookup: &ir::Lookup,
@@ -513,6 +551,20 @@ impl<'a> FunctionCompiler<'a> {
         Ok(())
     }
 
+    fn compile_convert(
+        &mut self,
+        convert: &ir::Convert,
+        span: SourceSpan,
+    ) -> error::SpannedResult<()> {
+        self.compile_atom(&convert.atom)?;
+        let sequence_type_id = self
+            .builder
+            .add_sequence_type(convert.sequence_type.clone());
+        self.builder
+            .emit(Instruction::Convert(sequence_type_id as u16), span);
+        Ok(())
+    }
+
     fn compile_map_constructor(
         &mut self,
         map_constructor: &ir::MapConstructor,
@@ -907,13 +959,33 @@ impl<'a> FunctionCompiler<'a> {
         Ok(())
     }
 
+    fn compile_match_pattern(
+        &mut self,
+        match_pattern: &ir::MatchPattern,
+        span: SourceSpan,
+    ) -> error::SpannedResult<()> {
+        let pattern = transform_pattern(&match_pattern.pattern, |function_definition| {
+            self.compile_function_id(function_definition, (0..0).into())
+        })?;
+        let pattern_id = self.builder.add_pattern(pattern);
+        self.compile_atom(&match_pattern.select)?;
+        self.builder
+            .emit(Instruction::MatchPattern(pattern_id as u16), span);
+        Ok(())
+    }
+
     fn compile_copy_shallow(
         &mut self,
         copy_shallow: &ir::CopyShallow,
         span: SourceSpan,
     ) -> error::SpannedResult<()> {
         self.compile_atom(&copy_shallow.select)?;
-        self.builder.emit(Instruction::CopyShallow, span);
+        if copy_shallow.copy_namespaces {
+            self.builder.emit(Instruction::CopyShallow, span);
+        } else {
+            self.builder
+                .emit(Instruction::CopyShallowNoNamespaces, span);
+        }
         Ok(())
     }
 
@@ -9,9 +9,10 @@ use rust_decimal::Decimal;
 
 pub use xee_interpreter::function::Name;
 use xee_interpreter::function::{CastType, Signature, StaticFunctionId};
+pub use xee_interpreter::pattern::OnNoMatch;
 use xee_interpreter::xml;
 use xee_schema_type::Xs;
-pub use xee_xpath_ast::ast::{BinaryOperator, SequenceType, UnaryOperator};
+pub use xee_xpath_ast::ast::{BinaryOperator, NameTest, SequenceType, UnaryOperator};
 use xee_xpath_ast::span::Spanned;
 use xee_xpath_ast::Pattern;
 use xot::xmlname;
@@ -36,10 +37,12 @@ pub enum Expr {
     Filter(Filter),
     PatternPredicate(PatternPredicate),
     Quantified(Quantified),
+    TryCatch(TryCatch),
     Cast(Cast),
     Castable(Castable),
     InstanceOf(InstanceOf),
     Treat(Treat),
+    Convert(Convert),
     MapConstructor(MapConstructor),
     ArrayConstructor(ArrayConstructor),
     XmlName(XmlName),
@@ -54,6 +57,7 @@ pub enum Expr {
     ApplyTemplates(ApplyTemplates),
     CopyShallow(CopyShallow),
     CopyDeep(CopyDeep),
+    MatchPattern(MatchPattern),
 }
 
 // not to be confused with an XPath atom; this is a variable or a constant
@@ -81,6 +85,12 @@ pub struct ContextNames {
     pub last: Name,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupNames {
+    pub group: Name,
+    pub key: Name,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Let {
     pub name: Name,
@@ -191,6 +201,20 @@ pub enum Quantifier {
     Every,
 }
 
+// the try expression is a function without parameters; each catch clause a
+// function that takes the err:* variables as parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryCatch {
+    pub try_: FunctionDefinition,
+    pub catch_clauses: Vec<CatchClause>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatchClause {
+    pub name_tests: Vec<NameTest>,
+    pub function_definition: FunctionDefinition,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Cast {
     pub atom: AtomS,
@@ -235,6 +259,14 @@ pub struct Treat {
     pub sequence_type: SequenceType,
 }
 
+// converts to the type declared by the as attribute of an XSLT variable or
+// parameter, using the function conversion rules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Convert {
+    pub atom: AtomS,
+    pub sequence_type: SequenceType,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MapConstructor {
     pub members: Vec<(AtomS, AtomS)>,
@@ -312,6 +344,7 @@ pub enum ApplyTemplatesModeValue {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CopyShallow {
     pub select: AtomS,
+    pub copy_namespaces: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -319,12 +352,21 @@ pub struct CopyDeep {
     pub select: AtomS,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchPattern {
+    pub pattern: Pattern<FunctionDefinition>,
+    pub select: AtomS,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Rule {
     pub modes: Vec<ModeValue>,
     pub priority: Decimal,
     pub pattern: Pattern<FunctionDefinition>,
     pub function_definition: FunctionDefinition,
+    // the rule is the identity template, which shallow copies the node and
+    // applies templates to its attributes and children in the same mode
+    pub identity: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -335,12 +377,49 @@ pub enum ModeValue {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Mode {}
+pub struct Mode {
+    pub on_no_match: OnNoMatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accumulator {
+    pub name: xmlname::OwnedName,
+    pub initial_value: FunctionDefinition,
+    // rules in document order, by phase
+    pub start_rules: Vec<AccumulatorRule>,
+    pub end_rules: Vec<AccumulatorRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccumulatorRule {
+    pub pattern: Pattern<FunctionDefinition>,
+    // takes the context item, position and size, and the current value of
+    // the accumulator
+    pub function_definition: FunctionDefinition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Global {
+    // the name by which the global is referenced
+    pub name: Name,
+    pub param: Option<GlobalParam>,
+    // takes the context item, position and size
+    pub function_definition: FunctionDefinition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalParam {
+    pub name: xmlname::OwnedName,
+    pub required: bool,
+    pub sequence_type: Option<SequenceType>,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Declarations {
     pub rules: Vec<Rule>,
     pub modes: HashMap<Option<xmlname::OwnedName>, Mode>,
+    pub accumulators: Vec<Accumulator>,
+    pub globals: Vec<Global>,
     pub functions: Vec<FunctionBinding>,
     pub main: FunctionDefinition,
 }
@@ -350,6 +429,8 @@ impl Declarations {
         Self {
             rules: Vec::new(),
             modes: HashMap::new(),
+            accumulators: Vec::new(),
+            globals: Vec::new(),
             functions: Vec::new(),
             main,
         }
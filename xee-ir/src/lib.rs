@@ -10,7 +10,7 @@ mod variables;
 pub use binding::{Binding, Bindings};
 pub use builder::FunctionBuilder;
 pub use compile::{compile_xpath, compile_xslt};
-pub use declaration_compiler::ModeIds;
+pub use declaration_compiler::{GlobalIds, ModeIds};
 pub use function_compiler::FunctionCompiler;
 
 pub use scope::Scopes;
@@ -18,6 +18,7 @@ pub struct Variables {
     counter: usize,
     variables: HashMap<ast::Name, ir::Name>,
     context_scope: Vec<ContextItem>,
+    group_scope: Vec<ir::GroupNames>,
 }
 
 impl Variables {
@@ -26,6 +27,7 @@ impl Variables {
             counter: 0,
             variables: HashMap::new(),
             context_scope: Vec::new(),
+            group_scope: Vec::new(),
         }
     }
 
@@ -71,6 +73,19 @@ impl Variables {
         self.context_scope.pop();
     }
 
+    pub fn push_group(&mut self) -> ir::GroupNames {
+        let names = ir::GroupNames {
+            group: self.new_name(),
+            key: self.new_name(),
+        };
+        self.group_scope.push(names.clone());
+        names
+    }
+
+    pub fn pop_group(&mut self) {
+        self.group_scope.pop();
+    }
+
     pub fn explicit_context_names(&mut self, name: ir::Name) -> ir::ContextNames {
         ir::ContextNames {
             item: name,
@@ -136,4 +151,26 @@ impl Variables {
     pub fn fn_last(&mut self, span: Span) -> error::SpannedResult<Bindings> {
         self.context_name(|names| names.last.clone(), span)
     }
+
+    fn group_name<F>(&self, get_name: F, span: Span) -> Option<Bindings>
+    where
+        F: Fn(&ir::GroupNames) -> ir::Name,
+    {
+        let ir_name = get_name(self.group_scope.last()?);
+        Some(Bindings::new(Binding::new(
+            ir_name.clone(),
+            ir::Expr::Atom(Spanned::new(ir::Atom::Variable(ir_name), span)),
+            span,
+        )))
+    }
+
+    // the current group and grouping key are only available within
+    // xsl:for-each-group
+    pub fn current_group(&self, span: Span) -> Option<Bindings> {
+        self.group_name(|names| names.group.clone(), span)
+    }
+
+    pub fn current_grouping_key(&self, span: Span) -> Option<Bindings> {
+        self.group_name(|names| names.key.clone(), span)
+    }
 }
@@ -2,7 +2,7 @@ use ahash::HashMapExt;
 use insta::assert_debug_snapshot;
 
 use xee_interpreter::interpreter::{instruction::decode_instructions, Program};
-use xee_ir::{ir, FunctionBuilder, FunctionCompiler, ModeIds, Scopes};
+use xee_ir::{ir, FunctionBuilder, FunctionCompiler, GlobalIds, ModeIds, Scopes};
 use xee_xpath_ast::span::Spanned;
 
 fn spanned<T>(t: T) -> Spanned<T> {
@@ -85,7 +85,13 @@ fn test_generate_element() {
     let function_builder = FunctionBuilder::new(&mut program);
     let mut scopes = Scopes::new();
     let empty_mode_ids = ModeIds::new();
-    let mut compiler = FunctionCompiler::new(function_builder, &mut scopes, &empty_mode_ids);
+    let empty_global_ids = GlobalIds::new();
+    let mut compiler = FunctionCompiler::new(
+        function_builder,
+        &mut scopes,
+        &empty_mode_ids,
+        &empty_global_ids,
+    );
 
     compiler.compile_expr(&outer_expr).unwrap();
 
@@ -5,6 +5,8 @@
 mod namespaces;
 mod variable_names;
 
-pub use namespaces::{NamespaceLookup, Namespaces, DEFAULT_NAMESPACES, FN_NAMESPACE, XS_NAMESPACE};
+pub use namespaces::{
+    NamespaceLookup, Namespaces, DEFAULT_NAMESPACES, FN_NAMESPACE, XEE_NAMESPACE, XS_NAMESPACE,
+};
 pub use variable_names::VariableNames;
 pub use xot::xmlname::OwnedName as Name;
@@ -1,13 +1,16 @@
 use ahash::{HashMap, HashMapExt};
 use std::sync::LazyLock;
+use xot::{Node, Xot};
 
 /// The XPath FN namespace URI
 pub const FN_NAMESPACE: &str = "http://www.w3.org/2005/xpath-functions";
 /// The XML Schema XS namespace URI
 pub const XS_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+/// The namespace URI of Xee's extension functions
+pub const XEE_NAMESPACE: &str = "https://github.com/Paligo/xee/functions";
 const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
 
-const STATIC_NAMESPACES: [(&str, &str); 7] = [
+const STATIC_NAMESPACES: [(&str, &str); 8] = [
     ("xs", XS_NAMESPACE),
     ("fn", FN_NAMESPACE),
     ("math", "http://www.w3.org/2005/xpath-functions/math"),
@@ -15,6 +18,7 @@ const STATIC_NAMESPACES: [(&str, &str); 7] = [
     ("array", "http://www.w3.org/2005/xpath-functions/array"),
     ("err", "http://www.w3.org/2005/xqt-errors"),
     ("output", "http://www.w3.org/2010/xslt-xquery-serialization"),
+    ("xee", XEE_NAMESPACE),
 ];
 
 /// Static default namespaces.
@@ -47,6 +51,26 @@ impl Namespaces {
         }
     }
 
+    /// Create a namespace struct with the namespaces in scope for a node.
+    ///
+    /// This starts with the default known namespaces for XPath, and then adds
+    /// the namespace declarations in scope for the node, typically the
+    /// document element. A declaration in the document overrides a default
+    /// known namespace with the same prefix.
+    ///
+    /// If a default namespace (`xmlns="..."`) is in scope, it becomes the
+    /// default element namespace, so that unprefixed element names in XPath
+    /// select elements in that namespace, just like they do in the document.
+    pub fn from_node(xot: &Xot, node: Node) -> Self {
+        let mut namespaces = Self::default();
+        for (prefix, namespace) in xot.namespaces_in_scope(node) {
+            let prefix = xot.prefix_str(prefix);
+            let namespace = xot.namespace_str(namespace);
+            namespaces.add(&[(prefix, namespace)]);
+        }
+        namespaces
+    }
+
     /// The default known namespaces for XPath.
     pub fn default_namespaces() -> HashMap<String, String> {
         let mut namespaces = HashMap::new();
@@ -81,6 +105,19 @@ impl Namespaces {
     pub fn default_element_namespace(&self) -> &str {
         self.default_element_namespace.as_str()
     }
+
+    /// Iterate over the namespace declarations as (prefix, uri) pairs.
+    ///
+    /// If there is a default element namespace, it's included with an empty
+    /// prefix. This is the form accepted by `StaticContextBuilder::namespaces`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        let default_element_namespace = (!self.default_element_namespace.is_empty())
+            .then_some(("", self.default_element_namespace.as_str()));
+        self.namespaces
+            .iter()
+            .map(|(prefix, uri)| (prefix.as_str(), uri.as_str()))
+            .chain(default_element_namespace)
+    }
 }
 
 impl Default for Namespaces {
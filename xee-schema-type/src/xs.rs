@@ -158,60 +158,65 @@ impl Xs {
         XS_NAMESPACE
     }
 
-    pub fn local_name(&self) -> &str {
+    pub fn local_name(&self) -> &'static str {
+        &self.prefixed_name()[3..]
+    }
+
+    /// The name of the type with the `xs` prefix, such as `xs:integer`.
+    pub fn prefixed_name(&self) -> &'static str {
         use Xs::*;
         match self {
-            AnyType => "anyType",
-            AnySimpleType => "anySimpleType",
-            Error => "error",
-            Untyped => "untyped",
-            AnyAtomicType => "anyAtomicType",
-            Numeric => "numeric",
-            String => "string",
-            UntypedAtomic => "untypedAtomic",
-            Boolean => "boolean",
-            Decimal => "decimal",
-            NonPositiveInteger => "nonPositiveInteger",
-            NegativeInteger => "negativeInteger",
-            NonNegativeInteger => "nonNegativeInteger",
-            PositiveInteger => "positiveInteger",
-            Integer => "integer",
-            Long => "long",
-            Int => "int",
-            Short => "short",
-            Byte => "byte",
-            UnsignedLong => "unsignedLong",
-            UnsignedInt => "unsignedInt",
-            UnsignedShort => "unsignedShort",
-            UnsignedByte => "unsignedByte",
-            Float => "float",
-            Double => "double",
-            QName => "QName",
-            Notation => "NOTATION",
-            Duration => "duration",
-            YearMonthDuration => "yearMonthDuration",
-            DayTimeDuration => "dayTimeDuration",
-            Time => "time",
-            GYearMonth => "gYearMonth",
-            GYear => "gYear",
-            GMonthDay => "gMonthDay",
-            GMonth => "gMonth",
-            GDay => "gDay",
-            Base64Binary => "base64Binary",
-            HexBinary => "hexBinary",
-            AnyURI => "anyURI",
-            DateTime => "dateTime",
-            DateTimeStamp => "dateTimeStamp",
-            Date => "date",
-            NormalizedString => "normalizedString",
-            Token => "token",
-            Language => "language",
-            NMTOKEN => "NMTOKEN",
-            Name => "Name",
-            NCName => "NCName",
-            ID => "ID",
-            IDREF => "IDREF",
-            ENTITY => "ENTITY",
+            AnyType => "xs:anyType",
+            AnySimpleType => "xs:anySimpleType",
+            Error => "xs:error",
+            Untyped => "xs:untyped",
+            AnyAtomicType => "xs:anyAtomicType",
+            Numeric => "xs:numeric",
+            String => "xs:string",
+            UntypedAtomic => "xs:untypedAtomic",
+            Boolean => "xs:boolean",
+            Decimal => "xs:decimal",
+            NonPositiveInteger => "xs:nonPositiveInteger",
+            NegativeInteger => "xs:negativeInteger",
+            NonNegativeInteger => "xs:nonNegativeInteger",
+            PositiveInteger => "xs:positiveInteger",
+            Integer => "xs:integer",
+            Long => "xs:long",
+            Int => "xs:int",
+            Short => "xs:short",
+            Byte => "xs:byte",
+            UnsignedLong => "xs:unsignedLong",
+            UnsignedInt => "xs:unsignedInt",
+            UnsignedShort => "xs:unsignedShort",
+            UnsignedByte => "xs:unsignedByte",
+            Float => "xs:float",
+            Double => "xs:double",
+            QName => "xs:QName",
+            Notation => "xs:NOTATION",
+            Duration => "xs:duration",
+            YearMonthDuration => "xs:yearMonthDuration",
+            DayTimeDuration => "xs:dayTimeDuration",
+            Time => "xs:time",
+            GYearMonth => "xs:gYearMonth",
+            GYear => "xs:gYear",
+            GMonthDay => "xs:gMonthDay",
+            GMonth => "xs:gMonth",
+            GDay => "xs:gDay",
+            Base64Binary => "xs:base64Binary",
+            HexBinary => "xs:hexBinary",
+            AnyURI => "xs:anyURI",
+            DateTime => "xs:dateTime",
+            DateTimeStamp => "xs:dateTimeStamp",
+            Date => "xs:date",
+            NormalizedString => "xs:normalizedString",
+            Token => "xs:token",
+            Language => "xs:language",
+            NMTOKEN => "xs:NMTOKEN",
+            Name => "xs:Name",
+            NCName => "xs:NCName",
+            ID => "xs:ID",
+            IDREF => "xs:IDREF",
+            ENTITY => "xs:ENTITY",
         }
     }
 
@@ -11,6 +11,7 @@ mod xpath;
 mod xslt;
 
 pub(crate) use core::{Environment, EnvironmentRef, TestCaseEnvironment};
+pub(crate) use decimal_format::DecimalFormat;
 pub(crate) use iterator::EnvironmentIterator;
 pub(crate) use shared::SharedEnvironments;
 pub(crate) use xpath::XPathEnvironmentSpec;
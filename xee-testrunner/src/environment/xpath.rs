@@ -1,8 +1,10 @@
 use anyhow::Result;
 
-use xee_xpath::{Queries, Query};
+use xee_xpath::{context::DecimalFormatProps, Queries, Query};
 use xee_xpath_load::{convert_string, ContextLoadable};
 
+use xot::xmlname::OwnedName as Name;
+
 use crate::catalog::LoadContext;
 
 use super::{
@@ -81,12 +83,68 @@ impl Environment for XPathEnvironmentSpec {
             let uri = namespace_uri_query.execute(session, item)?;
             Ok(Namespace { prefix, uri })
         })?;
+
+        // the name is a lexical QName that uses the namespaces in scope on
+        // the decimal-format element; without a prefix it's in no namespace,
+        // not in the default namespace
+        let local_name_query = queries.option(
+            "@name ! local-name-from-QName(resolve-QName(., ..))",
+            convert_string,
+        )?;
+        let namespace_uri_query = queries.option(
+            "@name[contains(., ':')] ! namespace-uri-from-QName(resolve-QName(., ..))",
+            convert_string,
+        )?;
+        let prefix_query = queries.option(
+            "@name ! prefix-from-QName(resolve-QName(., ..))",
+            convert_string,
+        )?;
+        let property_query =
+            |name: &str| queries.option(&format!("@{name}/string()"), convert_string);
+        let decimal_separator_query = property_query("decimal-separator")?;
+        let grouping_separator_query = property_query("grouping-separator")?;
+        let exponent_separator_query = property_query("exponent-separator")?;
+        let infinity_query = property_query("infinity")?;
+        let minus_sign_query = property_query("minus-sign")?;
+        let nan_query = property_query("NaN")?;
+        let percent_query = property_query("percent")?;
+        let per_mille_query = property_query("per-mille")?;
+        let zero_digit_query = property_query("zero-digit")?;
+        let digit_query = property_query("digit")?;
+        let pattern_separator_query = property_query("pattern-separator")?;
+        let decimal_formats_query = queries.many("decimal-format", move |session, item| {
+            let name = match local_name_query.execute(session, item)? {
+                Some(local_name) => Some(Name::new(
+                    local_name,
+                    namespace_uri_query
+                        .execute(session, item)?
+                        .unwrap_or_default(),
+                    prefix_query.execute(session, item)?.unwrap_or_default(),
+                )),
+                None => None,
+            };
+            let char_property = |s: Option<String>| s.and_then(|s| s.chars().next());
+            let props = DecimalFormatProps {
+                decimal_separator: char_property(decimal_separator_query.execute(session, item)?),
+                grouping_separator: char_property(grouping_separator_query.execute(session, item)?),
+                exponent_separator: char_property(exponent_separator_query.execute(session, item)?),
+                infinity: infinity_query.execute(session, item)?,
+                minus_sign: char_property(minus_sign_query.execute(session, item)?),
+                nan: nan_query.execute(session, item)?,
+                percent: char_property(percent_query.execute(session, item)?),
+                per_mille: char_property(per_mille_query.execute(session, item)?),
+                zero_digit: char_property(zero_digit_query.execute(session, item)?),
+                digit: char_property(digit_query.execute(session, item)?),
+                pattern_separator: char_property(pattern_separator_query.execute(session, item)?),
+            };
+            Ok(DecimalFormat { name, props })
+        })?;
         let xpath_environment_spec_query = queries.one(".", move |session, item| {
             Ok(XPathEnvironmentSpec {
                 environment_spec: environment_spec_query.execute(session, item)?,
                 namespaces: namespaces_query.execute(session, item)?,
+                decimal_formats: decimal_formats_query.execute(session, item)?,
                 // TODO
-                decimal_formats: vec![],
                 context_items: vec![],
                 static_base_uris: vec![],
             })
@@ -56,6 +56,16 @@ impl Language for XPathLanguage {
                 type_: "xsd-version".to_string(),
                 value: "1.1".to_string(),
             },
+            DependencySpec {
+                type_: "default-language".to_string(),
+                value: "en".to_string(),
+            },
+            // any decimal digit family is supported by fn:format-integer;
+            // this is the one the tests use
+            DependencySpec {
+                type_: "format-integer-sequence".to_string(),
+                value: "\u{661}".to_string(),
+            },
         ];
         KnownDependencies::new(&specs)
     }
@@ -9,6 +9,7 @@ use xee_xpath_load::{convert_string, ContextLoadable};
 
 use crate::{
     catalog::{Catalog, LoadContext},
+    environment::DecimalFormat,
     language::XPathLanguage,
     runcontext::RunContext,
     testset::TestSet,
@@ -43,6 +44,21 @@ impl XPathTestCase {
 
         Ok(namespaces)
     }
+
+    fn decimal_formats(
+        &self,
+        catalog: &Catalog<XPathLanguage>,
+        test_set: &TestSet<XPathLanguage>,
+    ) -> anyhow::Result<Vec<DecimalFormat>> {
+        let environments = self
+            .test_case
+            .environments(catalog, test_set)
+            .collect::<Result<Vec<_>, crate::error::Error>>()?;
+        Ok(environments
+            .iter()
+            .flat_map(|environment| environment.decimal_formats.iter().cloned())
+            .collect())
+    }
 }
 
 impl Runnable<XPathLanguage> for XPathTestCase {
@@ -100,10 +116,25 @@ impl Runnable<XPathLanguage> for XPathTestCase {
         };
         static_context_builder.namespaces(namespaces);
 
+        // and the decimal formats
+        let decimal_formats = match self.decimal_formats(catalog, test_set) {
+            Ok(decimal_formats) => decimal_formats,
+            Err(error) => return TestOutcome::EnvironmentError(error.to_string()),
+        };
+        for decimal_format in decimal_formats {
+            if let Err(error) =
+                static_context_builder.decimal_format(decimal_format.name, decimal_format.props)
+            {
+                return TestOutcome::EnvironmentError(error.to_string());
+            }
+        }
+
         // now construct a query with that static context
-        let static_context = static_context_builder.build();
         let queries = Queries::default();
-        let query = queries.sequence_with_context(&self.test, static_context);
+        let query = match static_context_builder.build() {
+            Ok(static_context) => queries.sequence_with_context(&self.test, static_context),
+            Err(error) => Err(error.into()),
+        };
 
         // handle any errors during parsing
         let query = match query {
@@ -70,8 +70,13 @@ impl Runnable<XsltLanguage> for XsltTestCase {
                 ))
             }
         };
-        let static_context_builder = StaticContextBuilder::default();
-        let static_context = static_context_builder.build();
+        let mut static_context_builder = StaticContextBuilder::default();
+        // for lexical QNames resolved at runtime, such as by system-property
+        static_context_builder.add_namespace("xsl", "http://www.w3.org/1999/XSL/Transform");
+        let static_context = match static_context_builder.build() {
+            Ok(static_context) => static_context,
+            Err(error) => return TestOutcome::EnvironmentError(error.to_string()),
+        };
         let program = xee_xslt_compiler::parse(static_context, &xslt);
         let program = match program {
             Ok(program) => program,
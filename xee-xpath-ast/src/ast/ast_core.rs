@@ -47,6 +47,7 @@ pub enum ExprSingle {
     Binary(BinaryExpr),
     For(ForExpr),
     Quantified(QuantifiedExpr),
+    TryCatch(TryCatchExpr),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -82,6 +83,50 @@ pub struct IfExpr {
     pub else_: Box<ExprSingleS>,
 }
 
+// try/catch isn't part of XPath 3.1, but of XQuery 3.1. We support it as an
+// extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TryCatchExpr {
+    pub try_expr: ExprOrEmptyS,
+    pub catch_clauses: Vec<CatchClause>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CatchClause {
+    pub name_tests: Vec<NameTest>,
+    // the err:code, err:description, etc variables in scope in the catch
+    // expression; these are kept here so they can be renamed
+    pub var_names: Vec<Name>,
+    pub expr: ExprOrEmptyS,
+}
+
+impl CatchClause {
+    /// The names of the variables in scope in a catch expression, in the
+    /// order they are bound.
+    pub fn error_variable_names() -> Vec<Name> {
+        [
+            "code",
+            "description",
+            "value",
+            "module",
+            "line-number",
+            "column-number",
+            "additional",
+        ]
+        .iter()
+        .map(|local_name| {
+            Name::new(
+                local_name.to_string(),
+                "http://www.w3.org/2005/xqt-errors".to_string(),
+                "err".to_string(),
+            )
+        })
+        .collect()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Quantifier {
@@ -107,6 +107,16 @@ impl AstVisitor for Renamer {
         self.pop_name();
     }
 
+    fn visit_catch_clause(&mut self, expr: &mut ast::CatchClause) {
+        for var_name in &mut expr.var_names {
+            *var_name = self.push_name(var_name);
+        }
+        self.visit_expr_or_empty(&mut expr.expr);
+        for _ in &expr.var_names {
+            self.pop_name();
+        }
+    }
+
     fn visit_inline_function(&mut self, expr: &mut ast::InlineFunction) {
         for param in &mut expr.params {
             param.name = self.push_name(&param.name);
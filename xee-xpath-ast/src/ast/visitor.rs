@@ -15,6 +15,8 @@ pub(crate) trait AstVisitor {
     fn visit_binary_expr(&mut self, expr: &mut ast::BinaryExpr);
     fn visit_for_expr(&mut self, expr: &mut ast::ForExpr);
     fn visit_quantified_expr(&mut self, expr: &mut ast::QuantifiedExpr);
+    fn visit_try_catch_expr(&mut self, expr: &mut ast::TryCatchExpr);
+    fn visit_catch_clause(&mut self, expr: &mut ast::CatchClause);
     fn visit_step_expr(&mut self, expr: &mut ast::StepExprS);
     fn visit_apply_operator(&mut self, expr: &mut ast::ApplyOperator);
     fn visit_var_ref(&mut self, expr: &mut ast::Name);
@@ -85,6 +87,7 @@ pub(crate) mod visit {
             ast::ExprSingle::Quantified(quantified_expr) => {
                 v.visit_quantified_expr(quantified_expr)
             }
+            ast::ExprSingle::TryCatch(try_catch_expr) => v.visit_try_catch_expr(try_catch_expr),
         }
     }
 
@@ -161,6 +164,29 @@ pub(crate) mod visit {
         v.visit_expr_single(&mut expr.satisfies_expr);
     }
 
+    pub(crate) fn visit_try_catch_expr<V: AstVisitor + ?Sized>(
+        v: &mut V,
+        expr: &mut ast::TryCatchExpr,
+    ) {
+        v.visit_expr_or_empty(&mut expr.try_expr);
+        for catch_clause in expr.catch_clauses.iter_mut() {
+            v.visit_catch_clause(catch_clause);
+        }
+    }
+
+    pub(crate) fn visit_catch_clause<V: AstVisitor + ?Sized>(
+        v: &mut V,
+        expr: &mut ast::CatchClause,
+    ) {
+        for name_test in expr.name_tests.iter_mut() {
+            v.visit_name_test(name_test);
+        }
+        for var_name in expr.var_names.iter_mut() {
+            v.visit_var_binding(var_name);
+        }
+        v.visit_expr_or_empty(&mut expr.expr);
+    }
+
     pub(crate) fn visit_quantifier<V: AstVisitor + ?Sized>(
         _v: &mut V,
         _expr: &mut ast::Quantifier,
@@ -16,6 +16,8 @@ where
     pub(crate) node_test: BoxedParser<'a, I, ast::NodeTest>,
     pub(crate) abbrev_forward_step: BoxedParser<'a, I, (ast::Axis, ast::NodeTest)>,
     pub(crate) axis_node_test: BoxedParser<'a, I, (ast::Axis, ast::NodeTest)>,
+    // a name test without a default namespace, as used by catch clauses
+    pub(crate) name_test: BoxedParser<'a, I, ast::NameTest>,
 }
 
 pub(crate) fn parser_axis_node_test<'a, I>(
@@ -122,7 +124,7 @@ where
     let node_test_attribute_name = kind_test
         .clone()
         .map(ast::NodeTest::KindTest)
-        .or(name_test_attribute.map(ast::NodeTest::NameTest))
+        .or(name_test_attribute.clone().map(ast::NodeTest::NameTest))
         .boxed();
 
     let abbrev_reverse_step = just(Token::DotDot).to((
@@ -219,5 +221,6 @@ where
         node_test,
         abbrev_forward_step,
         axis_node_test,
+        name_test: name_test_attribute,
     }
 }
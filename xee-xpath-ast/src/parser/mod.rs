@@ -123,6 +123,13 @@ pub fn parse_item_type<'a>(
     parse(parser().item_type, tokens(input), Cow::Borrowed(namespaces))
 }
 
+pub fn parse_name_test<'a>(
+    src: &'a str,
+    namespaces: &'a Namespaces,
+) -> Result<ast::NameTest, ParserError> {
+    parse(parser().name_test, tokens(src), Cow::Borrowed(namespaces))
+}
+
 pub fn parse_name<'a>(src: &'a str, namespaces: &'a Namespaces) -> Result<ast::NameS, ParserError> {
     parse(parser().name, tokens(src), Cow::Borrowed(namespaces))
 }
@@ -842,6 +849,11 @@ mod tests {
         assert_ron_snapshot!(parse_xpath_simple("'$' => fn:concat(?)"))
     }
 
+    #[test]
+    fn test_arrow_function_dynamic_with_placeholder() {
+        assert_ron_snapshot!(parse_xpath_simple("'$' => $f(?, 'x')"))
+    }
+
     #[test]
     fn test_inline_function_with_empty_body() {
         assert_ron_snapshot!(parse_xpath_simple(
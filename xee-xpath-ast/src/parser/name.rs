@@ -150,6 +150,8 @@ where
         just(Token::Union).to("union"),
         just(Token::Switch).to("switch"),
         just(Token::Typeswitch).to("typeswitch"),
+        just(Token::Try).to("try"),
+        just(Token::Catch).to("catch"),
     ])
     .boxed()
 }
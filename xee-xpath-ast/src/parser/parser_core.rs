@@ -28,6 +28,7 @@ where
     pub(crate) item_type: BoxedParser<'a, I, ast::ItemType>,
     pub(crate) sequence_type: BoxedParser<'a, I, ast::SequenceType>,
     pub(crate) kind_test: BoxedParser<'a, I, ast::KindTest>,
+    pub(crate) name_test: BoxedParser<'a, I, ast::NameTest>,
     pub(crate) xpath: BoxedParser<'a, I, ast::XPath>,
     pub(crate) xpath_right_brace: BoxedParser<'a, I, ast::XPath>,
 }
@@ -63,8 +64,11 @@ where
         single_type,
     } = parser_type(eqname.clone(), empty_call.clone(), kind_test.clone());
 
-    let ParserAxisNodeTestOutput { axis_node_test, .. } =
-        parser_axis_node_test(eqname.clone(), kind_test.clone());
+    let ParserAxisNodeTestOutput {
+        axis_node_test,
+        name_test,
+        ..
+    } = parser_axis_node_test(eqname.clone(), kind_test.clone());
 
     let ParserSignatureOutput {
         signature,
@@ -208,7 +212,7 @@ where
         let inline_function_expr = just(Token::Function)
             .ignore_then(param_list.delimited_by(just(Token::LeftParen), just(Token::RightParen)))
             .then(just(Token::As).ignore_then(sequence_type.clone()).or_not())
-            .then(function_body)
+            .then(function_body.clone())
             .map_with(|((params, return_type), body), extra| {
                 ast::PrimaryExpr::InlineFunction(ast::InlineFunction {
                     params,
@@ -480,21 +484,22 @@ where
             span: Span,
         ) -> ast::ExprSingleS {
             let (arguments, params) = placeholder_arguments(&argument_list);
-            let primary = if params.is_empty() {
-                primary
+            let step_expr = ast::StepExpr::PostfixExpr {
+                primary,
+                postfixes: vec![ast::Postfix::ArgumentList(arguments)],
+            }
+            .with_span(span);
+            if params.is_empty() {
+                ast::ExprSingle::Path(ast::PathExpr {
+                    steps: vec![step_expr],
+                })
+                .with_span(span)
             } else {
-                let step_expr = ast::StepExpr::PrimaryExpr(primary).with_span(span);
-                placeholder_wrapper_function(step_expr, params, span)
-            };
-
-            ast::ExprSingle::Path(ast::PathExpr {
-                steps: vec![ast::StepExpr::PostfixExpr {
-                    primary,
-                    postfixes: vec![ast::Postfix::ArgumentList(arguments)],
-                }
-                .with_span(span)],
-            })
-            .with_span(span)
+                // like a static function call, the call including the
+                // arguments goes into the wrapper function, as the
+                // arguments refer to its placeholder params
+                primary_expr_to_expr_single(placeholder_wrapper_function(step_expr, params, span))
+            }
         }
 
         let arrow_expr = unary_expr
@@ -814,10 +819,39 @@ where
         })
         .boxed();
 
+        let catch_clause = just(Token::Catch)
+            .ignore_then(
+                name_test
+                    .clone()
+                    .separated_by(just(Token::Pipe))
+                    .at_least(1)
+                    .collect::<Vec<_>>(),
+            )
+            .then(function_body.clone())
+            .map(|(name_tests, expr)| ast::CatchClause {
+                name_tests,
+                var_names: ast::CatchClause::error_variable_names(),
+                expr,
+            })
+            .boxed();
+
+        let try_catch_expr = just(Token::Try)
+            .ignore_then(function_body)
+            .then(catch_clause.repeated().at_least(1).collect::<Vec<_>>())
+            .map_with(|(try_expr, catch_clauses), extra| {
+                ast::ExprSingle::TryCatch(ast::TryCatchExpr {
+                    try_expr,
+                    catch_clauses,
+                })
+                .with_span(extra.span())
+            })
+            .boxed();
+
         let expr_single_ = let_expr
             .or(for_expr)
             .or(if_expr)
             .or(quantified_expr)
+            .or(try_catch_expr)
             .or(path_expr)
             .boxed();
 
@@ -861,6 +895,7 @@ where
         sequence_type,
         item_type,
         kind_test,
+        name_test,
     }
 }
 
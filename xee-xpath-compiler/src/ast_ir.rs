@@ -10,6 +10,10 @@ pub struct IrConverter<'a> {
     static_context: &'a context::StaticContext,
     fn_position: ast::Name,
     fn_last: ast::Name,
+    fn_current_group: ast::Name,
+    fn_current_grouping_key: ast::Name,
+    fn_exists: ast::Name,
+    fn_empty: ast::Name,
 }
 
 impl<'a> IrConverter<'a> {
@@ -23,6 +27,22 @@ impl<'a> IrConverter<'a> {
                 String::new(),
             ),
             fn_last: ast::Name::new("last".to_string(), FN_NAMESPACE.to_string(), String::new()),
+            fn_current_group: ast::Name::new(
+                "current-group".to_string(),
+                FN_NAMESPACE.to_string(),
+                String::new(),
+            ),
+            fn_current_grouping_key: ast::Name::new(
+                "current-grouping-key".to_string(),
+                FN_NAMESPACE.to_string(),
+                String::new(),
+            ),
+            fn_exists: ast::Name::new(
+                "exists".to_string(),
+                FN_NAMESPACE.to_string(),
+                String::new(),
+            ),
+            fn_empty: ast::Name::new("empty".to_string(), FN_NAMESPACE.to_string(), String::new()),
         }
     }
 
@@ -87,6 +107,7 @@ impl<'a> IrConverter<'a> {
             ast::ExprSingle::Binary(ast) => self.binary_expr(ast, span),
             ast::ExprSingle::For(ast) => self.for_expr(ast, span),
             ast::ExprSingle::Quantified(ast) => self.quantified_expr(ast, span),
+            ast::ExprSingle::TryCatch(ast) => self.try_catch_expr(ast, span),
         }
     }
 
@@ -525,6 +546,57 @@ impl<'a> IrConverter<'a> {
         Ok(var_bindings.bind(binding))
     }
 
+    fn try_catch_expr(
+        &mut self,
+        ast: &ast::TryCatchExpr,
+        span: Span,
+    ) -> error::SpannedResult<Bindings> {
+        // the try expression and the catch clauses become functions that
+        // share the context of their environment, so that the interpreter
+        // can call them and catch errors as they are raised
+        let try_bindings = self.expr_or_empty(&ast.try_expr)?;
+        let try_ = ir::FunctionDefinition {
+            params: vec![],
+            return_type: None,
+            body: Box::new(try_bindings.expr()),
+        };
+        let catch_clauses = ast
+            .catch_clauses
+            .iter()
+            .map(|catch_clause| self.catch_clause(catch_clause))
+            .collect::<error::SpannedResult<Vec<_>>>()?;
+        let expr = ir::Expr::TryCatch(ir::TryCatch {
+            try_,
+            catch_clauses,
+        });
+        let binding = self.variables.new_binding(expr, span);
+        Ok(Bindings::new(binding))
+    }
+
+    fn catch_clause(
+        &mut self,
+        catch_clause: &ast::CatchClause,
+    ) -> error::SpannedResult<ir::CatchClause> {
+        // the err:* variables are passed in as parameters
+        let params = catch_clause
+            .var_names
+            .iter()
+            .map(|name| ir::Param {
+                name: self.variables.new_var_name(name),
+                type_: None,
+            })
+            .collect();
+        let body_bindings = self.expr_or_empty(&catch_clause.expr)?;
+        Ok(ir::CatchClause {
+            name_tests: catch_clause.name_tests.clone(),
+            function_definition: ir::FunctionDefinition {
+                params,
+                return_type: None,
+                body: Box::new(body_bindings.expr()),
+            },
+        })
+    }
+
     fn quantifier(&mut self, quantifier: &ast::Quantifier) -> ir::Quantifier {
         match quantifier {
             ast::Quantifier::Some => ir::Quantifier::Some,
@@ -596,12 +668,46 @@ impl<'a> IrConverter<'a> {
             }
             return self.variables.fn_last(span);
         }
+        // fn:current-group and fn:current-grouping-key only exist within
+        // xsl:for-each-group; elsewhere they're unknown functions
+        if arity == 0 {
+            let bindings = if ast.name.value == self.fn_current_group {
+                self.variables.current_group(span)
+            } else if ast.name.value == self.fn_current_grouping_key {
+                self.variables.current_grouping_key(span)
+            } else {
+                None
+            };
+            if let Some(bindings) = bindings {
+                return Ok(bindings);
+            }
+        }
 
         // advice: format!("Either the function name {:?} does not exist, or you are calling it with the wrong number of arguments ({})", ast.name, arity),
         let static_function_id = self
             .static_context
             .function_id_by_name(&ast.name.value, arity as u8)
             .ok_or(Error::XPST0017.with_ast_span(span))?;
+
+        // fn:exists and fn:empty of a path of plain axis steps stop at the
+        // first node found, instead of building the whole path result
+        if arity == 1 {
+            let quantifier = if ast.name.value == self.fn_exists {
+                Some(ir::Quantifier::Some)
+            } else if ast.name.value == self.fn_empty {
+                Some(ir::Quantifier::Every)
+            } else {
+                None
+            };
+            if let (Some(quantifier), ast::ExprSingle::Path(path)) =
+                (quantifier, &ast.arguments[0].value)
+            {
+                if path.steps.len() > 1 && path.steps.iter().all(is_plain_axis_step) {
+                    return self.quantified_path(&path.steps, static_function_id, quantifier, span);
+                }
+            }
+        }
+
         // TODO we don't know yet how to get the proper span here
         let empty_span = (0..0).into();
         let mut static_function_ref_bindings =
@@ -615,6 +721,51 @@ impl<'a> IrConverter<'a> {
             .bind(binding))
     }
 
+    // exists(a/b/c) is some $a in a satisfies (some $b in $a/b satisfies
+    // exists($b/c)), and empty(a/b/c) is the same with every and empty.
+    // The steps are plain axis steps, which only ever select nodes, and
+    // whether there is a node doesn't depend on deduplication or document
+    // order, so we can leave them out.
+    fn quantified_path(
+        &mut self,
+        steps: &[ast::StepExprS],
+        static_function_id: function::StaticFunctionId,
+        quantifier: ir::Quantifier,
+        span: Span,
+    ) -> error::SpannedResult<Bindings> {
+        let mut step_bindings = self.step_expr(&steps[0])?;
+        let step_atom = step_bindings.atom();
+        let rest_steps = &steps[1..];
+        let context_names = self.variables.push_context();
+        let satisfies_bindings = if rest_steps.len() == 1 {
+            let empty_span = (0..0).into();
+            let mut static_function_ref_bindings =
+                self.static_function_ref(static_function_id, empty_span);
+            let atom = static_function_ref_bindings.atom();
+            let mut last_step_bindings = self.step_expr(&rest_steps[0])?;
+            let arg_atom = last_step_bindings.atom();
+            let expr = ir::Expr::FunctionCall(ir::FunctionCall {
+                atom,
+                args: vec![arg_atom],
+            });
+            let binding = self.variables.new_binding(expr, span);
+            static_function_ref_bindings
+                .concat(last_step_bindings)
+                .bind(binding)
+        } else {
+            self.quantified_path(rest_steps, static_function_id, quantifier.clone(), span)?
+        };
+        self.variables.pop_context();
+        let expr = ir::Expr::Quantified(ir::Quantified {
+            quantifier,
+            context_names,
+            var_atom: step_atom,
+            satisifies_expr: Box::new(satisfies_bindings.expr()),
+        });
+        let binding = self.variables.new_binding(expr, span);
+        Ok(step_bindings.bind(binding))
+    }
+
     fn named_function_ref(
         &mut self,
         ast: &ast::NamedFunctionRef,
@@ -766,6 +917,12 @@ impl<'a> IrConverter<'a> {
     }
 }
 
+// an axis step without predicates, as its result doesn't depend on the
+// order or deduplication of the previous step
+fn is_plain_axis_step(step: &ast::StepExprS) -> bool {
+    matches!(&step.value, ast::StepExpr::AxisStep(axis_step) if axis_step.predicates.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
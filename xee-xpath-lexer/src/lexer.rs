@@ -275,6 +275,11 @@ pub enum Token<'a> {
     Switch,
     #[token("typeswitch")]
     Typeswitch,
+    // try/catch expressions, from XQuery 3.1
+    #[token("try")]
+    Try,
+    #[token("catch")]
+    Catch,
 }
 
 fn integer_literal<'a>(lex: &mut Lexer<'a, Token<'a>>) -> IBig {
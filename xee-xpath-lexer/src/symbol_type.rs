@@ -97,7 +97,9 @@ impl Token<'_> {
             | Treat
             | Union 
             | Switch 
-            | Typeswitch => SymbolType::NonDelimiting,
+            | Typeswitch
+            | Try
+            | Catch => SymbolType::NonDelimiting,
 
         
             Token::Whitespace => SymbolType::Whitespace,
@@ -20,6 +20,20 @@ fn range(bencher: Bencher) {
     });
 }
 
+#[divan::bench]
+fn exists_large_range(bencher: Bencher) {
+    let queries = Queries::default();
+    let mut q = queries.sequence("exists(1 to 1000000000)").unwrap();
+
+    let mut documents = Documents::new();
+
+    bencher.bench_local(move || {
+        black_box(&mut q)
+            .execute_build_context(&mut documents, |_build| ())
+            .unwrap();
+    });
+}
+
 #[divan::bench]
 fn string_concat(bencher: Bencher) {
     let queries = Queries::default();
@@ -86,3 +100,21 @@ fn element_with_attribute(bencher: Bencher) {
         black_box(&mut q).execute(&mut documents, handle).unwrap();
     });
 }
+
+#[divan::bench]
+fn wide_element_children(bencher: Bencher) {
+    let mut documents = Documents::new();
+    // a document with 100k element children, each preceded by whitespace
+    // as in an indented document
+    let mut doc = String::from("<doc>");
+    for i in 0..100_000 {
+        doc.push_str(&format!("\n  <p>{}</p>", i));
+    }
+    doc.push_str("\n</doc>");
+    let handle = documents.add_string_without_uri(&doc).unwrap();
+    let queries = Queries::default();
+    let mut q = queries.sequence("count(/doc/p)").unwrap();
+    bencher.bench_local(move || {
+        black_box(&mut q).execute(&mut documents, handle).unwrap();
+    });
+}
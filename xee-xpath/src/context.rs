@@ -14,6 +14,8 @@
 //! [`StaticContext`] and [`DynamicContext`].
 
 pub use xee_interpreter::context::{
-    DynamicContext, DynamicContextBuilder, StaticContext, StaticContextBuilder, Variables,
+    DecimalFormatProps, DynamicContext, DynamicContextBuilder, OrderingMode, Resource,
+    StaticContext, StaticContextBuilder, Variables, DEFAULT_MAX_STACK_DEPTH,
 };
 pub use xee_interpreter::string::Collation;
+pub use xee_xpath_ast::Namespaces;
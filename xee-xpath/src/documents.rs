@@ -1,9 +1,16 @@
+use std::cell::Cell;
+
 use iri_string::types::IriStr;
 use xee_interpreter::{
     context::DocumentsRef,
-    xml::{DocumentHandle, DocumentsError},
+    error::Error,
+    sequence::Item,
+    xml::{node_at_path, DocumentHandle, DocumentsError, IdAttributes, LoadOptions},
 };
-use xot::Xot;
+use xee_xpath_ast::Namespaces;
+use xot::{xmlname::OwnedName, Xot};
+
+use crate::error;
 
 /// A collection of XML documents as can be used by XPath and XSLT.
 ///
@@ -16,6 +23,8 @@ use xot::Xot;
 pub struct Documents {
     pub(crate) xot: Xot,
     pub(crate) documents: DocumentsRef,
+    // the documents may have changed since their ID attributes were indexed
+    id_indexes_stale: Cell<bool>,
 }
 
 impl Documents {
@@ -24,13 +33,15 @@ impl Documents {
         Self {
             xot: Xot::new(),
             documents: DocumentsRef::new(),
+            id_indexes_stale: Cell::new(false),
         }
     }
 
     /// Load a string as an XML document. Designate it with a URI.
     ///
     /// Something may go wrong during processing of the XML document; this is
-    /// a [`xot::Error`].
+    /// a [`DocumentsError`]. If the XML fails to parse,
+    /// [`DocumentsError::location`] gives the line and column of the error.
     pub fn add_string(
         &mut self,
         uri: &IriStr,
@@ -41,23 +52,225 @@ impl Documents {
             .add_string(&mut self.xot, Some(uri), xml)
     }
 
+    /// Load a string as an XML document with the given [`LoadOptions`].
+    /// Designate it with a URI.
+    ///
+    /// With the options, whitespace-only text nodes such as indentation can
+    /// be stripped as the document is loaded, so they don't show up in the
+    /// results of `text()` for instance.
+    pub fn add_string_with_options(
+        &mut self,
+        uri: &IriStr,
+        xml: &str,
+        options: LoadOptions,
+    ) -> Result<DocumentHandle, DocumentsError> {
+        self.documents
+            .borrow_mut()
+            .add_string_with_options(&mut self.xot, Some(uri), xml, &options)
+    }
+
     /// Load a string as an XML document without designating it with a URI.
     ///
     /// Something may go wrong during processing of the XML document; this is
-    /// a [`xot::Error`].
+    /// a [`DocumentsError`]. If the XML fails to parse,
+    /// [`DocumentsError::location`] gives the line and column of the error.
     pub fn add_string_without_uri(&mut self, xml: &str) -> Result<DocumentHandle, DocumentsError> {
         self.documents
             .borrow_mut()
             .add_string(&mut self.xot, None, xml)
     }
 
+    /// Load a string as an HTML document. Designate it with a URI.
+    ///
+    /// The HTML is parsed with an HTML5 parser, so it doesn't have to be
+    /// well-formed XML: the document is repaired the same way a browser
+    /// would. HTML elements are placed in the XHTML namespace
+    /// (`http://www.w3.org/1999/xhtml`), so to query them you need to set
+    /// this as the default element namespace or bind a prefix to it.
+    ///
+    /// This is only available with the `html` feature.
+    #[cfg(feature = "html")]
+    pub fn add_html_string(
+        &mut self,
+        uri: &IriStr,
+        html: &str,
+    ) -> Result<DocumentHandle, DocumentsError> {
+        let root = crate::html::parse_html(&mut self.xot, html);
+        self.documents.borrow_mut().add_root(Some(uri), root)
+    }
+
+    /// Add a deep copy of a document, and give back a handle to it.
+    ///
+    /// The copy is independent: its nodes have new identities, so they are
+    /// never the same node as the ones in the original, and changing the
+    /// copy leaves the original untouched. The copy has the same base URI
+    /// as the original, but it's not designated by its URI; `fn:doc` keeps
+    /// returning the original.
+    ///
+    /// Returns `None` if the handle isn't from this collection.
+    pub fn clone_document(&mut self, handle: DocumentHandle) -> Option<DocumentHandle> {
+        self.documents
+            .borrow_mut()
+            .clone_document(&mut self.xot, handle)
+    }
+
+    /// Given the URI of a document give back its handle.
+    ///
+    /// This only works for documents that were designated with a URI. Along
+    /// with [`DocumentHandle::as_index`] this lets you refer to documents
+    /// outside of this collection, for instance to reconstruct references
+    /// after loading the same documents again.
+    pub fn handle_for_uri(&self, uri: &IriStr) -> Option<DocumentHandle> {
+        self.documents.borrow().get_handle_by_uri(uri)
+    }
+
+    /// Given the index of a document, as returned by
+    /// [`DocumentHandle::as_index`], give back its handle.
+    pub fn handle_for_index(&self, index: usize) -> Option<DocumentHandle> {
+        self.documents.borrow().get_handle_by_index(index)
+    }
+
     /// Given a handle give back the document node
     pub fn document_node(&self, handle: DocumentHandle) -> Option<xot::Node> {
         self.documents.borrow().get_node_by_handle(handle)
     }
 
+    /// Given a handle give back the document element
+    ///
+    /// This is the outermost element of the document, skipping any comments
+    /// and processing instructions at the top level. If the document has no
+    /// element, this returns `None`.
+    pub fn document_element(&self, handle: DocumentHandle) -> Option<xot::Node> {
+        let document_node = self.document_node(handle)?;
+        self.xot.document_element(document_node).ok()
+    }
+
+    /// Given a handle give back the node at a path, as returned by
+    /// `fn:path`.
+    ///
+    /// This is the inverse of `fn:path`, so a node can be referred to by
+    /// its path, for instance in a log, and be found again later. Returns
+    /// `None` if the path isn't in the format `fn:path` produces, or if
+    /// there is no node at that path in the document.
+    pub fn node_at_path(&self, handle: DocumentHandle, path: &str) -> Option<xot::Node> {
+        let document_node = self.document_node(handle)?;
+        node_at_path(document_node, path, &self.xot)
+    }
+
+    /// Given a handle give back the namespaces declared on its document
+    /// element.
+    ///
+    /// These can be passed to
+    /// [`StaticContextBuilder::namespaces`](crate::context::StaticContextBuilder::namespaces)
+    /// so that XPath expressions can use the same prefixes as the document.
+    /// A default namespace declared in the document becomes the default
+    /// element namespace. See [`Namespaces::from_node`] for details.
+    ///
+    /// If the document has no element, this returns `None`.
+    pub fn namespaces(&self, handle: DocumentHandle) -> Option<Namespaces> {
+        let document_element = self.document_element(handle)?;
+        Some(Namespaces::from_node(&self.xot, document_element))
+    }
+
+    /// Set which attributes are treated as ID and IDREFS attributes.
+    ///
+    /// We don't process DTDs or schemas, so attributes are recognized by
+    /// their name. This is used by `fn:id`, `fn:element-with-id` and
+    /// `fn:idref`. See [`IdAttributes`] for the defaults.
+    pub fn set_id_attributes(&mut self, id_attributes: IdAttributes) {
+        self.documents.borrow_mut().set_id_attributes(id_attributes)
+    }
+
+    /// Build a new element with the given name and children.
+    ///
+    /// The children are added the way XSLT adds content to a new element:
+    /// nodes are copied, so the originals are left untouched, and a document
+    /// node contributes its children. Attribute and namespace nodes end up on
+    /// the element; they have to come before any other children, otherwise
+    /// this results in an error. Adjacent atomic values become a single text
+    /// node, separated by a space. A function item cannot be added and
+    /// results in an error.
+    ///
+    /// If the name is in a namespace, the prefix of the name is declared for
+    /// it on the element. The new element is not part of any document, but
+    /// it can be queried and serialized like any other node.
+    pub fn build_element(
+        &mut self,
+        name: &OwnedName,
+        children: impl IntoIterator<Item = Item>,
+    ) -> error::Result<xot::Node> {
+        let name = name.to_ref(&mut self.xot);
+        let (name_id, prefix_id, namespace_id) =
+            (name.name_id(), name.prefix_id(), name.namespace_id());
+        let element = self.xot.new_element(name_id);
+        if namespace_id != self.xot.no_namespace() {
+            self.xot
+                .namespaces_mut(element)
+                .insert(prefix_id, namespace_id);
+        }
+        let mut string_values = Vec::new();
+        for item in children {
+            match item {
+                Item::Node(node) => {
+                    self.append_string_values(element, &mut string_values)?;
+                    if (self.xot.is_attribute_node(node) || self.xot.is_namespace_node(node))
+                        && self.xot.first_child(element).is_some()
+                    {
+                        return Err(Error::XTDE0410.into());
+                    }
+                    if let Some(attribute) = self.xot.attribute_node(node) {
+                        // an attribute carries no prefixes of its own, so we
+                        // declare the one it has in its original place
+                        let namespace_id = self.xot.namespace_for_name(attribute.name());
+                        if namespace_id != self.xot.no_namespace() {
+                            if let Some(prefix_id) =
+                                self.xot.prefix_for_namespace(node, namespace_id)
+                            {
+                                self.xot
+                                    .namespaces_mut(element)
+                                    .insert(prefix_id, namespace_id);
+                            }
+                        }
+                    }
+                    let node = self.xot.clone_with_prefixes(node);
+                    if self.xot.is_document(node) {
+                        while let Some(child) = self.xot.first_child(node) {
+                            self.xot
+                                .append(element, child)
+                                .map_err(|_| Error::Internal)?;
+                        }
+                    } else {
+                        self.xot
+                            .any_append(element, node)
+                            .map_err(|_| Error::Internal)?;
+                    }
+                }
+                Item::Atomic(atomic) => string_values.push(atomic.string_value()),
+                Item::Function(_) => return Err(Error::XTDE0450.into()),
+            }
+        }
+        self.append_string_values(element, &mut string_values)?;
+        Ok(element)
+    }
+
+    fn append_string_values(
+        &mut self,
+        element: xot::Node,
+        string_values: &mut Vec<String>,
+    ) -> error::Result<()> {
+        if !string_values.is_empty() {
+            let text = self.xot.new_text(&string_values.join(" "));
+            self.xot
+                .append(element, text)
+                .map_err(|_| Error::Internal)?;
+            string_values.clear();
+        }
+        Ok(())
+    }
+
     /// Get a reference to the documents
     pub fn documents(&self) -> &DocumentsRef {
+        self.clear_stale_id_indexes();
         &self.documents
     }
 
@@ -67,9 +280,26 @@ impl Documents {
     }
 
     /// Get a mutable reference to the Xot arena
+    ///
+    /// As you may change the documents through it, what we know about their
+    /// ID attributes is forgotten before the next query runs; they are
+    /// indexed again when needed.
     pub fn xot_mut(&mut self) -> &mut Xot {
+        self.id_indexes_stale.set(true);
         &mut self.xot
     }
+
+    // The documents may be borrowed while the Xot arena is changed, so we
+    // only clear the ID indexes once we can.
+    fn clear_stale_id_indexes(&self) {
+        if !self.id_indexes_stale.get() {
+            return;
+        }
+        if let Ok(mut documents) = self.documents.try_borrow_mut() {
+            documents.clear_id_indexes();
+            self.id_indexes_stale.set(false);
+        }
+    }
 }
 
 impl Default for Documents {
@@ -0,0 +1,101 @@
+use html5ever::tendril::TendrilSink;
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+use xot::{NamespaceId, Xot};
+
+/// Parse a string as HTML into a Xot document.
+///
+/// This uses an HTML5 parser, so the input doesn't need to be well-formed:
+/// tags are closed automatically and implied elements such as `<tbody>` are
+/// inserted, just like a browser would.
+///
+/// HTML elements end up in the XHTML namespace; SVG and MathML elements in
+/// their own namespaces. The doctype is dropped.
+pub(crate) fn parse_html(xot: &mut Xot, html: &str) -> xot::Node {
+    let dom = html5ever::parse_document(RcDom::default(), Default::default()).one(html);
+    let root = xot.new_document();
+    let no_namespace = xot.no_namespace();
+    for child in dom.document.children.borrow().iter() {
+        convert_node(xot, root, child, no_namespace);
+    }
+    // attributes in a namespace we have no prefix for still need one to
+    // be serializable
+    xot.create_missing_prefixes(root)
+        .expect("document node is always a valid root");
+    root
+}
+
+fn convert_node(xot: &mut Xot, parent: xot::Node, handle: &Handle, parent_namespace: NamespaceId) {
+    let node = match &handle.data {
+        NodeData::Document | NodeData::Doctype { .. } => return,
+        NodeData::Text { contents } => xot.new_text(&contents.borrow()),
+        NodeData::Comment { contents } => xot.new_comment(contents),
+        NodeData::ProcessingInstruction { target, contents } => {
+            let target = xot.add_name(target);
+            xot.new_processing_instruction(target, Some(contents))
+        }
+        NodeData::Element {
+            name,
+            attrs,
+            template_contents,
+            ..
+        } => {
+            let namespace = xot.add_namespace(&name.ns);
+            let element_name = xot.add_name_ns(&name.local, namespace);
+            let element = xot.new_element(element_name);
+            if namespace != parent_namespace {
+                let empty_prefix = xot.empty_prefix();
+                xot.namespaces_mut(element).insert(empty_prefix, namespace);
+            }
+            for attr in attrs.borrow().iter() {
+                let attribute_name = if attr.name.ns.is_empty() {
+                    xot.add_name(&attr.name.local)
+                } else {
+                    let attribute_namespace = xot.add_namespace(&attr.name.ns);
+                    if let Some(prefix) = &attr.name.prefix {
+                        if attribute_namespace != xot.xml_namespace() {
+                            let prefix = xot.add_prefix(prefix);
+                            xot.namespaces_mut(element)
+                                .insert(prefix, attribute_namespace);
+                        }
+                    }
+                    xot.add_name_ns(&attr.name.local, attribute_namespace)
+                };
+                xot.attributes_mut(element)
+                    .insert(attribute_name, attr.value.to_string());
+            }
+            // the content of a template element is kept separately by the
+            // parser; we treat it as ordinary children
+            let children = template_contents
+                .borrow()
+                .as_ref()
+                .map(|contents| contents.children.borrow().clone())
+                .unwrap_or_else(|| handle.children.borrow().clone());
+            for child in children.iter() {
+                convert_node(xot, element, child, namespace);
+            }
+            xot.append(parent, element)
+                .expect("element can always be appended");
+            return;
+        }
+    };
+    xot.append(parent, node)
+        .expect("node can always be appended");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_html_namespaces() {
+        let mut xot = Xot::new();
+        let root = parse_html(
+            &mut xot,
+            r##"<p>A<svg><a xlink:href="#x"/></svg><!--c--></p>"##,
+        );
+        assert_eq!(
+            xot.to_string(root).unwrap(),
+            r##"<html xmlns="http://www.w3.org/1999/xhtml"><head/><body><p>A<svg xmlns="http://www.w3.org/2000/svg"><a xmlns:xlink="http://www.w3.org/1999/xlink" xlink:href="#x"/></svg><!--c--></p></body></html>"##
+        );
+    }
+}
@@ -1,3 +1,181 @@
 //! Various iterators.
+use std::collections::VecDeque;
+
 pub use xee_interpreter::occurrence::{one, option};
 pub use xee_interpreter::sequence::AtomizedIter;
+
+use crate::{error, Item, Sequence};
+
+/// Adaptors for iterators over items, such as the one returned by
+/// [`Sequence::iter`].
+///
+/// These are lazy: they only take as many items from the underlying
+/// iterator as they need, so they can be used on large sequences such as
+/// ranges without materializing them.
+///
+/// ```rust
+/// use xee_xpath::{Documents, Queries, Query};
+/// use xee_xpath::iter::ItemIterExt;
+///
+/// let mut documents = Documents::new();
+/// let doc = documents.add_string_without_uri("<root/>").unwrap();
+/// let queries = Queries::default();
+/// let q = queries.sequence("1 to 5")?;
+/// let sequence = q.execute(&mut documents, doc)?;
+///
+/// let chunks = sequence.iter().chunks(2).collect::<Vec<_>>();
+/// assert_eq!(chunks.len(), 3);
+/// assert_eq!(chunks[2].len(), 1);
+/// # Ok::<(), xee_xpath::error::Error>(())
+/// ```
+pub trait ItemIterExt: Iterator<Item = Item> + Sized {
+    /// Group the items into sequences of `size` items.
+    ///
+    /// The last sequence has fewer items if there aren't enough left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    fn chunks(self, size: usize) -> Chunks<Self> {
+        assert!(size != 0, "chunk size must be non-zero");
+        Chunks { iter: self, size }
+    }
+
+    /// Sequences of `size` consecutive items, overlapping, like
+    /// [`slice::windows`].
+    ///
+    /// If there are fewer than `size` items, there are no windows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    fn windows(self, size: usize) -> Windows<Self> {
+        assert!(size != 0, "window size must be non-zero");
+        Windows {
+            iter: self,
+            size,
+            window: VecDeque::with_capacity(size),
+        }
+    }
+
+    /// Transform each item.
+    ///
+    /// Stops at the first error.
+    fn map_items<F>(self, f: F) -> MapItems<Self, F>
+    where
+        F: FnMut(Item) -> error::Result<Item>,
+    {
+        MapItems {
+            iter: self,
+            f,
+            failed: false,
+        }
+    }
+}
+
+impl<I> ItemIterExt for I where I: Iterator<Item = Item> {}
+
+/// An iterator over sequences of consecutive items.
+///
+/// Created by [`ItemIterExt::chunks`].
+pub struct Chunks<I> {
+    iter: I,
+    size: usize,
+}
+
+impl<I> Iterator for Chunks<I>
+where
+    I: Iterator<Item = Item>,
+{
+    type Item = Sequence;
+
+    fn next(&mut self) -> Option<Sequence> {
+        let chunk = self.iter.by_ref().take(self.size).collect::<Vec<_>>();
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk.into())
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        (
+            lower.div_ceil(self.size),
+            upper.map(|upper| upper.div_ceil(self.size)),
+        )
+    }
+}
+
+/// An iterator over overlapping sequences of consecutive items.
+///
+/// Created by [`ItemIterExt::windows`].
+pub struct Windows<I> {
+    iter: I,
+    size: usize,
+    window: VecDeque<Item>,
+}
+
+impl<I> Iterator for Windows<I>
+where
+    I: Iterator<Item = Item>,
+{
+    type Item = Sequence;
+
+    fn next(&mut self) -> Option<Sequence> {
+        if self.window.len() == self.size {
+            self.window.pop_front();
+        }
+        while self.window.len() < self.size {
+            self.window.push_back(self.iter.next()?);
+        }
+        Some(self.window.iter().cloned().collect::<Vec<_>>().into())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        // the number of items we still need before the next window is
+        // complete
+        let needed = if self.window.len() == self.size {
+            1
+        } else {
+            self.size - self.window.len()
+        };
+        let windows = |n: usize| n.saturating_add(1).saturating_sub(needed);
+        (windows(lower), upper.map(windows))
+    }
+}
+
+/// An iterator that transforms items.
+///
+/// Created by [`ItemIterExt::map_items`].
+pub struct MapItems<I, F> {
+    iter: I,
+    f: F,
+    failed: bool,
+}
+
+impl<I, F> Iterator for MapItems<I, F>
+where
+    I: Iterator<Item = Item>,
+    F: FnMut(Item) -> error::Result<Item>,
+{
+    type Item = error::Result<Item>;
+
+    fn next(&mut self) -> Option<error::Result<Item>> {
+        if self.failed {
+            return None;
+        }
+        let result = (self.f)(self.iter.next()?);
+        self.failed = result.is_err();
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            (0, Some(0))
+        } else {
+            self.iter.size_hint()
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! JSON input.
+//!
+//! [`fn:parse-json`](https://www.w3.org/TR/xpath-functions-31/#func-parse-json)
+//! turns a whole JSON text into a map or array. For huge JSON documents
+//! that are an array of records that takes a lot of memory, so
+//! [`parse_json_array_members`] lets you handle the records one at a time
+//! instead.
+//!
+//! ```rust
+//! use xee_xpath::{json, Documents, Queries, Query};
+//!
+//! let text = r#"[{"name": "a", "size": 1}, {"name": "b", "size": 2}]"#;
+//!
+//! let queries = Queries::default();
+//! let q = queries.one("?size", |_, item| Ok(item.try_into_value::<f64>()?))?;
+//! let mut documents = Documents::new();
+//!
+//! let mut total = 0.0;
+//! json::parse_json_array_members(text.as_bytes(), |record| {
+//!     // each record is a map, or None for JSON null
+//!     if let Some(record) = record {
+//!         total += q.execute(&mut documents, &record)?;
+//!     }
+//!     Ok(())
+//! })?;
+//! assert_eq!(total, 3.0);
+//! # Ok::<(), xee_xpath::error::Error>(())
+//! ```
+
+pub use xee_interpreter::parse_json_array_members;
@@ -37,6 +37,52 @@
 //! # Ok::<(), xee_xpath::error::Error>(())
 //! ```
 //!
+//! To turn a result sequence into XML text, use
+//! [`SerializeSequence::serialize_to_string`]:
+//!
+//! ```rust
+//! use xee_xpath::{Documents, Queries, Query, SerializationParameters, SerializeSequence};
+//!
+//! let mut documents = Documents::new();
+//! let doc = documents.add_string("http://example.com".try_into().unwrap(), "<root><a>foo</a></root>").unwrap();
+//!
+//! let queries = Queries::default();
+//! // this query returns the result sequence as is
+//! let q = queries.sequence("/root/a")?;
+//! let sequence = q.execute(&mut documents, doc)?;
+//!
+//! let mut params = SerializationParameters::new();
+//! params.omit_xml_declaration = true;
+//! let r = sequence.serialize_to_string(&mut documents, &params)?;
+//! assert_eq!(r, "<a>foo</a>");
+//!
+//! # Ok::<(), xee_xpath::error::Error>(())
+//! ```
+//!
+//! Besides the standard function library Xee offers a few extension
+//! functions, in the namespace bound to the `xee` prefix (see
+//! [`XEE_NAMESPACE`](xee_interpreter::XEE_NAMESPACE)). These aren't part of
+//! any specification:
+//!
+//! - `xee:deep-equal-approx($parameter1, $parameter2, $epsilon)` compares
+//!   like `fn:deep-equal`, but considers floats and doubles equal if they're
+//!   within `$epsilon` of each other. This is useful for assertions about
+//!   the results of floating point calculations.
+//! - `xee:deep-equal($parameter1, $parameter2, $options)` compares like
+//!   `fn:deep-equal`, with options for comparing documents. With
+//!   `ignore-whitespace` set to true, whitespace-only text nodes such as
+//!   indentation are ignored, so formatted and compact XML compare equal.
+//!   With `ignore-comments` set to false, comments are compared too.
+//! - `xee:group-by($seq, $key)` groups the items of `$seq` by the keys the
+//!   function `$key` returns for them, like `xsl:for-each-group` does. It
+//!   returns a map `map { 'key': ..., 'items': ... }` for each group, in
+//!   order of first appearance. Keys are compared using the default
+//!   collation, or the collation given as a third argument.
+//! - `xee:curry($f)` turns a function that takes n arguments into a chain
+//!   of functions that each take one argument. Applying it to fewer than
+//!   all arguments returns a partially applied function, so
+//!   `xee:curry(concat#3)('a')('b')('c')` returns `"abc"`.
+//!
 //! Note that to represent URLs, we use the
 //! [`iri-string`](https://docs.rs/iri-string/latest/iri_string/) crate.
 //! To make an `IriString` from a string, you can use the `try_into` method:
@@ -64,15 +110,24 @@ pub mod context;
 mod documents;
 pub mod error;
 pub mod function;
+#[cfg(feature = "html")]
+mod html;
 mod itemable;
 pub mod iter;
+pub mod json;
 mod queries;
 pub mod query;
+mod serialize;
+#[cfg(feature = "xslt")]
+mod xslt;
 
 pub use documents::Documents;
 pub use itemable::Itemable;
 pub use queries::Queries;
 pub use query::{Query, Recurse};
+pub use serialize::SerializeSequence;
 pub use xee_interpreter::atomic::Atomic;
 pub use xee_interpreter::sequence::{Item, Sequence, SerializationParameters};
-pub use xee_interpreter::xml::DocumentHandle;
+pub use xee_interpreter::xml::{DocumentHandle, IdAttributes, LoadOptions};
+#[cfg(feature = "xslt")]
+pub use xslt::CompiledStylesheet;
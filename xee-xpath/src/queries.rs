@@ -5,11 +5,14 @@ use xee_interpreter::{
     error::SpannedResult as Result,
 };
 use xee_xpath_compiler::parse;
+use xot::xmlname::OwnedName;
 
 use crate::query::{
     Convert, ManyQuery, ManyRecurseQuery, OneQuery, OneRecurseQuery, OptionQuery,
     OptionRecurseQuery, SequenceQuery,
 };
+#[cfg(feature = "xslt")]
+use crate::xslt::CompiledStylesheet;
 
 /// A collection of XPath queries
 ///
@@ -41,7 +44,7 @@ impl<'a> Queries<'a> {
     where
         F: Convert<V>,
     {
-        self.one_with_context(s, convert, self.default_static_context_builder.build())
+        self.one_with_context(s, convert, self.default_static_context_builder.build()?)
     }
 
     /// Construct a query that expects a single item result.
@@ -75,7 +78,7 @@ impl<'a> Queries<'a> {
     /// which have the None or empty value. I think this means that
     /// `one_recurse` is not in fact useful.
     pub fn one_recurse(&self, s: &str) -> Result<OneRecurseQuery> {
-        self.one_recurse_with_context(s, self.default_static_context_builder.build())
+        self.one_recurse_with_context(s, self.default_static_context_builder.build()?)
     }
 
     /// Construct a query that expects a single item result, with explicit
@@ -97,7 +100,7 @@ impl<'a> Queries<'a> {
     where
         F: Convert<V>,
     {
-        self.option_with_context(s, convert, self.default_static_context_builder.build())
+        self.option_with_context(s, convert, self.default_static_context_builder.build()?)
     }
 
     /// Construct a query that expects an optional single item result with
@@ -124,7 +127,7 @@ impl<'a> Queries<'a> {
     /// function but through a recursive call that's passed in during
     /// execution.
     pub fn option_recurse(&self, s: &str) -> Result<OptionRecurseQuery> {
-        self.option_recurse_with_context(s, self.default_static_context_builder.build())
+        self.option_recurse_with_context(s, self.default_static_context_builder.build()?)
     }
 
     /// Construct a recursive query that expects an optional single item result, with
@@ -146,7 +149,7 @@ impl<'a> Queries<'a> {
     where
         F: Convert<V>,
     {
-        self.many_with_context(s, convert, self.default_static_context_builder.build())
+        self.many_with_context(s, convert, self.default_static_context_builder.build()?)
     }
 
     /// Construct a query that expects many items as a result, with explicit
@@ -173,7 +176,7 @@ impl<'a> Queries<'a> {
     /// function but through a recursive call that's passed in during
     /// execution.
     pub fn many_recurse(&self, s: &str) -> Result<ManyRecurseQuery> {
-        self.many_recurse_with_context(s, self.default_static_context_builder.build())
+        self.many_recurse_with_context(s, self.default_static_context_builder.build()?)
     }
 
     /// Construct a recursive query that expects many items as a result, with explicit
@@ -193,7 +196,40 @@ impl<'a> Queries<'a> {
     /// This is a low-level API that allows you to get the raw sequence
     /// without converting it into Rust values.
     pub fn sequence(&self, s: &str) -> Result<SequenceQuery> {
-        self.sequence_with_context(s, self.default_static_context_builder.build())
+        self.sequence_with_context(s, self.default_static_context_builder.build()?)
+    }
+
+    /// Construct a query that gets a [`Sequence`] as a result, with external
+    /// variables.
+    ///
+    /// This uses the default static context with the given variable names
+    /// declared, so you don't need to build a static context yourself. When
+    /// you execute the query with
+    /// [`execute_build_context`](crate::query::Query::execute_build_context)
+    /// you only need to supply the variable values.
+    pub fn sequence_with_variables(
+        &self,
+        s: &str,
+        variable_names: impl IntoIterator<Item = OwnedName>,
+    ) -> Result<SequenceQuery> {
+        let mut static_context_builder = self.default_static_context_builder.clone();
+        static_context_builder.variable_names(variable_names);
+        self.sequence_with_context(s, static_context_builder.build()?)
+    }
+
+    /// Construct a query that gets a [`Sequence`] as a result, without
+    /// sorting nodes in document order.
+    ///
+    /// Normally paths and the node set operators (`|`, `union`, `intersect`
+    /// and `except`) return their nodes in document order. Sorting can be
+    /// expensive for large node sets, so if you don't need that order, or
+    /// sort the nodes yourself, this query skips it. Duplicate nodes are
+    /// still removed. See [`OrderingMode`](context::OrderingMode) for the
+    /// details.
+    pub fn sequence_unordered(&self, s: &str) -> Result<SequenceQuery> {
+        let mut static_context_builder = self.default_static_context_builder.clone();
+        static_context_builder.ordering_mode(context::OrderingMode::Unordered);
+        self.sequence_with_context(s, static_context_builder.build()?)
     }
 
     /// Construct a query that gets a [`Sequence`] as a result, with explicit
@@ -207,6 +243,23 @@ impl<'a> Queries<'a> {
             program: Rc::new(parse(static_context, s)?),
         })
     }
+
+    /// Compile an XSLT stylesheet, so you can use it to transform source
+    /// documents.
+    ///
+    /// The stylesheet is compiled once, so this is more efficient than
+    /// compiling it for each transformation. The stylesheet is compiled
+    /// with the XSLT static context, not the default static context of
+    /// these queries.
+    #[cfg(feature = "xslt")]
+    pub fn xslt(&self, stylesheet: &str) -> Result<CompiledStylesheet> {
+        Ok(CompiledStylesheet {
+            program: Rc::new(xee_xslt_compiler::parse(
+                xee_xslt_compiler::static_context(),
+                stylesheet,
+            )?),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -44,6 +44,8 @@ pub trait Query<V> {
     fn dynamic_context_builder(&self, documents: &Documents) -> context::DynamicContextBuilder {
         let mut context = self.program().dynamic_context_builder();
         context.documents(documents.documents().clone());
+        #[cfg(feature = "xslt")]
+        context.xslt_compiler(crate::xslt::compile);
         context
     }
 
@@ -64,6 +66,35 @@ pub trait Query<V> {
         }
     }
 
+    /// Run another query for each item this query returns, with that item
+    /// as the context item, and combine the results into one sequence.
+    ///
+    /// The results of both queries need to convert into a [`Sequence`],
+    /// so you can use this with [`Queries::sequence`], or with any query
+    /// that gives back items.
+    ///
+    /// The combined result is in the order of this query's items, and for
+    /// each of them, in the order `next` returns its results. This is like
+    /// the XPath simple map operator `!`, not the path operator `/`: the
+    /// nodes aren't sorted into document order, and duplicates aren't
+    /// removed. If this query returns nodes nested in each other, such as
+    /// sections within sections, a node found from more than one of them is
+    /// in the result more than once.
+    fn and_then<W, N>(self, next: N) -> AndThenQuery<V, W, Self, N>
+    where
+        Self: Sized,
+        V: Into<Sequence>,
+        W: Into<Sequence>,
+        N: Query<W>,
+    {
+        AndThenQuery {
+            query: self,
+            next,
+            v: std::marker::PhantomData,
+            w: std::marker::PhantomData,
+        }
+    }
+
     /// Excute the query against an itemable
     fn execute(&self, documents: &mut Documents, item: impl Itemable) -> Result<V> {
         let context_item = item.to_item(documents)?;
@@ -86,6 +117,24 @@ pub trait Query<V> {
         let context = dynamic_context_builder.build();
         self.execute_with_context(documents, &context)
     }
+
+    /// Execute the query against an itemable, and get the resulting
+    /// [`Sequence`] without conversion.
+    ///
+    /// The query's convert function isn't called, and neither are the
+    /// cardinality checks of queries constructed with [`Queries::one`] and
+    /// [`Queries::option`]: you get all the items, however many there are.
+    /// For a [`MapQuery`] the map function isn't called either.
+    ///
+    /// This is useful for debugging, or for generic tooling that doesn't
+    /// know about the query's result type.
+    fn execute_sequence(&self, documents: &mut Documents, item: impl Itemable) -> Result<Sequence> {
+        let context_item = item.to_item(documents)?;
+        let mut dynamic_context_builder = self.dynamic_context_builder(documents);
+        dynamic_context_builder.context_item(context_item);
+        let context = dynamic_context_builder.build();
+        self.program().runnable(&context).many(&mut documents.xot)
+    }
 }
 
 /// A recursive query that can be executed against an [`Itemable`]
@@ -118,7 +167,9 @@ pub trait RecurseQuery<C, V> {
     /// to use with `execute_with_context`.
     fn dynamic_context_builder(&self, document: &Documents) -> context::DynamicContextBuilder {
         let mut context = self.program().dynamic_context_builder();
-        context.documents(document.documents.clone());
+        context.documents(document.documents().clone());
+        #[cfg(feature = "xslt")]
+        context.xslt_compiler(crate::xslt::compile);
         context
     }
 
@@ -213,7 +264,7 @@ where
         document: &mut Documents,
         context: &context::DynamicContext,
     ) -> Result<V> {
-        let sequence = self.program.runnable(context).many(document.xot_mut())?;
+        let sequence = self.program.runnable(context).many(&mut document.xot)?;
         let item = sequence.one()?;
         (self.convert)(document, &item)
     }
@@ -253,7 +304,7 @@ impl OneRecurseQuery {
         context: &context::DynamicContext,
         recurse: &Recurse<V>,
     ) -> Result<V> {
-        let sequence = self.program.runnable(context).many(document.xot_mut())?;
+        let sequence = self.program.runnable(context).many(&mut document.xot)?;
         let item = sequence.one()?;
         recurse.execute(document, &item)
     }
@@ -306,7 +357,7 @@ where
         document: &mut Documents,
         context: &context::DynamicContext,
     ) -> Result<Option<V>> {
-        let sequence = self.program.runnable(context).many(document.xot_mut())?;
+        let sequence = self.program.runnable(context).many(&mut document.xot)?;
         let item = sequence.option()?;
         item.map(|item| (self.convert)(document, &item)).transpose()
     }
@@ -343,7 +394,7 @@ impl OptionRecurseQuery {
         context: &context::DynamicContext,
         recurse: &Recurse<V>,
     ) -> Result<Option<V>> {
-        let sequence = self.program.runnable(context).many(document.xot_mut())?;
+        let sequence = self.program.runnable(context).many(&mut document.xot)?;
         let item = sequence.option()?;
         item.map(|item| recurse.execute(document, &item))
             .transpose()
@@ -394,7 +445,7 @@ where
         document: &mut Documents,
         context: &context::DynamicContext,
     ) -> Result<Vec<V>> {
-        let sequence = self.program.runnable(context).many(document.xot_mut())?;
+        let sequence = self.program.runnable(context).many(&mut document.xot)?;
         let items = sequence
             .iter()
             .map(|item| (self.convert)(document, &item))
@@ -437,7 +488,7 @@ impl ManyRecurseQuery {
         context: &context::DynamicContext,
         recurse: &Recurse<V>,
     ) -> Result<Vec<V>> {
-        let sequence = self.program.runnable(context).many(document.xot_mut())?;
+        let sequence = self.program.runnable(context).many(&mut document.xot)?;
         let items = sequence
             .iter()
             .map(|item| recurse.execute(document, &item))
@@ -482,7 +533,7 @@ impl SequenceQuery {
         document: &mut Documents,
         context: &context::DynamicContext,
     ) -> Result<Sequence> {
-        self.program.runnable(context).many(document.xot_mut())
+        self.program.runnable(context).many(&mut document.xot)
     }
 }
 
@@ -554,3 +605,47 @@ where
         (self.f)(v, document, context)
     }
 }
+
+/// A query that runs another query for each item in the result of a query.
+///
+/// Construct this using [`Query::and_then`].
+#[derive(Debug, Clone)]
+pub struct AndThenQuery<V, W, Q: Query<V> + Sized, N: Query<W>> {
+    query: Q,
+    next: N,
+    v: std::marker::PhantomData<V>,
+    w: std::marker::PhantomData<W>,
+}
+
+impl<V, W, Q, N> Query<Sequence> for AndThenQuery<V, W, Q, N>
+where
+    V: Into<Sequence>,
+    W: Into<Sequence>,
+    Q: Query<V> + Sized,
+    N: Query<W>,
+{
+    fn program(&self) -> &Program {
+        self.query.program()
+    }
+
+    fn execute_with_context(
+        &self,
+        documents: &mut Documents,
+        context: &context::DynamicContext,
+    ) -> Result<Sequence> {
+        let sequence: Sequence = self.query.execute_with_context(documents, context)?.into();
+        let mut items = Vec::new();
+        for item in sequence.iter() {
+            // the next query runs with the same settings, such as the
+            // variables and the deadline, but with the item as its context
+            let mut builder = context.builder_for(self.next.program());
+            builder.context_item(item);
+            let next: Sequence = self
+                .next
+                .execute_with_context(documents, &builder.build())?
+                .into();
+            items.extend(next.iter());
+        }
+        Ok(items.into())
+    }
+}
@@ -0,0 +1,53 @@
+use xee_interpreter::sequence::{Sequence, SerializationParameters};
+
+use crate::{error::Result, Documents};
+
+/// Serialize a [`Sequence`] to a string, using the XML in [`Documents`].
+///
+/// This covers the common case of turning a query result into XML (or HTML
+/// or JSON, depending on the `method` parameter) text. Nodes are serialized
+/// with their subtree, atomic values by their string value.
+pub trait SerializeSequence {
+    /// Serialize this sequence to a string according to the serialization
+    /// parameters.
+    ///
+    /// Serialization may need to construct new nodes (to wrap the sequence
+    /// in a document), which is why this takes the documents mutably.
+    fn serialize_to_string(
+        &self,
+        documents: &mut Documents,
+        params: &SerializationParameters,
+    ) -> Result<String>;
+
+    /// Serialize this sequence as an iterator of string chunks.
+    ///
+    /// This is useful when the output is pulled piece by piece, for
+    /// instance to stream it as an HTTP response body. With the XML output
+    /// method there is a chunk per top-level node; other output comes as a
+    /// single chunk. Concatenated, the chunks are the same as the result of
+    /// [`SerializeSequence::serialize_to_string`].
+    fn serialize_chunks<'a>(
+        &self,
+        documents: &'a mut Documents,
+        params: &SerializationParameters,
+    ) -> impl Iterator<Item = Result<String>> + 'a;
+}
+
+impl SerializeSequence for Sequence {
+    fn serialize_to_string(
+        &self,
+        documents: &mut Documents,
+        params: &SerializationParameters,
+    ) -> Result<String> {
+        Ok(self.serialize(params.clone(), &mut documents.xot)?)
+    }
+
+    fn serialize_chunks<'a>(
+        &self,
+        documents: &'a mut Documents,
+        params: &SerializationParameters,
+    ) -> impl Iterator<Item = Result<String>> + 'a {
+        self.serialize_iter(params.clone(), &mut documents.xot)
+            .map(|chunk| Ok(chunk?))
+    }
+}
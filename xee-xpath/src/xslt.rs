@@ -0,0 +1,58 @@
+use std::rc::Rc;
+
+use xee_interpreter::{
+    context::Variables, error::SpannedResult as Result, interpreter::Program,
+    sequence::SerializationParameters,
+};
+
+use crate::{Documents, Itemable, Sequence};
+
+/// A compiled XSLT stylesheet
+///
+/// You get one with [`Queries::xslt`](crate::Queries::xslt). The stylesheet
+/// is compiled only once, so you can use it to transform any number of
+/// source documents.
+#[derive(Debug, Clone)]
+pub struct CompiledStylesheet {
+    pub(crate) program: Rc<Program>,
+}
+
+impl CompiledStylesheet {
+    /// Transform `source` with this stylesheet.
+    ///
+    /// The source is the context item of the transformation; templates are
+    /// applied to it in the unnamed mode. The principal result is returned
+    /// as a sequence, which you can serialize with
+    /// [`SerializeSequence`](crate::SerializeSequence).
+    pub fn transform(&self, documents: &mut Documents, source: impl Itemable) -> Result<Sequence> {
+        self.transform_with_params(documents, source, Variables::default())
+    }
+
+    /// Transform `source` with this stylesheet, with values for its
+    /// stylesheet parameters by name.
+    ///
+    /// Parameters that you don't supply get their default value.
+    pub fn transform_with_params(
+        &self,
+        documents: &mut Documents,
+        source: impl Itemable,
+        params: Variables,
+    ) -> Result<Sequence> {
+        let context_item = source.to_item(documents)?;
+        let mut dynamic_context_builder = self.program.dynamic_context_builder();
+        dynamic_context_builder.documents(documents.documents().clone());
+        dynamic_context_builder.context_item(context_item);
+        dynamic_context_builder.variables(params);
+        dynamic_context_builder.xslt_compiler(compile);
+        let context = dynamic_context_builder.build();
+        self.program.runnable(&context).many(&mut documents.xot)
+    }
+}
+
+/// Compile a stylesheet that `fn:transform` runs, along with the
+/// serialization parameters of its unnamed `xsl:output`.
+pub(crate) fn compile(stylesheet: &str) -> Result<(Program, SerializationParameters)> {
+    let program = xee_xslt_compiler::parse(xee_xslt_compiler::static_context(), stylesheet)?;
+    let parameters = xee_xslt_compiler::serialization_parameters(stylesheet)?;
+    Ok((program, parameters))
+}
@@ -1,6 +1,21 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use ibig::{ibig, IBig};
 use xee_interpreter::sequence::Sequence;
-use xee_xpath::{error, query::RecurseQuery, Documents, Item, Queries, Query, Recurse};
+use xee_xpath::iter::ItemIterExt;
+use xee_xpath::json::parse_json_array_members;
+use xee_xpath::{
+    context::{DecimalFormatProps, Resource, StaticContextBuilder, Variables},
+    error,
+    query::RecurseQuery,
+    Documents, IdAttributes, Item, Itemable, LoadOptions, Queries, Query, Recurse,
+    SerializationParameters, SerializeSequence,
+};
+use xot::xmlname::OwnedName;
 
 #[test]
 fn test_duplicate_document_uri() -> error::Result<()> {
@@ -25,6 +40,97 @@ fn test_duplicate_document_uri() -> error::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_load_options_strip_boundary_whitespace() -> error::Result<()> {
+    let xml = "<root>\n  <a>A</a>\n  <b xml:space=\"preserve\"> </b>\n</root>";
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_with_options(
+            "http://example.com/stripped".try_into().unwrap(),
+            xml,
+            LoadOptions {
+                strip_boundary_whitespace: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let unstripped = documents
+        .add_string("http://example.com/unstripped".try_into().unwrap(), xml)
+        .unwrap();
+
+    let queries = Queries::default();
+    let q = queries.one("count(//text())", |_, item| {
+        Ok(item.try_into_value::<IBig>()?)
+    })?;
+    // the indentation is gone, but the preserved space is still there
+    assert_eq!(q.execute(&mut documents, doc)?, ibig!(2));
+    assert_eq!(q.execute(&mut documents, unstripped)?, ibig!(5));
+    Ok(())
+}
+
+#[test]
+fn test_document_element() {
+    let mut documents = Documents::new();
+    let handle = documents
+        .add_string_without_uri("<!--comment--><?pi?><root><a/></root>")
+        .unwrap();
+    let document_element = documents.document_element(handle).unwrap();
+    let xot = documents.xot();
+    assert!(xot.is_element(document_element));
+    assert_eq!(
+        xot.parent(document_element),
+        documents.document_node(handle)
+    );
+    assert_eq!(
+        xot.to_string(document_element).unwrap(),
+        "<root><a/></root>"
+    );
+}
+
+#[test]
+fn test_document_handle_round_trip() -> error::Result<()> {
+    fn load(documents: &mut Documents) {
+        documents
+            .add_string("http://example.com/a".try_into().unwrap(), "<a/>")
+            .unwrap();
+        documents
+            .add_string("http://example.com/b".try_into().unwrap(), "<b/>")
+            .unwrap();
+    }
+
+    let mut documents = Documents::new();
+    load(&mut documents);
+    let b = documents
+        .handle_for_uri("http://example.com/b".try_into().unwrap())
+        .unwrap();
+    let index = b.as_index();
+    assert_eq!(index, 1);
+    assert_eq!(documents.handle_for_index(index), Some(b));
+    assert_eq!(documents.handle_for_index(2), None);
+    assert_eq!(
+        documents.handle_for_uri("http://example.com/c".try_into().unwrap()),
+        None
+    );
+
+    // after loading the same documents again, the index and the URI refer
+    // to the same document, but the old handle doesn't work
+    let mut reloaded = Documents::new();
+    load(&mut reloaded);
+    assert_eq!(reloaded.document_node(b), None);
+    let by_index = reloaded.handle_for_index(index).unwrap();
+    let by_uri = reloaded
+        .handle_for_uri("http://example.com/b".try_into().unwrap())
+        .unwrap();
+    assert_eq!(by_index, by_uri);
+
+    let queries = Queries::default();
+    let q = queries.one("local-name(*)", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    assert_eq!(q.execute(&mut reloaded, by_index)?, "b");
+    Ok(())
+}
+
 #[test]
 fn test_simple_query() -> error::Result<()> {
     let mut documents = Documents::new();
@@ -58,6 +164,46 @@ fn test_sequence_query() -> error::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_execute_sequence_bypasses_one() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            "<root><a>1</a><a>2</a></root>",
+        )
+        .unwrap();
+
+    let queries = Queries::default();
+    let q = queries.one("/root/a/string()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+
+    // executing normally fails as there's more than one item
+    assert!(q.execute(&mut documents, doc).is_err());
+    let r = q.execute_sequence(&mut documents, doc)?;
+    let sequence: Sequence = vec!["1", "2"].into();
+    assert_eq!(r, sequence);
+    Ok(())
+}
+
+#[test]
+fn test_execute_sequence_map_query() -> error::Result<()> {
+    let queries = Queries::default();
+    let q = queries
+        .option("()", |_, item| {
+            let v: IBig = item.to_atomic()?.try_into()?;
+            Ok(v)
+        })?
+        .map(|v, _, _| Ok(v.unwrap_or(ibig!(1))));
+
+    let mut documents = Documents::new();
+
+    let r = q.execute_sequence(&mut documents, &1i64.into())?;
+    assert!(r.is_empty());
+    Ok(())
+}
+
 #[test]
 fn test_option_query() -> error::Result<()> {
     let mut documents = Documents::new();
@@ -250,6 +396,120 @@ fn test_map_query() -> error::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_and_then_query() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            r#"<doc><section><title>A</title><p><title>B</title></p></section><section><title>C</title></section></doc>"#,
+        )
+        .unwrap();
+
+    let queries = Queries::default();
+    let q = queries
+        .sequence("//section")?
+        .and_then(queries.sequence(".//title")?);
+
+    let r = q.execute(&mut documents, doc)?;
+    let titles = r
+        .iter()
+        .map(|item| item.string_value(documents.xot()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(titles, vec!["A", "B", "C"]);
+    Ok(())
+}
+
+#[test]
+fn test_and_then_query_nested_duplicates() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            r#"<doc><section><title>A</title><section><title>B</title></section></section></doc>"#,
+        )
+        .unwrap();
+
+    let queries = Queries::default();
+    let q = queries
+        .sequence("//section")?
+        .and_then(queries.sequence(".//title")?);
+
+    // the title of the nested section is found from both sections, and
+    // isn't deduplicated
+    let r = q.execute(&mut documents, doc)?;
+    let titles = r
+        .iter()
+        .map(|item| item.string_value(documents.xot()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(titles, vec!["A", "B", "B"]);
+    Ok(())
+}
+
+#[test]
+fn test_and_then_query_chained() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            r#"<doc><section><title>A</title></section><section><title>B</title></section></doc>"#,
+        )
+        .unwrap();
+
+    let queries = Queries::default();
+    let q = queries
+        .sequence("//section")?
+        .and_then(queries.sequence("title")?)
+        .and_then(queries.one("string()", |_, item| Ok(item.clone()))?);
+
+    let r = q.execute(&mut documents, doc)?;
+    let sequence: Sequence = vec![Item::from("A"), Item::from("B")].into();
+    assert_eq!(r, sequence);
+    Ok(())
+}
+
+#[test]
+fn test_and_then_query_context() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            r#"<doc><section><title>A</title></section><section><title>B</title></section></doc>"#,
+        )
+        .unwrap();
+
+    let name = OwnedName::name("prefix");
+    let mut static_context_builder = StaticContextBuilder::default();
+    static_context_builder.variable_names([name.clone()]);
+    let queries = Queries::new(static_context_builder);
+    let q = queries
+        .sequence("//section")?
+        .and_then(queries.sequence("$prefix || title")?);
+
+    // the next query sees the variables of the context
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.context_node(documents.document_node(doc).unwrap());
+    let variables: Variables = [(name, Item::from("-").into())].into_iter().collect();
+    context_builder.variables(variables.clone());
+    let r = q.execute_with_context(&mut documents, &context_builder.build())?;
+    let sequence: Sequence = vec![Item::from("-A"), Item::from("-B")].into();
+    assert_eq!(r, sequence);
+
+    // as well as its deadline
+    let q = queries
+        .sequence("//section")?
+        .and_then(queries.sequence("(1 to 1000000000) ! (if (. = 0) then . else ())")?);
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.context_node(documents.document_node(doc).unwrap());
+    context_builder.variables(variables);
+    context_builder.deadline(Instant::now() + Duration::from_millis(50));
+    let err = q
+        .execute_with_context(&mut documents, &context_builder.build())
+        .unwrap_err();
+    assert_eq!(err.code(), Some(error::ErrorCode::Cancelled));
+    Ok(())
+}
+
 #[test]
 fn test_map_query_clone() -> error::Result<()> {
     let queries = Queries::default();
@@ -266,3 +526,1651 @@ fn test_map_query_clone() -> error::Result<()> {
     assert_eq!(r, ibig!(4));
     Ok(())
 }
+
+#[test]
+fn test_copy_of_independent() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            r#"<root><a x="1"><b/></a></root>"#,
+        )
+        .unwrap();
+
+    let queries = Queries::default();
+    let original_q = queries.one("/root/a", |_, item| Ok(item.to_node()?))?;
+    let copy_q = queries.one("copy-of(/root/a)", |_, item| Ok(item.to_node()?))?;
+
+    let original = original_q.execute(&mut documents, doc)?;
+    let copy = copy_q.execute(&mut documents, doc)?;
+
+    // changing the original doesn't affect the copy
+    let xot = documents.xot_mut();
+    let x = xot.add_name("x");
+    xot.set_attribute(original, x, "2");
+    let c = xot.add_name("c");
+    let c = xot.new_element(c);
+    xot.append(original, c).unwrap();
+
+    assert_eq!(xot.to_string(original).unwrap(), r#"<a x="2"><b/><c/></a>"#);
+    // the copy keeps the base URI of the document it was copied from,
+    // without an xml:base attribute
+    assert_eq!(xot.to_string(copy).unwrap(), r#"<a x="1"><b/></a>"#);
+    let base_uri_q = queries.one("string(base-uri(.))", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    assert_eq!(
+        base_uri_q.execute(&mut documents, copy)?,
+        "http://example.com"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_document_namespaces() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            r#"<ns:root xmlns:ns="http://example.com/ns"><ns:foo>A</ns:foo></ns:root>"#,
+        )
+        .unwrap();
+
+    let namespaces = documents.namespaces(doc).unwrap();
+    let mut static_context_builder = StaticContextBuilder::default();
+    static_context_builder.namespaces(namespaces.iter());
+    let queries = Queries::new(static_context_builder);
+    let q = queries.one("//ns:foo/string()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+
+    let r = q.execute(&mut documents, doc)?;
+    assert_eq!(r, "A");
+    Ok(())
+}
+
+#[test]
+fn test_document_namespaces_default() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            r#"<root xmlns="http://example.com/ns"><foo>A</foo></root>"#,
+        )
+        .unwrap();
+
+    let namespaces = documents.namespaces(doc).unwrap();
+    assert_eq!(
+        namespaces.default_element_namespace(),
+        "http://example.com/ns"
+    );
+    let mut static_context_builder = StaticContextBuilder::default();
+    static_context_builder.namespaces(namespaces.iter());
+    let queries = Queries::new(static_context_builder);
+    let q = queries.one("/root/foo/string()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+
+    let r = q.execute(&mut documents, doc)?;
+    assert_eq!(r, "A");
+    Ok(())
+}
+
+#[test]
+fn test_declare_namespace() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            r#"<a:root xmlns:a="http://example.com/a" xmlns:b="http://example.com/b"><b:foo>A</b:foo></a:root>"#,
+        )
+        .unwrap();
+
+    let mut static_context_builder = StaticContextBuilder::default();
+    for (prefix, uri) in [("x", "http://example.com/a"), ("y", "http://example.com/b")] {
+        // the prefix doesn't need to outlive the builder
+        let prefix = prefix.to_string();
+        static_context_builder.declare_namespace(&prefix, uri);
+    }
+    let queries = Queries::new(static_context_builder);
+    let q = queries.one("/x:root/y:foo/string()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+
+    let r = q.execute(&mut documents, doc)?;
+    assert_eq!(r, "A");
+    Ok(())
+}
+
+#[test]
+fn test_declare_default_element_namespace() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            r#"<root xmlns="http://example.com/ns"><foo>A</foo></root>"#,
+        )
+        .unwrap();
+
+    let mut static_context_builder = StaticContextBuilder::default();
+    static_context_builder.declare_default_element_namespace("http://example.com/other");
+    // redeclaring overrides it
+    static_context_builder.declare_default_element_namespace("http://example.com/ns");
+    let queries = Queries::new(static_context_builder);
+    let q = queries.one("/root/foo/string()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+
+    let r = q.execute(&mut documents, doc)?;
+    assert_eq!(r, "A");
+    Ok(())
+}
+
+#[test]
+fn test_parse_xml_inherits_static_base_uri() -> error::Result<()> {
+    let mut static_context_builder = StaticContextBuilder::default();
+    static_context_builder.static_base_uri(Some("http://example.com/base/".try_into().unwrap()));
+    let queries = Queries::new(static_context_builder);
+    // the parsed document has no URI of its own, so relative xml:base
+    // attributes resolve against the static base URI
+    let q = queries.one(
+        r#"let $doc := parse-xml('<a xml:base="x/"><b xml:base="y/"><c/></b></a>')
+           return (base-uri($doc), resolve-uri('z', base-uri($doc//c))) => string-join(' ')"#,
+        |_, item| Ok(item.try_into_value::<String>()?),
+    )?;
+    let mut documents = Documents::new();
+    let r = q.execute(&mut documents, &1i64.into())?;
+    assert_eq!(r, "http://example.com/base/ http://example.com/base/x/y/z");
+    Ok(())
+}
+
+#[test]
+fn test_default_function_namespace() -> error::Result<()> {
+    // the xee function library is in a namespace of its own
+    let mut static_context_builder = StaticContextBuilder::default();
+    static_context_builder.default_function_namespace("https://github.com/Paligo/xee/functions");
+    let queries = Queries::new(static_context_builder);
+    let mut documents = Documents::new();
+
+    // an unprefixed call resolves into the default function namespace
+    let q = queries.one("deep-equal-approx(1.0e0, 1.1e0, 0.5)", |_, item| {
+        Ok(item.try_into_value::<bool>()?)
+    })?;
+    let r = q.execute(&mut documents, &1i64.into())?;
+    assert!(r);
+
+    // built-in functions remain reachable with an explicit prefix, and
+    // operators that are defined in terms of functions keep working
+    let q = queries.one(
+        "fn:string-join(('a', 'b') ! fn:upper-case(.)) || 'c'",
+        |_, item| Ok(item.try_into_value::<String>()?),
+    )?;
+    let r = q.execute(&mut documents, &1i64.into())?;
+    assert_eq!(r, "ABc");
+
+    // but not without it, as they're not in the default function namespace
+    let err = queries.sequence("myfunc()").unwrap_err();
+    assert_eq!(err.error, error::ErrorValue::XPST0017);
+    let err = queries.sequence("upper-case('a')").unwrap_err();
+    assert_eq!(err.error, error::ErrorValue::XPST0017);
+    Ok(())
+}
+
+#[test]
+fn test_decimal_format() -> error::Result<()> {
+    let mut static_context_builder = StaticContextBuilder::default();
+    static_context_builder.add_namespace("ex", "http://example.com");
+    static_context_builder
+        .decimal_format(
+            Some(OwnedName::new(
+                "swiss".to_string(),
+                "http://example.com".to_string(),
+                "ex".to_string(),
+            )),
+            DecimalFormatProps {
+                grouping_separator: Some('\''),
+                percent: Some('٪'),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let queries = Queries::new(static_context_builder);
+    let mut documents = Documents::new();
+
+    // the name can be a lexical QName or an EQName
+    let q = queries.many(
+        "format-number(1234567.891, '#''##0.00', 'ex:swiss'), format-number(0.25, '0٪', ' Q{http://example.com}swiss ')",
+        |_, item| Ok(item.try_into_value::<String>()?),
+    )?;
+    let r = q.execute(&mut documents, &1i64.into())?;
+    assert_eq!(r, vec!["1'234'567.89", "25٪"]);
+
+    // without a name the unnamed decimal format is used
+    let q = queries.one("format-number(1234.5, '#,##0.0%')", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    let r = q.execute(&mut documents, &1i64.into())?;
+    assert_eq!(r, "123,450.0%");
+
+    let q = queries.one("format-number(1, '0', 'ex:unknown')", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    let err = q.execute(&mut documents, &1i64.into()).unwrap_err();
+    assert_eq!(err.error, error::ErrorValue::FODF1280);
+    Ok(())
+}
+
+#[test]
+fn test_decimal_format_conflict() {
+    let mut static_context_builder = StaticContextBuilder::default();
+    let name = Some(OwnedName::new(
+        "f".to_string(),
+        "".to_string(),
+        "".to_string(),
+    ));
+    let props = |grouping_separator| DecimalFormatProps {
+        grouping_separator: Some(grouping_separator),
+        ..Default::default()
+    };
+    // declaring the same property again with the same value is fine
+    static_context_builder
+        .decimal_format(name.clone(), props('.'))
+        .unwrap();
+    static_context_builder
+        .decimal_format(name.clone(), props('.'))
+        .unwrap();
+    let err = static_context_builder
+        .decimal_format(name, props(' '))
+        .unwrap_err();
+    assert_eq!(err, error::ErrorValue::XTSE1290);
+}
+
+#[test]
+fn test_decimal_format_invalid() {
+    let declare = |props| {
+        StaticContextBuilder::default()
+            .decimal_format(None, props)?
+            .build()
+            .map(|_| ())
+    };
+    // the zero digit has to be a decimal digit with the value zero
+    let zero_digit = |zero_digit| DecimalFormatProps {
+        zero_digit: Some(zero_digit),
+        ..Default::default()
+    };
+    assert_eq!(declare(zero_digit('\u{660}')), Ok(()));
+    assert_eq!(declare(zero_digit('a')), Err(error::ErrorValue::XTSE1295));
+    assert_eq!(
+        declare(zero_digit('\u{661}')),
+        Err(error::ErrorValue::XTSE1295)
+    );
+    // the last code point can't be a zero digit either
+    assert_eq!(
+        declare(zero_digit(char::MAX)),
+        Err(error::ErrorValue::XTSE1295)
+    );
+
+    // the characters used in a picture string have to be distinct
+    assert_eq!(
+        declare(DecimalFormatProps {
+            grouping_separator: Some('.'),
+            ..Default::default()
+        }),
+        Err(error::ErrorValue::XTSE1300)
+    );
+    // this includes the digits of the zero digit's family
+    assert_eq!(
+        declare(DecimalFormatProps {
+            digit: Some('5'),
+            ..Default::default()
+        }),
+        Err(error::ErrorValue::XTSE1300)
+    );
+    // other characters may be the same
+    assert_eq!(
+        declare(DecimalFormatProps {
+            minus_sign: Some('%'),
+            ..Default::default()
+        }),
+        Ok(())
+    );
+
+    // only the merged declarations have to be valid, so a declaration may
+    // clash with a default that a later one overrides
+    let mut static_context_builder = StaticContextBuilder::default();
+    static_context_builder
+        .decimal_format(
+            None,
+            DecimalFormatProps {
+                grouping_separator: Some('.'),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        static_context_builder.build().map(|_| ()),
+        Err(error::ErrorValue::XTSE1300)
+    );
+    static_context_builder
+        .decimal_format(
+            None,
+            DecimalFormatProps {
+                decimal_separator: Some(','),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(static_context_builder.build().map(|_| ()), Ok(()));
+}
+
+#[test]
+fn test_type_name() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents.add_string_without_uri("<root a='1'/>").unwrap();
+    let queries = Queries::default();
+    let q = queries
+        .sequence("(1, xs:dateTime('2024-01-01T00:00:00'), /root, /root/@a, map {}, [], true#0)")?;
+    let sequence = q.execute(&mut documents, doc)?;
+    let type_names = sequence
+        .iter()
+        .map(|item| item.type_name(documents.xot()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        type_names,
+        vec![
+            "xs:integer",
+            "xs:dateTime",
+            "element()",
+            "attribute()",
+            "map(*)",
+            "array(*)",
+            "function(*)"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_virtual_environment_variables() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents.add_string_without_uri("<root/>").unwrap();
+    let queries = Queries::default();
+    let q = queries.sequence(
+        "available-environment-variables(), environment-variable('XEE_TEST'), environment-variable('PATH')",
+    )?;
+
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.context_item(doc.to_item(&documents)?);
+    context_builder.environment_variables(
+        [
+            ("XEE_TEST".to_string(), "controlled".to_string()),
+            ("XEE_OTHER".to_string(), "other".to_string()),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    // the virtual environment takes precedence, so PATH isn't there
+    context_builder.initialize_env();
+    let context = context_builder.build();
+
+    let sequence = q.execute_with_context(&mut documents, &context)?;
+    assert_eq!(
+        string_values(&documents, &sequence)?,
+        vec!["XEE_OTHER", "XEE_TEST", "controlled"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_resource_map() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+    let q = queries.sequence(
+        "doc('http://example.com/a.xml')/root/string(), unparsed-text-lines('http://example.com/a.txt'), collection('http://example.com/c')/root/string(), doc-available('http://example.com/missing.xml')",
+    )?;
+
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.resource_map(
+        [
+            (
+                "http://example.com/a.xml".to_string(),
+                Resource::Xml("<root>document</root>".to_string()),
+            ),
+            (
+                "http://example.com/a.txt".to_string(),
+                Resource::Text("first\r\nsecond\n".to_string()),
+            ),
+            (
+                "http://example.com/c".to_string(),
+                Resource::Collection(vec!["http://example.com/a.xml".to_string()]),
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    let context = context_builder.build();
+
+    let sequence = q.execute_with_context(&mut documents, &context)?;
+    assert_eq!(
+        string_values(&documents, &sequence)?,
+        vec!["document", "first", "second", "document", "false"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_resource_map_text_of_xml() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+    let q = queries.one(
+        "unparsed-text('http://example.com/a.xml') eq '<root/>' and doc('http://example.com/a.xml') is doc('http://example.com/a.xml')",
+        |_, item| Ok(item.try_into_value::<bool>()?),
+    )?;
+
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.resource_map(
+        [(
+            "http://example.com/a.xml".to_string(),
+            Resource::Xml("<root/>".to_string()),
+        )]
+        .into_iter()
+        .collect(),
+    );
+    let context = context_builder.build();
+
+    assert!(q.execute_with_context(&mut documents, &context)?);
+    Ok(())
+}
+
+#[test]
+fn test_resource_map_json_doc() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+    let q = queries.sequence(
+        "json-doc('http://example.com/a.json')?people(2)?name, json-doc('http://example.com/a.json', map { 'duplicates': 'use-last' })?version, json-doc(())",
+    )?;
+
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.resource_map(
+        [(
+            "http://example.com/a.json".to_string(),
+            Resource::Text(
+                r#"{"version": 1, "people": [{"name": "Alice"}, {"name": "Bob"}], "version": 2}"#
+                    .to_string(),
+            ),
+        )]
+        .into_iter()
+        .collect(),
+    );
+    let context = context_builder.build();
+
+    let sequence = q.execute_with_context(&mut documents, &context)?;
+    assert_eq!(string_values(&documents, &sequence)?, vec!["Bob", "2"]);
+
+    // a missing resource is an error
+    let q = queries.sequence("json-doc('http://example.com/missing.json')")?;
+    let err = q
+        .execute_with_context(&mut documents, &context)
+        .unwrap_err();
+    assert_eq!(err.error, error::ErrorValue::FOUT1170);
+    Ok(())
+}
+
+#[test]
+fn test_dynamic_context_focus() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+    let q = queries.many("position(), last()", |_, item| {
+        Ok(item.try_into_value::<IBig>()?)
+    })?;
+
+    let item: Item = "a".into();
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.context_item(item.clone());
+    let context = context_builder.build();
+    assert_eq!(context.context_item(), Some(&item));
+    assert_eq!(context.position(), Ok(1));
+    assert_eq!(context.last(), Ok(1));
+    // these are the values fn:position and fn:last give at the top level
+    assert_eq!(
+        q.execute_with_context(&mut documents, &context)?,
+        vec![ibig!(1), ibig!(1)]
+    );
+
+    // without a context item the focus is absent
+    let context_builder = q.dynamic_context_builder(&documents);
+    let context = context_builder.build();
+    assert_eq!(context.context_item(), None);
+    assert_eq!(context.position(), Err(error::ErrorValue::XPDY0002));
+    assert_eq!(context.last(), Err(error::ErrorValue::XPDY0002));
+    Ok(())
+}
+
+#[test]
+fn test_trace_sink() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri("<root><a>1</a><a>2</a></root>")
+        .unwrap();
+    let queries = Queries::default();
+    let q = queries.sequence("trace(/root/a, 'a') ! trace(xs:integer(.) * 10)")?;
+
+    let traces = Rc::new(RefCell::new(Vec::new()));
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.context_item(doc.to_item(&documents)?);
+    let sink_traces = traces.clone();
+    context_builder.trace_sink(move |label, value, xot| {
+        let value = value
+            .iter()
+            .map(|item| match item {
+                Item::Node(node) => xot.to_string(node).unwrap(),
+                Item::Atomic(atomic) => atomic.string_value(),
+                Item::Function(_) => panic!("unexpected function"),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        sink_traces
+            .borrow_mut()
+            .push(format!("{}: {}", label, value));
+    });
+    let context = context_builder.build();
+
+    // the traced values are returned unchanged
+    let sequence = q.execute_with_context(&mut documents, &context)?;
+    let values = sequence
+        .iter()
+        .map(|item| item.to_atomic()?.try_into())
+        .collect::<Result<Vec<IBig>, _>>()?;
+    assert_eq!(values, vec![ibig!(10), ibig!(20)]);
+
+    assert_eq!(
+        *traces.borrow(),
+        vec![
+            "a: <a>1</a> <a>2</a>".to_string(),
+            ": 10".to_string(),
+            ": 20".to_string()
+        ]
+    );
+    Ok(())
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_trace_tracing_events() -> error::Result<()> {
+    use tracing::field::{Field, Visit};
+    use tracing::span;
+
+    type Event = (tracing::Level, Vec<(String, String)>);
+
+    // a subscriber that records the fields of each event
+    #[derive(Default)]
+    struct Recorder {
+        events: std::sync::Mutex<Vec<Event>>,
+    }
+
+    struct Fields<'a>(&'a mut Vec<(String, String)>);
+
+    impl Visit for Fields<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.push((field.name().to_string(), value.to_string()));
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    impl tracing::Subscriber for &'static Recorder {
+        fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+        fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut fields = Vec::new();
+            event.record(&mut Fields(&mut fields));
+            self.events
+                .lock()
+                .unwrap()
+                .push((*event.metadata().level(), fields));
+        }
+        fn enter(&self, _span: &span::Id) {}
+        fn exit(&self, _span: &span::Id) {}
+    }
+
+    let recorder: &'static Recorder = Box::leak(Box::default());
+
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri("<root><a>1</a></root>")
+        .unwrap();
+    let queries = Queries::default();
+    let q = queries.sequence("trace(/root/a, 'a') ! trace(xs:integer(.) * 10)")?;
+
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.context_item(doc.to_item(&documents)?);
+    let context = context_builder.build();
+    tracing::subscriber::with_default(recorder, || {
+        q.execute_with_context(&mut documents, &context)
+    })?;
+
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.context_item(doc.to_item(&documents)?);
+    context_builder.trace_level(tracing::Level::WARN);
+    let context = context_builder.build();
+    tracing::subscriber::with_default(recorder, || {
+        q.execute_with_context(&mut documents, &context)
+    })?;
+
+    let field = |name: &str, value: &str| (name.to_string(), value.to_string());
+    assert_eq!(
+        *recorder.events.lock().unwrap(),
+        vec![
+            (
+                tracing::Level::DEBUG,
+                vec![field("label", "a"), field("value", "<a>1</a>")]
+            ),
+            (
+                tracing::Level::DEBUG,
+                vec![field("label", ""), field("value", "10")]
+            ),
+            (
+                tracing::Level::WARN,
+                vec![field("label", "a"), field("value", "<a>1</a>")]
+            ),
+            (
+                tracing::Level::WARN,
+                vec![field("label", ""), field("value", "10")]
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_run_to_writer() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri("<root><a>1</a><a>2</a></root>")
+        .unwrap();
+    let queries = Queries::default();
+    let q = queries.sequence("/root/a")?;
+
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.context_item(doc.to_item(&documents)?);
+    let context = context_builder.build();
+    let mut buf = Vec::new();
+    q.program().runnable(&context).run_to_writer(
+        documents.xot_mut(),
+        &mut buf,
+        SerializationParameters::new(),
+    )?;
+
+    // the same output as serializing the result sequence as a string
+    let sequence = q.execute(&mut documents, doc)?;
+    let expected = sequence.serialize(SerializationParameters::new(), documents.xot_mut())?;
+    assert_eq!(String::from_utf8(buf).unwrap(), expected);
+    Ok(())
+}
+
+// a writer that fails to write, but keeps track of whether it was flushed
+#[derive(Default)]
+struct BrokenWriter {
+    flushed: bool,
+}
+
+impl std::io::Write for BrokenWriter {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::BrokenPipe,
+            "broken",
+        ))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flushed = true;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_run_to_writer_errors() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+
+    // writing fails
+    let q = queries.sequence("'a'")?;
+    let context_builder = q.dynamic_context_builder(&documents);
+    let context = context_builder.build();
+    let mut writer = BrokenWriter::default();
+    let err = q
+        .program()
+        .runnable(&context)
+        .run_to_writer(
+            documents.xot_mut(),
+            &mut writer,
+            SerializationParameters::new(),
+        )
+        .unwrap_err();
+    assert_eq!(err.error.error_code(), Some(error::ErrorCode::Io));
+    // the error of the writer is kept as the source
+    let source = std::error::Error::source(&err)
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .unwrap();
+    assert_eq!(source.kind(), std::io::ErrorKind::BrokenPipe);
+    assert_eq!(source.to_string(), "broken");
+    assert!(writer.flushed);
+
+    // serialization fails; a function cannot be serialized as XML
+    let q = queries.sequence("('a', function() { 1 })")?;
+    let context_builder = q.dynamic_context_builder(&documents);
+    let context = context_builder.build();
+    let mut writer = BrokenWriter::default();
+    let err = q
+        .program()
+        .runnable(&context)
+        .run_to_writer(
+            documents.xot_mut(),
+            &mut writer,
+            SerializationParameters::new(),
+        )
+        .unwrap_err();
+    assert_eq!(err.error, error::ErrorValue::SENR0001);
+    assert!(writer.flushed);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "xslt")]
+fn test_xslt_transform_twice() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc1 = documents
+        .add_string_without_uri("<list><item>a</item><item>b</item></list>")
+        .unwrap();
+    let doc2 = documents
+        .add_string_without_uri("<list><item>c</item></list>")
+        .unwrap();
+    let queries = Queries::default();
+    let stylesheet = queries.xslt(
+        r#"<xsl:transform version="3.0" xmlns:xsl="http://www.w3.org/1999/XSL/Transform">
+  <xsl:template match="/list">
+    <ul><xsl:apply-templates select="item"/></ul>
+  </xsl:template>
+  <xsl:template match="item">
+    <li><xsl:value-of select="."/></li>
+  </xsl:template>
+</xsl:transform>"#,
+    )?;
+
+    let mut params = SerializationParameters::new();
+    params.omit_xml_declaration = true;
+    let result = stylesheet.transform(&mut documents, doc1)?;
+    assert_eq!(
+        result.serialize_to_string(&mut documents, &params)?,
+        "<ul><li>a</li><li>b</li></ul>"
+    );
+    let result = stylesheet.transform(&mut documents, doc2)?;
+    assert_eq!(
+        result.serialize_to_string(&mut documents, &params)?,
+        "<ul><li>c</li></ul>"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "xslt")]
+fn test_xslt_transform_with_params() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri("<list><item>a</item><item>b</item></list>")
+        .unwrap();
+    let queries = Queries::default();
+    let stylesheet = queries.xslt(
+        r#"<xsl:transform version="3.0" xmlns:xsl="http://www.w3.org/1999/XSL/Transform">
+  <xsl:param name="separator" select="','"/>
+  <xsl:template match="/list">
+    <xsl:value-of select="item" separator="{$separator}"/>
+  </xsl:template>
+</xsl:transform>"#,
+    )?;
+
+    let transform = |documents: &mut Documents, params: Variables| -> error::Result<String> {
+        let result = stylesheet.transform_with_params(documents, doc, params)?;
+        Ok(result.string_value(documents.xot())?)
+    };
+    // without the parameter we get its default value
+    assert_eq!(transform(&mut documents, Variables::default())?, "a,b");
+    let params = [(OwnedName::name("separator"), Item::from("|").into())]
+        .into_iter()
+        .collect();
+    assert_eq!(transform(&mut documents, params)?, "a|b");
+    Ok(())
+}
+
+#[test]
+fn test_serialize_chunks() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri(
+            r#"<root xmlns:p="http://example.com/p"><a>1</a><p:b>2 &amp; 3</p:b></root>"#,
+        )
+        .unwrap();
+    let queries = Queries::default();
+    let q = queries.sequence("(/root/a, 'x', 'y', /root/*:b)")?;
+    let sequence = q.execute(&mut documents, doc)?;
+
+    let params = SerializationParameters::new();
+    let chunks = sequence
+        .serialize_chunks(&mut documents, &params)
+        .collect::<error::Result<Vec<_>>>()?;
+    // a chunk per top-level node; the adjacent strings are a single text node
+    assert_eq!(
+        chunks,
+        vec![
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<a>1</a>"#,
+            "x y",
+            r#"<p:b xmlns:p="http://example.com/p">2 &amp; 3</p:b>"#
+        ]
+    );
+    let expected = sequence.serialize_to_string(&mut documents, &params)?;
+    assert_eq!(chunks.concat(), expected);
+
+    // output that is produced as a single chunk
+    let mut params = SerializationParameters::new();
+    params.indent = true;
+    let chunks = sequence
+        .serialize_chunks(&mut documents, &params)
+        .collect::<error::Result<Vec<_>>>()?;
+    assert_eq!(chunks.len(), 1);
+    let expected = sequence.serialize_to_string(&mut documents, &params)?;
+    assert_eq!(chunks.concat(), expected);
+
+    // the empty sequence still has a declaration
+    let empty = Sequence::default();
+    let chunks = empty
+        .serialize_chunks(&mut documents, &SerializationParameters::new())
+        .collect::<error::Result<Vec<_>>>()?;
+    assert_eq!(
+        chunks,
+        vec![
+            r#"<?xml version="1.0" encoding="utf-8"?>
+"#
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_serialize_chunks_error() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents.add_string_without_uri("<root/>").unwrap();
+    let queries = Queries::default();
+    // a function cannot be serialized as XML
+    let q = queries.sequence("('a', function() { 1 })")?;
+    let sequence = q.execute(&mut documents, doc)?;
+    let mut chunks = sequence.serialize_chunks(&mut documents, &SerializationParameters::new());
+    let err = chunks.next().unwrap().unwrap_err();
+    assert_eq!(err.error, error::ErrorValue::SENR0001);
+    assert!(chunks.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn test_error_code() -> error::Result<()> {
+    use xot::xmlname::NameStrInfo;
+
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+
+    let q = queries.one("xs:integer('a')", |_, item| {
+        Ok(item.try_into_value::<i64>()?)
+    })?;
+    let err = q.execute(&mut documents, &1i64.into()).unwrap_err();
+    match err.code() {
+        Some(error::ErrorCode::FORG0001) => {}
+        code => panic!("unexpected error code {:?}", code),
+    }
+    let qname = err.code_qname();
+    assert_eq!(qname.prefix(), "err");
+    assert_eq!(qname.local_name(), "FORG0001");
+    assert_eq!(qname.namespace(), "http://www.w3.org/2005/xqt-errors");
+
+    // an application error has no known error code, but it does have a
+    // qualified name
+    let q = queries.one(
+        "error(QName('http://example.com', 'my:oops'))",
+        |_, item| Ok(item.try_into_value::<i64>()?),
+    )?;
+    let err = q.execute(&mut documents, &1i64.into()).unwrap_err();
+    assert_eq!(err.code(), None);
+    let qname = err.code_qname();
+    assert_eq!(qname.local_name(), "oops");
+    assert_eq!(qname.namespace(), "http://example.com");
+    Ok(())
+}
+
+#[test]
+fn test_error_object() -> error::Result<()> {
+    use xot::xmlname::NameStrInfo;
+
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string("http://example.com".try_into().unwrap(), "<root/>")
+        .unwrap();
+    let queries = Queries::default();
+
+    let q = queries.one(
+        "error(QName('http://example.com', 'my:oops'), 'Something went wrong', (42, /root, map {}))",
+        |_, item| Ok(item.try_into_value::<i64>()?),
+    )?;
+    let err = q.execute(&mut documents, doc).unwrap_err();
+    let application_error = err.application_error().unwrap();
+    assert_eq!(application_error.qname().local_name(), "oops");
+    assert_eq!(application_error.qname().namespace(), "http://example.com");
+    assert_eq!(application_error.description(), "Something went wrong");
+    let root = documents.document_element(doc).unwrap();
+    assert_eq!(
+        application_error.error_object(),
+        &[
+            error::ErrorObjectItem::Atomic {
+                value: "42".to_string(),
+                schema_type: xee_schema_type::Xs::Integer,
+            },
+            error::ErrorObjectItem::Node(root),
+            error::ErrorObjectItem::Function,
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_error_without_code() -> error::Result<()> {
+    use xot::xmlname::NameStrInfo;
+
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+
+    let q = queries.one("error()", |_, item| Ok(item.try_into_value::<i64>()?))?;
+    let err = q.execute(&mut documents, &1i64.into()).unwrap_err();
+    assert_eq!(err.code(), Some(error::ErrorCode::FOER0000));
+    assert!(err.application_error().is_none());
+
+    // without a code but with a description, the code is still FOER0000
+    let q = queries.one("error((), 'Something went wrong')", |_, item| {
+        Ok(item.try_into_value::<i64>()?)
+    })?;
+    let err = q.execute(&mut documents, &1i64.into()).unwrap_err();
+    assert_eq!(err.code(), Some(error::ErrorCode::FOER0000));
+    assert_eq!(err.code_qname().local_name(), "FOER0000");
+    let application_error = err.application_error().unwrap();
+    assert_eq!(application_error.description(), "Something went wrong");
+    assert!(application_error.error_object().is_empty());
+    Ok(())
+}
+
+#[cfg(feature = "html")]
+#[test]
+fn test_html_document() -> error::Result<()> {
+    let mut documents = Documents::new();
+    // unclosed tags, no tbody and no html/head/body: the HTML5 parser
+    // repairs all of this
+    let doc = documents
+        .add_html_string(
+            "http://example.com".try_into().unwrap(),
+            "<!DOCTYPE html><title>Test</title><p>One<p>Two<table><tr><td>cell</table>",
+        )
+        .unwrap();
+
+    let mut static_context_builder = StaticContextBuilder::default();
+    static_context_builder.default_element_namespace("http://www.w3.org/1999/xhtml");
+    let queries = Queries::new(static_context_builder);
+
+    let q = queries.many("/html/body/p/string()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    let r = q.execute(&mut documents, doc)?;
+    assert_eq!(r, vec!["One".to_string(), "Two".to_string()]);
+
+    let q = queries.one("//td/ancestor::*[2]/local-name()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    let r = q.execute(&mut documents, doc)?;
+    assert_eq!(r, "tbody");
+
+    let q = queries.one("/html/head/title/following::p[1]/string()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    let r = q.execute(&mut documents, doc)?;
+    assert_eq!(r, "One");
+    Ok(())
+}
+
+#[test]
+fn test_build_element() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri(
+            r#"<root xmlns:p="http://example.com/p"><a p:x="1">A</a><b>B</b></root>"#,
+        )
+        .unwrap();
+    let queries = Queries::default();
+    let q = queries.sequence("/root/a/@*, /root/*, 1, 2.5, 'three'")?;
+    let children = q.execute(&mut documents, doc)?;
+
+    let name = xot::xmlname::OwnedName::new(
+        "result".to_string(),
+        "http://example.com/r".to_string(),
+        "r".to_string(),
+    );
+    let element = documents.build_element(&name, children.iter())?;
+    assert_eq!(
+        documents.xot().to_string(element).unwrap(),
+        r#"<r:result xmlns:r="http://example.com/r" xmlns:p="http://example.com/p" p:x="1"><a xmlns:p="http://example.com/p" p:x="1">A</a><b>B</b>1 2.5 three</r:result>"#
+    );
+    // the children were copied; the original document is untouched
+    assert_eq!(
+        documents
+            .xot()
+            .to_string(documents.document_node(doc).unwrap())
+            .unwrap(),
+        r#"<root xmlns:p="http://example.com/p"><a p:x="1">A</a><b>B</b></root>"#
+    );
+
+    // the new element can be queried like any other node
+    let q = queries.one("count(*)", |_, item| Ok(item.try_into_value::<IBig>()?))?;
+    assert_eq!(q.execute(&mut documents, element)?, ibig!(2));
+    Ok(())
+}
+
+#[test]
+fn test_build_element_function_item() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents.add_string_without_uri("<root/>").unwrap();
+    let queries = Queries::default();
+    let q = queries.sequence("fn:count#1")?;
+    let children = q.execute(&mut documents, doc)?;
+    let result = documents.build_element(&xot::xmlname::OwnedName::name("result"), children.iter());
+    assert_eq!(result.unwrap_err().code(), Some(error::ErrorCode::XTDE0450));
+    Ok(())
+}
+
+#[test]
+fn test_build_element_attribute_after_child() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri(r#"<root><a x="1"/></root>"#)
+        .unwrap();
+    let queries = Queries::default();
+    let q = queries.sequence("/root/a, /root/a/@x")?;
+    let children = q.execute(&mut documents, doc)?;
+    let result = documents.build_element(&xot::xmlname::OwnedName::name("result"), children.iter());
+    assert_eq!(result.unwrap_err().code(), Some(error::ErrorCode::XTDE0410));
+    Ok(())
+}
+
+#[test]
+fn test_clone_document() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com/doc".try_into().unwrap(),
+            "<root><a/></root>",
+        )
+        .unwrap();
+    let clone = documents.clone_document(doc).unwrap();
+    let doc_node = documents.document_node(doc).unwrap();
+    let clone_node = documents.document_node(clone).unwrap();
+    assert_ne!(doc_node, clone_node);
+    assert_eq!(
+        documents.xot().to_string(clone_node).unwrap(),
+        "<root><a/></root>"
+    );
+
+    let queries = Queries::default();
+    // the clone has the same base URI, but fn:doc gives back the original
+    let q = queries.one(
+        "string(base-uri(/)) || ' ' || empty(document-uri(/)) || ' ' || (/root/a is doc('http://example.com/doc')/root/a)",
+        |_, item| Ok(item.try_into_value::<String>()?),
+    )?;
+    assert_eq!(
+        q.execute(&mut documents, doc)?,
+        "http://example.com/doc false true"
+    );
+    assert_eq!(
+        q.execute(&mut documents, clone)?,
+        "http://example.com/doc true false"
+    );
+
+    // changing the clone leaves the original untouched
+    let xot = documents.xot_mut();
+    let root = xot.document_element(clone_node).unwrap();
+    let b = xot.add_name("b");
+    let b = xot.new_element(b);
+    xot.append(root, b).unwrap();
+    let q = queries.one("count(/root/*)", |_, item| {
+        Ok(item.try_into_value::<IBig>()?)
+    })?;
+    assert_eq!(q.execute(&mut documents, doc)?, ibig!(1));
+    assert_eq!(q.execute(&mut documents, clone)?, ibig!(2));
+    Ok(())
+}
+
+#[test]
+fn test_clone_document_of_clone() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string(
+            "http://example.com/doc".try_into().unwrap(),
+            "<root><a/></root>",
+        )
+        .unwrap();
+    let clone = documents.clone_document(doc).unwrap();
+    let clone_of_clone = documents.clone_document(clone).unwrap();
+
+    let queries = Queries::default();
+    // the base URI carries over to clones of clones too
+    let q = queries.one(
+        "string(base-uri(/)) || ' ' || empty(document-uri(/))",
+        |_, item| Ok(item.try_into_value::<String>()?),
+    )?;
+    assert_eq!(
+        q.execute(&mut documents, clone_of_clone)?,
+        "http://example.com/doc true"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_node_at_path_round_trip() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri(
+            r#"<root xmlns:ns="http://example.com/a/b"><a id="1">text<!--one--><b/>more<!--two--></a><?pi data?><ns:a ns:id="2"/><a><?pi more?><ns:a/></a></root>"#,
+        )
+        .unwrap();
+    let queries = Queries::default();
+    let nodes = queries.many("/, //node(), //@*", |_, item| Ok(item.to_node()?))?;
+    let paths = queries.many("(/, //node(), //@*) ! path(.)", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    let nodes = nodes.execute(&mut documents, doc)?;
+    let paths = paths.execute(&mut documents, doc)?;
+    assert_eq!(nodes.len(), paths.len());
+    for (node, path) in nodes.iter().zip(paths.iter()) {
+        assert_eq!(documents.node_at_path(doc, path), Some(*node), "{}", path);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_node_at_path() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri(r#"<root><a/><a id="x">text</a></root>"#)
+        .unwrap();
+    let queries = Queries::default();
+    let q = queries.one("/root/a[2]/@id", |_, item| Ok(item.to_node()?))?;
+    let id = q.execute(&mut documents, doc)?;
+
+    assert_eq!(
+        documents.node_at_path(doc, "/Q{}root[1]/Q{}a[2]/@id"),
+        Some(id)
+    );
+    // there is no third a element
+    assert_eq!(documents.node_at_path(doc, "/Q{}root[1]/Q{}a[3]"), None);
+    // positions start at 1
+    assert_eq!(documents.node_at_path(doc, "/Q{}root[1]/Q{}a[0]"), None);
+    // not in the format fn:path produces
+    assert_eq!(documents.node_at_path(doc, "/root/a"), None);
+    assert_eq!(documents.node_at_path(doc, "Q{}root[1]"), None);
+    Ok(())
+}
+
+fn string_values(documents: &Documents, sequence: &Sequence) -> error::Result<Vec<String>> {
+    Ok(sequence
+        .iter()
+        .map(|item| item.string_value(documents.xot()))
+        .collect::<error::ValueResult<Vec<_>>>()?)
+}
+
+#[test]
+fn test_union_document_order() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri("<root><b>1</b><a>2</a><b>3</b><a>4</a></root>")
+        .unwrap();
+    let queries = Queries::default();
+    // duplicates are removed and the result is sorted in document order,
+    // no matter the order of the operands
+    let q = queries.sequence("//b | //a | //a")?;
+    let sequence = q.execute(&mut documents, doc)?;
+    assert_eq!(
+        string_values(&documents, &sequence)?,
+        vec!["1", "2", "3", "4"]
+    );
+    let q = queries.sequence("(//a, //b, //a)/.")?;
+    let sequence = q.execute(&mut documents, doc)?;
+    assert_eq!(
+        string_values(&documents, &sequence)?,
+        vec!["1", "2", "3", "4"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_sequence_unordered() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri("<root><b>1</b><a>2</a><b>3</b><a>4</a></root>")
+        .unwrap();
+    let queries = Queries::default();
+    // duplicates are still removed, but the nodes aren't sorted
+    let q = queries.sequence_unordered("//b | //a | //a")?;
+    let sequence = q.execute(&mut documents, doc)?;
+    let mut sequence = string_values(&documents, &sequence)?;
+    assert_eq!(sequence.len(), 4);
+    sequence.sort();
+    assert_eq!(sequence, vec!["1", "2", "3", "4"]);
+
+    let q = queries.sequence_unordered("(//a, //b, //a)/. except //b[1]")?;
+    let sequence = q.execute(&mut documents, doc)?;
+    let mut sequence = string_values(&documents, &sequence)?;
+    sequence.sort();
+    assert_eq!(sequence, vec!["2", "3", "4"]);
+
+    // other expressions keep their order
+    let q = queries.sequence_unordered("(//a, //b)")?;
+    let sequence = q.execute(&mut documents, doc)?;
+    assert_eq!(
+        string_values(&documents, &sequence)?,
+        vec!["2", "4", "1", "3"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_max_stack_depth_runaway_recursion() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents.add_string_without_uri("<root/>").unwrap();
+    let queries = Queries::default();
+    // directly, and through a function that calls back into the interpreter
+    for xpath in [
+        "let $f := function($f) { $f($f) } return $f($f)",
+        "let $f := function($f) { for-each(1, function($x) { $f($f) }) } return $f($f)",
+    ] {
+        let q = queries.sequence(xpath)?;
+        let err = q.execute(&mut documents, doc).unwrap_err();
+        assert_eq!(err.code(), Some(error::ErrorCode::StackOverflow));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_max_stack_depth() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+    let q = queries.sequence(
+        "let $f := function($f, $n) { if ($n = 0) then 0 else 1 + $f($f, $n - 1) } return $f($f, 50)",
+    )?;
+
+    let context_builder = q.dynamic_context_builder(&documents);
+    let context = context_builder.build();
+    let result = q.execute_with_context(&mut documents, &context)?;
+    assert_eq!(result.iter().next().unwrap().to_atomic()?, ibig!(50).into());
+
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.max_stack_depth(10);
+    let context = context_builder.build();
+    let err = q
+        .execute_with_context(&mut documents, &context)
+        .unwrap_err();
+    assert_eq!(err.code(), Some(error::ErrorCode::StackOverflow));
+    Ok(())
+}
+
+#[test]
+fn test_deadline() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+    let q = queries.sequence("(1 to 1000000000) ! (if (. = 0) then . else ())")?;
+
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.deadline(Instant::now() + Duration::from_millis(50));
+    let context = context_builder.build();
+    let start = Instant::now();
+    let err = q
+        .execute_with_context(&mut documents, &context)
+        .unwrap_err();
+    assert_eq!(err.code(), Some(error::ErrorCode::Cancelled));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "xslt")]
+fn test_deadline_transform() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+    // the deadline also applies to the transformation
+    let q = queries.sequence(
+        r#"transform(map {
+            'stylesheet-text': '<xsl:stylesheet xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3.0"><xsl:template match="/"><xsl:sequence select="(1 to 1000000000) ! (if (. = 0) then . else ())"/></xsl:template></xsl:stylesheet>',
+            'source-node': parse-xml('<doc/>'),
+            'delivery-format': 'raw' })"#,
+    )?;
+
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.deadline(Instant::now() + Duration::from_millis(50));
+    let context = context_builder.build();
+    let start = Instant::now();
+    let err = q
+        .execute_with_context(&mut documents, &context)
+        .unwrap_err();
+    assert_eq!(err.code(), Some(error::ErrorCode::Cancelled));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    Ok(())
+}
+
+#[test]
+fn test_cancellation_flag() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let queries = Queries::default();
+    let q = queries.sequence("(1 to 1000000000) ! (if (. = 0) then . else ())")?;
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let mut context_builder = q.dynamic_context_builder(&documents);
+    context_builder.cancellation_flag(cancelled.clone());
+    let context = context_builder.build();
+
+    let start = Instant::now();
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        cancelled.store(true, Ordering::Relaxed);
+    });
+    let err = q
+        .execute_with_context(&mut documents, &context)
+        .unwrap_err();
+    canceller.join().unwrap();
+    assert_eq!(err.code(), Some(error::ErrorCode::Cancelled));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    Ok(())
+}
+
+#[test]
+fn test_documents_parse_error_location() {
+    let mut documents = Documents::new();
+    let err = documents
+        .add_string(
+            "http://example.com".try_into().unwrap(),
+            "<root>\n  <a>text</b>\n</root>",
+        )
+        .unwrap_err();
+    assert_eq!(err.location(), Some((2, 12)));
+    match err {
+        error::DocumentsError::Parse(e) => {
+            assert_eq!(e.span(), 18..19);
+            assert_eq!(e.snippet(), "  <a>text</b>");
+        }
+        _ => panic!("expected parse error"),
+    }
+}
+
+#[test]
+fn test_documents_duplicate_uri_has_no_location() {
+    let mut documents = Documents::new();
+    documents
+        .add_string("http://example.com".try_into().unwrap(), "<root/>")
+        .unwrap();
+    let err = documents
+        .add_string("http://example.com".try_into().unwrap(), "<root/>")
+        .unwrap_err();
+    assert_eq!(err.location(), None);
+}
+
+fn range_sequence(xpath: &str) -> error::Result<Sequence> {
+    let mut documents = Documents::new();
+    let doc = documents.add_string_without_uri("<root/>").unwrap();
+    let queries = Queries::default();
+    queries.sequence(xpath)?.execute(&mut documents, doc)
+}
+
+fn integers(sequence: &Sequence) -> Vec<IBig> {
+    sequence
+        .iter()
+        .map(|item| item.try_into_value::<IBig>().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_iter_chunks() -> error::Result<()> {
+    let sequence = range_sequence("1 to 7")?;
+    let chunks = sequence.iter().chunks(3).collect::<Vec<_>>();
+    assert_eq!(
+        chunks.iter().map(integers).collect::<Vec<_>>(),
+        vec![
+            vec![ibig!(1), ibig!(2), ibig!(3)],
+            vec![ibig!(4), ibig!(5), ibig!(6)],
+            vec![ibig!(7)],
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_iter_chunks_lazy() -> error::Result<()> {
+    // a range this size would take far too much memory to materialize
+    let sequence = range_sequence("1 to 4000000000")?;
+    let mut chunks = sequence.iter().chunks(2);
+    assert_eq!(chunks.size_hint(), (2000000000, Some(2000000000)));
+    let first = chunks.next().unwrap();
+    let second = chunks.next().unwrap();
+    assert_eq!(integers(&first), vec![ibig!(1), ibig!(2)]);
+    assert_eq!(integers(&second), vec![ibig!(3), ibig!(4)]);
+    Ok(())
+}
+
+#[test]
+fn test_iter_windows() -> error::Result<()> {
+    let sequence = range_sequence("1 to 4")?;
+    let windows = sequence.iter().windows(3).collect::<Vec<_>>();
+    assert_eq!(
+        windows.iter().map(integers).collect::<Vec<_>>(),
+        vec![
+            vec![ibig!(1), ibig!(2), ibig!(3)],
+            vec![ibig!(2), ibig!(3), ibig!(4)],
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_iter_windows_too_short() -> error::Result<()> {
+    let sequence = range_sequence("1 to 2")?;
+    let mut windows = sequence.iter().windows(3);
+    assert_eq!(windows.size_hint(), (0, Some(0)));
+    assert!(windows.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn test_iter_windows_lazy() -> error::Result<()> {
+    let sequence = range_sequence("1 to 4000000000")?;
+    let mut windows = sequence.iter().windows(2);
+    windows.next().unwrap();
+    let second = windows.next().unwrap();
+    assert_eq!(integers(&second), vec![ibig!(2), ibig!(3)]);
+    Ok(())
+}
+
+#[test]
+fn test_iter_map_items_lazy() -> error::Result<()> {
+    let sequence = range_sequence("1 to 4000000000")?;
+    let doubled = sequence
+        .iter()
+        .map_items(|item| {
+            let i: IBig = item.try_into_value()?;
+            Ok(Item::from(i * 2))
+        })
+        .take(3)
+        .collect::<error::Result<Vec<_>>>()?;
+    assert_eq!(
+        integers(&doubled.into()),
+        vec![ibig!(2), ibig!(4), ibig!(6)]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_iter_map_items_stops_at_error() -> error::Result<()> {
+    let sequence = range_sequence("(1, 'a', 3)")?;
+    let results = sequence
+        .iter()
+        .map_items(|item| {
+            let i: IBig = item.try_into_value()?;
+            Ok(Item::from(i))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+    Ok(())
+}
+
+#[test]
+fn test_id_attributes_configured() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let mut id_attributes = IdAttributes::empty();
+    id_attributes
+        .add_id(OwnedName::name("key"))
+        .add_idref(OwnedName::name("refs"));
+    documents.set_id_attributes(id_attributes);
+    let doc = documents
+        .add_string_without_uri(
+            r#"<doc><a key="k1" id="i1"/><b xml:id="k2" refs="k2 k1 k2"/><c refs="k1"/></doc>"#,
+        )
+        .unwrap();
+
+    let queries = Queries::default();
+    let ids = queries.many("id('k1 k2 i1') ! local-name()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    assert_eq!(ids.execute(&mut documents, doc)?, vec!["a", "b"]);
+
+    let idrefs = queries.many("idref('k1') ! ../local-name()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    assert_eq!(idrefs.execute(&mut documents, doc)?, vec!["b", "c"]);
+
+    let idrefs = queries.many("idref(('k2', 'k1')) ! string()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    assert_eq!(idrefs.execute(&mut documents, doc)?, vec!["k2 k1 k2", "k1"]);
+    Ok(())
+}
+
+#[test]
+fn test_id_after_mutation() -> error::Result<()> {
+    let mut documents = Documents::new();
+    let doc = documents
+        .add_string_without_uri(r#"<doc><a id="a"/></doc>"#)
+        .unwrap();
+    let queries = Queries::default();
+    let ids = queries.many("id(('a', 'b')) ! local-name()", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    assert_eq!(ids.execute(&mut documents, doc)?, vec!["a"]);
+
+    // the ids are indexed again once the document has changed
+    let root = documents.document_node(doc).unwrap();
+    let xot = documents.xot_mut();
+    let doc_element = xot.document_element(root).unwrap();
+    let b = xot.add_name("b");
+    let b = xot.new_element(b);
+    let id = xot.add_name("id");
+    xot.attributes_mut(b).insert(id, "b".to_string());
+    xot.append(doc_element, b).unwrap();
+    assert_eq!(ids.execute(&mut documents, doc)?, vec!["a", "b"]);
+
+    // the Xot arena can be changed while the documents are borrowed
+    let documents_ref = documents.documents().clone();
+    let handle = documents_ref
+        .borrow_mut()
+        .add_string(documents.xot_mut(), None, r#"<doc><c id="c"/></doc>"#)
+        .unwrap();
+    assert_eq!(ids.execute(&mut documents, handle)?, Vec::<String>::new());
+    Ok(())
+}
+
+// each member of a JSON array serialized as JSON again, so we can see what
+// was parsed
+fn json_array_members(json: &str) -> error::Result<Vec<String>> {
+    let queries = Queries::default();
+    let q = queries.one("serialize(., map { 'method': 'json' })", |_, item| {
+        Ok(item.try_into_value::<String>()?)
+    })?;
+    let mut documents = Documents::new();
+    let mut members = Vec::new();
+    parse_json_array_members(json.as_bytes(), |member| {
+        members.push(match member {
+            Some(member) => q.execute(&mut documents, &member)?,
+            None => "null".to_string(),
+        });
+        Ok(())
+    })?;
+    Ok(members)
+}
+
+#[test]
+fn test_parse_json_array_members() -> error::Result<()> {
+    assert_eq!(
+        json_array_members(r#" [{"a": 1}, "b", null, true, [2, [3]]] "#)?,
+        vec![r#"{"a":1}"#, r#""b""#, "null", "true", "[2,[3]]"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_json_array_members_empty() -> error::Result<()> {
+    assert!(json_array_members("[]")?.is_empty());
+    assert!(json_array_members("[ \n ]")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_parse_json_array_members_strings() -> error::Result<()> {
+    // commas, brackets and escaped quotes in strings don't end a member
+    assert_eq!(
+        json_array_members(r#"["a,b", {"c]": "\"}"}]"#)?,
+        vec![r#""a,b""#, r#"{"c]":"\"}"}"#]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_json_array_members_errors() {
+    for json in ["{}", "1", "[1, 2", "[1,]", "[1] 2", r#"["a]"#, "[{]}"] {
+        let err = json_array_members(json).unwrap_err();
+        assert_eq!(err.error, error::ErrorValue::FOJS0001, "{}", json);
+    }
+}
+
+#[test]
+fn test_parse_json_array_members_callback_error() {
+    let mut count = 0;
+    let err = parse_json_array_members("[1, 2, 3]".as_bytes(), |_| {
+        count += 1;
+        if count == 2 {
+            Err(error::ErrorValue::FOER0000.into())
+        } else {
+            Ok(())
+        }
+    })
+    .unwrap_err();
+    assert_eq!(err.error, error::ErrorValue::FOER0000);
+    assert_eq!(count, 2);
+}
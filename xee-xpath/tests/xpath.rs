@@ -25,6 +25,49 @@ fn test_arith_empty_sequence_right() {
     assert_debug_snapshot!(run("1 + ()"));
 }
 
+#[test]
+fn test_idiv_by_zero() {
+    assert_debug_snapshot!(run("5 idiv 0"));
+}
+
+#[test]
+fn test_idiv_decimal_by_zero() {
+    // dividing by zero to get infinity doesn't work for decimals either
+    assert_debug_snapshot!(run("(1 div 0.0) idiv 1"));
+}
+
+#[test]
+fn test_idiv_infinity() {
+    assert_debug_snapshot!(run("(1 div 0e0) idiv 1"));
+}
+
+#[test]
+fn test_idiv_nan() {
+    assert_debug_snapshot!(run("5 idiv xs:double('NaN')"));
+}
+
+#[test]
+fn test_idiv_by_infinity() {
+    assert_debug_snapshot!(run("5 idiv xs:double('INF')"));
+}
+
+#[test]
+fn test_idiv_promotes_integer_to_double() {
+    assert_debug_snapshot!(run("10 idiv 2.5e0"));
+}
+
+#[test]
+fn test_idiv_large_double() {
+    assert_debug_snapshot!(run("1e40 idiv 1"));
+}
+
+#[test]
+fn test_multiply_large_integers() {
+    assert_debug_snapshot!(run(
+        "99999999999999999999999999999999 * 99999999999999999999999999999999"
+    ));
+}
+
 #[test]
 fn test_comma() {
     assert_debug_snapshot!(run("1, 2"));
@@ -586,6 +629,27 @@ fn test_fn_root_absent() {
     assert_debug_snapshot!(run("fn:root()"));
 }
 
+#[test]
+fn test_fn_has_children() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc x="1"><leaf/><text>t</text></doc>"#,
+        "(has-children(doc/leaf), has-children(doc/text), has-children(doc/@x), has-children(()))"
+    ));
+}
+
+#[test]
+fn test_fn_has_children_context() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><leaf/><text>t</text></doc>"#,
+        "doc/* ! has-children()"
+    ));
+}
+
+#[test]
+fn test_fn_has_children_non_node() {
+    assert_debug_snapshot!(run("has-children(1)"));
+}
+
 #[test]
 fn test_fn_root_implicit() {
     assert_debug_snapshot!(run_xml(
@@ -649,6 +713,62 @@ fn test_string_element_node() {
     assert_debug_snapshot!(run_xml(r#"<doc><a>A</a><b>B</b></doc>"#, "string(doc/a)"));
 }
 
+#[test]
+fn test_string_element_descendants() {
+    // comments and processing instructions don't contribute to the string
+    // value
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><a>A<!--comment--><b>B</b><?pi data?>C</a></doc>"#,
+        "string(doc/a)"
+    ));
+}
+
+#[test]
+fn test_string_root_document_node() {
+    assert_debug_snapshot!(run_xml(
+        r#"<!--comment--><doc><a>A</a>B</doc>"#,
+        "string(/)"
+    ));
+}
+
+#[test]
+fn test_string_attribute_node() {
+    assert_debug_snapshot!(run_xml(r#"<doc a="A"/>"#, "string(doc/@a)"));
+}
+
+#[test]
+fn test_string_comment_node() {
+    assert_debug_snapshot!(run_xml(r#"<doc><!-- C --></doc>"#, "string(doc/comment())"));
+}
+
+#[test]
+fn test_string_processing_instruction_node() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><?target data here?></doc>"#,
+        "string(doc/processing-instruction())"
+    ));
+}
+
+#[test]
+fn test_string_context_item() {
+    assert_debug_snapshot!(run_xml(r#"<doc><a>A</a><b>B</b></doc>"#, "doc/*/string()"));
+}
+
+#[test]
+fn test_string_absent_context_item() {
+    assert_debug_snapshot!(run("string()"));
+}
+
+#[test]
+fn test_string_function_item() {
+    assert_debug_snapshot!(run("string(abs#1)"));
+}
+
+#[test]
+fn test_string_map() {
+    assert_debug_snapshot!(run("string(map { 'a': 1 })"));
+}
+
 #[test]
 fn test_string_integer() {
     assert_debug_snapshot!(run("fn:string(1)"));
@@ -695,6 +815,50 @@ fn test_atomize_xml_attribute_missing() {
     assert_debug_snapshot!(run_xml(r#"<doc><a/></doc>"#, "doc/a/@f eq 'FOO'",));
 }
 
+#[test]
+fn test_data_array() {
+    assert_debug_snapshot!(run("data([1, (2, 3), 4])"));
+}
+
+#[test]
+fn test_data_map() {
+    assert_debug_snapshot!(run("data(map { 'a': 1 })"));
+}
+
+#[test]
+fn test_data_element_untyped() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><a>A</a></doc>"#,
+        "data(doc/a) instance of xs:untypedAtomic",
+    ));
+}
+
+#[test]
+fn test_data_comment_string() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><!--A--></doc>"#,
+        "data(doc/comment()) instance of xs:string",
+    ));
+}
+
+#[test]
+fn test_nilled_true() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><a xsi:nil="true"/></doc>"#,
+        "nilled(doc/a)",
+    ));
+}
+
+#[test]
+fn test_nilled_false() {
+    assert_debug_snapshot!(run_xml(r#"<doc><a/></doc>"#, "doc/a/nilled()",));
+}
+
+#[test]
+fn test_nilled_attribute() {
+    assert_debug_snapshot!(run_xml(r#"<doc><a f="F"/></doc>"#, "nilled(doc/a/@f)",));
+}
+
 #[test]
 fn test_attribute_predicate() -> error::Result<()> {
     assert_nodes(
@@ -780,6 +944,43 @@ fn test_generate_id() {
     assert_debug_snapshot!(run_xml(r#"<doc><a/><b/><c/></doc>"#, "generate-id(doc/a)",));
 }
 
+#[test]
+fn test_generate_id_stable() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><a/><b/><c/></doc>"#,
+        "generate-id(doc/a) eq generate-id(doc/b/preceding-sibling::a)",
+    ));
+}
+
+#[test]
+fn test_generate_id_distinct() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc a="1"><a/><b/><c/>text</doc>"#,
+        "count(distinct-values((//node(), //@*, /) ! generate-id(.))) eq count((//node(), //@*, /))",
+    ));
+}
+
+#[test]
+fn test_generate_id_ncname() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc a="1"><a/><!--c--><?pi?>text</doc>"#,
+        "every $n in (//node(), //@*, /) satisfies generate-id($n) castable as xs:NCName",
+    ));
+}
+
+#[test]
+fn test_generate_id_context_node() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><a/><b/><c/></doc>"#,
+        "doc/b/generate-id() eq generate-id(doc/b)",
+    ));
+}
+
+#[test]
+fn test_generate_id_empty() {
+    assert_debug_snapshot!(run("generate-id(())"));
+}
+
 #[test]
 fn test_fn_string() {
     assert_debug_snapshot!(run_xml(
@@ -1280,6 +1481,81 @@ fn test_negative_round_integer4() {
     assert_debug_snapshot!(run("round(-151, -2)"));
 }
 
+#[test]
+fn test_negative_round_integer_tie() {
+    assert_debug_snapshot!(run("round(150, -2)"));
+}
+
+#[test]
+fn test_negative_round_integer_negative_tie() {
+    assert_debug_snapshot!(run("round(-150, -2)"));
+}
+
+#[test]
+fn test_round_large_negative_precision() {
+    assert_debug_snapshot!(run("round(12345, -100)"));
+}
+
+#[test]
+fn test_round_double_large_precision() {
+    assert_debug_snapshot!(run("round(1.5e0, 400)"));
+}
+
+#[test]
+fn test_round_double_negative_zero() {
+    assert_debug_snapshot!(run("round(-0.4e0)"));
+}
+
+#[test]
+fn test_round_half_to_even_2_5() {
+    assert_debug_snapshot!(run("round-half-to-even(2.5)"));
+}
+
+#[test]
+fn test_round_half_to_even_3_5() {
+    assert_debug_snapshot!(run("round-half-to-even(3.5)"));
+}
+
+#[test]
+fn test_round_half_to_even_negative_precision() {
+    assert_debug_snapshot!(run("round-half-to-even(35612.25, -2)"));
+}
+
+#[test]
+fn test_round_half_to_even_integer_negative_precision() {
+    assert_debug_snapshot!(run("round-half-to-even(12500, -3)"));
+}
+
+#[test]
+fn test_round_half_to_even_double() {
+    assert_debug_snapshot!(run("round-half-to-even(3.567812e+3, 2)"));
+}
+
+#[test]
+fn test_round_half_to_even_float() {
+    assert_debug_snapshot!(run("round-half-to-even(xs:float(2.5))"));
+}
+
+#[test]
+fn test_round_half_to_even_double_negative_zero() {
+    assert_debug_snapshot!(run("round-half-to-even(-0.4e0)"));
+}
+
+#[test]
+fn test_round_half_to_even_infinity() {
+    assert_debug_snapshot!(run("round-half-to-even(xs:double('-INF'), 2)"));
+}
+
+#[test]
+fn test_round_half_to_even_large_double() {
+    assert_debug_snapshot!(run("round-half-to-even(1.5e300, 2)"));
+}
+
+#[test]
+fn test_round_half_to_even_huge_precision() {
+    assert_debug_snapshot!(run("round-half-to-even(12345.6, 100000000000000)"));
+}
+
 #[test]
 fn test_deep_equal_equal_to_itself() {
     assert_debug_snapshot!(run_xml(r#"<doc><a/></doc>"#, "deep-equal(/, /)",));
@@ -1299,6 +1575,49 @@ fn test_qname_without_prefix() {
     assert_debug_snapshot!(run("QName('http://example.com', 'foo')"));
 }
 
+const REBOUND_PREFIX_XML: &str =
+    r#"<a xmlns:p="urn:one" xmlns="urn:d"><b xmlns:p="urn:two" xmlns=""><c/></b></a>"#;
+
+#[test]
+fn test_namespace_uri_for_prefix_rebound() {
+    assert_debug_snapshot!(run_xml(
+        REBOUND_PREFIX_XML,
+        "//*!namespace-uri-for-prefix('p', .)"
+    ));
+}
+
+#[test]
+fn test_namespace_uri_for_prefix_default() {
+    assert_debug_snapshot!(run_xml(
+        REBOUND_PREFIX_XML,
+        "//*!string(namespace-uri-for-prefix('', .))"
+    ));
+}
+
+#[test]
+fn test_namespace_uri_for_prefix_empty_sequence() {
+    assert_debug_snapshot!(run_xml(
+        REBOUND_PREFIX_XML,
+        "namespace-uri-for-prefix((), /*)"
+    ));
+}
+
+#[test]
+fn test_namespace_uri_for_prefix_unbound() {
+    assert_debug_snapshot!(run_xml(
+        REBOUND_PREFIX_XML,
+        "namespace-uri-for-prefix('q', /*)"
+    ));
+}
+
+#[test]
+fn test_in_scope_prefixes_rebound() {
+    assert_debug_snapshot!(run_xml(
+        REBOUND_PREFIX_XML,
+        "//*!string-join(sort(in-scope-prefixes(.)), ',')"
+    ));
+}
+
 #[test]
 fn test_run_focus_independent_function_on_focus() {
     assert_debug_snapshot!(run_xml(r#"<doc><a/></doc>"#, "doc/a/default-collation()"));
@@ -1312,6 +1631,42 @@ fn test_run_function_lookup_on_focus() {
     ));
 }
 
+#[test]
+fn test_function_lookup_call() {
+    assert_debug_snapshot!(run(
+        "function-lookup(xs:QName('fn:concat'), 3)('a', 'b', 'c')"
+    ));
+}
+
+#[test]
+fn test_function_lookup_not_found() {
+    assert_debug_snapshot!(run("function-lookup(xs:QName('fn:concat'), 1)"));
+}
+
+#[test]
+fn test_function_name_lookup() {
+    assert_debug_snapshot!(run(
+        "function-name(function-lookup(xs:QName('fn:concat'), 3))"
+    ));
+}
+
+#[test]
+fn test_function_name_inline_function() {
+    assert_debug_snapshot!(run("function-name(function($x) { $x })"));
+}
+
+#[test]
+fn test_function_arity_lookup() {
+    assert_debug_snapshot!(run(
+        "function-arity(function-lookup(xs:QName('fn:concat'), 3))"
+    ));
+}
+
+#[test]
+fn test_function_arity_partial_application() {
+    assert_debug_snapshot!(run("function-arity(substring(?, 1))"));
+}
+
 #[test]
 fn test_curly_array() {
     assert_debug_snapshot!(run("array {'a', 2, 3}(1)"));
@@ -1336,3 +1691,1744 @@ fn test_curly_map() {
 fn test_cast_negative_zero() {
     assert_debug_snapshot!(run("xs:unsignedLong('-0')"));
 }
+
+#[test]
+fn test_tokenize_whitespace() {
+    assert_debug_snapshot!(run("tokenize('  a  b ')"));
+}
+
+#[test]
+fn test_tokenize_whitespace_only() {
+    assert_debug_snapshot!(run("tokenize(' \t\n ')"));
+}
+
+#[test]
+fn test_tokenize_regex() {
+    assert_debug_snapshot!(run("tokenize('a, b,c', ',\\s*')"));
+}
+
+#[test]
+fn test_tokenize_zero_length_match() {
+    assert_debug_snapshot!(run("tokenize('abc', 'x*')"));
+}
+
+#[test]
+fn test_map_for_each() {
+    assert_debug_snapshot!(run(
+        "sort(map:for-each(map{1: 'a', 2: 'b'}, function($k, $v) { $v || $k }))"
+    ));
+}
+
+#[test]
+fn test_map_for_each_empty() {
+    assert_debug_snapshot!(run("map:for-each(map{}, function($k, $v) { $v })"));
+}
+
+#[test]
+fn test_map_find_nested() {
+    assert_debug_snapshot!(run(
+        "map:find(([map{'a': 1}, map{'b': [map{'a': 2}]}], map{'a': map{'a': 3}}), 'a')"
+    ));
+}
+
+#[test]
+fn test_map_find_no_match() {
+    assert_debug_snapshot!(run("map:find((1, [map{'b': 2}]), 'a')"));
+}
+
+#[test]
+fn test_map_size() {
+    assert_debug_snapshot!(run("(map:size(map{}), map:size(map{1: 'a', 2: 'b'}))"));
+}
+
+#[test]
+fn test_map_keys() {
+    assert_debug_snapshot!(run("sort(map:keys(map{3: 'c', 1: 'a', 2: 'b'}))"));
+}
+
+#[test]
+fn test_map_keys_stable() {
+    assert_debug_snapshot!(run(
+        "let $m := map{3: 'c', 1: 'a', 2: 'b', 'x': 'd'} return deep-equal(map:keys($m), map:keys($m))"
+    ));
+}
+
+#[test]
+fn test_map_contains() {
+    assert_debug_snapshot!(run("map:contains(map{1: 'a'}, 1)"));
+}
+
+#[test]
+fn test_map_contains_absent_key() {
+    assert_debug_snapshot!(run("map:contains(map{1: 'a'}, 2)"));
+}
+
+#[test]
+fn test_map_contains_empty_sequence_value() {
+    assert_debug_snapshot!(run("map:contains(map{1: ()}, 1)"));
+}
+
+#[test]
+fn test_map_contains_key_type_normalization() {
+    assert_debug_snapshot!(run(
+        "(map:contains(map{1: 'a'}, xs:double(1)), map:contains(map{'a': 1}, xs:untypedAtomic('a')), map:contains(map{'a': 1}, xs:anyURI('a')), map:contains(map{'1': 1}, 1))"
+    ));
+}
+
+#[test]
+fn test_map_get() {
+    assert_debug_snapshot!(run("map:get(map{1: 'a', 2: 'b'}, 2)"));
+}
+
+#[test]
+fn test_map_get_absent_key() {
+    assert_debug_snapshot!(run("map:get(map{1: 'a'}, 2)"));
+}
+
+#[test]
+fn test_map_get_key_type_normalization() {
+    assert_debug_snapshot!(run(
+        "(map:get(map{1: 'a'}, 1.0), map:get(map{1: 'b'}, xs:float(1)), map:get(map{1.5: 'c'}, xs:double(1.5)))"
+    ));
+}
+
+#[test]
+fn test_map_put() {
+    assert_debug_snapshot!(run(
+        "let $m := map{1: 'a'}, $n := map:put($m, 2, 'b') return (map:size($m), map:size($n), $n(2))"
+    ));
+}
+
+#[test]
+fn test_map_put_replaces_same_key() {
+    assert_debug_snapshot!(run(
+        "let $m := map{1: 'a'}, $n := map:put($m, 1.0, 'b') return ($m(1), $n(1), map:size($n), map:keys($n) instance of xs:decimal)"
+    ));
+}
+
+#[test]
+fn test_map_remove() {
+    assert_debug_snapshot!(run(
+        "let $m := map{1: 'a', 2: 'b', 3: 'c'}, $n := map:remove($m, (1, 3.0, 4)) return (map:size($m), map:keys($n))"
+    ));
+}
+
+#[test]
+fn test_map_entry() {
+    assert_debug_snapshot!(run(
+        "let $m := map:entry('a', (1, 2)) return (map:size($m), $m('a'))"
+    ));
+}
+
+// these would take a very long time if the range were materialized
+#[test]
+fn test_exists_large_range() {
+    assert_debug_snapshot!(run("exists(1 to 1000000000)"));
+}
+
+#[test]
+fn test_empty_large_range() {
+    assert_debug_snapshot!(run("empty(1 to 1000000000)"));
+}
+
+#[test]
+fn test_head_large_range() {
+    assert_debug_snapshot!(run("head(1 to 1000000000)"));
+}
+
+#[test]
+fn test_tail_large_range() {
+    assert_debug_snapshot!(run("count(tail(1 to 1000000000))"));
+}
+
+#[test]
+fn test_exists_empty_path() {
+    assert_debug_snapshot!(run_xml(
+        "<doc><a><b/></a><a/></doc>",
+        "(exists(doc/a/b), exists(doc/a/c), empty(doc/a/b), empty(doc/a/c))"
+    ));
+}
+
+// only paths of plain axis steps stop at the first node, so the second a
+// raises an error
+#[test]
+fn test_exists_path_not_plain() {
+    assert_debug_snapshot!(run_xml(
+        "<doc><a/><a x='1'/></doc>",
+        "exists(doc/a/(if (@x) then error() else .))"
+    ));
+}
+
+#[test]
+fn test_empty_path_mixed_nodes_and_atomics() {
+    assert_debug_snapshot!(run_xml("<doc><a/></doc>", "empty(doc/(a, 1))"));
+}
+
+#[test]
+fn test_exists_path_with_predicate() {
+    assert_debug_snapshot!(run_xml(
+        "<doc><a><b/></a><a><b/><b/></a></doc>",
+        "(exists(doc/a/b[2]), exists(doc/a/b[3]))"
+    ));
+}
+
+#[test]
+fn test_array_large_range() {
+    assert_debug_snapshot!(run("array{1 to 1000000000}"));
+}
+
+#[test]
+fn test_distinct_values_large_range() {
+    assert_debug_snapshot!(run("count(distinct-values(1 to 1000000000))"));
+}
+
+#[test]
+fn test_range_too_large() {
+    assert_debug_snapshot!(run("count(reverse(1 to 100000000000))"));
+}
+
+#[test]
+fn test_subsequence_large_range() {
+    assert_debug_snapshot!(run("subsequence(1 to 1000000000, 1000000000, 1)"));
+}
+
+#[test]
+fn test_subsequence_large_range_tail() {
+    assert_debug_snapshot!(run("subsequence(1 to 1000000000, 999999998)"));
+}
+
+#[test]
+fn test_subsequence_range_middle() {
+    assert_debug_snapshot!(run("deep-equal(subsequence(1 to 10, 3, 4), (3, 4, 5, 6))"));
+}
+
+#[test]
+fn test_subsequence_rounding() {
+    assert_debug_snapshot!(run("subsequence((1, 2, 3, 4, 5), 1.5, 2.5)"));
+}
+
+#[test]
+fn test_subsequence_rounding_two_arguments() {
+    assert_debug_snapshot!(run("subsequence((1, 2, 3), 1.5)"));
+}
+
+#[test]
+fn test_subsequence_negative_start() {
+    assert_debug_snapshot!(run("subsequence((1, 2, 3, 4, 5), -1, 3)"));
+}
+
+#[test]
+fn test_string_join_nodes_and_integers() {
+    assert_debug_snapshot!(run_xml(
+        "<doc><x>a</x><x><y>b</y>c</x></doc>",
+        "string-join((doc/x, 1, 02, -3), ', ')"
+    ));
+}
+
+#[test]
+fn test_string_join_lexical_forms() {
+    assert_debug_snapshot!(run("string-join((1.50, 1e0, xs:float('-0'), true()), '|')"));
+}
+
+#[test]
+fn test_string_join_no_separator() {
+    assert_debug_snapshot!(run("string-join((1, 'a', 2))"));
+}
+
+#[test]
+fn test_string_join_empty() {
+    assert_debug_snapshot!(run("string-join((), ', ')"));
+}
+
+#[test]
+fn test_fold_left() {
+    assert_debug_snapshot!(run(
+        "fold-left((1, 2, 3), (), function($a, $b) { ($b, $a) })"
+    ));
+}
+
+#[test]
+fn test_fold_left_empty() {
+    assert_debug_snapshot!(run("fold-left((), 'seed', function($a, $b) { $a || $b })"));
+}
+
+#[test]
+fn test_fold_right() {
+    assert_debug_snapshot!(run(
+        "fold-right((1, 2, 3), (), function($a, $b) { ($b, $a) })"
+    ));
+}
+
+#[test]
+fn test_fold_right_empty() {
+    assert_debug_snapshot!(run("fold-right((), 'seed', function($a, $b) { $a || $b })"));
+}
+
+#[test]
+fn test_for_each() {
+    assert_debug_snapshot!(run("for-each((1, 2, 3), function($a) { $a, $a * 10 })"));
+}
+
+#[test]
+fn test_filter() {
+    assert_debug_snapshot!(run("filter(1 to 10, function($a) { $a mod 3 = 0 })"));
+}
+
+#[test]
+fn test_filter_document_order() {
+    assert_debug_snapshot!(run_xml(
+        "<doc><a>1</a><b>2</b><a>3</a><a>4</a></doc>",
+        "filter(doc/*, function($n) { exists($n/self::a) }) ! string()"
+    ));
+}
+
+#[test]
+fn test_filter_predicate_not_boolean() {
+    assert_debug_snapshot!(run("filter((1, 2), function($a) { $a })"));
+}
+
+#[test]
+fn test_filter_predicate_not_single() {
+    assert_debug_snapshot!(run("filter((1, 2), function($a) { () })"));
+}
+
+#[test]
+fn test_for_each_pair() {
+    assert_debug_snapshot!(run(
+        "for-each-pair((1, 2, 3), (10, 20, 30), function($a, $b) { $a + $b })"
+    ));
+}
+
+#[test]
+fn test_for_each_pair_unequal_length() {
+    assert_debug_snapshot!(run(
+        "for-each-pair(('a', 'b', 'c'), (1, 2), function($a, $b) { $a || $b })"
+    ));
+}
+
+#[test]
+fn test_for_each_pair_large_range() {
+    assert_debug_snapshot!(run(
+        "for-each-pair(1 to 1000000000, (10, 20), function($a, $b) { $a + $b })"
+    ));
+}
+
+#[test]
+fn test_for_each_pair_empty() {
+    assert_debug_snapshot!(run(
+        "for-each-pair((), (1, 2), function($a, $b) { $a + $b })"
+    ));
+}
+
+#[test]
+fn test_add_year_month_duration_to_date() {
+    assert_debug_snapshot!(run(
+        "string(xs:date('2020-01-31') + xs:yearMonthDuration('P1M'))"
+    ));
+}
+
+#[test]
+fn test_add_day_time_duration_to_date_time_with_offset() {
+    assert_debug_snapshot!(run(
+        "string(xs:dateTime('2020-02-28T23:30:00-05:00') + xs:dayTimeDuration('PT1H'))"
+    ));
+}
+
+#[test]
+fn test_subtract_date_times_with_offsets() {
+    assert_debug_snapshot!(run(
+        "string(xs:dateTime('2020-03-01T10:00:00+02:00') - xs:dateTime('2020-02-28T09:30:00Z'))"
+    ));
+}
+
+#[test]
+fn test_multiply_duration() {
+    assert_debug_snapshot!(run(
+        "string(xs:yearMonthDuration('P1Y2M') * 1.5), string(xs:dayTimeDuration('P1D') div 4)"
+    ));
+}
+
+#[test]
+fn test_divide_durations() {
+    assert_debug_snapshot!(run(
+        "xs:dayTimeDuration('PT1H') div xs:dayTimeDuration('PT15M')"
+    ));
+}
+
+#[test]
+fn test_add_duration_to_date() {
+    assert_debug_snapshot!(run("xs:date('2020-01-01') + xs:duration('P1D')"));
+}
+
+#[test]
+fn test_duration_component_accessors() {
+    assert_debug_snapshot!(run(
+        "let $d := xs:duration('-P1Y13M3DT25H61M1.5S') return (years-from-duration($d), months-from-duration($d), days-from-duration($d), hours-from-duration($d), minutes-from-duration($d), seconds-from-duration($d))"
+    ));
+}
+
+#[test]
+fn test_distinct_values_first_appearance_order() {
+    assert_debug_snapshot!(run("distinct-values((3, 1, 2, 1, 3))"));
+}
+
+#[test]
+fn test_distinct_values_mixed_numeric() {
+    assert_debug_snapshot!(run("count(distinct-values((1, 1.0, 1e0, xs:float(1))))"));
+}
+
+#[test]
+fn test_distinct_values_nan() {
+    assert_debug_snapshot!(run(
+        "count(distinct-values((xs:double('NaN'), xs:float('NaN'))))"
+    ));
+}
+
+#[test]
+fn test_distinct_values_collation() {
+    assert_debug_snapshot!(run(
+        "distinct-values(('a', 'A', 'b'), 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive')"
+    ));
+}
+
+#[test]
+fn test_index_of_mixed_numeric() {
+    assert_debug_snapshot!(run("index-of((1, 1.0, 1e0, '1'), 1)"));
+}
+
+#[test]
+fn test_index_of_nan() {
+    assert_debug_snapshot!(run("index-of((1, xs:double('NaN')), xs:double('NaN'))"));
+}
+
+#[test]
+fn test_index_of_collation() {
+    assert_debug_snapshot!(run(
+        "index-of(('a', 'A', 'b'), 'a', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive')"
+    ));
+}
+
+#[test]
+fn test_copy_of_identity() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><a x="1"><b/></a></doc>"#,
+        "let $a := doc/a, $copy := copy-of($a) return ($copy is $a, deep-equal($copy, $a), exists($copy/..))"
+    ));
+}
+
+#[test]
+fn test_copy_of_namespaces() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc xmlns:p="http://example.com/p"><a/></doc>"#,
+        "in-scope-prefixes(copy-of(doc/a))"
+    ));
+}
+
+#[test]
+fn test_copy_of_base_uri() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc xml:base="http://example.com/"><a xml:base="a/"><b/></a></doc>"#,
+        "base-uri(copy-of(doc/a/b))"
+    ));
+}
+
+#[test]
+fn test_parse_xml_fragment_base_uri_nearest_ancestor() {
+    assert_debug_snapshot!(run(
+        r#"resolve-uri('c', base-uri(parse-xml-fragment('<a xml:base="http://example.com/x/"><b xml:base="y/"><c/></b></a>')//c))"#
+    ));
+}
+
+#[test]
+fn test_parse_xml_base_uri_absolute_overrides() {
+    assert_debug_snapshot!(run(
+        r#"base-uri(parse-xml('<a xml:base="http://example.com/x/"><b xml:base="http://other.org/"/></a>')//b)"#
+    ));
+}
+
+#[test]
+fn test_copy_of_atomic() {
+    assert_debug_snapshot!(run("copy-of((1, 'a'))"));
+}
+
+#[test]
+fn test_snapshot_ancestors() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc x="1"><a y="2"><b/></a><c/></doc>"#,
+        "let $b := snapshot(doc/a/b) return ($b is doc/a/b, $b/../@y/string(), $b/../../@x/string(), count(root($b)//*))"
+    ));
+}
+
+#[test]
+fn test_snapshot_attribute() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><a y="2"/></doc>"#,
+        "let $y := snapshot(doc/a/@y) return ($y is doc/a/@y, string($y), name($y/..))"
+    ));
+}
+
+#[test]
+fn test_snapshot_base_uri_without_xml_base() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc xml:base="http://example.com/"><a xml:base="a/"><b/></a></doc>"#,
+        "let $b := snapshot(doc/a/b), $c := copy-of(doc/a/b) return (base-uri($b), base-uri($c), exists(($b, $c)/@xml:base))"
+    ));
+}
+
+#[test]
+fn test_contains_token() {
+    assert_debug_snapshot!(run("contains-token('a b c', 'b')"));
+}
+
+#[test]
+fn test_contains_token_whitespace() {
+    assert_debug_snapshot!(run(
+        "contains-token(('x y', '\ta  b\n'), ' b '), contains-token('a b', ' ')"
+    ));
+}
+
+#[test]
+fn test_contains_token_not_xml_whitespace() {
+    assert_debug_snapshot!(run(
+        "contains-token('a', concat('a', codepoints-to-string(160)))"
+    ));
+}
+
+#[test]
+fn test_contains_token_empty_input() {
+    assert_debug_snapshot!(run("contains-token((), 'a')"));
+}
+
+#[test]
+fn test_contains_token_case_sensitive() {
+    assert_debug_snapshot!(run("contains-token('a b', 'A')"));
+}
+
+#[test]
+fn test_contains_token_collation() {
+    assert_debug_snapshot!(run(
+        "contains-token('a b', 'A', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive')"
+    ));
+}
+
+#[test]
+fn test_substring_before_after_collation() {
+    assert_debug_snapshot!(run(
+        "substring-before('Hello World', 'WORLD', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive'), substring-after('Hello World', 'o w', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive')"
+    ));
+}
+
+#[test]
+fn test_substring_before_after_uca_collation() {
+    assert_debug_snapshot!(run(
+        "substring-before('Die Straße und Weg', 'STRASSE', 'http://www.w3.org/2013/collation/UCA?lang=de;strength=primary'), substring-after('Straße und Weg', 'UND', 'http://www.w3.org/2013/collation/UCA?lang=de;strength=secondary')"
+    ));
+}
+
+#[test]
+fn test_substring_before_after_uca_collation_long_string() {
+    assert_debug_snapshot!(run(
+        "let $s := string-join((1 to 5000) ! 'a') || 'xb' return (substring-after($s, 'XB', 'http://www.w3.org/2013/collation/UCA?strength=primary'), string-length(substring-before($s, 'B', 'http://www.w3.org/2013/collation/UCA?strength=primary')))"
+    ));
+}
+
+#[test]
+fn test_substring_before_after_not_found() {
+    assert_debug_snapshot!(run(
+        "substring-before('abc', 'x'), substring-after('abc', 'x')"
+    ));
+}
+
+#[test]
+fn test_substring_before_after_empty_search() {
+    assert_debug_snapshot!(run(
+        "substring-before('abc', ''), substring-after('abc', ''), substring-after('abc', ())"
+    ));
+}
+
+#[test]
+fn test_contains_starts_ends_with_empty_search() {
+    assert_debug_snapshot!(run(
+        "contains('abc', ''), contains('', ''), contains((), ()), starts-with('', ''), starts-with('abc', ()), ends-with((), ''), ends-with('abc', '')"
+    ));
+}
+
+#[test]
+fn test_contains_starts_ends_with_case_insensitive() {
+    assert_debug_snapshot!(run(
+        "contains('Hello World', 'O WO', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive'), starts-with('Hello World', 'HELLO', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive'), ends-with('Hello World', 'WORLD', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive'), contains('Hello World', 'O WO')"
+    ));
+}
+
+#[test]
+fn test_contains_starts_ends_with_html_ascii_only_ascii() {
+    // only ASCII letters are case-insensitive
+    assert_debug_snapshot!(run(
+        "starts-with('Éa', 'éA', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive'), ends-with('aÉ', 'Aé', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive')"
+    ));
+}
+
+#[test]
+fn test_contains_starts_ends_with_uca_collation() {
+    assert_debug_snapshot!(run(
+        "contains('Die Straße und Weg', 'STRASSE', 'http://www.w3.org/2013/collation/UCA?lang=de;strength=primary'), starts-with('Straße und Weg', 'strasse', 'http://www.w3.org/2013/collation/UCA?lang=de;strength=primary'), ends-with('Die Straße', 'STRASSE', 'http://www.w3.org/2013/collation/UCA?lang=de;strength=primary'), ends-with('Die Straße', 'Weg', 'http://www.w3.org/2013/collation/UCA?lang=de;strength=primary')"
+    ));
+}
+
+#[test]
+fn test_starts_ends_with_uca_collation_long_string() {
+    assert_debug_snapshot!(run(
+        "let $s := 'x' || string-join((1 to 5000) ! 'a') || 'b' return (starts-with($s, 'XA', 'http://www.w3.org/2013/collation/UCA?strength=primary'), starts-with($s, 'B', 'http://www.w3.org/2013/collation/UCA?strength=primary'), ends-with($s, 'AB', 'http://www.w3.org/2013/collation/UCA?strength=primary'), ends-with($s, 'X', 'http://www.w3.org/2013/collation/UCA?strength=primary'))"
+    ));
+}
+
+#[test]
+fn test_replace_q_flag() {
+    assert_debug_snapshot!(run(
+        "replace('a.b', '.', 'X', 'q'), replace('a.b', '.', 'X')"
+    ));
+}
+
+#[test]
+fn test_replace_q_flag_literal_replacement() {
+    assert_debug_snapshot!(run(r"replace('abc', 'b', '$0\', 'q')"));
+}
+
+#[test]
+fn test_matches_q_flag() {
+    assert_debug_snapshot!(run(
+        "matches('a+b', 'a+b', 'q'), matches('aab', 'a+b', 'q')"
+    ));
+}
+
+#[test]
+fn test_matches_q_flag_case_insensitive() {
+    assert_debug_snapshot!(run("matches('A+B', 'a+b', 'qi')"));
+}
+
+#[test]
+fn test_tokenize_q_flag() {
+    assert_debug_snapshot!(run("tokenize('a.b.c', '.', 'q')"));
+}
+
+#[test]
+fn test_matches_x_flag() {
+    assert_debug_snapshot!(run(
+        "matches('abc', ' a b\n c ', 'x'), matches('abc', 'a b c')"
+    ));
+}
+
+#[test]
+fn test_matches_x_flag_char_class() {
+    // whitespace within a character class is kept
+    assert_debug_snapshot!(run("matches('a b', 'a[ ]b', 'x')"));
+}
+
+#[test]
+fn test_replace_x_flag() {
+    assert_debug_snapshot!(run("replace('abc', 'b c', 'X', 'x')"));
+}
+
+#[test]
+fn test_replace_function_upper_case_groups() {
+    assert_debug_snapshot!(run(
+        r"replace('hello world', '(\w)(\w*)', function($match, $groups) { upper-case($groups[1]) || $groups[2] }, '')"
+    ));
+}
+
+#[test]
+fn test_replace_function_match() {
+    assert_debug_snapshot!(run(
+        r"replace('a1b22', '\d+', function($match, $groups) { string(number($match) * 2) }, '')"
+    ));
+}
+
+#[test]
+fn test_replace_function_non_participating_groups() {
+    assert_debug_snapshot!(run(
+        "replace('ab', '(a)|(b)', function($match, $groups) { count($groups) || ':' || $groups[2] || ';' }, '')"
+    ));
+}
+
+#[test]
+fn test_replace_function_non_capturing_group() {
+    assert_debug_snapshot!(run(
+        "replace('abc', '(?:a)(b)', function($match, $groups) { count($groups) }, '')"
+    ));
+}
+
+#[test]
+fn test_replace_function_escaped_and_class_parens() {
+    assert_debug_snapshot!(run(
+        r"replace('(a)b', '\((a)\)[(]?(b)', function($match, $groups) { string-join($groups, ',') }, '')"
+    ));
+}
+
+#[test]
+fn test_replace_function_empty_result() {
+    assert_debug_snapshot!(run(
+        "replace('abc', 'b', function($match, $groups) { () }, '')"
+    ));
+}
+
+#[test]
+fn test_replace_function_matches_empty_string() {
+    assert_debug_snapshot!(run(
+        "replace('abc', 'x*', function($match, $groups) { 'y' }, '')"
+    ));
+}
+
+#[test]
+fn test_replace_untyped_replacement() {
+    assert_debug_snapshot!(run("replace('abc', 'b', xs:untypedAtomic('X'), '')"));
+}
+
+#[test]
+fn test_replace_replacement_wrong_type() {
+    assert_debug_snapshot!(run("replace('abc', 'b', 1, '')"));
+}
+
+#[test]
+fn test_math_sqrt_special() {
+    assert_debug_snapshot!(run(
+        "(math:sqrt(-1), math:sqrt(-0e0), math:sqrt(xs:double('INF')), math:sqrt(xs:double('NaN'))) ! string()"
+    ));
+}
+
+#[test]
+fn test_math_log_special() {
+    assert_debug_snapshot!(run(
+        "(math:log(0), math:log(-0e0), math:log(-1), math:log(xs:double('INF')), math:log10(0), math:log10(-1)) ! string()"
+    ));
+}
+
+#[test]
+fn test_math_exp_special() {
+    assert_debug_snapshot!(run(
+        "(math:exp(xs:double('-INF')), math:exp(xs:double('INF')), math:exp(xs:double('NaN')), math:exp10(xs:double('-INF')), math:exp10(xs:double('NaN'))) ! string()"
+    ));
+}
+
+#[test]
+fn test_math_pow_special() {
+    assert_debug_snapshot!(run(
+        "(math:pow(xs:double('NaN'), 0), math:pow(-0e0, -3), math:pow(0e0, -3.1e0), math:pow(1, xs:double('NaN')), math:pow(-1, xs:double('INF')), math:pow(-2.5e0, 2.00000001e0)) ! string()"
+    ));
+}
+
+#[test]
+fn test_math_pow_large_integer_exponent() {
+    assert_debug_snapshot!(run(
+        "(math:pow(2, 99999999999), math:pow(0.5, 99999999999), math:pow(1, -99999999999), math:pow(-1, 99999999999)) ! string()"
+    ));
+}
+
+#[test]
+fn test_math_trigonometry_special() {
+    assert_debug_snapshot!(run(
+        "(math:sin(-0e0), math:sin(xs:double('INF')), math:cos(xs:double('NaN')), math:tan(-0e0), math:asin(2), math:acos(2), math:atan(xs:double('INF')) eq math:pi() div 2) ! string()"
+    ));
+}
+
+#[test]
+fn test_math_atan2_special() {
+    assert_debug_snapshot!(run(
+        "(math:atan2(+0e0, -0e0) eq math:pi(), math:atan2(-0e0, -0e0) eq -math:pi(), math:atan2(-0e0, 0e0), math:atan2(xs:double('NaN'), 1)) ! string()"
+    ));
+}
+
+#[test]
+fn test_parse_ietf_date_rfc_822() {
+    assert_debug_snapshot!(run(
+        "(parse-ietf-date('Wed, 06 Jun 1994 07:29:35 GMT'), parse-ietf-date('Wed, 6 Jun 94 07:29:35 GMT'), parse-ietf-date('Sunday, 06-Nov-94 08:49:37 GMT'), parse-ietf-date('6 Jun 1994 7:29 UT')) ! string()"
+    ));
+}
+
+#[test]
+fn test_parse_ietf_date_asctime() {
+    assert_debug_snapshot!(run(
+        "(parse-ietf-date('Wed Jun 06 11:54:45 EST 2013'), parse-ietf-date('Wed Jun  6 11:54:45 2013'), parse-ietf-date('Jun 6 11:54:45 94')) ! string()"
+    ));
+}
+
+#[test]
+fn test_parse_ietf_date_timezones() {
+    assert_debug_snapshot!(run(
+        "(parse-ietf-date('Wed, 06 Jun 1994 07:29:35 +0500'), parse-ietf-date('Wed, 06 Jun 1994 07:29:35 -01:30 (EST)'), parse-ietf-date('Wed, 06 Jun 1994 07:29:35 pdt'), parse-ietf-date('Wed, 06 Jun 1994 07:29:35.5 UTC')) ! string()"
+    ));
+}
+
+#[test]
+fn test_parse_ietf_date_empty() {
+    assert_debug_snapshot!(run("parse-ietf-date(())"));
+}
+
+#[test]
+fn test_parse_ietf_date_invalid_date() {
+    assert_debug_snapshot!(run("parse-ietf-date('Wed, 30 Feb 1994 07:29:35 GMT')"));
+}
+
+#[test]
+fn test_parse_ietf_date_invalid_syntax() {
+    assert_debug_snapshot!(run("parse-ietf-date('1994-06-06T07:29:35Z')"));
+}
+
+#[test]
+fn test_parse_ietf_date_offset_out_of_range() {
+    assert_debug_snapshot!(run("parse-ietf-date('Aug 20 19:36:01 -15:00 2014')"));
+}
+
+#[test]
+fn test_collation_key_case_insensitive_map_key() {
+    assert_debug_snapshot!(run(
+        "map:size(map:merge(for $s in ('ABC', 'abc', 'x') return map { collation-key($s, 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive'): $s }, map { 'duplicates': 'use-first' }))"
+    ));
+}
+
+#[test]
+fn test_collation_key_codepoint() {
+    assert_debug_snapshot!(run("collation-key('ABC') eq collation-key('abc')"));
+}
+
+#[test]
+fn test_collation_key_uca_unsupported() {
+    assert_debug_snapshot!(run(
+        "collation-key('a', 'http://www.w3.org/2013/collation/UCA')"
+    ));
+}
+
+#[test]
+fn test_try_catch_code() {
+    assert_debug_snapshot!(run(
+        "try { xs:integer('a') } catch err:FORG0001 { local-name-from-QName($err:code) }"
+    ));
+}
+
+#[test]
+fn test_try_catch_star_description() {
+    assert_debug_snapshot!(run("try { 1 div 0 } catch * { $err:description }"));
+}
+
+#[test]
+fn test_try_catch_no_error() {
+    assert_debug_snapshot!(run("try { 1 + 1 } catch * { 0 }"));
+}
+
+#[test]
+fn test_try_catch_multiple_name_tests() {
+    assert_debug_snapshot!(run(
+        "try { xs:integer('a') } catch err:FOAR0001 | err:FORG0001 { 'caught' }"
+    ));
+}
+
+#[test]
+fn test_try_catch_unmatched() {
+    assert_debug_snapshot!(run("try { xs:integer('a') } catch err:FOAR0001 { 0 }"));
+}
+
+#[test]
+fn test_try_catch_rethrow() {
+    assert_debug_snapshot!(run(
+        "try { xs:integer('a') } catch err:FORG0001 { error($err:code, 'rethrown') }"
+    ));
+}
+
+#[test]
+fn test_try_catch_rethrow_nested() {
+    assert_debug_snapshot!(run(
+        "try { try { xs:integer('a') } catch * { error($err:code, 'rethrown') } } catch * { $err:description }"
+    ));
+}
+
+#[test]
+fn test_try_catch_error_value() {
+    assert_debug_snapshot!(run(
+        "try { error(xs:QName('err:FOO'), 'bad', (1, 2)) } catch err:FOO { sum($err:value) }"
+    ));
+}
+
+#[test]
+fn test_try_catch_in_loop() {
+    assert_debug_snapshot!(run(
+        "for $i in (1, 0, 2) return try { 2 div $i } catch * { -1 }"
+    ));
+}
+
+#[test]
+fn test_string_to_codepoints_emoji() {
+    assert_debug_snapshot!(run("string-to-codepoints('a😀')"));
+}
+
+#[test]
+fn test_string_to_codepoints_empty() {
+    assert_debug_snapshot!(run("string-to-codepoints('')"));
+}
+
+#[test]
+fn test_codepoints_to_string_emoji() {
+    assert_debug_snapshot!(run("codepoints-to-string((97, 128512))"));
+}
+
+#[test]
+fn test_codepoints_round_trip_emoji() {
+    assert_debug_snapshot!(run(
+        "codepoints-to-string(string-to-codepoints('😀x😀')) eq '😀x😀'"
+    ));
+}
+
+#[test]
+fn test_codepoints_to_string_control_character() {
+    assert_debug_snapshot!(run("codepoints-to-string(7)"));
+}
+
+#[test]
+fn test_codepoints_to_string_surrogate() {
+    assert_debug_snapshot!(run("codepoints-to-string(55357)"));
+}
+
+#[test]
+fn test_normalize_unicode_default_nfc() {
+    assert_debug_snapshot!(run(
+        "normalize-unicode(codepoints-to-string((101, 769))) eq codepoints-to-string(233)"
+    ));
+}
+
+#[test]
+fn test_normalize_unicode_nfc() {
+    assert_debug_snapshot!(run(
+        "string-to-codepoints(normalize-unicode(codepoints-to-string((101, 769)), 'NFC'))"
+    ));
+}
+
+#[test]
+fn test_normalize_unicode_nfd() {
+    assert_debug_snapshot!(run(
+        "string-to-codepoints(normalize-unicode(codepoints-to-string(233), ' nfd '))"
+    ));
+}
+
+#[test]
+fn test_normalize_unicode_fully_normalized_unsupported() {
+    assert_debug_snapshot!(run("normalize-unicode('a', 'FULLY-NORMALIZED')"));
+}
+
+#[test]
+fn test_normalize_unicode_unknown_form() {
+    assert_debug_snapshot!(run("normalize-unicode('a', 'NFX')"));
+}
+
+#[test]
+fn test_resolve_uri_dot_segments() {
+    assert_debug_snapshot!(run("resolve-uri('a/b/../c', 'http://example.com/x/y')"));
+}
+
+#[test]
+fn test_resolve_uri_parent_segments() {
+    assert_debug_snapshot!(run("resolve-uri('../../g', 'http://a/b/c/d;p?q')"));
+}
+
+#[test]
+fn test_resolve_uri_scheme_relative() {
+    assert_debug_snapshot!(run("resolve-uri('//other.org/p', 'https://example.com/x')"));
+}
+
+#[test]
+fn test_resolve_uri_absolute_returned_unchanged() {
+    assert_debug_snapshot!(run(
+        "resolve-uri('http://a.org/../b', 'http://example.com/')"
+    ));
+}
+
+#[test]
+fn test_resolve_uri_empty_returns_base() {
+    assert_debug_snapshot!(run("resolve-uri('', 'http://example.com/x?q')"));
+}
+
+#[test]
+fn test_resolve_uri_base_fragment() {
+    assert_debug_snapshot!(run("resolve-uri('c', 'http://example.com/x#frag')"));
+}
+
+#[test]
+fn test_resolve_uri_relative_base() {
+    assert_debug_snapshot!(run("resolve-uri('a', 'relative/base')"));
+}
+
+#[test]
+fn test_resolve_uri_no_base() {
+    assert_debug_snapshot!(run("resolve-uri('a')"));
+}
+
+#[test]
+fn test_resolve_uri_no_base_absolute() {
+    assert_debug_snapshot!(run("resolve-uri('http://example.com/a')"));
+}
+
+#[test]
+fn test_resolve_uri_empty_sequence() {
+    assert_debug_snapshot!(run("resolve-uri(())"));
+}
+
+#[test]
+fn test_encode_for_uri() {
+    assert_debug_snapshot!(run("encode-for-uri('Los Angeles/a~b_c-d.e')"));
+}
+
+#[test]
+fn test_encode_for_uri_non_ascii() {
+    assert_debug_snapshot!(run("encode-for-uri('~bébé')"));
+}
+
+#[test]
+fn test_encode_for_uri_percent() {
+    assert_debug_snapshot!(run("encode-for-uri('100% organic')"));
+}
+
+#[test]
+fn test_iri_to_uri() {
+    assert_debug_snapshot!(run(
+        "iri-to-uri('http://www.example.com/00/Weather/CA/Los%20Angeles#ocean')"
+    ));
+}
+
+#[test]
+fn test_iri_to_uri_escapes_space_and_non_ascii() {
+    assert_debug_snapshot!(run("iri-to-uri('http://www.example.com/~bébé/a b?q={x}')"));
+}
+
+#[test]
+fn test_escape_html_uri() {
+    assert_debug_snapshot!(run(
+        "escape-html-uri('http://www.example.com/00/Weather/CA/Los Angeles#ocean')"
+    ));
+}
+
+#[test]
+fn test_escape_html_uri_non_ascii() {
+    assert_debug_snapshot!(run(
+        "escape-html-uri(\"javascript:if (navigator.browserLanguage == 'fr') window.open('http://www.example.com/~bébé');\")"
+    ));
+}
+
+#[test]
+fn test_escape_html_uri_percent() {
+    assert_debug_snapshot!(run("escape-html-uri('a%20b')"));
+}
+
+#[test]
+fn test_uri_escaping_empty_sequence() {
+    assert_debug_snapshot!(run(
+        "(encode-for-uri(()), iri-to-uri(()), escape-html-uri(()))"
+    ));
+}
+
+#[test]
+fn test_max_promotes_integer_to_decimal() {
+    assert_debug_snapshot!(run("max((1, 2.5, 3))"));
+}
+
+#[test]
+fn test_min_promotes_integer_to_decimal() {
+    assert_debug_snapshot!(run("min((1, 2.5, 3))"));
+}
+
+#[test]
+fn test_max_promotes_to_double() {
+    assert_debug_snapshot!(run("max((1, 2.5, 3e0))"));
+}
+
+#[test]
+fn test_max_promotes_integer_to_float() {
+    assert_debug_snapshot!(run("max((3, xs:float(2.5)))"));
+}
+
+#[test]
+fn test_max_untyped_atomic_is_double() {
+    assert_debug_snapshot!(run("max((xs:untypedAtomic('3'), 2))"));
+}
+
+#[test]
+fn test_max_nan() {
+    assert_debug_snapshot!(run("max((1, xs:double('NaN'), 3))"));
+}
+
+#[test]
+fn test_min_nan() {
+    assert_debug_snapshot!(run("min((xs:double('NaN'), 1))"));
+}
+
+#[test]
+fn test_max_incomparable() {
+    assert_debug_snapshot!(run("max(('a', 1))"));
+}
+
+#[test]
+fn test_max_string_codepoint() {
+    assert_debug_snapshot!(run("max(('a', 'B'))"));
+}
+
+#[test]
+fn test_max_string_collation() {
+    assert_debug_snapshot!(run(
+        "max(('a', 'B'), 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive')"
+    ));
+}
+
+#[test]
+fn test_min_string_collation() {
+    assert_debug_snapshot!(run(
+        "min(('b', 'A'), 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive')"
+    ));
+}
+
+#[test]
+fn test_max_any_uri_and_string() {
+    assert_debug_snapshot!(run("max(('a', xs:anyURI('b')))"));
+}
+
+#[test]
+fn test_parse_json_invalid_xml_character_replaced() {
+    assert_debug_snapshot!(run(r#"string-to-codepoints(parse-json('"a\uFFFFb"'))"#));
+}
+
+#[test]
+fn test_parse_json_unpaired_surrogate_replaced() {
+    assert_debug_snapshot!(run(r#"string-to-codepoints(parse-json('"\uDEAD"'))"#));
+}
+
+#[test]
+fn test_parse_json_surrogate_pair() {
+    assert_debug_snapshot!(run(r#"string-to-codepoints(parse-json('"\uD834\uDD1E"'))"#));
+}
+
+#[test]
+fn test_parse_json_fallback() {
+    assert_debug_snapshot!(run(
+        r#"parse-json('"a\uDEADb"', map { 'fallback': function($s) { '[' || upper-case($s) || ']' } })"#
+    ));
+}
+
+#[test]
+fn test_parse_json_fallback_for_key() {
+    assert_debug_snapshot!(run(
+        r#"map:keys(parse-json('{"\b":1}', map { 'fallback': lower-case#1 }))"#
+    ));
+}
+
+#[test]
+fn test_parse_json_fallback_wrong_arity() {
+    assert_debug_snapshot!(run(
+        r#"parse-json('"\b"', map { 'fallback': substring#2 })"#
+    ));
+}
+
+#[test]
+fn test_parse_json_fallback_with_escape() {
+    assert_debug_snapshot!(run(
+        r#"parse-json('"\b"', map { 'escape': true(), 'fallback': lower-case#1 })"#
+    ));
+}
+
+#[test]
+fn test_parse_json_escape() {
+    assert_debug_snapshot!(run(
+        r#"parse-json('"\\ \u0041 \/ \t \u0000 \uDEAD"', map { 'escape': true() })"#
+    ));
+}
+
+#[test]
+fn test_parse_json_escape_unescaped_special_character() {
+    assert_debug_snapshot!(run(
+        r#"parse-json('"' || codepoints-to-string(133) || '"', map { 'escape': true() })"#
+    ));
+}
+
+#[test]
+fn test_parse_json_no_escape() {
+    assert_debug_snapshot!(run(
+        r#"parse-json('"\\ A \/ \t"', map { 'escape': false() })"#
+    ));
+}
+
+#[test]
+fn test_parse_json_invalid_escape() {
+    assert_debug_snapshot!(run(r#"parse-json('"\x20"')"#));
+}
+
+#[test]
+fn test_parse_json_duplicates_use_first() {
+    assert_debug_snapshot!(run(r#"parse-json('{"a":1, "b":2, "a":3}')?a"#));
+}
+
+#[test]
+fn test_parse_json_duplicates_use_last() {
+    assert_debug_snapshot!(run(
+        r#"parse-json('{"a":1, "b":2, "a":3}', map { 'duplicates': 'use-last' })?a"#
+    ));
+}
+
+#[test]
+fn test_parse_json_duplicates_reject_after_escape() {
+    assert_debug_snapshot!(run(
+        r#"parse-json('{"%":1, "%":2}', map { 'escape': true(), 'duplicates': 'reject' })"#
+    ));
+}
+
+#[test]
+fn test_parse_json_number_canonical_double() {
+    assert_debug_snapshot!(run(
+        r#"parse-json('[1e308, 0.1, 12345678901234567890, 1]')?* ! string(.)"#
+    ));
+}
+
+#[test]
+fn test_xml_to_json_number_canonical_double() {
+    assert_debug_snapshot!(run_xml(
+        r#"<array xmlns="http://www.w3.org/2005/xpath-functions"><number>1e308</number><number>0.1</number><number>12345678901234567890</number><number> 1.0 </number><number>-0</number></array>"#,
+        "xml-to-json(/)"
+    ));
+}
+
+#[test]
+fn test_xml_to_json_number_round_trip() {
+    assert_debug_snapshot!(run_xml(
+        r#"<array xmlns="http://www.w3.org/2005/xpath-functions"><number>1e308</number><number>0.1</number><number>12345678901234567890</number></array>"#,
+        "deep-equal(parse-json(xml-to-json(/)), array { /*/* ! xs:double(.) })"
+    ));
+}
+
+#[test]
+fn test_xml_to_json_number_not_finite() {
+    assert_debug_snapshot!(run_xml(
+        r#"<number xmlns="http://www.w3.org/2005/xpath-functions">INF</number>"#,
+        "xml-to-json(/)"
+    ));
+}
+
+#[test]
+fn test_xml_to_json_string_escaping() {
+    assert_debug_snapshot!(run_xml(
+        r#"<map xmlns="http://www.w3.org/2005/xpath-functions"><string key="a/b">"x"&#9;\</string><string key="\u0041" escaped-key="true" escaped="true">\u0041\n/</string></map>"#,
+        "xml-to-json(/)"
+    ));
+}
+
+#[test]
+fn test_xml_to_json_duplicate_key_after_unescape() {
+    assert_debug_snapshot!(run_xml(
+        r#"<map xmlns="http://www.w3.org/2005/xpath-functions"><null key="A"/><null key="\u0041" escaped-key="true"/></map>"#,
+        "xml-to-json(/)"
+    ));
+}
+
+#[test]
+fn test_xml_to_json_indent() {
+    assert_debug_snapshot!(run_xml(
+        r#"<map xmlns="http://www.w3.org/2005/xpath-functions"><array key="a"><number>1</number><boolean>true</boolean></array><map key="b"/></map>"#,
+        "xml-to-json(/, map { 'indent': true() })"
+    ));
+}
+
+#[test]
+fn test_id_multiple_in_document_order() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><x id="b"/><y xml:id="a"/><z id="c"/></doc>"#,
+        "id('a b') ! local-name()"
+    ));
+}
+
+#[test]
+fn test_id_sequence_of_idrefs() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><x id="a"/><y id="b"/><z id="c"/></doc>"#,
+        "id(('c', ' a  b ', 'missing')) ! local-name()"
+    ));
+}
+
+#[test]
+fn test_id_duplicate_uses_first() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><x id="a"/><y id="a"/></doc>"#,
+        "id('a') ! local-name()"
+    ));
+}
+
+#[test]
+fn test_id_context_node() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><x id="a"/></doc>"#,
+        "doc/x/id('a') ! local-name()"
+    ));
+}
+
+#[test]
+fn test_element_with_id() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><x id="a"/><y id="b"/></doc>"#,
+        "element-with-id('b a') ! local-name()"
+    ));
+}
+
+#[test]
+fn test_idref_without_idref_attributes() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc><x id="a"/><y idref="a"/></doc>"#,
+        "idref('a')"
+    ));
+}
+
+#[test]
+fn test_deep_equal_approx() {
+    assert_debug_snapshot!(run("xee:deep-equal-approx(0.1e0 + 0.2e0, 0.3e0, 1e-9)"));
+}
+
+#[test]
+fn test_deep_equal_approx_outside_epsilon() {
+    assert_debug_snapshot!(run("xee:deep-equal-approx(0.1e0 + 0.2e0, 0.3e0, 1e-20)"));
+}
+
+#[test]
+fn test_deep_equal_approx_nested() {
+    assert_debug_snapshot!(run(
+        "xee:deep-equal-approx(([0.1e0 + 0.2e0], map { 'a': 1e0 div 3 }, 'x'), ([0.3e0], map { 'a': 0.333333 }, 'x'), 1e-6)"
+    ));
+}
+
+#[test]
+fn test_deep_equal_approx_decimal_exact() {
+    // decimals aren't compared with a tolerance
+    assert_debug_snapshot!(run("xee:deep-equal-approx(1.0, 1.1, 0.5)"));
+}
+
+#[test]
+fn test_deep_equal_options_whitespace_significant() {
+    assert_debug_snapshot!(run(
+        "xee:deep-equal(parse-xml('<a>\n  <b>x</b>\n</a>'), parse-xml('<a><b>x</b></a>'), map {})"
+    ));
+}
+
+#[test]
+fn test_deep_equal_options_ignore_whitespace() {
+    assert_debug_snapshot!(run(
+        "xee:deep-equal(parse-xml('<a>\n  <b>x</b>\n</a>'), parse-xml('<a><b>x</b></a>'), map { 'ignore-whitespace': true() })"
+    ));
+}
+
+#[test]
+fn test_deep_equal_options_ignore_whitespace_keeps_text() {
+    // text that isn't whitespace only is still compared as is
+    assert_debug_snapshot!(run(
+        "xee:deep-equal(parse-xml('<a><b> x </b></a>'), parse-xml('<a><b>x</b></a>'), map { 'ignore-whitespace': true() })"
+    ));
+}
+
+#[test]
+fn test_deep_equal_options_ignore_comments_default() {
+    assert_debug_snapshot!(run(
+        "xee:deep-equal(parse-xml('<a><!-- note --><b/></a>'), parse-xml('<a><b/></a>'), map {})"
+    ));
+}
+
+#[test]
+fn test_deep_equal_options_compare_comments() {
+    assert_debug_snapshot!(run(
+        "xee:deep-equal(parse-xml('<a><!-- note --><b/></a>'), parse-xml('<a><b/></a>'), map { 'ignore-comments': false() })"
+    ));
+}
+
+#[test]
+fn test_deep_equal_options_compare_comments_formatted() {
+    assert_debug_snapshot!(run(
+        "xee:deep-equal(parse-xml('<a>\n  <!-- note -->\n  <b/>\n</a>'), parse-xml('<a><!-- note --><b/></a>'), map { 'ignore-whitespace': true(), 'ignore-comments': false() })"
+    ));
+}
+
+#[test]
+fn test_deep_equal_options_nested() {
+    assert_debug_snapshot!(run(
+        "xee:deep-equal([parse-xml('<a>\n  <b/>\n</a>')/a], [parse-xml('<a><b/></a>')/a], map { 'ignore-whitespace': true() })"
+    ));
+}
+
+#[test]
+fn test_deep_equal_options_invalid() {
+    assert_debug_snapshot!(run(
+        "xee:deep-equal(1, 1, map { 'ignore-whitespace': 'yes' })"
+    ));
+}
+
+#[test]
+fn test_curry() {
+    assert_debug_snapshot!(run(
+        "let $f := xee:curry(function($a, $b, $c) { $a * 100 + $b * 10 + $c }) return $f(1)(2)(3)"
+    ));
+}
+
+#[test]
+fn test_curry_partial() {
+    // each application before the last gives back a unary function
+    assert_debug_snapshot!(run(
+        "let $f := xee:curry(concat#3), $g := $f('a'), $h := $g('b') return (function-arity($f), function-arity($g), function-arity($h), $h('c'), $h('d'))"
+    ));
+}
+
+#[test]
+fn test_curry_sequence_arguments() {
+    assert_debug_snapshot!(run(
+        "xee:curry(function($a, $b) { count($a) + count($b) })((1, 2, 3))(())"
+    ));
+}
+
+#[test]
+fn test_curry_unary() {
+    assert_debug_snapshot!(run("xee:curry(abs#1)(-3)"));
+}
+
+#[test]
+fn test_group_by_records() {
+    // groups are in order of the first record with the key
+    assert_debug_snapshot!(run(
+        "let $doc := parse-xml('<r><p n=\"a\" city=\"Oslo\"/><p n=\"b\" city=\"Rome\"/><p n=\"c\" city=\"Oslo\"/><p n=\"d\" city=\"Bern\"/></r>') return xee:group-by($doc//p, function($p) { string($p/@city) }) ! (?key || ': ' || string-join(?items/@n, ','))"
+    ));
+}
+
+#[test]
+fn test_group_by_computed_key() {
+    assert_debug_snapshot!(run(
+        "xee:group-by(('apple', 'avocado', 'banana', 'blueberry', 'cherry'), function($s) { substring($s, 1, 1) }) ! [?key, ?items]"
+    ));
+}
+
+#[test]
+fn test_group_by_key_and_items() {
+    assert_debug_snapshot!(run(
+        "xee:group-by((5, 2, 8, 3, 4), function($i) { $i mod 2 = 0 }) ! [?key, ?items]"
+    ));
+}
+
+#[test]
+fn test_group_by_multiple_keys() {
+    // an item is in the group of each of its distinct keys; an item without
+    // a key isn't in any group
+    assert_debug_snapshot!(run(
+        "xee:group-by(('ab', '', 'bc', 'bb'), function($s) { string-to-codepoints($s) ! codepoints-to-string(.) }) ! [?key, ?items]"
+    ));
+}
+
+#[test]
+fn test_group_by_untyped_key() {
+    // untyped keys are compared as strings, not as numbers
+    assert_debug_snapshot!(run(
+        "let $doc := parse-xml('<r><p>1</p><p>1.0</p><p>1</p></r>') return xee:group-by($doc//p, function($p) { $p }) ! [?key, count(?items)]"
+    ));
+}
+
+#[test]
+fn test_group_by_collation() {
+    assert_debug_snapshot!(run(
+        "xee:group-by(('a', 'B', 'A', 'b'), function($s) { $s }, 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive') ! [?key, ?items]"
+    ));
+}
+
+#[test]
+fn test_group_by_empty() {
+    assert_debug_snapshot!(run("xee:group-by((), function($i) { $i })"));
+}
+
+#[test]
+fn test_substring_spec_motor_car() {
+    assert_debug_snapshot!(run(r#"substring("motor car", 6)"#));
+}
+
+#[test]
+fn test_substring_spec_metadata() {
+    assert_debug_snapshot!(run(r#"substring("metadata", 4, 3)"#));
+}
+
+#[test]
+fn test_substring_spec_rounded_arguments() {
+    assert_debug_snapshot!(run(r#"substring("12345", 1.5, 2.6)"#));
+}
+
+#[test]
+fn test_substring_spec_start_zero() {
+    assert_debug_snapshot!(run(r#"substring("12345", 0, 3)"#));
+}
+
+#[test]
+fn test_substring_spec_negative_length() {
+    assert_debug_snapshot!(run(r#"substring("12345", 5, -3)"#));
+}
+
+#[test]
+fn test_substring_spec_negative_start() {
+    assert_debug_snapshot!(run(r#"substring("12345", -3, 5)"#));
+}
+
+#[test]
+fn test_substring_spec_nan_start() {
+    assert_debug_snapshot!(run(r#"substring("12345", 0 div 0E0, 3)"#));
+}
+
+#[test]
+fn test_substring_spec_nan_length() {
+    assert_debug_snapshot!(run(r#"substring("12345", 1, 0 div 0E0)"#));
+}
+
+#[test]
+fn test_substring_spec_empty_sequence() {
+    assert_debug_snapshot!(run(r#"substring((), 1, 3)"#));
+}
+
+#[test]
+fn test_substring_spec_infinite_length() {
+    assert_debug_snapshot!(run(r#"substring("12345", -42, 1 div 0E0)"#));
+}
+
+#[test]
+fn test_substring_spec_infinite_start_and_length() {
+    assert_debug_snapshot!(run(r#"substring("12345", -1 div 0E0, 1 div 0E0)"#));
+}
+
+#[test]
+fn test_substring_negative_start_rounds_half_up() {
+    assert_debug_snapshot!(run(r#"substring("12345", -1.5, 4.5)"#));
+}
+
+#[test]
+fn test_substring_negative_half_start() {
+    assert_debug_snapshot!(run(r#"substring("12345", -0.5, 3)"#));
+}
+
+#[test]
+fn test_substring_infinite_start() {
+    assert_debug_snapshot!(run(r#"substring("12345", 1 div 0E0)"#));
+}
+
+#[test]
+fn test_substring_non_ascii() {
+    assert_debug_snapshot!(run(r#"substring("déjà vu", 2, 3)"#));
+}
+
+#[test]
+fn test_format_integer_words() {
+    assert_debug_snapshot!(run("format-integer(123, 'w')"));
+}
+
+#[test]
+fn test_format_integer_ordinal() {
+    assert_debug_snapshot!(run("format-integer(21, '1;o')"));
+}
+
+#[test]
+fn test_format_integer_ordinal_words() {
+    assert_debug_snapshot!(run("format-integer(21, 'Ww;o')"));
+}
+
+#[test]
+fn test_format_integer_alphabetic() {
+    assert_debug_snapshot!(run("format-integer(28, 'A')"));
+}
+
+#[test]
+fn test_format_integer_grouping() {
+    assert_debug_snapshot!(run("format-integer(1234567, '#,##0')"));
+}
+
+#[test]
+fn test_format_integer_modifier_without_semicolon() {
+    // the format modifier has to be separated by a semicolon
+    assert_debug_snapshot!(run("format-integer(21, '1o')"));
+}
+
+#[test]
+fn test_format_integer_invalid_picture() {
+    assert_debug_snapshot!(run("format-integer((), '0#')"));
+}
+
+#[test]
+fn test_arrow_named_function() {
+    assert_debug_snapshot!(run("'hello' => fn:substring(1, 3)"));
+}
+
+#[test]
+fn test_arrow_named_function_default_namespace() {
+    assert_debug_snapshot!(run("'hello' => upper-case()"));
+}
+
+#[test]
+fn test_arrow_variable() {
+    assert_debug_snapshot!(run(
+        "let $f := substring#3 return 'hello' => $f(2, 2)"
+    ));
+}
+
+#[test]
+fn test_arrow_parenthesized_inline_function() {
+    assert_debug_snapshot!(run(
+        "'hello' => (function($s, $n) { substring($s, $n) })(3)"
+    ));
+}
+
+#[test]
+fn test_arrow_named_function_partial_application() {
+    assert_debug_snapshot!(run("('hello' => substring(?, 2))(3)"));
+}
+
+#[test]
+fn test_arrow_variable_partial_application() {
+    assert_debug_snapshot!(run(
+        "let $f := substring#3 return ('hello' => $f(?, 2))(3)"
+    ));
+}
+
+#[test]
+fn test_arrow_chained() {
+    assert_debug_snapshot!(run("'hello' => substring(2) => upper-case()"));
+}
+
+#[test]
+fn test_arrow_not_a_function() {
+    assert_debug_snapshot!(run("let $f := 1 return 'hello' => $f()"));
+}
+
+#[test]
+fn test_arrow_function_item_to_itself() {
+    // the function item is passed as the argument, and can't be atomized
+    assert_debug_snapshot!(run("let $f := upper-case#1 return $f => $f()"));
+}
+
+#[test]
+fn test_zero_or_one_empty() {
+    assert_debug_snapshot!(run("zero-or-one(())"));
+}
+
+#[test]
+fn test_zero_or_one_one() {
+    assert_debug_snapshot!(run("zero-or-one(1)"));
+}
+
+#[test]
+fn test_zero_or_one_two() {
+    assert_debug_snapshot!(run("zero-or-one((1, 2))"));
+}
+
+#[test]
+fn test_one_or_more_empty() {
+    assert_debug_snapshot!(run("one-or-more(())"));
+}
+
+#[test]
+fn test_one_or_more_one() {
+    assert_debug_snapshot!(run("one-or-more(1)"));
+}
+
+#[test]
+fn test_one_or_more_two() {
+    assert_debug_snapshot!(run("one-or-more((1, 2))"));
+}
+
+#[test]
+fn test_one_or_more_range() {
+    // a range is returned as is, without expanding it
+    assert_debug_snapshot!(run("one-or-more(1 to 1000000) => count()"));
+}
+
+#[test]
+fn test_exactly_one_empty() {
+    assert_debug_snapshot!(run("exactly-one(())"));
+}
+
+#[test]
+fn test_exactly_one_one() {
+    assert_debug_snapshot!(run("exactly-one(1)"));
+}
+
+#[test]
+fn test_exactly_one_two() {
+    assert_debug_snapshot!(run("exactly-one((1, 2))"));
+}
+
+const TRANSFORM_TEXT_STYLESHEET: &str = r#"<xsl:stylesheet xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3.0"><xsl:output omit-xml-declaration="yes"/><xsl:template match="/">Hello <xsl:value-of select="doc/@name"/></xsl:template></xsl:stylesheet>"#;
+
+fn transform_text(delivery_format: &str, expr: &str) -> error::Result<Sequence> {
+    run_xml(
+        r#"<doc name="World"/>"#,
+        &format!(
+            "let $result := transform(map {{ 'stylesheet-text': '{}', 'source-node': /, 'delivery-format': '{}' }}) return {}",
+            TRANSFORM_TEXT_STYLESHEET, delivery_format, expr
+        ),
+    )
+}
+
+#[test]
+#[cfg(feature = "xslt")]
+fn test_transform_serialized_text() {
+    assert_debug_snapshot!(transform_text(
+        "serialized",
+        "($result?output, $result?output instance of xs:string)"
+    ));
+}
+
+#[test]
+#[cfg(feature = "xslt")]
+fn test_transform_raw_text() {
+    assert_debug_snapshot!(transform_text(
+        "raw",
+        "($result?output instance of text()+, string-join($result?output))"
+    ));
+}
+
+#[test]
+#[cfg(feature = "xslt")]
+fn test_transform_document_text() {
+    assert_debug_snapshot!(transform_text(
+        "document",
+        "($result?output instance of document-node(), string($result?output))"
+    ));
+}
+
+#[test]
+#[cfg(feature = "xslt")]
+fn test_transform_document_by_default() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc name="World"/>"#,
+        r#"transform(map { 'stylesheet-node': parse-xml('<xsl:stylesheet xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3.0"><xsl:template match="doc"><greeting to="{@name}"/></xsl:template></xsl:stylesheet>'), 'source-node': / })?output/greeting/@to/string()"#
+    ));
+}
+
+#[test]
+fn test_transform_unsupported_delivery_format() {
+    assert_debug_snapshot!(transform_text("file", "$result?output"));
+}
+
+#[test]
+fn test_transform_without_stylesheet() {
+    assert_debug_snapshot!(run("transform(map { 'delivery-format': 'raw' })"));
+}
+
+#[test]
+#[cfg(feature = "xslt")]
+fn test_transform_error_caught() {
+    assert_debug_snapshot!(run_xml(
+        r#"<doc/>"#,
+        r#"try { transform(map { 'stylesheet-node': parse-xml('<xsl:stylesheet xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3.0"><xsl:template match="/"><xsl:sequence select="error((), ''oops'', (42, 1.5, ''x''))"/></xsl:template></xsl:stylesheet>'), 'source-node': / }) } catch * { $err:code, $err:description, $err:value }"#
+    ));
+}
+
+#[test]
+fn test_system_property_not_in_xpath() {
+    assert_debug_snapshot!(run("system-property('xsl:version')"));
+}
+
+#[test]
+fn test_accumulator_before_not_in_xpath() {
+    assert_debug_snapshot!(run("accumulator-before('a')"));
+}
+
+#[test]
+fn test_serialize_html_character_maps() {
+    assert_debug_snapshot!(run(
+        r#"serialize(parse-xml('<p title="«a»">«br» a &amp; b</p>'), map { 'method': 'html', 'use-character-maps': map { '«': '<', '»': '>', 'a': 'A' } })"#
+    ));
+}
@@ -255,6 +255,16 @@ pub struct AccumulatorRule {
     pub span: Span,
 }
 
+impl SelectOrSequenceConstructor for AccumulatorRule {
+    fn select(&self) -> Option<&Expression> {
+        self.select.as_ref()
+    }
+
+    fn sequence_constructor(&self) -> &SequenceConstructor {
+        &self.sequence_constructor
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum AccumulatorPhase {
@@ -432,7 +442,7 @@ impl From<CallTemplate> for SequenceConstructorItem {
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Catch {
-    pub errors: Option<Vec<Token>>,
+    pub errors: Option<Vec<xpath_ast::NameTest>>,
     pub select: Option<Expression>,
 
     pub sequence_constructor: SequenceConstructor,
@@ -440,6 +450,16 @@ pub struct Catch {
     pub span: Span,
 }
 
+impl SelectOrSequenceConstructor for Catch {
+    fn select(&self) -> Option<&Expression> {
+        self.select.as_ref()
+    }
+
+    fn sequence_constructor(&self) -> &SequenceConstructor {
+        &self.sequence_constructor
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct CharacterMap {
@@ -451,6 +471,12 @@ pub struct CharacterMap {
     pub span: Span,
 }
 
+impl From<CharacterMap> for Declaration {
+    fn from(i: CharacterMap) -> Self {
+        Declaration::CharacterMap(Box::new(i))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Choose {
@@ -965,6 +991,12 @@ pub struct Mode {
     pub span: Span,
 }
 
+impl From<Mode> for Declaration {
+    fn from(i: Mode) -> Self {
+        Declaration::Mode(Box::new(i))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum OnNoMatch {
@@ -1199,6 +1231,12 @@ pub struct Output {
     pub span: Span,
 }
 
+impl From<Output> for Declaration {
+    fn from(i: Output) -> Self {
+        Declaration::Output(Box::new(i))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum OutputMethod {
@@ -1337,6 +1375,12 @@ pub struct Param {
     pub span: Span,
 }
 
+impl From<Param> for Declaration {
+    fn from(i: Param) -> Self {
+        Declaration::Param(Box::new(i))
+    }
+}
+
 impl From<Param> for OverrideContent {
     fn from(i: Param) -> Self {
         OverrideContent::Param(Box::new(i))
@@ -1580,6 +1624,22 @@ pub struct Try {
     pub span: Span,
 }
 
+impl SelectOrSequenceConstructor for Try {
+    fn select(&self) -> Option<&Expression> {
+        self.select.as_ref()
+    }
+
+    fn sequence_constructor(&self) -> &SequenceConstructor {
+        &self.sequence_constructor
+    }
+}
+
+impl From<Try> for SequenceConstructorItem {
+    fn from(i: Try) -> Self {
+        SequenceConstructorInstruction::Try(Box::new(i)).into()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TryCatchOrFallback {
@@ -1654,6 +1714,12 @@ impl From<Variable> for SequenceConstructorItem {
     }
 }
 
+impl From<Variable> for Declaration {
+    fn from(v: Variable) -> Self {
+        Declaration::Variable(Box::new(v))
+    }
+}
+
 impl From<Variable> for OverrideContent {
     fn from(v: Variable) -> Self {
         OverrideContent::Variable(Box::new(v))
@@ -2,7 +2,9 @@ use std::str::FromStr;
 
 use ahash::{HashSet, HashSetExt};
 use rust_decimal::Decimal;
-use xee_xpath_ast::{ast as xpath_ast, parse_item_type, parse_name, parse_sequence_type};
+use xee_xpath_ast::{
+    ast as xpath_ast, parse_item_type, parse_name, parse_name_test, parse_sequence_type,
+};
 
 use crate::ast_core as ast;
 use crate::content::Content;
@@ -325,6 +327,27 @@ impl<'a> Attributes<'a> {
         |s, span| self._eqnames(s, span)
     }
 
+    fn _name_tests(&self, s: &str, span: Span) -> Result<Vec<xpath_ast::NameTest>, AttributeError> {
+        let mut result = Vec::new();
+        for (s, span) in split_whitespace_with_spans(s, span) {
+            if let Ok(name_test) = parse_name_test(s, &self.content.parser_context().namespaces) {
+                result.push(name_test);
+            } else {
+                return Err(AttributeError::InvalidNameTest {
+                    value: s.to_string(),
+                    span,
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    pub(crate) fn name_tests(
+        &self,
+    ) -> impl Fn(&'a str, Span) -> Result<Vec<xpath_ast::NameTest>, AttributeError> + '_ {
+        |s, span| self._name_tests(s, span)
+    }
+
     fn _modes(&self, s: &str, span: Span) -> Result<Vec<ast::ModeValue>, AttributeError> {
         let mut result = Vec::new();
         for (s, span) in split_whitespace_with_spans(s, span) {
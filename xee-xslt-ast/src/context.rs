@@ -69,6 +69,15 @@ impl Context {
         }
     }
 
+    pub(crate) fn with_variable_names(&self, names: &[xpath_ast::Name]) -> Self {
+        let mut variable_names = self.variable_names.clone();
+        variable_names.extend(names.iter().cloned());
+        Self {
+            variable_names,
+            ..self.clone()
+        }
+    }
+
     pub(crate) fn with_static_standard(
         &self,
         namespaces: xot::Namespaces,
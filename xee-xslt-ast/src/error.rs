@@ -11,6 +11,8 @@ pub enum AttributeError {
     Invalid { value: String, span: Span },
     // An eqname was invalid
     InvalidEqName { value: String, span: Span },
+    // A name test was invalid
+    InvalidNameTest { value: String, span: Span },
     // XPath parser error
     XPathParser(xee_xpath_ast::ParserError),
     // A value template could not be parsed
@@ -410,13 +410,20 @@ impl InstructionParser for ast::CallTemplate {
 impl InstructionParser for ast::Catch {
     fn parse(content: &Content, attributes: &Attributes) -> Result<Self> {
         let names = &content.state.names;
+        // the err:* variables are in scope within the catch
+        let catch_content = content.with_context(
+            content
+                .context
+                .with_variable_names(&xpath_ast::CatchClause::error_variable_names()),
+        );
+        let catch_attributes = catch_content.clone().attributes(attributes.element);
         Ok(ast::Catch {
-            errors: attributes.optional(names.errors, attributes.tokens())?,
-            select: attributes.optional(names.select, attributes.xpath())?,
+            errors: attributes.optional(names.errors, attributes.name_tests())?,
+            select: attributes.optional(names.select, catch_attributes.xpath())?,
 
             span: content.span()?,
 
-            sequence_constructor: content.sequence_constructor()?,
+            sequence_constructor: catch_content.sequence_constructor()?,
         })
     }
 }
@@ -1617,6 +1624,42 @@ impl InstructionParser for ast::Text {
     }
 }
 
+static TRY_CONTENT: ContentParseLock<(
+    (ast::SequenceConstructor, ast::Catch),
+    Vec<ast::TryCatchOrFallback>,
+)> = OnceLock::new();
+
+impl InstructionParser for ast::Try {
+    fn parse(content: &Content, attributes: &Attributes) -> Result<Self> {
+        let names = &content.state.names;
+        let parse = TRY_CONTENT.get_or_init(|| {
+            children(
+                sequence_constructor()
+                    .then(instruction(names.xsl_catch))
+                    .then(
+                        instruction(names.xsl_catch)
+                            .map(ast::TryCatchOrFallback::Catch)
+                            .or(instruction(names.xsl_fallback)
+                                .map(ast::TryCatchOrFallback::Fallback))
+                            .many(),
+                    ),
+            )
+        });
+        let ((sequence_constructor, catch), catches) = parse(content)?;
+
+        Ok(ast::Try {
+            select: attributes.optional(names.select, attributes.xpath())?,
+            rollback_output: attributes.optional(names.rollback_output, attributes.boolean())?,
+
+            span: content.span()?,
+
+            sequence_constructor,
+            catch,
+            catches,
+        })
+    }
+}
+
 impl InstructionParser for ast::Transform {
     fn parse(content: &Content, attributes: &Attributes) -> Result<Self> {
         let names = &content.state.names;
@@ -105,6 +105,7 @@ impl SequenceConstructorName {
                 ast::SourceDocument::parse_sequence_constructor_item(attributes)
             }
             SequenceConstructorName::Text => ast::Text::parse_sequence_constructor_item(attributes),
+            SequenceConstructorName::Try => ast::Try::parse_sequence_constructor_item(attributes),
             SequenceConstructorName::ValueOf => {
                 ast::ValueOf::parse_sequence_constructor_item(attributes)
             }
@@ -134,7 +135,12 @@ impl DeclarationName {
     pub(crate) fn parse(&self, attributes: &Attributes) -> Result<ast::Declaration, ElementError> {
         match self {
             DeclarationName::Accumulator => ast::Accumulator::parse_declaration(attributes),
+            DeclarationName::CharacterMap => ast::CharacterMap::parse_declaration(attributes),
+            DeclarationName::Mode => ast::Mode::parse_declaration(attributes),
+            DeclarationName::Output => ast::Output::parse_declaration(attributes),
+            DeclarationName::Param => ast::Param::parse_declaration(attributes),
             DeclarationName::Template => ast::Template::parse_declaration(attributes),
+            DeclarationName::Variable => ast::Variable::parse_declaration(attributes),
             _ => Err(ElementError::Unsupported),
         }
     }
@@ -190,6 +196,7 @@ pub(crate) struct Names {
     // XSL elements
     pub(crate) xsl_accumulator_rule: xot::NameId,
     pub(crate) xsl_attribute: xot::NameId,
+    pub(crate) xsl_catch: xot::NameId,
     pub(crate) xsl_fallback: xot::NameId,
     pub(crate) xsl_for_each: xot::NameId,
     pub(crate) xsl_for_each_group: xot::NameId,
@@ -296,6 +303,7 @@ pub(crate) struct Names {
     pub(crate) regex: xot::NameId,
     pub(crate) required: xot::NameId,
     pub(crate) result_prefix: xot::NameId,
+    pub(crate) rollback_output: xot::NameId,
     pub(crate) schema_aware: xot::NameId,
     pub(crate) schema_location: xot::NameId,
     pub(crate) select: xot::NameId,
@@ -433,6 +441,7 @@ impl Names {
 
             xsl_accumulator_rule: xot.add_name_ns("accumulator-rule", xsl_ns),
             xsl_attribute: xot.add_name_ns("attribute", xsl_ns),
+            xsl_catch: xot.add_name_ns("catch", xsl_ns),
             xsl_fallback: xot.add_name_ns("fallback", xsl_ns),
             xsl_for_each: xot.add_name_ns("for-each", xsl_ns),
             xsl_for_each_group: xot.add_name_ns("for-each-group", xsl_ns),
@@ -538,6 +547,7 @@ impl Names {
             regex: xot.add_name("regex"),
             required: xot.add_name("required"),
             result_prefix: xot.add_name("result-prefix"),
+            rollback_output: xot.add_name("rollback-output"),
             schema_aware: xot.add_name("schema-aware"),
             schema_location: xot.add_name("schema-location"),
             select: xot.add_name("select"),
@@ -28,9 +28,14 @@ pub fn parse_transform(s: &str) -> Result<ast::Transform> {
 pub fn parse_sequence_constructor_item(s: &str) -> Result<ast::SequenceConstructorItem> {
     let mut xot = Xot::new();
     let names = Names::new(&mut xot);
-    let (node, span_info) = xot.parse_with_span_info(s).unwrap();
+    let (node, span_info) = xot
+        .parse_with_span_info(s)
+        .map_err(|_e| Error::Unsupported)?;
     let state = State::new(xot, span_info, names);
-    let node = state.xot.document_element(node).unwrap();
+    let node = state
+        .xot
+        .document_element(node)
+        .map_err(|_e| Error::Internal)?;
 
     if let Some(element) = state.xot.element(node) {
         let context = Context::new(state.xot.prefixes(node));
@@ -19,7 +19,11 @@
 use xot::{NameId, Node, Xot};
 
 use xee_xpath_ast::ast as xpath_ast;
-use xee_xpath_compiler::{compile, context::Variables, sequence::Sequence};
+use xee_xpath_compiler::{
+    compile,
+    context::{StaticContext, Variables},
+    sequence::Sequence,
+};
 
 use crate::attributes::Attributes;
 use crate::content::Content;
@@ -224,7 +228,9 @@ impl StaticEvaluator {
         xot: &mut Xot,
     ) -> Result<Sequence, xee_xpath_compiler::error::SpannedError> {
         let parser_context = content.parser_context();
-        let static_context = parser_context.into();
+        let mut static_context: StaticContext = parser_context.into();
+        // use-when can call XSLT functions such as system-property
+        static_context.enable_xslt_functions();
         let program = compile(static_context, xpath)?;
         let mut dynamic_context_builder = program.dynamic_context_builder();
         // TODO doing the clone here of the global variables isn't ideal
@@ -1,4 +1,5 @@
-use xot::{Node, NodeEdge, Value, Xot};
+use xee_interpreter::xml::strip_whitespace_text;
+use xot::{Node, Value, Xot};
 
 use crate::names::Names;
 
@@ -9,41 +10,9 @@ pub(crate) fn strip_whitespace(xot: &mut Xot, names: &Names, node: Node) {
     // some whitespace text nodes in the next/previous sibling rules
     strip_comment_pi(xot, node);
 
-    let mut to_remove = vec![];
-    let mut xml_space_preserve = vec![];
-    for edge in xot.traverse(node) {
-        match edge {
-            NodeEdge::Start(node) => match xot.value(node) {
-                Value::Text(text) => {
-                    if is_xml_whitespace(text.get())
-                        && !is_xml_space_preserve(xot, names, node, &xml_space_preserve)
-                    {
-                        to_remove.push(node);
-                    }
-                }
-                Value::Element(_) => {
-                    if let Some(xml_space) = xot.attributes(node).get(xot.xml_space_name()) {
-                        if xml_space == "preserve" {
-                            xml_space_preserve.push(true);
-                        } else if xml_space == "default" {
-                            xml_space_preserve.push(false);
-                        }
-                    }
-                }
-                _ => {}
-            },
-            NodeEdge::End(node) => {
-                if xot.is_element(node) && xot.attributes(node).get(xot.xml_space_name()).is_some()
-                {
-                    let _ = xml_space_preserve.pop();
-                }
-            }
-        }
-    }
-
-    for node in to_remove {
-        let _ = xot.remove(node);
-    }
+    strip_whitespace_text(xot, node, |xot, node, preserve| {
+        !is_xml_space_preserve(xot, names, node, preserve)
+    });
 }
 
 fn strip_comment_pi(xot: &mut Xot, node: Node) {
@@ -66,12 +35,7 @@ fn strip_comment_pi(xot: &mut Xot, node: Node) {
     }
 }
 
-fn is_xml_space_preserve(
-    xot: &Xot,
-    names: &Names,
-    node: Node,
-    xml_space_preserve: &[bool],
-) -> bool {
+fn is_xml_space_preserve(xot: &Xot, names: &Names, node: Node, xml_space_preserve: bool) -> bool {
     // if the parent is in the ignore list, we never preserve whitespace
     if let Some(parent) = xot.parent(node) {
         if let Some(element) = xot.element(parent) {
@@ -109,19 +73,7 @@ fn is_xml_space_preserve(
     }
 
     // otherwise we look into the state of xml:space
-    if let Some(last) = xml_space_preserve.last() {
-        *last
-    } else {
-        false
-    }
-}
-
-fn is_xml_whitespace_char(c: char) -> bool {
-    matches!(c, '\u{9}' | '\u{A}' | '\u{D}' | '\u{20}')
-}
-
-fn is_xml_whitespace(s: &str) -> bool {
-    s.chars().all(is_xml_whitespace_char)
+    xml_space_preserve
 }
 
 #[cfg(test)]
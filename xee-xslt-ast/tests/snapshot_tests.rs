@@ -168,6 +168,20 @@ fn test_accumulator() {
     ));
 }
 
+#[test]
+fn test_character_map() {
+    assert_ron_snapshot!(parse_transform(
+        r#"<xsl:transform version="3.0" xmlns:xsl="http://www.w3.org/1999/XSL/Transform"><xsl:output use-character-maps="html"/><xsl:character-map name="html" use-character-maps="base"><xsl:output-character character="&#160;" string="&amp;nbsp;"/></xsl:character-map></xsl:transform>"#
+    ));
+}
+
+#[test]
+fn test_global_variable_and_param() {
+    assert_ron_snapshot!(parse_transform(
+        r#"<xsl:transform version="3.0" xmlns:xsl="http://www.w3.org/1999/XSL/Transform" xmlns:xs="http://www.w3.org/2001/XMLSchema"><xsl:param name="p" as="xs:integer" required="yes"/><xsl:variable name="v" select="$p"/></xsl:transform>"#
+    ));
+}
+
 #[test]
 fn test_should_be_empty_not_empty() {
     assert_ron_snapshot!(parse_sequence_constructor_item(
@@ -0,0 +1,63 @@
+use divan::{black_box, Bencher};
+
+use xee_interpreter::context::StaticContext;
+use xee_xslt_compiler::{evaluate_program, parse};
+use xot::Xot;
+
+fn main() {
+    divan::main();
+}
+
+// a document of 100k nodes: each item is an element, an attribute, a child
+// element and a text node
+fn large_document() -> String {
+    let mut xml = String::from("<doc>");
+    for i in 0..25_000 {
+        xml.push_str(&format!(r#"<item id="{i}"><name>Item {i}</name></item>"#));
+    }
+    xml.push_str("</doc>");
+    xml
+}
+
+// the identity template; whole subtrees are copied at once
+const IDENTITY: &str = r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/" priority="2">
+    <xsl:apply-templates select="doc"/>
+  </xsl:template>
+  <xsl:template match="@*|node()" priority="1">
+    <xsl:copy><xsl:apply-templates select="@*|node()"/></xsl:copy>
+  </xsl:template>
+</xsl:transform>"#;
+
+// the same transformation, but the template isn't recognized as the
+// identity template, so each node is processed by calling it
+const IDENTITY_PER_NODE: &str = r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/" priority="2">
+    <xsl:apply-templates select="doc"/>
+  </xsl:template>
+  <xsl:template match="@*|node()" priority="1">
+    <xsl:copy><xsl:sequence select="()"/><xsl:apply-templates select="@*|node()"/></xsl:copy>
+  </xsl:template>
+</xsl:transform>"#;
+
+fn identity_transform(bencher: Bencher, xslt: &str) {
+    let mut xot = Xot::new();
+    let root = xot.parse(&large_document()).unwrap();
+    let program = parse(StaticContext::default(), xslt).unwrap();
+
+    bencher.bench_local(move || {
+        black_box(evaluate_program(&mut xot, &program, Some(root)).unwrap());
+    });
+}
+
+#[divan::bench(sample_count = 10)]
+fn identity(bencher: Bencher) {
+    identity_transform(bencher, IDENTITY)
+}
+
+#[divan::bench(sample_count = 10)]
+fn identity_per_node(bencher: Bencher) {
+    identity_transform(bencher, IDENTITY_PER_NODE)
+}
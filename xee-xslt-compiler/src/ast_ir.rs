@@ -1,4 +1,4 @@
-use ahash::HashSetExt;
+use ahash::{HashMap, HashMapExt, HashSetExt};
 use xee_name::{Name, Namespaces, FN_NAMESPACE};
 
 use xee_interpreter::{context::StaticContext, error, interpreter};
@@ -7,39 +7,57 @@ use xee_xpath_ast::{ast as xpath_ast, pattern::transform_pattern, span::Spanned}
 use xee_xslt_ast::{ast, parse_transform};
 use xot::xmlname::NameStrInfo;
 
-use crate::{default_declarations::text_only_copy_declarations, priority::default_priority};
+use crate::{
+    identity::is_identity_template,
+    invocation::{EntryPoints, Invocation},
+    priority::default_priority,
+    serialization::check_character_maps,
+};
 
 struct IrConverter<'a> {
     variables: Variables,
     static_context: &'a StaticContext,
+    // named templates are inlined where they're called
+    named_templates: HashMap<ast::EqName, ast::Template>,
+    // the named templates we're inlining right now, to detect recursion
+    called_templates: Vec<ast::EqName>,
 }
 
 pub fn compile(
     transform: ast::Transform,
-    static_context: StaticContext,
+    mut static_context: StaticContext,
+    invocation: &Invocation,
 ) -> error::SpannedResult<interpreter::Program> {
+    static_context.enable_xslt_functions();
+    check_character_maps(&transform)?;
     let mut ir_converter = IrConverter::new(&static_context);
-    let declarations = ir_converter.transform(&transform)?;
+    let declarations = ir_converter.transform(&transform, invocation)?;
     compile_xslt(declarations, static_context)
 }
 
 pub fn parse(
     static_context: StaticContext,
     xslt: &str,
+) -> error::SpannedResult<interpreter::Program> {
+    parse_with_invocation(static_context, xslt, &Invocation::default())
+}
+
+/// Parse a stylesheet into a program that starts as described by
+/// `invocation`.
+pub fn parse_with_invocation(
+    static_context: StaticContext,
+    xslt: &str,
+    invocation: &Invocation,
 ) -> error::SpannedResult<interpreter::Program> {
     let transform = parse_transform(xslt);
     // TODO: better error handling
-    let mut transform = match transform {
+    let transform = match transform {
         Ok(transform) => transform,
         Err(_e) => {
             return Err(error::Error::Unsupported.into());
         }
     };
-    // insert default rules early on in precedence order
-    let mut declarations = text_only_copy_declarations().unwrap();
-    declarations.extend(transform.declarations);
-    transform.declarations = declarations;
-    compile(transform, static_context)
+    compile(transform, static_context, invocation)
 }
 
 impl<'a> IrConverter<'a> {
@@ -47,15 +65,50 @@ impl<'a> IrConverter<'a> {
         IrConverter {
             variables: Variables::new(),
             static_context,
+            named_templates: HashMap::new(),
+            called_templates: Vec::new(),
         }
     }
 
-    fn main_sequence_constructor(&mut self) -> ast::SequenceConstructor {
-        vec![ast::SequenceConstructorItem::Instruction(
+    fn main_sequence_constructor(
+        &mut self,
+        transform: &ast::Transform,
+        invocation: &Invocation,
+    ) -> error::SpannedResult<ast::SequenceConstructor> {
+        EntryPoints::new(transform).check(invocation)?;
+        let mode = match invocation {
+            Invocation::ApplyTemplates(Some(mode)) => {
+                ast::ApplyTemplatesModeValue::EqName(mode.clone())
+            }
+            Invocation::ApplyTemplates(None) => ast::ApplyTemplatesModeValue::Unnamed,
+            Invocation::CallTemplate(name) => {
+                // the initial template is called without any parameters
+                let template = self
+                    .named_templates
+                    .get(name)
+                    .ok_or(error::Error::XTDE0040)?;
+                if template
+                    .params
+                    .iter()
+                    .any(|param| param.required || is_implicitly_required(param))
+                {
+                    return Err(error::Error::XTDE0700.into());
+                }
+                let span = template.span;
+                return Ok(vec![ast::SequenceConstructorItem::Instruction(
+                    ast::SequenceConstructorInstruction::CallTemplate(Box::new(
+                        ast::CallTemplate {
+                            name: name.clone(),
+                            with_params: vec![],
+                            span,
+                        },
+                    )),
+                )]);
+            }
+        };
+        Ok(vec![ast::SequenceConstructorItem::Instruction(
             ast::SequenceConstructorInstruction::ApplyTemplates(Box::new(ast::ApplyTemplates {
-                // TODO: mode should be configurable from the outside somehow,
-                // the XSTL test suite I think requires this.
-                mode: ast::ApplyTemplatesModeValue::Unnamed,
+                mode,
                 select: ast::Expression {
                     xpath: xee_xpath_ast::ast::XPath::parse(
                         "/",
@@ -68,7 +121,7 @@ impl<'a> IrConverter<'a> {
                 content: vec![],
                 span: xee_xslt_ast::ast::Span::new(0, 0),
             })),
-        )]
+        )])
     }
 
     fn simple_content_atom(&mut self) -> ir::Atom {
@@ -106,8 +159,31 @@ impl<'a> IrConverter<'a> {
         })
     }
 
-    fn transform(&mut self, transform: &ast::Transform) -> error::SpannedResult<ir::Declarations> {
-        let main_sequence_constructor = self.main_sequence_constructor();
+    fn transform(
+        &mut self,
+        transform: &ast::Transform,
+        invocation: &Invocation,
+    ) -> error::SpannedResult<ir::Declarations> {
+        // globals can be referenced from anywhere, so we know their names
+        // before we compile anything. The same goes for named templates.
+        for declaration in &transform.declarations {
+            match declaration {
+                ast::Declaration::Template(template) => {
+                    if let Some(name) = &template.name {
+                        self.named_templates
+                            .insert(name.clone(), template.as_ref().clone());
+                    }
+                }
+                ast::Declaration::Variable(variable) => {
+                    self.variables.new_var_name(&variable.name);
+                }
+                ast::Declaration::Param(param) => {
+                    self.variables.new_var_name(&param.name);
+                }
+                _ => {}
+            }
+        }
+        let main_sequence_constructor = self.main_sequence_constructor(transform, invocation)?;
         let main = self.sequence_constructor_function(&main_sequence_constructor)?;
         let mut declarations = ir::Declarations::new(main);
 
@@ -126,6 +202,12 @@ impl<'a> IrConverter<'a> {
         match declaration {
             Template(template) => self.template(declarations, template),
             Mode(mode) => self.mode(declarations, mode),
+            Accumulator(accumulator) => self.accumulator(declarations, accumulator),
+            Variable(variable) => self.global_variable(declarations, variable),
+            Param(param) => self.global_param(declarations, param),
+            // these only affect serialization of the result, see
+            // serialization_parameters
+            Output(_) | CharacterMap(_) => Ok(()),
             _ => Err(error::Error::Unsupported.into()),
         }
     }
@@ -161,8 +243,12 @@ impl<'a> IrConverter<'a> {
                 modes,
                 pattern: transform_pattern(&pattern.pattern, |expr| self.pattern_predicate(expr))?,
                 function_definition,
+                identity: is_identity_template(template),
             });
             Ok(())
+        } else if template.name.is_some() {
+            // named templates are inlined by call_template
+            Ok(())
         } else {
             Err(error::Error::Unsupported.into())
         }
@@ -173,7 +259,103 @@ impl<'a> IrConverter<'a> {
         declarations: &mut ir::Declarations,
         mode: &ast::Mode,
     ) -> error::SpannedResult<()> {
-        declarations.modes.insert(mode.name.clone(), ir::Mode {});
+        let on_no_match = match mode.on_no_match {
+            Some(ast::OnNoMatch::DeepCopy) => ir::OnNoMatch::DeepCopy,
+            Some(ast::OnNoMatch::ShallowCopy) => ir::OnNoMatch::ShallowCopy,
+            Some(ast::OnNoMatch::DeepSkip) => ir::OnNoMatch::DeepSkip,
+            Some(ast::OnNoMatch::ShallowSkip) => ir::OnNoMatch::ShallowSkip,
+            Some(ast::OnNoMatch::TextOnlyCopy) | None => ir::OnNoMatch::TextOnlyCopy,
+            Some(ast::OnNoMatch::Fail) => ir::OnNoMatch::Fail,
+        };
+        // TODO: we have no way to report warnings yet, so
+        // warning-on-no-match is accepted but has no effect
+        declarations
+            .modes
+            .insert(mode.name.clone(), ir::Mode { on_no_match });
+        Ok(())
+    }
+
+    fn accumulator(
+        &mut self,
+        declarations: &mut ir::Declarations,
+        accumulator: &ast::Accumulator,
+    ) -> error::SpannedResult<()> {
+        let initial_value =
+            self.context_function(Vec::new(), |s| s.expression(&accumulator.initial_value))?;
+        let mut start_rules = Vec::new();
+        let mut end_rules = Vec::new();
+        for rule in &accumulator.rules {
+            // the rule has access to the current value of the accumulator
+            // as $value
+            let value_param = ir::Param {
+                name: self.variables.new_var_name(&xpath_ast::Name::name("value")),
+                type_: None,
+            };
+            let function_definition = self.context_function(vec![value_param], |s| {
+                s.select_or_sequence_constructor(rule)
+            })?;
+            let accumulator_rule = ir::AccumulatorRule {
+                pattern: transform_pattern(&rule.match_.pattern, |expr| {
+                    self.pattern_predicate(expr)
+                })?,
+                function_definition,
+            };
+            match rule.phase {
+                Some(ast::AccumulatorPhase::Start) | None => start_rules.push(accumulator_rule),
+                Some(ast::AccumulatorPhase::End) => end_rules.push(accumulator_rule),
+            }
+        }
+        declarations.accumulators.push(ir::Accumulator {
+            name: accumulator.name.clone(),
+            initial_value,
+            start_rules,
+            end_rules,
+        });
+        Ok(())
+    }
+
+    fn global_variable(
+        &mut self,
+        declarations: &mut ir::Declarations,
+        variable: &ast::Variable,
+    ) -> error::SpannedResult<()> {
+        let function_definition = self.context_function(Vec::new(), |s| {
+            s.variable_value(
+                variable.select.as_ref(),
+                &variable.sequence_constructor,
+                variable.as_.as_ref(),
+            )
+        })?;
+        declarations.globals.push(ir::Global {
+            name: self.variables.new_var_name(&variable.name),
+            param: None,
+            function_definition,
+        });
+        Ok(())
+    }
+
+    fn global_param(
+        &mut self,
+        declarations: &mut ir::Declarations,
+        param: &ast::Param,
+    ) -> error::SpannedResult<()> {
+        // the default value, used if no value is supplied
+        let function_definition = self.context_function(Vec::new(), |s| {
+            s.variable_value(
+                param.select.as_ref(),
+                &param.sequence_constructor,
+                param.as_.as_ref(),
+            )
+        })?;
+        declarations.globals.push(ir::Global {
+            name: self.variables.new_var_name(&param.name),
+            param: Some(ir::GlobalParam {
+                name: param.name.clone(),
+                required: param.required || is_implicitly_required(param),
+                sequence_type: param.as_.clone(),
+            }),
+            function_definition,
+        });
         Ok(())
     }
 
@@ -188,11 +370,21 @@ impl<'a> IrConverter<'a> {
     fn sequence_constructor_function(
         &mut self,
         sequence_constructor: &ast::SequenceConstructor,
+    ) -> error::SpannedResult<ir::FunctionDefinition> {
+        self.context_function(Vec::new(), |s| s.sequence_constructor(sequence_constructor))
+    }
+
+    // a function that takes the context item, position and size, followed
+    // by extra_params, and returns the result of body
+    fn context_function(
+        &mut self,
+        extra_params: Vec<ir::Param>,
+        body: impl FnOnce(&mut Self) -> error::SpannedResult<Bindings>,
     ) -> error::SpannedResult<ir::FunctionDefinition> {
         let context_names = self.variables.push_context();
-        let bindings = self.sequence_constructor(sequence_constructor)?;
+        let bindings = body(self)?;
         self.variables.pop_context();
-        let params = vec![
+        let mut params = vec![
             ir::Param {
                 name: context_names.item,
                 type_: None,
@@ -206,6 +398,7 @@ impl<'a> IrConverter<'a> {
                 type_: None,
             },
         ];
+        params.extend(extra_params);
         Ok(ir::FunctionDefinition {
             params,
             return_type: None,
@@ -284,6 +477,7 @@ impl<'a> IrConverter<'a> {
             If(if_) => self.if_(if_),
             Choose(choose) => self.choose(choose),
             ForEach(for_each) => self.for_each(for_each),
+            ForEachGroup(for_each_group) => self.for_each_group(for_each_group),
             Copy(copy) => self.copy(copy),
             CopyOf(copy_of) => self.copy_of(copy_of),
             Sequence(sequence) => self.sequence(sequence),
@@ -293,6 +487,9 @@ impl<'a> IrConverter<'a> {
             Namespace(namespace) => self.namespace(namespace),
             Comment(comment) => self.comment(comment),
             ProcessingInstruction(pi) => self.processing_instruction(pi),
+            Number(number) => self.number(number),
+            CallTemplate(call_template) => self.call_template(call_template),
+            Try(try_) => self.try_(try_),
             // TODO: xsl:variable does not produce content and is handled
             // earlier already should be unreachable!() but at this point this
             // can be reached so return unsupported
@@ -398,6 +595,114 @@ impl<'a> IrConverter<'a> {
         )
     }
 
+    // A named template is inlined: the supplied parameter values are
+    // evaluated where the template is called, after which the template
+    // parameters are bound around its body.
+    fn call_template(
+        &mut self,
+        call_template: &ast::CallTemplate,
+    ) -> error::SpannedResult<Bindings> {
+        let template = self
+            .named_templates
+            .get(&call_template.name)
+            .cloned()
+            .ok_or(error::Error::XTSE0650)?;
+        if self.called_templates.contains(&call_template.name) {
+            // we can't inline a recursive template
+            return Err(error::Error::Unsupported.into());
+        }
+        let mut supplied = Vec::new();
+        for with_param in &call_template.with_params {
+            if with_param.tunnel {
+                return Err(error::Error::Unsupported.into());
+            }
+            if !template
+                .params
+                .iter()
+                .any(|param| param.name == with_param.name)
+            {
+                return Err(error::Error::XTSE0680.into());
+            }
+            let bindings = self.variable_value(
+                with_param.select.as_ref(),
+                &with_param.sequence_constructor,
+                with_param.as_.as_ref(),
+            )?;
+            supplied.push((with_param.name.clone(), self.variables.new_name(), bindings));
+        }
+        self.called_templates.push(call_template.name.clone());
+        let body = self.template_params(&template, &template.params, &supplied);
+        self.called_templates.pop();
+        let mut bindings = body?;
+        for (_, name, var_bindings) in supplied.into_iter().rev() {
+            let expr = ir::Expr::Let(ir::Let {
+                name,
+                var_expr: Box::new(var_bindings.expr()),
+                return_expr: Box::new(bindings.expr()),
+            });
+            bindings = Bindings::new(self.variables.new_binding(expr, (0..0).into()));
+        }
+        Ok(bindings)
+    }
+
+    // bind the template parameters one by one, and then the body of the
+    // template
+    fn template_params(
+        &mut self,
+        template: &ast::Template,
+        params: &[ast::Param],
+        supplied: &[(ast::EqName, ir::Name, Bindings)],
+    ) -> error::SpannedResult<Bindings> {
+        let Some((param, rest)) = params.split_first() else {
+            return self.variable_value(
+                None,
+                &template.sequence_constructor,
+                template.as_.as_ref(),
+            );
+        };
+        if param.tunnel {
+            return Err(error::Error::Unsupported.into());
+        }
+        let supplied_name = supplied
+            .iter()
+            .find(|(name, _, _)| *name == param.name)
+            .map(|(_, name, _)| name.clone());
+        let var_bindings = if let Some(supplied_name) = supplied_name {
+            let atom = Spanned::new(ir::Atom::Variable(supplied_name), (0..0).into());
+            if let Some(sequence_type) = &param.as_ {
+                let expr = ir::Expr::Convert(ir::Convert {
+                    atom,
+                    sequence_type: sequence_type.clone(),
+                });
+                Bindings::new(self.variables.new_binding_no_span(expr))
+            } else {
+                Bindings::new(
+                    self.variables
+                        .new_binding(ir::Expr::Atom(atom), (0..0).into()),
+                )
+            }
+        } else if param.required {
+            return Err(error::Error::XTSE0690.into());
+        } else {
+            // the default value
+            self.variable_value(
+                param.select.as_ref(),
+                &param.sequence_constructor,
+                param.as_.as_ref(),
+            )?
+        };
+        let name = self.variables.new_var_name(&param.name);
+        let return_bindings = self.template_params(template, rest, supplied)?;
+        let expr = ir::Expr::Let(ir::Let {
+            name,
+            var_expr: Box::new(var_bindings.expr()),
+            return_expr: Box::new(return_bindings.expr()),
+        });
+        Ok(Bindings::new(
+            self.variables.new_binding(expr, (0..0).into()),
+        ))
+    }
+
     fn apply_templates(
         &mut self,
         apply_templates: &ast::ApplyTemplates,
@@ -481,10 +786,13 @@ impl<'a> IrConverter<'a> {
         ))
     }
 
-    fn attribute_value_template(
+    fn attribute_value_template<V>(
         &mut self,
-        value_template: &ast::ValueTemplate<String>,
-    ) -> error::SpannedResult<Bindings> {
+        value_template: &ast::ValueTemplate<V>,
+    ) -> error::SpannedResult<Bindings>
+    where
+        V: Clone + PartialEq + Eq,
+    {
         let mut all_bindings = Vec::new();
         for item in &value_template.template {
             let bindings = match item {
@@ -552,17 +860,42 @@ impl<'a> IrConverter<'a> {
         ) = item
         {
             let name = self.variables.new_var_name(&variable.name);
-            let var_bindings = if let Some(select) = &variable.select {
-                self.expression(select)?
-            } else {
-                self.sequence_constructor(&variable.sequence_constructor)?
-            };
+            let var_bindings = self.variable_value(
+                variable.select.as_ref(),
+                &variable.sequence_constructor,
+                variable.as_.as_ref(),
+            )?;
             Ok(Some((name, var_bindings)))
         } else {
             Ok(None)
         }
     }
 
+    // the value of a variable or parameter, converted to its declared type
+    // if it has one
+    fn variable_value(
+        &mut self,
+        select: Option<&ast::Expression>,
+        sequence_constructor: &ast::SequenceConstructor,
+        as_: Option<&ast::SequenceType>,
+    ) -> error::SpannedResult<Bindings> {
+        let bindings = if let Some(select) = select {
+            self.expression(select)?
+        } else {
+            self.sequence_constructor(sequence_constructor)?
+        };
+        if let Some(sequence_type) = as_ {
+            let (atom, bindings) = bindings.atom_bindings();
+            let expr = ir::Expr::Convert(ir::Convert {
+                atom,
+                sequence_type: sequence_type.clone(),
+            });
+            Ok(bindings.bind_expr_no_span(&mut self.variables, expr))
+        } else {
+            Ok(bindings)
+        }
+    }
+
     fn empty_sequence(&mut self) -> ir::ExprS {
         Spanned::new(
             ir::Expr::Atom(Spanned::new(
@@ -623,6 +956,54 @@ impl<'a> IrConverter<'a> {
         Ok(bindings.bind_expr_no_span(&mut self.variables, expr))
     }
 
+    fn try_(&mut self, try_: &ast::Try) -> error::SpannedResult<Bindings> {
+        let try_bindings = self.select_or_sequence_constructor(try_)?;
+        let try_function = ir::FunctionDefinition {
+            params: vec![],
+            return_type: None,
+            body: Box::new(try_bindings.expr()),
+        };
+        // xsl:fallback is only used by processors that don't support
+        // xsl:try, so we ignore it
+        let catch_clauses = std::iter::once(&try_.catch)
+            .chain(try_.catches.iter().filter_map(|catch| match catch {
+                ast::TryCatchOrFallback::Catch(catch) => Some(catch),
+                ast::TryCatchOrFallback::Fallback(_) => None,
+            }))
+            .map(|catch| self.catch(catch))
+            .collect::<error::SpannedResult<Vec<_>>>()?;
+        let expr = ir::Expr::TryCatch(ir::TryCatch {
+            try_: try_function,
+            catch_clauses,
+        });
+        Ok(Bindings::new(self.variables.new_binding_no_span(expr)))
+    }
+
+    fn catch(&mut self, catch: &ast::Catch) -> error::SpannedResult<ir::CatchClause> {
+        // the err:* variables are passed in as parameters
+        let params = xpath_ast::CatchClause::error_variable_names()
+            .iter()
+            .map(|name| ir::Param {
+                name: self.variables.new_var_name(name),
+                type_: None,
+            })
+            .collect();
+        let bindings = self.select_or_sequence_constructor(catch)?;
+        // without an errors attribute, all errors are caught
+        let name_tests = catch
+            .errors
+            .clone()
+            .unwrap_or_else(|| vec![xpath_ast::NameTest::Star]);
+        Ok(ir::CatchClause {
+            name_tests,
+            function_definition: ir::FunctionDefinition {
+                params,
+                return_type: None,
+                body: Box::new(bindings.expr()),
+            },
+        })
+    }
+
     fn for_each(&mut self, for_each: &ast::ForEach) -> error::SpannedResult<Bindings> {
         let (var_atom, bindings) = self.expression(&for_each.select)?.atom_bindings();
 
@@ -638,6 +1019,211 @@ impl<'a> IrConverter<'a> {
         Ok(bindings.bind_expr_no_span(&mut self.variables, expr))
     }
 
+    fn for_each_group(
+        &mut self,
+        for_each_group: &ast::ForEachGroup,
+    ) -> error::SpannedResult<Bindings> {
+        let (population_atom, bindings) = self.expression(&for_each_group.select)?.atom_bindings();
+
+        // determine the groups, as a sequence of arrays with two members:
+        // the items in the group and the grouping key
+        let (groups_atom, groups_bindings) = match (
+            &for_each_group.group_by,
+            &for_each_group.group_adjacent,
+            &for_each_group.group_starting_with,
+            &for_each_group.group_ending_with,
+        ) {
+            (Some(group_by), None, None, None) => {
+                self.group_keys("group-by", population_atom, group_by, for_each_group)?
+            }
+            (None, Some(group_adjacent), None, None) => self.group_keys(
+                "group-adjacent",
+                population_atom,
+                group_adjacent,
+                for_each_group,
+            )?,
+            (None, None, Some(group_starting_with), None) => {
+                self.group_pattern("group-starting-with", population_atom, group_starting_with)?
+            }
+            (None, None, None, Some(group_ending_with)) => {
+                self.group_pattern("group-ending-with", population_atom, group_ending_with)?
+            }
+            _ => return Err(error::Error::XTSE1080.into()),
+        }
+        .atom_bindings();
+        let bindings = bindings.concat(groups_bindings);
+
+        let context_names = self.variables.push_context();
+        let group_names = self.variables.push_group();
+        let return_bindings = self.sequence_constructor(&for_each_group.sequence_constructor)?;
+        self.variables.pop_group();
+        self.variables.pop_context();
+
+        // we iterate over the group arrays; within each iteration the context
+        // item is the first item of the current group
+        let group_array = self.variables.new_name();
+        let group_array_atom = Spanned::new(ir::Atom::Variable(group_array.clone()), (0..0).into());
+        let group_atom = Spanned::new(ir::Atom::Variable(group_names.group.clone()), (0..0).into());
+        let head = ir::Expr::FunctionCall(ir::FunctionCall {
+            atom: Spanned::new(
+                self.static_function_atom("head", FN_NAMESPACE, 1),
+                (0..0).into(),
+            ),
+            args: vec![group_atom],
+        });
+        let return_expr = self.let_(context_names.item, head, return_bindings.expr());
+        let return_expr = self.let_(
+            group_names.key,
+            self.group_member(group_array_atom.clone(), 2),
+            return_expr,
+        );
+        let return_expr = self.let_(
+            group_names.group,
+            self.group_member(group_array_atom, 1),
+            return_expr,
+        );
+
+        let expr = ir::Expr::Map(ir::Map {
+            context_names: ir::ContextNames {
+                item: group_array,
+                position: context_names.position,
+                last: context_names.last,
+            },
+            var_atom: groups_atom,
+            return_expr: Box::new(return_expr),
+        });
+        Ok(bindings.bind_expr_no_span(&mut self.variables, expr))
+    }
+
+    // group using a grouping key calculated for each item in the population
+    fn group_keys(
+        &mut self,
+        function_name: &str,
+        population_atom: ir::AtomS,
+        key: &ast::Expression,
+        for_each_group: &ast::ForEachGroup,
+    ) -> error::SpannedResult<Bindings> {
+        // wrap each key in an array, so we retain the key sequence for each item
+        let context_names = self.variables.push_context();
+        let (key_atom, key_bindings) = self.expression(key)?.atom_bindings();
+        self.variables.pop_context();
+        let key_bindings = key_bindings.bind_expr_no_span(
+            &mut self.variables,
+            ir::Expr::ArrayConstructor(ir::ArrayConstructor::Square(vec![key_atom])),
+        );
+        let keys_expr = ir::Expr::Map(ir::Map {
+            context_names,
+            var_atom: population_atom.clone(),
+            return_expr: Box::new(key_bindings.expr()),
+        });
+        let (keys_atom, bindings) = Bindings::empty()
+            .bind_expr_no_span(&mut self.variables, keys_expr)
+            .atom_bindings();
+
+        let composite_name = if for_each_group.composite {
+            "true"
+        } else {
+            "false"
+        };
+        let composite_expr = ir::Expr::FunctionCall(ir::FunctionCall {
+            atom: Spanned::new(
+                self.static_function_atom(composite_name, FN_NAMESPACE, 0),
+                (0..0).into(),
+            ),
+            args: vec![],
+        });
+        let (composite_atom, composite_bindings) = Bindings::empty()
+            .bind_expr_no_span(&mut self.variables, composite_expr)
+            .atom_bindings();
+        let bindings = bindings.concat(composite_bindings);
+
+        let mut args = vec![population_atom, keys_atom, composite_atom];
+        let bindings = if let Some(collation) = &for_each_group.collation {
+            let (collation_atom, collation_bindings) =
+                self.attribute_value_template(collation)?.atom_bindings();
+            args.push(collation_atom);
+            bindings.concat(collation_bindings)
+        } else {
+            bindings
+        };
+        let expr = ir::Expr::FunctionCall(ir::FunctionCall {
+            atom: Spanned::new(
+                self.static_function_atom(function_name, FN_NAMESPACE, args.len() as u8),
+                (0..0).into(),
+            ),
+            args,
+        });
+        Ok(bindings.bind_expr_no_span(&mut self.variables, expr))
+    }
+
+    // group by matching a pattern against each item in the population
+    fn group_pattern(
+        &mut self,
+        function_name: &str,
+        population_atom: ir::AtomS,
+        pattern: &ast::Pattern,
+    ) -> error::SpannedResult<Bindings> {
+        let (matches_atom, bindings) = self
+            .pattern_matches(population_atom.clone(), pattern)?
+            .atom_bindings();
+        let expr = ir::Expr::FunctionCall(ir::FunctionCall {
+            atom: Spanned::new(
+                self.static_function_atom(function_name, FN_NAMESPACE, 2),
+                (0..0).into(),
+            ),
+            args: vec![population_atom, matches_atom],
+        });
+        Ok(bindings.bind_expr_no_span(&mut self.variables, expr))
+    }
+
+    // a sequence of booleans, parallel to the population, that indicates
+    // whether each item matches the pattern
+    fn pattern_matches(
+        &mut self,
+        population_atom: ir::AtomS,
+        pattern: &ast::Pattern,
+    ) -> error::SpannedResult<Bindings> {
+        let pattern = transform_pattern(&pattern.pattern, |expr| self.pattern_predicate(expr))?;
+        let context_names = self.variables.push_context();
+        let (item_atom, item_bindings) =
+            self.variables.context_item((0..0).into())?.atom_bindings();
+        self.variables.pop_context();
+        let matches_bindings = item_bindings.bind_expr_no_span(
+            &mut self.variables,
+            ir::Expr::MatchPattern(ir::MatchPattern {
+                pattern,
+                select: item_atom,
+            }),
+        );
+        let matches_expr = ir::Expr::Map(ir::Map {
+            context_names,
+            var_atom: population_atom,
+            return_expr: Box::new(matches_bindings.expr()),
+        });
+        Ok(Bindings::empty().bind_expr_no_span(&mut self.variables, matches_expr))
+    }
+
+    fn group_member(&self, group_array_atom: ir::AtomS, index: u8) -> ir::Expr {
+        ir::Expr::Lookup(ir::Lookup {
+            atom: group_array_atom,
+            arg_atom: Spanned::new(
+                ir::Atom::Const(ir::Const::Integer(index.into())),
+                (0..0).into(),
+            ),
+        })
+    }
+
+    fn let_(&self, name: ir::Name, var_expr: ir::Expr, return_expr: ir::ExprS) -> ir::ExprS {
+        Spanned::new(
+            ir::Expr::Let(ir::Let {
+                name,
+                var_expr: Box::new(Spanned::new(var_expr, (0..0).into())),
+                return_expr: Box::new(return_expr),
+            }),
+            (0..0).into(),
+        )
+    }
+
     fn copy(&mut self, copy: &ast::Copy) -> error::SpannedResult<Bindings> {
         let (context_atom, bindings) = if let Some(select) = &copy.select {
             self.expression(select)?.atom_bindings()
@@ -647,6 +1233,7 @@ impl<'a> IrConverter<'a> {
         // copy shallow this item
         let expr = ir::Expr::CopyShallow(ir::CopyShallow {
             select: context_atom,
+            copy_namespaces: copy.copy_namespaces,
         });
         let (copy_atom, bindings) = bindings
             .bind_expr_no_span(&mut self.variables, expr)
@@ -843,6 +1430,176 @@ impl<'a> IrConverter<'a> {
         ))
     }
 
+    fn number(&mut self, number: &ast::Number) -> error::SpannedResult<Bindings> {
+        if number.ordinal.is_some() {
+            return Err(error::Error::Unsupported.into());
+        }
+        let (numbers_atom, bindings) = if let Some(value) = &number.value {
+            if number.select.is_some()
+                || number.level.is_some()
+                || number.count.is_some()
+                || number.from.is_some()
+            {
+                return Err(error::Error::XTSE0975.into());
+            }
+            self.expression(value)?.atom_bindings()
+        } else {
+            self.number_place(number)?.atom_bindings()
+        };
+
+        let (format_atom, format_bindings) = self
+            .optional_attribute_value_template(&number.format, "1")?
+            .atom_bindings();
+        let (grouping_separator_atom, grouping_separator_bindings) =
+            if let Some(grouping_separator) = &number.grouping_separator {
+                self.attribute_value_template(grouping_separator)?
+                    .atom_bindings()
+            } else {
+                self.empty_sequence_atom_bindings()
+            };
+        let (grouping_size_atom, grouping_size_bindings) =
+            if let Some(grouping_size) = &number.grouping_size {
+                self.attribute_value_template(grouping_size)?
+                    .atom_bindings()
+            } else {
+                self.empty_sequence_atom_bindings()
+            };
+        let (start_at_atom, start_at_bindings) = self
+            .optional_attribute_value_template(&number.start_at, "1")?
+            .atom_bindings();
+        let bindings = bindings
+            .concat(format_bindings)
+            .concat(grouping_separator_bindings)
+            .concat(grouping_size_bindings)
+            .concat(start_at_bindings);
+
+        let expr = ir::Expr::FunctionCall(ir::FunctionCall {
+            atom: Spanned::new(
+                self.static_function_atom("number-format", FN_NAMESPACE, 5),
+                (0..0).into(),
+            ),
+            args: vec![
+                numbers_atom,
+                format_atom,
+                grouping_separator_atom,
+                grouping_size_atom,
+                start_at_atom,
+            ],
+        });
+        let (text_atom, bindings) = bindings
+            .bind_expr_no_span(&mut self.variables, expr)
+            .atom_bindings();
+        Ok(bindings.bind_expr_no_span(
+            &mut self.variables,
+            ir::Expr::XmlText(ir::XmlText { value: text_atom }),
+        ))
+    }
+
+    // the place of the selected node (or the context node) in the document,
+    // as a sequence of integers
+    fn number_place(&mut self, number: &ast::Number) -> error::SpannedResult<Bindings> {
+        let (node_atom, bindings) = if let Some(select) = &number.select {
+            self.expression(select)?.atom_bindings()
+        } else {
+            self.variables.context_item((0..0).into())?.atom_bindings()
+        };
+        let level = match number.level {
+            Some(ast::NumberLevel::Multiple) => "multiple",
+            Some(ast::NumberLevel::Any) => "any",
+            Some(ast::NumberLevel::Single) | None => "single",
+        };
+        let level_atom = Spanned::new(
+            ir::Atom::Const(ir::Const::String(level.to_string())),
+            (0..0).into(),
+        );
+        let selected_name = if number.select.is_some() {
+            "true"
+        } else {
+            "false"
+        };
+        let selected_expr = ir::Expr::FunctionCall(ir::FunctionCall {
+            atom: Spanned::new(
+                self.static_function_atom(selected_name, FN_NAMESPACE, 0),
+                (0..0).into(),
+            ),
+            args: vec![],
+        });
+        let (selected_atom, selected_bindings) = Bindings::empty()
+            .bind_expr_no_span(&mut self.variables, selected_expr)
+            .atom_bindings();
+        let bindings = bindings.concat(selected_bindings);
+
+        let candidates_expr = ir::Expr::FunctionCall(ir::FunctionCall {
+            atom: Spanned::new(
+                self.static_function_atom("number-candidates", FN_NAMESPACE, 3),
+                (0..0).into(),
+            ),
+            args: vec![node_atom.clone(), level_atom.clone(), selected_atom],
+        });
+        let (candidates_atom, candidates_bindings) = Bindings::empty()
+            .bind_expr_no_span(&mut self.variables, candidates_expr)
+            .atom_bindings();
+        let bindings = bindings.concat(candidates_bindings);
+
+        let (count_atom, count_bindings) = if let Some(count) = &number.count {
+            self.pattern_matches(candidates_atom.clone(), count)?
+                .atom_bindings()
+        } else {
+            self.empty_sequence_atom_bindings()
+        };
+        let (from_atom, from_bindings) = if let Some(from) = &number.from {
+            self.pattern_matches(candidates_atom.clone(), from)?
+                .atom_bindings()
+        } else {
+            self.empty_sequence_atom_bindings()
+        };
+        let bindings = bindings.concat(count_bindings).concat(from_bindings);
+
+        let expr = ir::Expr::FunctionCall(ir::FunctionCall {
+            atom: Spanned::new(
+                self.static_function_atom("number-node", FN_NAMESPACE, 5),
+                (0..0).into(),
+            ),
+            args: vec![
+                node_atom,
+                level_atom,
+                candidates_atom,
+                count_atom,
+                from_atom,
+            ],
+        });
+        Ok(bindings.bind_expr_no_span(&mut self.variables, expr))
+    }
+
+    fn optional_attribute_value_template<V>(
+        &mut self,
+        value_template: &Option<ast::ValueTemplate<V>>,
+        default: &str,
+    ) -> error::SpannedResult<Bindings>
+    where
+        V: Clone + PartialEq + Eq,
+    {
+        if let Some(value_template) = value_template {
+            self.attribute_value_template(value_template)
+        } else {
+            let default_atom = Spanned::new(
+                ir::Atom::Const(ir::Const::String(default.to_string())),
+                (0..0).into(),
+            );
+            Ok(Bindings::empty()
+                .bind_expr_no_span(&mut self.variables, ir::Expr::Atom(default_atom)))
+        }
+    }
+
+    fn empty_sequence_atom_bindings(&mut self) -> (ir::AtomS, Bindings) {
+        let empty_sequence = self.empty_sequence();
+        Bindings::new(
+            self.variables
+                .new_binding(empty_sequence.value, empty_sequence.span),
+        )
+        .atom_bindings()
+    }
+
     // fn throw_error(&mut self) -> error::SpannedResult<Bindings> {
     //     let error_atom = self.error_atom();
     //     let expr = ir::Expr::FunctionCall(ir::FunctionCall {
@@ -902,3 +1659,17 @@ impl<'a> IrConverter<'a> {
         })
     }
 }
+
+// a parameter without a default value is required if its declared type
+// doesn't allow the empty sequence
+fn is_implicitly_required(param: &ast::Param) -> bool {
+    let Some(xpath_ast::SequenceType::Item(item)) = &param.as_ else {
+        return false;
+    };
+    param.select.is_none()
+        && param.sequence_constructor.is_empty()
+        && matches!(
+            item.occurrence,
+            xpath_ast::Occurrence::One | xpath_ast::Occurrence::NonEmpty
+        )
+}
@@ -0,0 +1,210 @@
+// Recognize the identity template:
+//
+// <xsl:template match="..." mode="m">
+//   <xsl:copy>
+//     <xsl:apply-templates select="@*|node()" mode="m"/>
+//   </xsl:copy>
+// </xsl:template>
+//
+// When the interpreter knows that a rule is this template, it can copy a
+// subtree in bulk if every node in it would be processed by an identity
+// template as well.
+use xee_xpath_ast::ast as xpath_ast;
+use xee_xslt_ast::ast;
+
+pub(crate) fn is_identity_template(template: &ast::Template) -> bool {
+    if !template.params.is_empty() || template.as_.is_some() {
+        return false;
+    }
+    let mode = match template.mode.as_slice() {
+        [mode] => mode,
+        _ => return false,
+    };
+    let copy = match single_instruction(&template.sequence_constructor) {
+        Some(ast::SequenceConstructorInstruction::Copy(copy)) => copy,
+        _ => return false,
+    };
+    if copy.select.is_some()
+        || !copy.copy_namespaces
+        || copy.use_attribute_sets.is_some()
+        || copy.type_.is_some()
+    {
+        return false;
+    }
+    let apply_templates = match single_instruction(&copy.sequence_constructor) {
+        Some(ast::SequenceConstructorInstruction::ApplyTemplates(apply_templates)) => {
+            apply_templates
+        }
+        _ => return false,
+    };
+    apply_templates.content.is_empty()
+        && same_mode(mode, &apply_templates.mode)
+        && is_attributes_and_children(&apply_templates.select.xpath)
+}
+
+fn single_instruction(
+    sequence_constructor: &ast::SequenceConstructor,
+) -> Option<&ast::SequenceConstructorInstruction> {
+    match sequence_constructor.as_slice() {
+        [ast::SequenceConstructorItem::Instruction(instruction)] => Some(instruction),
+        _ => None,
+    }
+}
+
+fn same_mode(mode: &ast::ModeValue, apply_templates_mode: &ast::ApplyTemplatesModeValue) -> bool {
+    match (mode, apply_templates_mode) {
+        (ast::ModeValue::Unnamed, ast::ApplyTemplatesModeValue::Unnamed) => true,
+        (ast::ModeValue::EqName(name), ast::ApplyTemplatesModeValue::EqName(other)) => {
+            name == other
+        }
+        _ => false,
+    }
+}
+
+// `@*|node()` or `node()|@*`
+fn is_attributes_and_children(xpath: &xpath_ast::XPath) -> bool {
+    let binary = match single_expr(&xpath.0.value) {
+        Some(xpath_ast::ExprSingle::Binary(binary))
+            if binary.operator == xpath_ast::BinaryOperator::Union =>
+        {
+            binary
+        }
+        _ => return false,
+    };
+    let left = single_step(&binary.left);
+    let right = single_step(&binary.right);
+    match (left, right) {
+        (Some(left), Some(right)) => {
+            (is_all_attributes(left) && is_all_children(right))
+                || (is_all_children(left) && is_all_attributes(right))
+        }
+        _ => false,
+    }
+}
+
+// the single expression, looking through any parentheses around it
+fn single_expr(expr: &xpath_ast::Expr) -> Option<&xpath_ast::ExprSingle> {
+    let expr_single = match expr.0.as_slice() {
+        [expr_single] => &expr_single.value,
+        _ => return None,
+    };
+    if let xpath_ast::ExprSingle::Path(path) = expr_single {
+        if let [step] = path.steps.as_slice() {
+            if let xpath_ast::StepExpr::PrimaryExpr(primary) = &step.value {
+                if let xpath_ast::PrimaryExpr::Expr(inner) = &primary.value {
+                    return single_expr(inner.value.as_ref()?);
+                }
+            }
+        }
+    }
+    Some(expr_single)
+}
+
+fn single_step(path: &xpath_ast::PathExpr) -> Option<&xpath_ast::AxisStep> {
+    match path.steps.as_slice() {
+        [step] => match &step.value {
+            xpath_ast::StepExpr::AxisStep(axis_step) if axis_step.predicates.is_empty() => {
+                Some(axis_step)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_all_attributes(step: &xpath_ast::AxisStep) -> bool {
+    step.axis == xpath_ast::Axis::Attribute
+        && matches!(
+            step.node_test,
+            xpath_ast::NodeTest::NameTest(xpath_ast::NameTest::Star)
+        )
+}
+
+fn is_all_children(step: &xpath_ast::AxisStep) -> bool {
+    step.axis == xpath_ast::Axis::Child
+        && matches!(
+            step.node_test,
+            xpath_ast::NodeTest::KindTest(xpath_ast::KindTest::Any)
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use xee_xslt_ast::parse_transform;
+
+    fn first_template(xslt: &str) -> ast::Template {
+        let transform = parse_transform(xslt).unwrap();
+        transform
+            .declarations
+            .into_iter()
+            .find_map(|declaration| match declaration {
+                ast::Declaration::Template(template) => Some(*template),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_identity_template() {
+        let template = first_template(
+            r#"<xsl:stylesheet xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="node()" priority="1">
+    <xsl:copy>
+      <xsl:apply-templates select="@*|node()"/>
+    </xsl:copy>
+  </xsl:template>
+</xsl:stylesheet>"#,
+        );
+        assert!(is_identity_template(&template));
+    }
+
+    #[test]
+    fn test_identity_template_reversed_select() {
+        let template = first_template(
+            r#"<xsl:stylesheet xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="node()" mode="m" priority="1">
+    <xsl:copy><xsl:apply-templates select="node()|@*" mode="m"/></xsl:copy>
+  </xsl:template>
+</xsl:stylesheet>"#,
+        );
+        assert!(is_identity_template(&template));
+    }
+
+    #[test]
+    fn test_not_identity_template_other_mode() {
+        let template = first_template(
+            r#"<xsl:stylesheet xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="node()" mode="m" priority="1">
+    <xsl:copy><xsl:apply-templates select="@*|node()"/></xsl:copy>
+  </xsl:template>
+</xsl:stylesheet>"#,
+        );
+        assert!(!is_identity_template(&template));
+    }
+
+    #[test]
+    fn test_not_identity_template_extra_content() {
+        let template = first_template(
+            r#"<xsl:stylesheet xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="node()" priority="1">
+    <xsl:copy><x/><xsl:apply-templates select="@*|node()"/></xsl:copy>
+  </xsl:template>
+</xsl:stylesheet>"#,
+        );
+        assert!(!is_identity_template(&template));
+    }
+
+    #[test]
+    fn test_not_identity_template_only_children() {
+        let template = first_template(
+            r#"<xsl:stylesheet xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="node()" priority="1">
+    <xsl:copy><xsl:apply-templates select="node()"/></xsl:copy>
+  </xsl:template>
+</xsl:stylesheet>"#,
+        );
+        assert!(!is_identity_template(&template));
+    }
+}
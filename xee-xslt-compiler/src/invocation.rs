@@ -0,0 +1,98 @@
+use xee_interpreter::error;
+use xee_xslt_ast::{ast, parse_transform};
+use xot::xmlname::OwnedName;
+
+/// How a transformation is started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invocation {
+    /// Apply templates to the source document, in the given mode. `None` is
+    /// the unnamed mode.
+    ApplyTemplates(Option<OwnedName>),
+    /// Call the named template. The source document, if any, is the context
+    /// item.
+    CallTemplate(OwnedName),
+}
+
+impl Default for Invocation {
+    fn default() -> Self {
+        Invocation::ApplyTemplates(None)
+    }
+}
+
+/// The named templates and modes of a stylesheet, which can be used to
+/// start a transformation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntryPoints {
+    /// The names of the named templates, in declaration order.
+    pub templates: Vec<OwnedName>,
+    /// The names of the modes, in declaration order. The unnamed mode is
+    /// always available and isn't included.
+    pub modes: Vec<OwnedName>,
+}
+
+impl EntryPoints {
+    pub(crate) fn new(transform: &ast::Transform) -> Self {
+        let mut entry_points = EntryPoints::default();
+        for declaration in &transform.declarations {
+            match declaration {
+                ast::Declaration::Template(template) => {
+                    if let Some(name) = &template.name {
+                        entry_points.add_template(name);
+                    }
+                    for mode in &template.mode {
+                        if let ast::ModeValue::EqName(name) = mode {
+                            entry_points.add_mode(name);
+                        }
+                    }
+                }
+                ast::Declaration::Mode(mode) => {
+                    if let Some(name) = &mode.name {
+                        entry_points.add_mode(name);
+                    }
+                }
+                _ => {}
+            }
+        }
+        entry_points
+    }
+
+    fn add_template(&mut self, name: &OwnedName) {
+        if !self.templates.contains(name) {
+            self.templates.push(name.clone());
+        }
+    }
+
+    fn add_mode(&mut self, name: &OwnedName) {
+        if !self.modes.contains(name) {
+            self.modes.push(name.clone());
+        }
+    }
+
+    /// Check that the mode or template of the invocation exists.
+    pub fn check(&self, invocation: &Invocation) -> error::Result<()> {
+        match invocation {
+            Invocation::ApplyTemplates(None) => Ok(()),
+            Invocation::ApplyTemplates(Some(mode)) => {
+                if self.modes.contains(mode) {
+                    Ok(())
+                } else {
+                    Err(error::Error::XTDE0045)
+                }
+            }
+            Invocation::CallTemplate(name) => {
+                if self.templates.contains(name) {
+                    Ok(())
+                } else {
+                    Err(error::Error::XTDE0040)
+                }
+            }
+        }
+    }
+}
+
+/// The named templates and modes of a stylesheet.
+pub fn entry_points(xslt: &str) -> error::SpannedResult<EntryPoints> {
+    // TODO: better error handling
+    let transform = parse_transform(xslt).map_err(|_e| error::Error::Unsupported)?;
+    Ok(EntryPoints::new(&transform))
+}
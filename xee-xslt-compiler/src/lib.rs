@@ -1,7 +1,14 @@
 mod ast_ir;
-mod default_declarations;
+mod identity;
+mod invocation;
 mod priority;
 mod run;
+mod serialization;
 
-pub use ast_ir::parse;
-pub use run::evaluate;
+pub use ast_ir::{parse, parse_with_invocation};
+pub use invocation::{entry_points, EntryPoints, Invocation};
+pub use run::{
+    evaluate, evaluate_program, evaluate_program_with_params, evaluate_with_invocation,
+    evaluate_with_params, static_context,
+};
+pub use serialization::serialization_parameters;
@@ -1,37 +1,84 @@
 use xee_name::{Namespaces, FN_NAMESPACE};
 use xot::{Node, Xot};
 
-use xee_interpreter::context::StaticContext;
+use xee_interpreter::context::{StaticContext, Variables};
 use xee_interpreter::error;
 use xee_interpreter::interpreter::Program;
 use xee_interpreter::sequence;
 
-use crate::ast_ir::parse;
+use crate::ast_ir::parse_with_invocation;
+use crate::invocation::Invocation;
+
+const XSL_NAMESPACE: &str = "http://www.w3.org/1999/XSL/Transform";
 
 pub fn evaluate_program(
     xot: &mut Xot,
     program: &Program,
-    root: Node,
+    root: Option<Node>,
+) -> error::SpannedResult<sequence::Sequence> {
+    evaluate_program_with_params(xot, program, root, Variables::default())
+}
+
+/// Evaluate a compiled stylesheet, with values for its stylesheet
+/// parameters by name.
+pub fn evaluate_program_with_params(
+    xot: &mut Xot,
+    program: &Program,
+    root: Option<Node>,
+    params: Variables,
 ) -> error::SpannedResult<sequence::Sequence> {
     let mut documents = xee_interpreter::xml::Documents::new();
-    let handle = documents.add_root(None, root).unwrap();
-    let root = documents.get_node_by_handle(handle).unwrap();
     let mut dynamic_context_builder = program.dynamic_context_builder();
-    dynamic_context_builder.context_node(root);
+    if let Some(root) = root {
+        let handle = documents.add_root(None, root).unwrap();
+        let root = documents.get_node_by_handle(handle).unwrap();
+        dynamic_context_builder.context_node(root);
+    }
     dynamic_context_builder.documents(documents);
+    dynamic_context_builder.variables(params);
     let context = dynamic_context_builder.build();
     let runnable = program.runnable(&context);
     runnable.many(xot)
 }
 
 pub fn evaluate(xot: &mut Xot, xml: &str, xslt: &str) -> error::SpannedResult<sequence::Sequence> {
-    let namespaces = Namespaces::new(
-        Namespaces::default_namespaces(),
-        "".to_string(),
-        FN_NAMESPACE.to_string(),
-    );
-    let static_context = StaticContext::from_namespaces(namespaces);
+    evaluate_with_invocation(xot, Some(xml), xslt, &Invocation::default())
+}
+
+/// Evaluate a stylesheet, with values for its stylesheet parameters by name.
+pub fn evaluate_with_params(
+    xot: &mut Xot,
+    xml: &str,
+    xslt: &str,
+    params: Variables,
+) -> error::SpannedResult<sequence::Sequence> {
     let root = xot.parse(xml).unwrap();
-    let program = parse(static_context, xslt).unwrap();
+    let program = parse_with_invocation(static_context(), xslt, &Invocation::default())?;
+    evaluate_program_with_params(xot, &program, Some(root), params)
+}
+
+/// Evaluate a stylesheet, starting as described by `invocation`.
+///
+/// Without `xml` there is no source document, so there is no context item.
+/// This is useful with [`Invocation::CallTemplate`].
+pub fn evaluate_with_invocation(
+    xot: &mut Xot,
+    xml: Option<&str>,
+    xslt: &str,
+    invocation: &Invocation,
+) -> error::SpannedResult<sequence::Sequence> {
+    let root = xml.map(|xml| xot.parse(xml).unwrap());
+    let program = parse_with_invocation(static_context(), xslt, invocation)?;
     evaluate_program(xot, &program, root)
 }
+
+/// The static context to compile a stylesheet with.
+pub fn static_context() -> StaticContext {
+    // lexical QNames resolved at runtime, such as the argument of
+    // system-property, are resolved against these namespaces rather than
+    // those in scope in the stylesheet, so we at least bind the xsl prefix
+    let mut default_namespaces = Namespaces::default_namespaces();
+    default_namespaces.insert("xsl".to_string(), XSL_NAMESPACE.to_string());
+    let namespaces = Namespaces::new(default_namespaces, "".to_string(), FN_NAMESPACE.to_string());
+    StaticContext::from_namespaces(namespaces)
+}
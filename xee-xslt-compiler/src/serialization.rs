@@ -0,0 +1,112 @@
+use ahash::{HashMap, HashMapExt};
+
+use xee_interpreter::{error, sequence::SerializationParameters, span::SourceSpan};
+use xee_xslt_ast::{ast, parse_transform};
+
+/// The serialization parameters declared by the unnamed `xsl:output` of a
+/// stylesheet.
+///
+/// For now only `omit-xml-declaration` and `use-character-maps` are taken
+/// from `xsl:output`; the other parameters have their default values.
+pub fn serialization_parameters(xslt: &str) -> error::SpannedResult<SerializationParameters> {
+    let transform = parse_transform(xslt).map_err(|_| error::Error::Unsupported)?;
+    transform_serialization_parameters(&transform)
+}
+
+fn transform_serialization_parameters(
+    transform: &ast::Transform,
+) -> error::SpannedResult<SerializationParameters> {
+    let character_maps = CharacterMaps::new(transform)?;
+    let mut parameters = SerializationParameters::new();
+    for declaration in &transform.declarations {
+        if let ast::Declaration::Output(output) = declaration {
+            if output.name.is_some() {
+                continue;
+            }
+            parameters.omit_xml_declaration = output.omit_xml_declaration;
+            if let Some(names) = &output.use_character_maps {
+                let character_map = character_maps.character_map(names, &output.span)?;
+                parameters.use_character_maps.extend(character_map);
+            }
+        }
+    }
+    Ok(parameters)
+}
+
+/// Check the character maps of a stylesheet for unknown names and
+/// circularity.
+pub(crate) fn check_character_maps(transform: &ast::Transform) -> error::SpannedResult<()> {
+    CharacterMaps::new(transform).map(|_| ())
+}
+
+struct CharacterMaps<'a> {
+    character_maps: HashMap<&'a ast::EqName, &'a ast::CharacterMap>,
+}
+
+impl<'a> CharacterMaps<'a> {
+    fn new(transform: &'a ast::Transform) -> error::SpannedResult<Self> {
+        let mut character_maps = HashMap::new();
+        for declaration in &transform.declarations {
+            if let ast::Declaration::CharacterMap(character_map) = declaration {
+                if character_maps
+                    .insert(&character_map.name, character_map.as_ref())
+                    .is_some()
+                {
+                    return Err(error::Error::XTSE1580.with_span(source_span(&character_map.span)));
+                }
+            }
+        }
+        let character_maps = Self { character_maps };
+        // a character map is checked even if no xsl:output uses it
+        for &character_map in character_maps.character_maps.values() {
+            character_maps.resolve(&character_map.name, &character_map.span, &mut Vec::new())?;
+        }
+        Ok(character_maps)
+    }
+
+    // The combined character map of the named character maps. Characters
+    // of later maps take precedence over those of earlier maps.
+    fn character_map(
+        &self,
+        names: &'a [ast::EqName],
+        span: &ast::Span,
+    ) -> error::SpannedResult<HashMap<char, String>> {
+        let mut result = HashMap::new();
+        for name in names {
+            result.extend(self.resolve(name, span, &mut Vec::new())?);
+        }
+        Ok(result)
+    }
+
+    fn resolve(
+        &self,
+        name: &'a ast::EqName,
+        span: &ast::Span,
+        active: &mut Vec<&'a ast::EqName>,
+    ) -> error::SpannedResult<HashMap<char, String>> {
+        let character_map: &'a ast::CharacterMap = self
+            .character_maps
+            .get(name)
+            .copied()
+            .ok_or_else(|| error::Error::XTSE1590.with_span(source_span(span)))?;
+        if active.contains(&name) {
+            return Err(error::Error::XTSE1600.with_span(source_span(&character_map.span)));
+        }
+        active.push(name);
+        let mut result = HashMap::new();
+        for used in character_map.use_character_maps.iter().flatten() {
+            result.extend(self.resolve(used, &character_map.span, active)?);
+        }
+        active.pop();
+        // the characters of a map itself take precedence over those of the
+        // maps it uses
+        for output_character in &character_map.output_characters {
+            result.insert(output_character.character, output_character.string.clone());
+        }
+        Ok(result)
+    }
+}
+
+fn source_span(span: &ast::Span) -> SourceSpan {
+    (span.start..span.end).into()
+}
@@ -1,7 +1,11 @@
 use std::fmt::Write;
 
-use xee_interpreter::{error, sequence::Sequence};
-use xee_xslt_compiler::evaluate;
+use xee_interpreter::{atomic::Atomic, context::Variables, error, sequence::Sequence};
+use xee_xslt_compiler::{
+    entry_points, evaluate, evaluate_with_invocation, evaluate_with_params,
+    serialization_parameters, Invocation,
+};
+use xot::xmlname::OwnedName;
 use xot::Xot;
 
 fn xml(xot: &Xot, sequence: Sequence) -> String {
@@ -1143,3 +1147,1364 @@ fn test_generate_text_node() {
 
     assert_eq!(xml(&xot, output), r#"<out>test</out>"#);
 }
+
+#[test]
+fn test_for_each_group_by() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><item k="b">1</item><item k="a">2</item><item k="b">3</item></doc>"#,
+        r#"
+<xsl:transform expand-text="true" xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:for-each-group select="doc/item" group-by="@k">
+      <g key="{current-grouping-key()}" first="{.}">{current-group()}</g>
+    </xsl:for-each-group></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    // groups are in order of first appearance
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><g key="b" first="1">1 3</g><g key="a" first="2">2</g></o>"#
+    );
+}
+
+#[test]
+fn test_for_each_group_by_multiple_keys() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><item k="a b">1</item><item k="b">2</item></doc>"#,
+        r#"
+<xsl:transform expand-text="true" xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:for-each-group select="doc/item" group-by="tokenize(@k, ' ')">
+      <g key="{current-grouping-key()}">{current-group()}</g>
+    </xsl:for-each-group></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><g key="a">1</g><g key="b">1 2</g></o>"#
+    );
+}
+
+#[test]
+fn test_for_each_group_by_composite() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><item a="1" b="2">x</item><item a="1" b="3">y</item><item a="1" b="2">z</item></doc>"#,
+        r#"
+<xsl:transform expand-text="true" xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:for-each-group select="doc/item" group-by="@a, @b" composite="yes">
+      <g key="{current-grouping-key()}">{current-group()}</g>
+    </xsl:for-each-group></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><g key="1 2">x z</g><g key="1 3">y</g></o>"#
+    );
+}
+
+#[test]
+fn test_for_each_group_by_collation() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><item k="a">1</item><item k="A">2</item><item k="b">3</item></doc>"#,
+        r#"
+<xsl:transform expand-text="true" xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:for-each-group select="doc/item" group-by="@k" collation="http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive">
+      <g>{current-group()}</g>
+    </xsl:for-each-group></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), r#"<o><g>1 2</g><g>3</g></o>"#);
+}
+
+#[test]
+fn test_for_each_group_adjacent() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><item k="a">1</item><item k="a">2</item><item k="b">3</item><item k="a">4</item></doc>"#,
+        r#"
+<xsl:transform expand-text="true" xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:for-each-group select="doc/item" group-adjacent="@k">
+      <g key="{current-grouping-key()}" position="{position()}" last="{last()}">{current-group()}</g>
+    </xsl:for-each-group></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><g key="a" position="1" last="3">1 2</g><g key="b" position="2" last="3">3</g><g key="a" position="3" last="3">4</g></o>"#
+    );
+}
+
+#[test]
+fn test_for_each_group_adjacent_not_single_key() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><item>1</item></doc>"#,
+        r#"
+<xsl:transform expand-text="true" xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:for-each-group select="doc/item" group-adjacent="@k">
+      <g>{current-group()}</g>
+    </xsl:for-each-group></o>
+  </xsl:template>
+</xsl:transform>"#,
+    );
+
+    assert!(matches!(
+        output,
+        error::SpannedResult::Err(error::SpannedError {
+            error: error::Error::XTTE1100,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_for_each_group_starting_with() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><p>0</p><h>1</h><p>2</p><p>3</p><h>4</h><p>5</p></doc>"#,
+        r#"
+<xsl:transform expand-text="true" xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:for-each-group select="doc/*" group-starting-with="h">
+      <g>{current-group()}</g>
+    </xsl:for-each-group></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><g>0</g><g>1 2 3</g><g>4 5</g></o>"#
+    );
+}
+
+#[test]
+fn test_for_each_group_ending_with() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><p>0</p><e>1</e><p>2</p><p>3</p><e>4</e><p>5</p></doc>"#,
+        r#"
+<xsl:transform expand-text="true" xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:for-each-group select="doc/*" group-ending-with="e">
+      <g>{current-group()}</g>
+    </xsl:for-each-group></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><g>0 1</g><g>2 3 4</g><g>5</g></o>"#
+    );
+}
+
+#[test]
+fn test_for_each_group_starting_with_non_node() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc/>"#,
+        r#"
+<xsl:transform expand-text="true" xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:for-each-group select="(1, 2)" group-starting-with="h">
+      <g>{current-group()}</g>
+    </xsl:for-each-group></o>
+  </xsl:template>
+</xsl:transform>"#,
+    );
+
+    assert!(matches!(
+        output,
+        error::SpannedResult::Err(error::SpannedError {
+            error: error::Error::XTTE1120,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_number_single() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><x/><y/><x/><x/></doc>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:apply-templates select="doc/x"/></o>
+  </xsl:template>
+  <xsl:template match="x">
+    <n><xsl:number/></n>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), r#"<o><n>1</n><n>2</n><n>3</n></o>"#);
+}
+
+#[test]
+fn test_number_multiple() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><section><section/><section><section/><p/><section/><section/></section></section></doc>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:apply-templates select="//section"/></o>
+  </xsl:template>
+  <xsl:template match="section">
+    <n><xsl:number level="multiple" count="section"/></n>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><n>1</n><n>1.1</n><n>1.2</n><n>1.2.1</n><n>1.2.2</n><n>1.2.3</n></o>"#
+    );
+}
+
+#[test]
+fn test_number_multiple_format() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><chapter><section/><section/></chapter><chapter><section/></chapter></doc>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:apply-templates select="//section"/></o>
+  </xsl:template>
+  <xsl:template match="section">
+    <n><xsl:number level="multiple" count="chapter|section" format="I.a "/></n>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><n>I.a </n><n>I.b </n><n>II.a </n></o>"#
+    );
+}
+
+#[test]
+fn test_number_any_from() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><chapter><note/><p><note/></p></chapter><chapter><note/></chapter></doc>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:for-each select="//note">
+      <n><xsl:number level="any"/>/<xsl:number level="any" from="chapter" format="(i)"/></n>
+    </xsl:for-each></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><n>1/(i)</n><n>2/(ii)</n><n>3/(i)</n></o>"#
+    );
+}
+
+#[test]
+fn test_number_select() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><a/><a/><a/></doc>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:number select="doc/a[3]" format="A"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), r#"<o>C</o>"#);
+}
+
+#[test]
+fn test_number_value() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc/>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><a><xsl:number value="1234567" grouping-separator="," grouping-size="3"/></a><b><xsl:number value="(3, 1.6)" format="01-a"/></b><c><xsl:number value="5" start-at="0"/></c></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><a>1,234,567</a><b>03-b</b><c>4</c></o>"#
+    );
+}
+
+#[test]
+fn test_number_words() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc/>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><a><xsl:number value="21" format="w"/></a><b><xsl:number value="3" format="W"/></b><c><xsl:number value="(1, 2)" format="Ww"/></c></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><a>twenty-one</a><b>THREE</b><c>One.Two</c></o>"#
+    );
+}
+
+#[test]
+fn test_number_value_negative() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc/>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:number value="-1"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    );
+    assert!(matches!(
+        output,
+        error::SpannedResult::Err(error::SpannedError {
+            error: error::Error::XTDE0980,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_try_catch() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc/>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" xmlns:err="http://www.w3.org/2005/xqt-errors" version="3">
+  <xsl:template match="/">
+    <o><xsl:try select="xs:integer('a')" xmlns:xs="http://www.w3.org/2001/XMLSchema"><xsl:catch errors="err:FOAR0001"><a/></xsl:catch><xsl:catch errors="err:FORG0001"><b><xsl:value-of select="local-name-from-QName($err:code)"/></b></xsl:catch></xsl:try></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), r#"<o><b>FORG0001</b></o>"#);
+}
+
+#[test]
+fn test_try_catch_no_error() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc/>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:try><a/><xsl:catch select="'caught'"/></xsl:try></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), r#"<o><a/></o>"#);
+}
+
+const INVOCATION_XSLT: &str = r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template name="main"><main/></xsl:template>
+  <xsl:template match="/"><unnamed/></xsl:template>
+  <xsl:template match="/" mode="m"><m><xsl:apply-templates select="doc/*" mode="m"/></m></xsl:template>
+  <xsl:template match="x" mode="m"><x/></xsl:template>
+</xsl:transform>"#;
+
+#[test]
+fn test_initial_template_without_context_node() {
+    let mut xot = Xot::new();
+    let output = evaluate_with_invocation(
+        &mut xot,
+        None,
+        INVOCATION_XSLT,
+        &Invocation::CallTemplate(OwnedName::name("main")),
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<main/>");
+}
+
+#[test]
+fn test_initial_template_context_item_absent() {
+    let mut xot = Xot::new();
+    let output = evaluate_with_invocation(
+        &mut xot,
+        None,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template name="main"><o><xsl:value-of select="name(.)"/></o></xsl:template>
+</xsl:transform>"#,
+        &Invocation::CallTemplate(OwnedName::name("main")),
+    );
+    assert!(matches!(
+        output,
+        error::SpannedResult::Err(error::SpannedError {
+            error: error::Error::XPDY0002,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_initial_template_with_context_node() {
+    let mut xot = Xot::new();
+    let output = evaluate_with_invocation(
+        &mut xot,
+        Some("<doc/>"),
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template name="main"><o><xsl:value-of select="name(*)"/></o></xsl:template>
+</xsl:transform>"#,
+        &Invocation::CallTemplate(OwnedName::name("main")),
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<o>doc</o>");
+}
+
+#[test]
+fn test_initial_template_unknown() {
+    let mut xot = Xot::new();
+    let output = evaluate_with_invocation(
+        &mut xot,
+        None,
+        INVOCATION_XSLT,
+        &Invocation::CallTemplate(OwnedName::name("unknown")),
+    );
+    assert!(matches!(
+        output,
+        error::SpannedResult::Err(error::SpannedError {
+            error: error::Error::XTDE0040,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_initial_template_param_default() {
+    let mut xot = Xot::new();
+    let output = evaluate_with_invocation(
+        &mut xot,
+        None,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template name="main">
+    <xsl:param name="greeting" select="'hello'"/>
+    <o><xsl:value-of select="$greeting"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+        &Invocation::CallTemplate(OwnedName::name("main")),
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<o>hello</o>");
+}
+
+#[test]
+fn test_initial_template_param_required() {
+    let mut xot = Xot::new();
+    let output = evaluate_with_invocation(
+        &mut xot,
+        None,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template name="main">
+    <xsl:param name="greeting" required="yes"/>
+    <o><xsl:value-of select="$greeting"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+        &Invocation::CallTemplate(OwnedName::name("main")),
+    );
+    assert_eq!(output.unwrap_err().error, error::Error::XTDE0700);
+}
+
+#[test]
+fn test_call_template() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc><a/><b/></doc>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:call-template name="names"/></o>
+  </xsl:template>
+  <xsl:template name="names">
+    <xsl:for-each select="doc/*"><xsl:value-of select="name(.)"/></xsl:for-each>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<o>ab</o>");
+}
+
+#[test]
+fn test_call_template_with_param() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <xsl:variable name="a" select="'caller'"/>
+    <o>
+      <xsl:call-template name="t">
+        <xsl:with-param name="a" select="$a || '!'"/>
+      </xsl:call-template>
+      <xsl:value-of select="$a"/>
+    </o>
+  </xsl:template>
+  <xsl:template name="t">
+    <xsl:param name="a"/>
+    <xsl:param name="b" select="$a || '?'"/>
+    <t><xsl:value-of select="$a, $b"/></t>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<o><t>caller! caller!?</t>caller</o>");
+}
+
+#[test]
+fn test_call_template_param_as() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" xmlns:xs="http://www.w3.org/2001/XMLSchema" version="3">
+  <xsl:template match="/">
+    <o>
+      <xsl:call-template name="t">
+        <xsl:with-param name="n" select="'forty-one'"/>
+      </xsl:call-template>
+    </o>
+  </xsl:template>
+  <xsl:template name="t">
+    <xsl:param name="n" as="xs:integer"/>
+    <xsl:value-of select="$n + 1"/>
+  </xsl:template>
+</xsl:transform>"#,
+    );
+    assert_eq!(output.unwrap_err().error, error::Error::XTTE0570);
+}
+
+#[test]
+fn test_call_template_required_param_missing() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/"><o><xsl:call-template name="t"/></o></xsl:template>
+  <xsl:template name="t">
+    <xsl:param name="n" required="yes"/>
+    <xsl:value-of select="$n"/>
+  </xsl:template>
+</xsl:transform>"#,
+    );
+    assert_eq!(output.unwrap_err().error, error::Error::XTSE0690);
+}
+
+#[test]
+fn test_call_template_unknown_param() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o>
+      <xsl:call-template name="t">
+        <xsl:with-param name="m" select="1"/>
+      </xsl:call-template>
+    </o>
+  </xsl:template>
+  <xsl:template name="t"><t/></xsl:template>
+</xsl:transform>"#,
+    );
+    assert_eq!(output.unwrap_err().error, error::Error::XTSE0680);
+}
+
+#[test]
+fn test_initial_mode() {
+    let mut xot = Xot::new();
+    let output = evaluate_with_invocation(
+        &mut xot,
+        Some("<doc><x/><x/></doc>"),
+        INVOCATION_XSLT,
+        &Invocation::ApplyTemplates(Some(OwnedName::name("m"))),
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<m><x/><x/></m>");
+}
+
+#[test]
+fn test_initial_mode_unnamed() {
+    let mut xot = Xot::new();
+    let output = evaluate_with_invocation(
+        &mut xot,
+        Some("<doc/>"),
+        INVOCATION_XSLT,
+        &Invocation::ApplyTemplates(None),
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<unnamed/>");
+}
+
+#[test]
+fn test_initial_mode_unknown() {
+    let mut xot = Xot::new();
+    let output = evaluate_with_invocation(
+        &mut xot,
+        Some("<doc/>"),
+        INVOCATION_XSLT,
+        &Invocation::ApplyTemplates(Some(OwnedName::name("unknown"))),
+    );
+    assert!(matches!(
+        output,
+        error::SpannedResult::Err(error::SpannedError {
+            error: error::Error::XTDE0045,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_entry_points() {
+    let entry_points = entry_points(INVOCATION_XSLT).unwrap();
+    assert_eq!(entry_points.templates, vec![OwnedName::name("main")]);
+    assert_eq!(entry_points.modes, vec![OwnedName::name("m")]);
+}
+
+#[test]
+fn test_accumulator_sum() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><v>1</v><v>2</v><v>3</v></doc>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:accumulator name="sum" initial-value="0">
+    <xsl:accumulator-rule match="v" select="$value + xs:integer(.)" xmlns:xs="http://www.w3.org/2001/XMLSchema"/>
+  </xsl:accumulator>
+  <xsl:template match="/">
+    <o><xsl:apply-templates select="doc/v"/><total><xsl:value-of select="accumulator-after('sum')"/></total></o>
+  </xsl:template>
+  <xsl:template match="v">
+    <s><xsl:value-of select="accumulator-before('sum')"/></s>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        "<o><s>1</s><s>3</s><s>6</s><total>6</total></o>"
+    );
+}
+
+#[test]
+fn test_accumulator_phases() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><s><s/></s><s/></doc>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:accumulator name="depth" initial-value="0">
+    <xsl:accumulator-rule match="s" phase="start" select="$value + 1"/>
+    <xsl:accumulator-rule match="s" phase="end" select="$value - 1"/>
+  </xsl:accumulator>
+  <xsl:template match="/">
+    <o><xsl:apply-templates select="//s"/></o>
+  </xsl:template>
+  <xsl:template match="s">
+    <d before="{accumulator-before('depth')}" after="{accumulator-after('depth')}"/>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<o><d before="1" after="0"/><d before="2" after="1"/><d before="1" after="0"/></o>"#
+    );
+}
+
+#[test]
+fn test_accumulator_sequence_constructor() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><a/><b/><a/></doc>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:accumulator name="names" initial-value="()">
+    <xsl:accumulator-rule match="*">
+      <xsl:sequence select="$value, local-name()"/>
+    </xsl:accumulator-rule>
+  </xsl:accumulator>
+  <xsl:template match="/">
+    <o><xsl:value-of select="doc/a[2]/accumulator-before('names')"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<o>doc a b a</o>");
+}
+
+#[test]
+fn test_accumulator_last_matching_rule() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><a/><b/></doc>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:accumulator name="last" initial-value="''">
+    <xsl:accumulator-rule match="*" select="'any'"/>
+    <xsl:accumulator-rule match="a" select="'a'"/>
+  </xsl:accumulator>
+  <xsl:template match="/">
+    <o><xsl:value-of select="doc/*/accumulator-before('last')"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<o>a any</o>");
+}
+
+#[test]
+fn test_accumulator_initial_value_context() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><a/><a/></doc>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:accumulator name="remaining" initial-value="count(//a)">
+    <xsl:accumulator-rule match="a" phase="end" select="$value - 1"/>
+  </xsl:accumulator>
+  <xsl:template match="/">
+    <o><xsl:value-of select="accumulator-before('remaining'), doc/a/accumulator-after('remaining')"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<o>2 1 0</o>");
+}
+
+#[test]
+fn test_accumulator_unknown() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc/>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:value-of select="accumulator-before('unknown')"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    );
+    assert!(matches!(
+        output,
+        error::SpannedResult::Err(error::SpannedError {
+            error: error::Error::XTDE3340,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_accumulator_attribute() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc a="1"/>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:accumulator name="count" initial-value="0">
+    <xsl:accumulator-rule match="*" select="$value + 1"/>
+  </xsl:accumulator>
+  <xsl:template match="/">
+    <o><xsl:value-of select="doc/@a/accumulator-before('count')"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    );
+    assert!(matches!(
+        output,
+        error::SpannedResult::Err(error::SpannedError {
+            error: error::Error::XTTE3360,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_accumulator_cycle() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc/>"#,
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:accumulator name="cycle" initial-value="0">
+    <xsl:accumulator-rule match="doc" select="accumulator-after('cycle')"/>
+  </xsl:accumulator>
+  <xsl:template match="/">
+    <o><xsl:value-of select="accumulator-before('cycle')"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    );
+    assert!(matches!(
+        output,
+        error::SpannedResult::Err(error::SpannedError {
+            error: error::Error::XTDE3400,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_system_property() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o>
+      <version><xsl:value-of select="system-property('xsl:version')"/></version>
+      <vendor><xsl:value-of select="system-property('xsl:vendor') ne ''"/></vendor>
+      <product><xsl:value-of select="system-property('xsl:product-name')"/></product>
+      <unknown><xsl:value-of select="system-property('xsl:unknown')"/></unknown>
+      <no-namespace><xsl:value-of select="system-property('version')"/></no-namespace>
+    </o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        "<o><version>3.0</version><vendor>true</vendor><product>Xee</product><unknown/><no-namespace/></o>"
+    );
+}
+
+#[test]
+fn test_available_system_properties() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:value-of select="every $p in available-system-properties() satisfies system-property(string($p)) ne ''"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<o>true</o>");
+}
+
+#[test]
+fn test_system_property_undeclared_prefix() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/">
+    <o><xsl:value-of select="system-property('foo:version')"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    );
+    assert!(matches!(
+        output,
+        error::SpannedResult::Err(error::SpannedError {
+            error: error::Error::XTDE1390,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_identity_template() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc xmlns:p="urn:p" a="1"><p:x b="2">text<!--c--><?pi d?></p:x><y/></doc>"#,
+        r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/" priority="2"><out><xsl:apply-templates select="doc"/></out></xsl:template>
+  <xsl:template match="@*|node()" priority="1">
+    <xsl:copy><xsl:apply-templates select="@*|node()"/></xsl:copy>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<out><doc xmlns:p="urn:p" a="1"><p:x b="2">text<!--c--><?pi d?></p:x><y/></doc></out>"#
+    );
+}
+
+#[test]
+fn test_identity_template_with_override() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc a="1"><x b="2"><y c="3">text</y></x><y/></doc>"#,
+        r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/" priority="2"><out><xsl:apply-templates select="doc"/></out></xsl:template>
+  <xsl:template match="@*|node()" priority="1">
+    <xsl:copy><xsl:apply-templates select="@*|node()"/></xsl:copy>
+  </xsl:template>
+  <xsl:template match="@c" priority="2"><xsl:attribute name="d" select="."/></xsl:template>
+  <xsl:template match="y[not(node())]" priority="2"><z/></xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        r#"<out><doc a="1"><x b="2"><y d="3">text</y></x><z/></doc></out>"#
+    );
+}
+
+#[test]
+fn test_identity_template_deep_document() {
+    let depth = 10_000;
+    let doc = format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth));
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        &format!("<doc>{}</doc>", doc),
+        r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/" priority="2"><out><xsl:apply-templates select="doc/a"/></out></xsl:template>
+  <xsl:template match="@*|node()" priority="1">
+    <xsl:copy><xsl:apply-templates select="@*|node()"/></xsl:copy>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml(&xot, output),
+        format!("<out>{}</out>", doc.replace("<a></a>", "<a/>"))
+    );
+}
+
+fn copy_namespaces(copy_namespaces: &str) -> String {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<r xmlns:u="urn:unused"><a/></r>"#,
+        &format!(
+            r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:template match="/"><xsl:apply-templates select="r"/></xsl:template>
+  <xsl:template match="*">
+    <xsl:copy copy-namespaces="{copy_namespaces}"><xsl:apply-templates/></xsl:copy>
+  </xsl:template>
+</xsl:transform>"#
+        ),
+    )
+    .unwrap();
+    xml(&xot, output)
+}
+
+#[test]
+fn test_copy_namespaces_yes() {
+    assert_eq!(
+        copy_namespaces("yes"),
+        r#"<r xmlns:u="urn:unused"><a/></r>"#
+    );
+}
+
+#[test]
+fn test_copy_namespaces_no() {
+    assert_eq!(copy_namespaces("no"), "<r><a/></r>");
+}
+
+fn on_no_match(on_no_match: &str) -> error::SpannedResult<String> {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><a x="1">A<b>B</b></a><!--c--></doc>"#,
+        &format!(
+            r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:mode on-no-match="{on_no_match}"/>
+  <xsl:template match="/"><out><xsl:apply-templates/></out></xsl:template>
+  <xsl:template match="b"><B/></xsl:template>
+</xsl:transform>"#
+        ),
+    )?;
+    Ok(xml(&xot, output))
+}
+
+#[test]
+fn test_on_no_match_shallow_copy() {
+    assert_eq!(
+        on_no_match("shallow-copy").unwrap(),
+        r#"<out><doc><a x="1">A<B/></a><!--c--></doc></out>"#
+    );
+}
+
+#[test]
+fn test_on_no_match_deep_copy() {
+    assert_eq!(
+        on_no_match("deep-copy").unwrap(),
+        r#"<out><doc><a x="1">A<b>B</b></a><!--c--></doc></out>"#
+    );
+}
+
+#[test]
+fn test_on_no_match_deep_skip() {
+    assert_eq!(on_no_match("deep-skip").unwrap(), "<out/>");
+}
+
+#[test]
+fn test_on_no_match_shallow_skip() {
+    assert_eq!(on_no_match("shallow-skip").unwrap(), "<out><B/></out>");
+}
+
+#[test]
+fn test_on_no_match_text_only_copy() {
+    assert_eq!(on_no_match("text-only-copy").unwrap(), "<out>A<B/></out>");
+}
+
+#[test]
+fn test_on_no_match_fail() {
+    assert!(matches!(
+        on_no_match("fail"),
+        Err(error::SpannedError {
+            error: error::Error::XTDE0555,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_on_no_match_named_mode_without_rules() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        r#"<doc><a>A</a></doc>"#,
+        r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:mode name="copy" on-no-match="shallow-copy" warning-on-no-match="yes"/>
+  <xsl:template match="/"><out><xsl:apply-templates mode="copy"/></out></xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<out><doc><a>A</a></doc></out>");
+}
+
+fn serialize_with_character_maps(xslt: &str) -> error::SpannedResult<String> {
+    let mut xot = Xot::new();
+    let output = evaluate(&mut xot, "<doc/>", xslt)?;
+    let parameters = serialization_parameters(xslt)?;
+    Ok(output.serialize(parameters, &mut xot)?)
+}
+
+#[test]
+fn test_character_map_entity_reference() {
+    assert_eq!(
+        serialize_with_character_maps(
+            r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:output use-character-maps="html"/>
+  <xsl:character-map name="html">
+    <xsl:output-character character="&#160;" string="&amp;nbsp;"/>
+  </xsl:character-map>
+  <xsl:template match="/"><p title="a&#160;b">a&#160;b &amp; c</p></xsl:template>
+</xsl:transform>"#
+        )
+        .unwrap(),
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<p title=\"a&nbsp;b\">a&nbsp;b &amp; c</p>"
+    );
+}
+
+#[test]
+fn test_character_map_overrides_escaping() {
+    assert_eq!(
+        serialize_with_character_maps(
+            r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:output use-character-maps="raw"/>
+  <xsl:character-map name="raw">
+    <xsl:output-character character="«" string="&lt;"/>
+    <xsl:output-character character="»" string="&gt;"/>
+  </xsl:character-map>
+  <xsl:template match="/"><p>«br/» &lt;</p></xsl:template>
+</xsl:transform>"#
+        )
+        .unwrap(),
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<p><br/> &lt;</p>"
+    );
+}
+
+#[test]
+fn test_character_map_not_in_names() {
+    assert_eq!(
+        serialize_with_character_maps(
+            r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:output use-character-maps="e"/>
+  <xsl:character-map name="e">
+    <xsl:output-character character="é" string="&amp;eacute;"/>
+  </xsl:character-map>
+  <xsl:template match="/"><café é="é">é<xsl:comment>é</xsl:comment></café></xsl:template>
+</xsl:transform>"#
+        )
+        .unwrap(),
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<café é=\"&eacute;\">&eacute;<!--é--></café>"
+    );
+}
+
+#[test]
+fn test_character_map_used_maps() {
+    // the characters of a map take precedence over those of the maps it uses
+    assert_eq!(
+        serialize_with_character_maps(
+            r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:output use-character-maps="main"/>
+  <xsl:character-map name="main" use-character-maps="base">
+    <xsl:output-character character="a" string="A"/>
+  </xsl:character-map>
+  <xsl:character-map name="base">
+    <xsl:output-character character="a" string="1"/>
+    <xsl:output-character character="b" string="2"/>
+  </xsl:character-map>
+  <xsl:template match="/"><p>abc</p></xsl:template>
+</xsl:transform>"#
+        )
+        .unwrap(),
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<p>A2c</p>"
+    );
+}
+
+#[test]
+fn test_character_map_circular() {
+    assert!(matches!(
+        serialize_with_character_maps(
+            r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:character-map name="one" use-character-maps="two"/>
+  <xsl:character-map name="two" use-character-maps="one"/>
+  <xsl:template match="/"><p/></xsl:template>
+</xsl:transform>"#
+        ),
+        Err(error::SpannedError {
+            error: error::Error::XTSE1600,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_character_map_unknown() {
+    assert!(matches!(
+        serialize_with_character_maps(
+            r#"<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:output use-character-maps="missing"/>
+  <xsl:template match="/"><p/></xsl:template>
+</xsl:transform>"#
+        ),
+        Err(error::SpannedError {
+            error: error::Error::XTSE1590,
+            span: _
+        })
+    ));
+}
+
+#[test]
+fn test_local_variable_as() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" xmlns:xs="http://www.w3.org/2001/XMLSchema" version="3">
+  <xsl:template match="/">
+    <xsl:variable name="n" select="'1.5'" as="xs:string"/>
+    <o><xsl:value-of select="$n"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<o>1.5</o>");
+}
+
+#[test]
+fn test_local_variable_as_mismatch() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" xmlns:xs="http://www.w3.org/2001/XMLSchema" version="3">
+  <xsl:template match="/">
+    <xsl:variable name="n" select="1, 2" as="xs:integer"/>
+    <o><xsl:value-of select="$n"/></o>
+  </xsl:template>
+</xsl:transform>"#,
+    );
+    assert_eq!(output.unwrap_err().error, error::Error::XTTE0570);
+}
+
+#[test]
+fn test_global_variable() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc><a/><a/></doc>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:variable name="total" select="$count * 10"/>
+  <xsl:variable name="count" select="count(//a)"/>
+  <xsl:template match="/"><o><xsl:apply-templates select="doc/a"/></o></xsl:template>
+  <xsl:template match="a"><xsl:value-of select="$total"/></xsl:template>
+</xsl:transform>"#,
+    )
+    .unwrap();
+    assert_eq!(xml(&xot, output), "<o>2020</o>");
+}
+
+#[test]
+fn test_global_variable_circular() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:variable name="a" select="$b"/>
+  <xsl:variable name="b" select="$a"/>
+  <xsl:template match="/"><o><xsl:value-of select="$a"/></o></xsl:template>
+</xsl:transform>"#,
+    );
+    assert_eq!(output.unwrap_err().error, error::Error::XTDE0640);
+}
+
+const PARAM_XSLT: &str = r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" xmlns:xs="http://www.w3.org/2001/XMLSchema" version="3">
+  <xsl:param name="n" select="1" as="xs:integer"/>
+  <xsl:template match="/"><o><xsl:value-of select="$n + 1"/></o></xsl:template>
+</xsl:transform>"#;
+
+#[test]
+fn test_global_param_default() {
+    let mut xot = Xot::new();
+    let output = evaluate(&mut xot, "<doc/>", PARAM_XSLT).unwrap();
+    assert_eq!(xml(&xot, output), "<o>2</o>");
+}
+
+#[test]
+fn test_global_param_supplied() {
+    let mut xot = Xot::new();
+    let mut params = Variables::new();
+    params.insert(
+        OwnedName::name("n"),
+        Atomic::Untyped("41".to_string().into()).into(),
+    );
+    let output = evaluate_with_params(&mut xot, "<doc/>", PARAM_XSLT, params).unwrap();
+    assert_eq!(xml(&xot, output), "<o>42</o>");
+}
+
+#[test]
+fn test_global_param_supplied_mismatch() {
+    let mut xot = Xot::new();
+    let mut params = Variables::new();
+    params.insert(OwnedName::name("n"), "forty-one".into());
+    let output = evaluate_with_params(&mut xot, "<doc/>", PARAM_XSLT, params);
+    assert_eq!(output.unwrap_err().error, error::Error::XTTE0590);
+}
+
+#[test]
+fn test_global_param_as_rejects_string() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" xmlns:xs="http://www.w3.org/2001/XMLSchema" version="3">
+  <xsl:param name="n" select="'forty-one'" as="xs:integer"/>
+  <xsl:template match="/"><o><xsl:value-of select="$n"/></o></xsl:template>
+</xsl:transform>"#,
+    );
+    assert_eq!(output.unwrap_err().error, error::Error::XTTE0570);
+}
+
+#[test]
+fn test_global_param_required() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" version="3">
+  <xsl:param name="n" required="yes"/>
+  <xsl:template match="/"><o><xsl:value-of select="$n"/></o></xsl:template>
+</xsl:transform>"#,
+    );
+    assert_eq!(output.unwrap_err().error, error::Error::XTDE0050);
+}
+
+#[test]
+fn test_global_param_implicitly_required() {
+    let mut xot = Xot::new();
+    let output = evaluate(
+        &mut xot,
+        "<doc/>",
+        r#"
+<xsl:transform xmlns:xsl="http://www.w3.org/1999/XSL/Transform" xmlns:xs="http://www.w3.org/2001/XMLSchema" version="3">
+  <xsl:param name="n" as="xs:integer"/>
+  <xsl:template match="/"><o><xsl:value-of select="$n"/></o></xsl:template>
+</xsl:transform>"#,
+    );
+    assert_eq!(output.unwrap_err().error, error::Error::XTDE0050);
+}
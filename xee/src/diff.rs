@@ -0,0 +1,334 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::Parser;
+use xee_interpreter::xml::node_path;
+use xot::{Node, Value, Xot};
+
+use crate::common::input_xml;
+use crate::error::render_parse_error;
+
+#[derive(Debug, Parser)]
+pub(crate) struct Diff {
+    /// the expected xml file
+    expected: PathBuf,
+    /// the actual xml file
+    actual: PathBuf,
+    /// Don't ignore text nodes that consist of whitespace only.
+    ///
+    /// By default whitespace between elements, such as indentation, is
+    /// insignificant, so an indented document is the same as one that isn't.
+    #[arg(long)]
+    strict_whitespace: bool,
+}
+
+/// Exit code when the documents are different.
+pub(crate) const EXIT_DIFFERENT: u8 = 1;
+/// Exit code when a document cannot be read or is not well-formed.
+pub(crate) const EXIT_ERROR: u8 = 2;
+
+impl Diff {
+    pub(crate) fn run(&self) -> anyhow::Result<ExitCode> {
+        let mut xot = Xot::new();
+        let expected = match parse(&mut xot, &self.expected)? {
+            Some(root) => root,
+            None => return Ok(ExitCode::from(EXIT_ERROR)),
+        };
+        let actual = match parse(&mut xot, &self.actual)? {
+            Some(root) => root,
+            None => return Ok(ExitCode::from(EXIT_ERROR)),
+        };
+
+        let comparer = Comparer {
+            xot: &xot,
+            strict_whitespace: self.strict_whitespace,
+        };
+        if let Some(difference) = comparer.difference(expected, actual) {
+            println!(
+                "{}: {}",
+                node_path(difference.node, &xot),
+                difference.description
+            );
+            Ok(ExitCode::from(EXIT_DIFFERENT))
+        } else {
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+fn parse(xot: &mut Xot, path: &Path) -> anyhow::Result<Option<Node>> {
+    let input_xml = input_xml(&Some(path.to_path_buf()))?;
+    match xot.parse(&input_xml) {
+        Ok(root) => Ok(Some(root)),
+        Err(e) => {
+            render_parse_error(&input_xml, &e);
+            eprintln!("{} is not well-formed", path.display());
+            Ok(None)
+        }
+    }
+}
+
+/// The first difference found. The node is in the expected document, unless
+/// the actual document has a node that isn't expected.
+struct Difference {
+    node: Node,
+    description: String,
+}
+
+impl Difference {
+    fn new(node: Node, description: String) -> Self {
+        Self { node, description }
+    }
+}
+
+struct Comparer<'a> {
+    xot: &'a Xot,
+    strict_whitespace: bool,
+}
+
+impl Comparer<'_> {
+    // like fn:deep-equal, only elements and text nodes are considered in
+    // the content of a document or element; comments and processing
+    // instructions are ignored.
+    fn is_relevant(&self, node: Node) -> bool {
+        match self.xot.value(node) {
+            Value::Element(_) => true,
+            Value::Text(text) => {
+                self.strict_whitespace
+                    || !text
+                        .get()
+                        .chars()
+                        .all(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+            }
+            _ => false,
+        }
+    }
+
+    fn is_equal(&self, a: Node, b: Node) -> bool {
+        self.xot
+            .advanced_deep_equal(a, b, |node| self.is_relevant(node), |a, b| a == b)
+    }
+
+    fn difference(&self, a: Node, b: Node) -> Option<Difference> {
+        if self.is_equal(a, b) {
+            return None;
+        }
+        match (self.xot.value(a), self.xot.value(b)) {
+            (Value::Document, Value::Document) => self.children_difference(a, b),
+            (Value::Element(element_a), Value::Element(element_b)) => {
+                if element_a.name() != element_b.name() {
+                    return Some(Difference::new(
+                        a,
+                        format!(
+                            "expected element {}, found element {}",
+                            self.name(element_a.name()),
+                            self.name(element_b.name())
+                        ),
+                    ));
+                }
+                self.attributes_difference(a, b)
+                    .or_else(|| self.children_difference(a, b))
+            }
+            (Value::Text(text_a), Value::Text(text_b)) => Some(Difference::new(
+                a,
+                format!(
+                    "expected text {:?}, found text {:?}",
+                    text_a.get(),
+                    text_b.get()
+                ),
+            )),
+            _ => Some(Difference::new(
+                a,
+                format!("expected {}, found {}", self.describe(a), self.describe(b)),
+            )),
+        }
+    }
+
+    fn attributes_difference(&self, a: Node, b: Node) -> Option<Difference> {
+        let attributes_b = self.xot.attributes(b);
+        for attribute_node in self.xot.attribute_nodes(a) {
+            let attribute = self.xot.attribute_node(attribute_node).unwrap();
+            let name = self.name(attribute.name());
+            match attributes_b.get(attribute.name()) {
+                None => {
+                    return Some(Difference::new(
+                        attribute_node,
+                        format!("missing attribute {}", name),
+                    ))
+                }
+                Some(value) if value != attribute.value() => {
+                    return Some(Difference::new(
+                        attribute_node,
+                        format!(
+                            "expected attribute {}={:?}, found {}={:?}",
+                            name,
+                            attribute.value(),
+                            name,
+                            value
+                        ),
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        let attributes_a = self.xot.attributes(a);
+        for attribute_node in self.xot.attribute_nodes(b) {
+            let attribute = self.xot.attribute_node(attribute_node).unwrap();
+            if attributes_a.get(attribute.name()).is_none() {
+                return Some(Difference::new(
+                    attribute_node,
+                    format!("unexpected attribute {}", self.name(attribute.name())),
+                ));
+            }
+        }
+        None
+    }
+
+    fn children_difference(&self, a: Node, b: Node) -> Option<Difference> {
+        let children_a = self.relevant_children(a);
+        let children_b = self.relevant_children(b);
+        for (child_a, child_b) in children_a.iter().zip(children_b.iter()) {
+            if let Some(difference) = self.difference(*child_a, *child_b) {
+                return Some(difference);
+            }
+        }
+        if let Some(missing) = children_a.get(children_b.len()) {
+            return Some(Difference::new(
+                *missing,
+                format!("missing {}", self.describe(*missing)),
+            ));
+        }
+        if let Some(unexpected) = children_b.get(children_a.len()) {
+            return Some(Difference::new(
+                *unexpected,
+                format!("unexpected {}", self.describe(*unexpected)),
+            ));
+        }
+        // the nodes themselves are different, but not in a way we can
+        // pinpoint further
+        Some(Difference::new(a, "nodes are different".to_string()))
+    }
+
+    fn relevant_children(&self, node: Node) -> Vec<Node> {
+        self.xot
+            .children(node)
+            .filter(|child| self.is_relevant(*child))
+            .collect()
+    }
+
+    fn describe(&self, node: Node) -> String {
+        match self.xot.value(node) {
+            Value::Document => "document".to_string(),
+            Value::Element(element) => format!("element {}", self.name(element.name())),
+            Value::Text(text) => format!("text {:?}", text.get()),
+            Value::Comment(_) => "comment".to_string(),
+            Value::ProcessingInstruction(_) => "processing instruction".to_string(),
+            Value::Attribute(attribute) => format!("attribute {}", self.name(attribute.name())),
+            Value::Namespace(_) => "namespace".to_string(),
+        }
+    }
+
+    fn name(&self, name: xot::NameId) -> String {
+        let (local, namespace) = self.xot.name_ns_str(name);
+        if namespace.is_empty() {
+            local.to_string()
+        } else {
+            format!("Q{{{}}}{}", namespace, local)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the path and description of the first difference, if any
+    fn diff(expected: &str, actual: &str, strict_whitespace: bool) -> Option<(String, String)> {
+        let mut xot = Xot::new();
+        let expected = xot.parse(expected).unwrap();
+        let actual = xot.parse(actual).unwrap();
+        let comparer = Comparer {
+            xot: &xot,
+            strict_whitespace,
+        };
+        comparer
+            .difference(expected, actual)
+            .map(|difference| (node_path(difference.node, &xot), difference.description))
+    }
+
+    #[test]
+    fn test_equal() {
+        assert_eq!(
+            diff("<a><b>text</b><c/></a>", "<a><b>text</b><c/></a>", false),
+            None
+        );
+        // comments and processing instructions are ignored
+        assert_eq!(
+            diff("<a><!--x--><b/></a>", "<a><b/><?pi?></a>", false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_whitespace() {
+        assert_eq!(diff("<a><b/></a>", "<a>\n  <b/>\n</a>", false), None);
+        assert_eq!(
+            diff("<a><b/></a>", "<a>\n  <b/>\n</a>", true),
+            Some((
+                "/Q{}a[1]/Q{}b[1]".to_string(),
+                "expected element b, found text \"\\n  \"".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_attribute_order() {
+        assert_eq!(
+            diff(r#"<a x="1" y="2"/>"#, r#"<a y="2" x="1"/>"#, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_different() {
+        assert_eq!(
+            diff("<a><b/></a>", "<a><c/></a>", false),
+            Some((
+                "/Q{}a[1]/Q{}b[1]".to_string(),
+                "expected element b, found element c".to_string()
+            ))
+        );
+        assert_eq!(
+            diff("<a><b>one</b></a>", "<a><b>two</b></a>", false),
+            Some((
+                "/Q{}a[1]/Q{}b[1]/text()[1]".to_string(),
+                "expected text \"one\", found text \"two\"".to_string()
+            ))
+        );
+        assert_eq!(
+            diff(r#"<a x="1"/>"#, r#"<a x="2"/>"#, false),
+            Some((
+                "/Q{}a[1]/@x".to_string(),
+                "expected attribute x=\"1\", found x=\"2\"".to_string()
+            ))
+        );
+        assert_eq!(
+            diff(r#"<a x="1"/>"#, "<a/>", false),
+            Some(("/Q{}a[1]/@x".to_string(), "missing attribute x".to_string()))
+        );
+        assert_eq!(
+            diff("<a/>", r#"<a y="1"/>"#, false),
+            Some((
+                "/Q{}a[1]/@y".to_string(),
+                "unexpected attribute y".to_string()
+            ))
+        );
+        assert_eq!(
+            diff("<a><b/><c/></a>", "<a><b/></a>", false),
+            Some((
+                "/Q{}a[1]/Q{}c[1]".to_string(),
+                "missing element c".to_string()
+            ))
+        );
+    }
+}
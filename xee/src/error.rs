@@ -1,4 +1,8 @@
 use xee_xpath::error::Error;
+use xee_xslt_ast::{
+    ast::Span,
+    error::{AttributeError, ElementError},
+};
 
 pub(crate) fn render_error(src: &str, e: Error) {
     let red = ariadne::Color::Red;
@@ -20,9 +24,11 @@ pub(crate) fn render_error(src: &str, e: Error) {
     println!("{}", e.error.note());
 }
 
-pub(crate) fn render_parse_error(src: &str, e: xot::ParseError) {
+pub(crate) fn render_parse_error(src: &str, e: &xot::ParseError) {
     let red = ariadne::Color::Red;
-    let mut report = ariadne::Report::build(ariadne::ReportKind::Error, ("source", (0..0)));
+    // xot reports spans as byte offsets
+    let mut report = ariadne::Report::build(ariadne::ReportKind::Error, ("source", (0..0)))
+        .with_config(ariadne::Config::default().with_index_type(ariadne::IndexType::Byte));
 
     report = report.with_label(
         ariadne::Label::new(("source", e.span().range()))
@@ -35,3 +41,53 @@ pub(crate) fn render_parse_error(src: &str, e: xot::ParseError) {
         .eprint(("source", ariadne::Source::from(src)))
         .unwrap();
 }
+
+pub(crate) fn render_xslt_error(src: &str, e: &ElementError) {
+    let red = ariadne::Color::Red;
+    let (message, span) = describe_xslt_error(e);
+    // like xot, the XSLT parser reports spans as byte offsets
+    let mut report = ariadne::Report::build(ariadne::ReportKind::Error, ("source", (0..0)))
+        .with_config(ariadne::Config::default().with_index_type(ariadne::IndexType::Byte))
+        .with_message(&message);
+
+    if let Some(span) = span {
+        report = report.with_label(
+            ariadne::Label::new(("source", span.start..span.end))
+                .with_message(message)
+                .with_color(red),
+        );
+    }
+
+    report
+        .finish()
+        .eprint(("source", ariadne::Source::from(src)))
+        .unwrap();
+}
+
+fn describe_xslt_error(e: &ElementError) -> (String, Option<Span>) {
+    match e {
+        ElementError::Unexpected { span } => ("unexpected content".to_string(), Some(*span)),
+        ElementError::UnexpectedEnd => ("unexpected end of content".to_string(), None),
+        ElementError::Attribute(e) => match e {
+            AttributeError::NotFound { name, span } => {
+                (format!("missing attribute {}", name.local), Some(*span))
+            }
+            AttributeError::Unexpected { name, span } => {
+                (format!("unexpected attribute {}", name.local), Some(*span))
+            }
+            AttributeError::Invalid { value, span }
+            | AttributeError::InvalidEqName { value, span }
+            | AttributeError::InvalidNameTest { value, span } => {
+                (format!("invalid attribute value {:?}", value), Some(*span))
+            }
+            AttributeError::XPathParser(_) | AttributeError::ValueTemplate(_) => {
+                ("invalid XPath expression in attribute".to_string(), None)
+            }
+            AttributeError::Internal => ("internal error".to_string(), None),
+        },
+        ElementError::ValueTemplate(_) => ("invalid value template".to_string(), None),
+        ElementError::XPathRunTime(e) => (e.error.message().to_string(), None),
+        ElementError::Internal => ("internal error".to_string(), None),
+        ElementError::Unsupported => ("unsupported instruction".to_string(), None),
+    }
+}
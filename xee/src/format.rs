@@ -147,7 +147,7 @@ impl Format {
         let root = match xot.parse(&input_xml) {
             Ok(root) => root,
             Err(e) => {
-                render_parse_error(&input_xml, e);
+                render_parse_error(&input_xml, &e);
                 return Ok(());
             }
         };
@@ -1,12 +1,16 @@
 mod common;
+mod diff;
 mod error;
 mod format;
 mod indent;
 mod repl;
 mod repl_cmd;
+mod validate;
 mod xpath;
 mod xslt;
 
+use std::process::ExitCode;
+
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -30,9 +34,18 @@ enum Commands {
     Repl(repl::Repl),
     /// Transform an XML document using an XSLT stylesheet.
     Xslt(xslt::Xslt),
+    /// Check whether an XML document is well-formed, with optional
+    /// additional checks.
+    Validate(validate::Validate),
+    /// Compare two XML documents, ignoring differences that don't matter,
+    /// such as attribute order and indentation.
+    ///
+    /// Prints the path of the first difference and exits with code 1 if the
+    /// documents are different.
+    Diff(diff::Diff),
 }
 
-fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<ExitCode> {
     let cli = Cli::parse();
     match cli.command {
         Commands::Indent(indent) => {
@@ -42,7 +55,12 @@ fn main() -> anyhow::Result<()> {
             format.run()?;
         }
         Commands::Xpath(xpath) => {
-            xpath.run()?;
+            // xpath has its own exit codes, so we report errors here
+            // rather than let them exit with code 1
+            return Ok(xpath.run().unwrap_or_else(|e| {
+                eprintln!("Error: {:?}", e);
+                ExitCode::from(xpath::EXIT_ERROR)
+            }));
         }
         Commands::Repl(repl) => {
             repl.run()?;
@@ -50,6 +68,15 @@ fn main() -> anyhow::Result<()> {
         Commands::Xslt(xslt) => {
             xslt.run()?;
         }
+        Commands::Validate(validate) => {
+            validate.run()?;
+        }
+        Commands::Diff(diff) => {
+            return Ok(diff.run().unwrap_or_else(|e| {
+                eprintln!("Error: {:?}", e);
+                ExitCode::from(diff::EXIT_ERROR)
+            }));
+        }
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
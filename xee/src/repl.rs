@@ -7,10 +7,12 @@ use std::{
 use ahash::HashMap;
 use clap::{CommandFactory, Parser};
 use rustyline::error::ReadlineError;
-use xee_xpath::{DocumentHandle, Documents, Itemable, Query};
+use xee_xpath::{DocumentHandle, Documents, Item, Itemable, Query};
+use xee_xslt_compiler::Invocation;
+use xot::xmlname::OwnedName;
 
 use crate::{
-    error::{render_error, render_parse_error},
+    error::{render_error, render_parse_error, render_xslt_error},
     repl_cmd::{ArgumentDefinition, CommandDefinition, CommandDefinitions},
     Cli,
 };
@@ -30,22 +32,37 @@ pub(crate) struct Repl {
     /// The format is prefix=uri.
     #[arg(long)]
     pub(crate) namespace: Vec<String>,
+    /// Evaluate XSLT instead of XPath.
+    ///
+    /// Enter a sequence constructor, such as `<xsl:value-of select="@id"/>`,
+    /// and it's evaluated with the current node as the context item. The
+    /// `xsl` prefix is bound for you.
+    #[arg(long)]
+    pub(crate) xslt: bool,
 }
 
+const XSL_NAMESPACE: &str = "http://www.w3.org/1999/XSL/Transform";
+
 pub(crate) struct RunContext {
     documents: Documents,
     document_handle: Option<DocumentHandle>,
+    // the current node, if set with the context command; otherwise the
+    // document is the context
+    context_node: Option<xot::Node>,
     default_namespace_uri: Option<String>,
     namespaces: HashMap<String, String>,
+    xslt: bool,
 }
 
 impl RunContext {
-    fn new() -> Self {
+    fn new(xslt: bool) -> Self {
         Self {
             documents: Documents::new(),
             document_handle: None,
+            context_node: None,
             default_namespace_uri: None,
             namespaces: HashMap::default(),
+            xslt,
         }
     }
 
@@ -78,7 +95,9 @@ impl RunContext {
             Ok(doc) => Some(doc),
             Err(e) => {
                 match e {
-                    xee_xpath::error::DocumentsError::Parse(e) => render_parse_error(&input_xml, e),
+                    xee_xpath::error::DocumentsError::Parse(e) => {
+                        render_parse_error(&input_xml, e.error())
+                    }
                     xee_xpath::error::DocumentsError::DuplicateUri(uri) => {
                         eprintln!("Duplicate URI: {}", uri);
                     }
@@ -87,6 +106,56 @@ impl RunContext {
             }
         };
         self.document_handle = document_handle;
+        self.context_node = None;
+    }
+
+    fn context_item(&self) -> xee_xpath::error::Result<Option<Item>> {
+        if let Some(node) = self.context_node {
+            return Ok(Some(Item::Node(node)));
+        }
+        self.document_handle
+            .map(|doc| doc.to_item(&self.documents))
+            .transpose()
+    }
+
+    fn set_context_node(&mut self, xpath: &str) {
+        let queries = self.queries();
+        let sequence_query = match queries.sequence(xpath) {
+            Ok(sequence_query) => sequence_query,
+            Err(e) => {
+                render_error(xpath, e);
+                return;
+            }
+        };
+        let mut context_builder = sequence_query.dynamic_context_builder(&self.documents);
+        match self.context_item() {
+            Ok(Some(item)) => {
+                context_builder.context_item(item);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                render_error(xpath, e);
+                return;
+            }
+        }
+        let context = context_builder.build();
+        let sequence = match sequence_query.execute_with_context(&mut self.documents, &context) {
+            Ok(sequence) => sequence,
+            Err(e) => {
+                render_error(xpath, e);
+                return;
+            }
+        };
+        let node = if sequence.len() == 1 {
+            sequence.iter().next().and_then(|item| item.to_node().ok())
+        } else {
+            None
+        };
+        if let Some(node) = node {
+            self.context_node = Some(node);
+        } else {
+            println!("The context must be a single node");
+        }
     }
 
     fn queries(&self) -> xee_xpath::Queries {
@@ -100,7 +169,15 @@ impl RunContext {
         xee_xpath::Queries::new(static_context_builder)
     }
 
-    pub(crate) fn execute(&mut self, xpath: &str) -> xee_xpath::error::Result<()> {
+    pub(crate) fn execute(&mut self, input: &str) -> xee_xpath::error::Result<()> {
+        if self.xslt {
+            self.execute_xslt(input)
+        } else {
+            self.execute_xpath(input)
+        }
+    }
+
+    fn execute_xpath(&mut self, xpath: &str) -> xee_xpath::error::Result<()> {
         let queries = self.queries();
         let sequence_query = queries.sequence(xpath);
         let sequence_query = match sequence_query {
@@ -111,9 +188,10 @@ impl RunContext {
             }
         };
         let mut context_builder = sequence_query.dynamic_context_builder(&self.documents);
-        if let Some(doc) = self.document_handle {
-            context_builder.context_item(doc.to_item(&self.documents)?);
+        if let Some(item) = self.context_item()? {
+            context_builder.context_item(item);
         }
+        context_builder.trace_to_stderr();
         let context = context_builder.build();
 
         let sequence = sequence_query.execute_with_context(&mut self.documents, &context);
@@ -130,11 +208,85 @@ impl RunContext {
         );
         Ok(())
     }
+
+    fn execute_xslt(&mut self, fragment: &str) -> xee_xpath::error::Result<()> {
+        let sequence_constructor = self.sequence_constructor(fragment);
+        // check the fragment by itself first, so we can point out what's
+        // wrong with it
+        if let Err(e) = xot::Xot::new().parse(&sequence_constructor) {
+            render_parse_error(&sequence_constructor, &e);
+            return Ok(());
+        }
+        if let Err(e) = xee_xslt_ast::parse_sequence_constructor_item(&sequence_constructor) {
+            render_xslt_error(&sequence_constructor, &e);
+            return Ok(());
+        }
+
+        let stylesheet = format!(
+            r#"<xsl:stylesheet version="3.0" xmlns:xsl="{}"><xsl:template name="repl">{}</xsl:template></xsl:stylesheet>"#,
+            XSL_NAMESPACE, sequence_constructor
+        );
+        let invocation = Invocation::CallTemplate(OwnedName::name("repl"));
+        let program = match xee_xslt_compiler::parse_with_invocation(
+            xee_xslt_compiler::static_context(),
+            &stylesheet,
+            &invocation,
+        ) {
+            Ok(program) => program,
+            Err(e) => {
+                // the span isn't in the fragment, so we leave it out
+                render_error(fragment, e.error.into());
+                return Ok(());
+            }
+        };
+        let mut context_builder = program.dynamic_context_builder();
+        context_builder.documents(self.documents.documents().clone());
+        if let Some(item) = self.context_item()? {
+            context_builder.context_item(item);
+        }
+        context_builder.trace_to_stderr();
+        let context = context_builder.build();
+
+        let sequence = match program.runnable(&context).many(self.documents.xot_mut()) {
+            Ok(sequence) => sequence,
+            Err(e) => {
+                render_error(fragment, e.error.into());
+                return Ok(());
+            }
+        };
+        println!(
+            "{}",
+            sequence.display_representation(self.documents.xot(), &context)
+        );
+        Ok(())
+    }
+
+    // wrap the fragment in xsl:sequence, which declares the namespaces, so
+    // that it can be checked and compiled by itself
+    fn sequence_constructor(&self, fragment: &str) -> String {
+        let mut declarations = format!(r#"xmlns:xsl="{}""#, XSL_NAMESPACE);
+        for (prefix, uri) in &self.namespaces {
+            declarations.push_str(&format!(r#" xmlns:{}="{}""#, prefix, escape_attribute(uri)));
+        }
+        if let Some(default_namespace_uri) = &self.default_namespace_uri {
+            declarations.push_str(&format!(
+                r#" xpath-default-namespace="{}""#,
+                escape_attribute(default_namespace_uri)
+            ));
+        }
+        format!("<xsl:sequence {}>{}</xsl:sequence>", declarations, fragment)
+    }
+}
+
+fn escape_attribute(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
 }
 
 impl Repl {
     pub(crate) fn run(self) -> anyhow::Result<()> {
-        let mut run_context = RunContext::new();
+        let mut run_context = RunContext::new(self.xslt);
         if let Some(infile) = &self.infile {
             run_context.set_context_document(infile);
         }
@@ -163,6 +315,15 @@ impl Repl {
                     run_context.set_context_document(&path);
                 }),
             ),
+            CommandDefinition::new(
+                "context",
+                Some("c"),
+                "Make the node selected by an XPath expression the context",
+                vec![ArgumentDefinition::rest("xpath")],
+                Box::new(|args, run_context, _| {
+                    run_context.set_context_node(args[0]);
+                }),
+            ),
             CommandDefinition::new(
                 "default_namespace",
                 Some("d"),
@@ -189,8 +350,16 @@ impl Repl {
                 Some("h"),
                 "Display this help",
                 vec![],
-                Box::new(|_, _, definitions| {
-                    println!("Either enter an XPath expression or a special command prefixed by !");
+                Box::new(|_, run_context, definitions| {
+                    if run_context.xslt {
+                        println!(
+                            "Either enter an XSLT sequence constructor or a special command prefixed by !"
+                        );
+                    } else {
+                        println!(
+                            "Either enter an XPath expression or a special command prefixed by !"
+                        );
+                    }
                     println!("Commands:");
                     for definition in &definitions.definitions {
                         println!("  {}", definition.help());
@@ -201,7 +370,8 @@ impl Repl {
         ]);
 
         println!(
-            "Xee XPath REPL {}",
+            "Xee {} REPL {}",
+            if self.xslt { "XSLT" } else { "XPath" },
             Cli::command().get_version().unwrap_or_default(),
         );
         println!("Type !help for more information.");
@@ -22,11 +22,27 @@ pub(crate) struct CommandDefinitions {
 pub(crate) struct ArgumentDefinition {
     name: &'static str,
     default: Option<&'static str>,
+    // takes the rest of the line, including any whitespace
+    rest: bool,
 }
 
 impl ArgumentDefinition {
     pub fn new(name: &'static str, default: Option<&'static str>) -> Self {
-        Self { name, default }
+        Self {
+            name,
+            default,
+            rest: false,
+        }
+    }
+
+    /// An argument that takes the rest of the line. It has to be the last
+    /// one.
+    pub fn rest(name: &'static str) -> Self {
+        Self {
+            name,
+            default: None,
+            rest: true,
+        }
     }
 }
 
@@ -53,16 +69,15 @@ impl CommandDefinitions {
     }
 
     pub(crate) fn execute(&self, command: &str, run_context: &mut RunContext) {
-        let parts = command.split_whitespace().collect::<Vec<_>>();
-        let command_s = parts[0];
-        let args = &parts[1..];
+        let (command_s, rest) = split_word(command);
         let command = self.get(command_s);
         if let Some(command) = command {
+            let args = command.split_arguments(rest);
             if args.len() > command.args.len() {
                 println!("Too many arguments for command: {}", command_s);
                 return;
             }
-            let args = command.preprocess_arguments(args);
+            let args = command.preprocess_arguments(&args);
 
             if args.len() < command.args.len() {
                 println!("Too few arguments for command: {}", command_s);
@@ -127,6 +142,21 @@ impl CommandDefinition {
             .join(" ")
     }
 
+    fn split_arguments<'a>(&self, s: &'a str) -> Vec<&'a str> {
+        let mut args = Vec::new();
+        let mut rest = s.trim();
+        while !rest.is_empty() {
+            if self.args.get(args.len()).is_some_and(|arg| arg.rest) {
+                args.push(rest);
+                break;
+            }
+            let (arg, after) = split_word(rest);
+            args.push(arg);
+            rest = after;
+        }
+        args
+    }
+
     fn preprocess_arguments<'a>(&self, args: &[&'a str]) -> Vec<&'a str> {
         let mut result = Vec::with_capacity(self.args.len());
         let mut i = 0;
@@ -141,3 +171,10 @@ impl CommandDefinition {
         result
     }
 }
+
+fn split_word(s: &str) -> (&str, &str) {
+    match s.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (s, ""),
+    }
+}
@@ -0,0 +1,182 @@
+use std::path::PathBuf;
+
+use ahash::{HashMap, HashMapExt};
+use clap::Parser;
+use xot::{NameId, NamespaceId, Node, NodeEdge, SpanInfo, SpanInfoKey, Xot};
+
+use crate::common::input_xml;
+use crate::error::render_parse_error;
+
+#[derive(Debug, Parser)]
+pub(crate) struct Validate {
+    /// input xml file (default stdin)
+    infile: Option<PathBuf>,
+    /// Report `id` and `xml:id` attributes with a value that is already used
+    /// by another such attribute in the document.
+    ///
+    /// Without a DTD we cannot know which attributes are of type ID, so this
+    /// assumes `id` attributes (without namespace) are. Duplicate `xml:id`
+    /// values are always reported, as they make the document not
+    /// well-formed.
+    #[arg(long)]
+    duplicate_ids: bool,
+    /// List the namespace declarations in the document, and warn about
+    /// declarations that aren't used by any element or attribute.
+    ///
+    /// Prefixes that are used but not declared are always reported, as they
+    /// make the document not well-formed.
+    #[arg(long)]
+    namespace_report: bool,
+}
+
+impl Validate {
+    pub(crate) fn run(&self) -> anyhow::Result<()> {
+        let input_xml = input_xml(&self.infile)?;
+
+        let mut xot = Xot::new();
+        let (root, span_info) = match xot.parse_with_span_info(&input_xml) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                render_parse_error(&input_xml, &e);
+                return Err(anyhow::anyhow!("Document is not well-formed"));
+            }
+        };
+
+        let mut error_count = 0;
+        if self.duplicate_ids {
+            error_count += check_duplicate_ids(&mut xot, root, &span_info, &input_xml);
+        }
+        if self.namespace_report {
+            namespace_report(&xot, root, &span_info, &input_xml);
+        }
+
+        if error_count > 0 {
+            return Err(anyhow::anyhow!("Found {} error(s)", error_count));
+        }
+        Ok(())
+    }
+}
+
+fn check_duplicate_ids(xot: &mut Xot, root: Node, span_info: &SpanInfo, src: &str) -> usize {
+    // `id` and `xml:id` values share the same space
+    let names = [
+        xot.add_name("id"),
+        xot.add_name_ns("id", xot.xml_namespace()),
+    ];
+    let mut seen: HashMap<String, (Node, NameId)> = HashMap::new();
+    let mut error_count = 0;
+    for node in xot.descendants(root) {
+        for name in names {
+            let Some(value) = xot.get_attribute(node, name) else {
+                continue;
+            };
+            let value = value.trim().to_string();
+            if let Some((first, first_name)) = seen.get(&value) {
+                let duplicate = span_info.get(SpanInfoKey::AttributeValue(node, name));
+                let first = span_info.get(SpanInfoKey::AttributeValue(*first, *first_name));
+                if let (Some(duplicate), Some(first)) = (duplicate, first) {
+                    render_duplicate_id(src, &value, duplicate.range(), first.range());
+                }
+                error_count += 1;
+            } else {
+                seen.insert(value, (node, name));
+            }
+        }
+    }
+    error_count
+}
+
+fn render_duplicate_id(
+    src: &str,
+    value: &str,
+    duplicate: std::ops::Range<usize>,
+    first: std::ops::Range<usize>,
+) {
+    ariadne::Report::build(ariadne::ReportKind::Error, ("source", duplicate.clone()))
+        .with_message(format!("Duplicate id {:?}", value))
+        .with_label(
+            ariadne::Label::new(("source", duplicate))
+                .with_message("this id is already in use")
+                .with_color(ariadne::Color::Red),
+        )
+        .with_label(
+            ariadne::Label::new(("source", first))
+                .with_message("first used here")
+                .with_color(ariadne::Color::Blue),
+        )
+        .finish()
+        .eprint(("source", ariadne::Source::from(src)))
+        .unwrap();
+}
+
+// A namespace declaration, and whether an element or attribute within the
+// declaring element is in its namespace.
+struct Declaration {
+    text: String,
+    namespace: NamespaceId,
+    used: bool,
+}
+
+fn namespace_report(xot: &Xot, root: Node, span_info: &SpanInfo, src: &str) {
+    // the declarations of the elements we're in, and where those of each
+    // element start
+    let mut in_scope: Vec<Declaration> = Vec::new();
+    let mut starts = Vec::new();
+    for edge in xot.traverse(root) {
+        match edge {
+            NodeEdge::Start(node) => {
+                starts.push(in_scope.len());
+                for (prefix, namespace) in xot.namespaces(node).iter() {
+                    let prefix = xot.prefix_str(prefix);
+                    let uri = xot.namespace_str(*namespace);
+                    let text = if prefix.is_empty() {
+                        format!("xmlns=\"{}\"", uri)
+                    } else {
+                        format!("xmlns:{}=\"{}\"", prefix, uri)
+                    };
+                    println!("{}", text);
+                    in_scope.push(Declaration {
+                        text,
+                        namespace: *namespace,
+                        used: false,
+                    });
+                }
+                if let Some(element) = xot.element(node) {
+                    let attributes = xot.attributes(node);
+                    for name in std::iter::once(element.name()).chain(attributes.keys()) {
+                        let namespace = xot.namespace_for_name(name);
+                        for declaration in &mut in_scope {
+                            if declaration.namespace == namespace {
+                                declaration.used = true;
+                            }
+                        }
+                    }
+                }
+            }
+            NodeEdge::End(node) => {
+                let start = starts.pop().unwrap();
+                for declaration in in_scope.drain(start..) {
+                    if declaration.used {
+                        continue;
+                    }
+                    if let Some(span) = span_info.get(SpanInfoKey::ElementStart(node)) {
+                        render_unused_namespace(src, &declaration.text, span.range());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn render_unused_namespace(src: &str, declaration: &str, span: std::ops::Range<usize>) {
+    ariadne::Report::build(ariadne::ReportKind::Warning, ("source", span.clone()))
+        .with_message(format!("Unused namespace declaration {}", declaration))
+        .with_label(
+            ariadne::Label::new(("source", span))
+                .with_message("declared on this element")
+                .with_color(ariadne::Color::Yellow),
+        )
+        .finish()
+        .eprint(("source", ariadne::Source::from(src)))
+        .unwrap();
+}
@@ -1,17 +1,23 @@
+use crate::common::input_xml;
+use crate::error::{render_error, render_parse_error};
 use clap::Parser;
 use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Instant;
 use xee_xpath::context::StaticContextBuilder;
+use xee_xpath::error::DocumentsError;
 use xee_xpath::Itemable;
-use xee_xpath::Query;
-use crate::common::input_xml;
-use crate::error::render_error;
 
 #[derive(Debug, Parser)]
 pub(crate) struct XPath {
     /// xpath expression
     pub(crate) xpath: String,
-    /// input xml file (default stdin)
-    pub(crate) infile: Option<PathBuf>,
+    /// input xml files (default stdin)
+    ///
+    /// When more than one file is given, all of them together form the
+    /// default collection (`fn:collection()`), and the first file is the
+    /// context item.
+    pub(crate) infiles: Vec<PathBuf>,
     /// Namespace URI to use in XPath for element names without a namespace
     /// prefix.
     ///
@@ -23,58 +29,180 @@ pub(crate) struct XPath {
     /// The format is prefix=uri.
     #[arg(long)]
     pub(crate) namespace: Vec<String>,
+    /// Report how long each phase (document load, parse, compile, execute)
+    /// took on stderr.
+    #[arg(long)]
+    pub(crate) timing: bool,
+    /// Print nothing if the result is the empty sequence, instead of `()`.
+    #[arg(long)]
+    pub(crate) null_output_on_empty: bool,
+    /// Print nothing, but exit with code 0 if the effective boolean value
+    /// of the result is true, and with code 1 if it is false.
+    #[arg(long)]
+    pub(crate) boolean: bool,
+}
+
+/// Exit code when `--boolean` is given and the effective boolean value of
+/// the result is false.
+pub(crate) const EXIT_FALSE: u8 = 1;
+/// Exit code when the document cannot be loaded, or the expression cannot
+/// be compiled or evaluated.
+pub(crate) const EXIT_ERROR: u8 = 2;
+
+/// How to present the result of a query.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct OutputOptions {
+    pub(crate) null_output_on_empty: bool,
+    pub(crate) boolean: bool,
 }
 
 impl XPath {
-    pub(crate) fn run(&self) -> Result<(), anyhow::Error> {
-        let input_xml = input_xml(&self.infile)?;
+    pub(crate) fn run(&self) -> Result<ExitCode, anyhow::Error> {
+        let timing = Timing::new(self.timing);
+        let input_xmls = if self.infiles.is_empty() {
+            vec![input_xml(&None)?]
+        } else {
+            self.infiles
+                .iter()
+                .map(|infile| input_xml(&Some(infile.clone())))
+                .collect::<Result<Vec<_>, _>>()?
+        };
 
         let mut documents = xee_xpath::Documents::new();
-        let doc = documents.add_string_without_uri(&input_xml)?;
+        let docs = timing.measure("load", || {
+            input_xmls
+                .iter()
+                .map(|input_xml| {
+                    documents
+                        .add_string_without_uri(input_xml)
+                        .map_err(|e| (input_xml, e))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        });
+        let docs = match docs {
+            Ok(docs) => docs,
+            Err((input_xml, DocumentsError::Parse(e))) => {
+                render_parse_error(input_xml, e.error());
+                eprintln!("Document is not well-formed");
+                return Ok(ExitCode::from(EXIT_ERROR));
+            }
+            Err((_, e)) => return Err(e.into()),
+        };
 
         let static_context_builder = make_static_context_builder(
             self.default_namespace_uri.as_deref(),
             self.namespace.as_slice(),
         )?;
 
-        let queries = xee_xpath::Queries::new(static_context_builder);
-        execute_query(&self.xpath, &queries, &mut documents, Some(doc))
+        execute_query(
+            &self.xpath,
+            &static_context_builder,
+            &mut documents,
+            &docs,
+            &timing,
+            OutputOptions {
+                null_output_on_empty: self.null_output_on_empty,
+                boolean: self.boolean,
+            },
+        )
+    }
+}
+
+/// Reports the duration of phases on stderr, if enabled.
+pub(crate) struct Timing {
+    enabled: bool,
+}
+
+impl Timing {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    pub(crate) fn measure<T>(&self, phase: &str, f: impl FnOnce() -> T) -> T {
+        if !self.enabled {
+            return f();
+        }
+        let start = Instant::now();
+        let result = f();
+        eprintln!("{}: {:?}", phase, start.elapsed());
+        result
     }
 }
 
 pub(crate) fn execute_query(
     xpath: &str,
-    queries: &xee_xpath::Queries<'_>,
+    static_context_builder: &StaticContextBuilder,
     documents: &mut xee_xpath::Documents,
-    doc: Option<xee_xpath::DocumentHandle>,
-) -> Result<(), anyhow::Error> {
-    let sequence_query = queries.sequence(xpath);
-    let sequence_query = match sequence_query {
-        Ok(sequence_query) => sequence_query,
+    docs: &[xee_xpath::DocumentHandle],
+    timing: &Timing,
+    output: OutputOptions,
+) -> Result<ExitCode, anyhow::Error> {
+    let static_context = match static_context_builder.build() {
+        Ok(static_context) => static_context,
+        Err(e) => {
+            render_error(xpath, e.into());
+            return Ok(ExitCode::from(EXIT_ERROR));
+        }
+    };
+    let ast = timing.measure("parse", || static_context.parse_xpath(xpath));
+    let ast = match ast {
+        Ok(ast) => ast,
+        Err(e) => {
+            render_error(xpath, e.into());
+            return Ok(ExitCode::from(EXIT_ERROR));
+        }
+    };
+    let program = timing.measure("compile", || {
+        xee_xpath_compiler::compile(static_context, ast)
+    });
+    let program = match program {
+        Ok(program) => program,
         Err(e) => {
             render_error(xpath, e);
-            return Ok(());
+            return Ok(ExitCode::from(EXIT_ERROR));
         }
     };
-    let mut context_builder = sequence_query.dynamic_context_builder(documents);
-    if let Some(doc) = doc {
-        context_builder.context_item(doc.to_item(documents)?);
+    let mut context_builder = program.dynamic_context_builder();
+    context_builder.documents(documents.documents().clone());
+    let items = docs
+        .iter()
+        .map(|doc| doc.to_item(documents))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(item) = items.first() {
+        context_builder.context_item(item.clone());
     }
+    context_builder.default_collection(items.into());
+    context_builder.trace_to_stderr();
     let context = context_builder.build();
 
-    let sequence = sequence_query.execute_with_context(documents, &context);
+    let sequence = timing.measure("execute", || {
+        program.runnable(&context).many(documents.xot_mut())
+    });
     let sequence = match sequence {
         Ok(sequence) => sequence,
         Err(e) => {
             render_error(xpath, e);
-            return Ok(());
+            return Ok(ExitCode::from(EXIT_ERROR));
         }
     };
+    if output.boolean {
+        return match sequence.effective_boolean_value() {
+            Ok(true) => Ok(ExitCode::SUCCESS),
+            Ok(false) => Ok(ExitCode::from(EXIT_FALSE)),
+            Err(e) => {
+                render_error(xpath, e.into());
+                Ok(ExitCode::from(EXIT_ERROR))
+            }
+        };
+    }
+    if output.null_output_on_empty && sequence.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
     println!(
         "{}",
         sequence.display_representation(documents.xot(), &context)
     );
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
 
 pub(crate) fn make_static_context_builder<'a>(
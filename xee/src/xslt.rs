@@ -3,10 +3,10 @@ use std::path::PathBuf;
 use crate::error::render_error;
 use anyhow::Context;
 use clap::Parser;
-use xee_xslt_compiler;
+use xee_xslt_compiler::{self, EntryPoints, Invocation};
+use xot::xmlname::{NameStrInfo, OwnedName};
 use xot::Xot;
 use crate::common::input_xml;
-use xee_interpreter::sequence::SerializationParameters;
 
 #[derive(Debug, Parser)]
 pub(crate) struct Xslt {
@@ -19,6 +19,17 @@ pub(crate) struct Xslt {
     /// Output file (default stdout)
     #[arg(long, short)]
     pub(crate) output: Option<PathBuf>,
+
+    /// Apply templates to the input in this mode, instead of the unnamed
+    /// mode. Use `Q{namespace}local` for a name in a namespace.
+    #[arg(long)]
+    pub(crate) initial_mode: Option<String>,
+
+    /// Start by calling this named template. Use `Q{namespace}local` for a
+    /// name in a namespace. Without an input file, there is no context
+    /// item.
+    #[arg(long, conflicts_with = "initial_mode")]
+    pub(crate) initial_template: Option<String>,
 }
 
 impl Xslt {
@@ -31,12 +42,28 @@ impl Xslt {
             )
         })?;
 
-        // Read the input XML
-        let xml = input_xml(&self.infile)?;
+        let invocation = self.invocation();
+        // if the stylesheet can't be parsed, we let the transformation
+        // report the error
+        if let Ok(entry_points) = xee_xslt_compiler::entry_points(&stylesheet) {
+            check_invocation(&entry_points, &invocation)?;
+        }
+
+        // Read the input XML; a named template doesn't need any
+        let xml = if self.infile.is_none() && self.initial_template.is_some() {
+            None
+        } else {
+            Some(input_xml(&self.infile)?)
+        };
 
         // Perform the XSLT transformation
         let mut xot = Xot::new();
-        let result = match xee_xslt_compiler::evaluate(&mut xot, &xml, &stylesheet) {
+        let result = match xee_xslt_compiler::evaluate_with_invocation(
+            &mut xot,
+            xml.as_deref(),
+            &stylesheet,
+            &invocation,
+        ) {
             Ok(result) => result,
             Err(e) => {
                 render_error(&stylesheet, e);
@@ -44,8 +71,15 @@ impl Xslt {
             }
         };
 
-        // Convert result to string
-        let output_str = result.serialize(SerializationParameters::new(), &mut xot)?;//serialize_result(&mut xot, result)?;
+        // Convert result to string, as declared by xsl:output
+        let parameters = match xee_xslt_compiler::serialization_parameters(&stylesheet) {
+            Ok(parameters) => parameters,
+            Err(e) => {
+                render_error(&stylesheet, e);
+                return Ok(());
+            }
+        };
+        let output_str = result.serialize(parameters, &mut xot)?;
 
         // Output the result
         if let Some(output_path) = &self.output {
@@ -58,4 +92,64 @@ impl Xslt {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn invocation(&self) -> Invocation {
+        if let Some(template) = &self.initial_template {
+            Invocation::CallTemplate(parse_name(template))
+        } else {
+            Invocation::ApplyTemplates(self.initial_mode.as_deref().map(parse_name))
+        }
+    }
+}
+
+// A name is either a local name without namespace, or an EQName of the
+// form `Q{namespace}local`.
+fn parse_name(name: &str) -> OwnedName {
+    if let Some((namespace, local_name)) = name
+        .strip_prefix("Q{")
+        .and_then(|rest| rest.split_once('}'))
+    {
+        OwnedName::new(local_name.to_string(), namespace.to_string(), String::new())
+    } else {
+        OwnedName::name(name)
+    }
+}
+
+fn display_name(name: &OwnedName) -> String {
+    if name.namespace().is_empty() {
+        name.local_name().to_string()
+    } else {
+        format!("Q{{{}}}{}", name.namespace(), name.local_name())
+    }
+}
+
+fn display_names(names: &[OwnedName]) -> String {
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names
+            .iter()
+            .map(display_name)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn check_invocation(entry_points: &EntryPoints, invocation: &Invocation) -> anyhow::Result<()> {
+    if entry_points.check(invocation).is_ok() {
+        return Ok(());
+    }
+    match invocation {
+        Invocation::ApplyTemplates(Some(mode)) => anyhow::bail!(
+            "Unknown initial mode: {}. Available modes: {}",
+            display_name(mode),
+            display_names(&entry_points.modes)
+        ),
+        Invocation::CallTemplate(name) => anyhow::bail!(
+            "Unknown initial template: {}. Available templates: {}",
+            display_name(name),
+            display_names(&entry_points.templates)
+        ),
+        Invocation::ApplyTemplates(None) => Ok(()),
+    }
+}